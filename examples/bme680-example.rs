@@ -148,13 +148,13 @@ fn main() -> ExitCode {
         if gas_result.gas_valid && gas_result.heat_stab {
             // -- it's a stretch: 
             // -- using a scaling factor to get a voc raw value usable for the VOC algo
-            let voc_raw = (gas_result.gas_res * 1.5) as u16;            
+            let voc_raw = (gas_result.gas_res.ohms() * 1.5) as u16;            
             let voc_index = voc_algo.process(voc_raw);
             info!("voc_raw: {voc_raw}, voc_index: {voc_index}");
         }        
 
         // -- store ambient temperature for next loop
-        ambient_temperature = temperature;
+        ambient_temperature = temperature.celsius();
         
         // -- delay next measuring
         let measuring_delay = time::Duration::from_millis(MEASURING_DELAY_SEC * 1000);
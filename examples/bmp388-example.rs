@@ -6,6 +6,7 @@ use std::process::ExitCode;
 use std::{thread, time};
 
 use i2c_sensors::bmp388::*;
+use i2c_sensors::units::Temperature;
 
 const EXIT_CODE_SET_CTR_C_HNDLR_FAILED: u8 = 0x02;
 const EXIT_CODE_BMP388_INIT_FAILED: u8 = 0x61;
@@ -104,7 +105,7 @@ fn main() -> ExitCode {
             }
         }
     }
-    let mut temperature_last = 20.0;
+    let mut temperature_last = Temperature::from_celsius(20.0);
     loop {
         if args.mode == AcquisitionMode::Forced {
             info!("Setting forced mode");
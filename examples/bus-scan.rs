@@ -0,0 +1,41 @@
+use clap::Parser;
+use log::{error, info};
+use std::path::Path;
+use std::process::ExitCode;
+
+use i2c_sensors::scanner;
+
+const EXIT_CODE_SCAN_FAILED: u8 = 0x02;
+
+#[derive(Parser)]
+struct Args {
+    // -- i2c bus device
+    bus_path: String,
+}
+
+fn main() -> ExitCode {
+
+    // -- read .env file
+    dotenv::dotenv().ok();
+    // -- setup logger
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args = Args::parse();
+    let bus_path = args.bus_path;
+    info!("Scanning i2c bus device {bus_path}");
+
+    let results = match scanner::scan_bus(Path::new(&bus_path)) {
+        Ok(results) => results,
+        Err(err) => {
+            error!("ERROR - Failed to scan bus: {err}");
+            return ExitCode::from(EXIT_CODE_SCAN_FAILED);
+        }
+    };
+    if results.is_empty() {
+        info!("No supported chips found");
+    }
+    for result in results {
+        info!("{result}");
+    }
+    ExitCode::SUCCESS
+}
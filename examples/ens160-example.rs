@@ -34,7 +34,7 @@ fn main() {
 
     let ens160_tvoc = ens160.get_total_volatile_organic_compounds()
         .expect("Failed to get total volatile organic compounds from ENS160");
-    println!("ENS160 Total Volatile Organic Compounds Concentration (ppb): {ens160_tvoc}");
+    println!("ENS160 Total Volatile Organic Compounds Concentration: {ens160_tvoc}");
 
     let ens160_eco2 = ens160.get_equivalent_co2()
         .expect("Failed to get equivalent CO2 from ENS160");
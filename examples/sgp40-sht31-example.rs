@@ -12,9 +12,11 @@ use i2c_sensors::sht31::{
     SHT31ContinuousAcquisition,
 };
 use i2c_sensors::sgp40::SGP40;
+use i2c_sensors::shared_bus::SharedBus;
 
 const EXIT_CODE_SET_CTR_C_HNDLR_FAILED: u8 = 0x02;
 const EXIT_CODE_UNSUPPORTED_MODE: u8 = 0x03;
+const EXIT_CODE_OPEN_BUS_FAILED: u8 = 0x04;
 const EXIT_CODE_SHT31_INIT_FAILED: u8 = 0x51;
 const EXIT_CODE_SHT31_SINGLE_SHOT_DATA_ACQUISITION_FAILED: u8 = 0x52;
 const EXIT_CODE_SHT31_START_CONTINUOUS_MODE_FAILED: u8 = 0x53;
@@ -57,9 +59,18 @@ fn main() -> ExitCode {
         return ExitCode::from(EXIT_CODE_SET_CTR_C_HNDLR_FAILED);
     }
 
+    // -- both sensors share one adapter
+    let bus_path = Path::new(&bus_path);
+    let shared_bus = match SharedBus::open(bus_path) {
+        Ok(shared_bus) => shared_bus,
+        Err(err) => {
+            error!("ERROR - Failed to open i2c bus: {err}");
+            return ExitCode::from(EXIT_CODE_OPEN_BUS_FAILED);
+        }
+    };
+
     info!("Initializing SGP40");
-    let bus_path = Path::new(&bus_path);    
-    let mut sgp40 = match SGP40::new(bus_path) {
+    let mut sgp40 = match SGP40::with_bus(shared_bus.device()) {
         Ok(sgp40) => sgp40,
         Err(err) => {
             error!("ERROR - Failed to initialize SGP40: {err}");
@@ -68,9 +79,8 @@ fn main() -> ExitCode {
     };
 
     info!("Initializing SHT31");
-    let bus_path = Path::new(&bus_path);
     let dev_addr = SHT31DeviceAddress::Default;
-    let mut sht31 = match SHT31::new(bus_path, dev_addr) {
+    let mut sht31 = match SHT31::with_bus(shared_bus.device(), dev_addr) {
         Ok(sht31) => sht31,
         Err(err) => {
             error!("ERROR - Failed to initialize SHT31: {err}");
@@ -129,8 +139,8 @@ fn main() -> ExitCode {
             return ExitCode::from(EXIT_CODE_UNSUPPORTED_MODE);
         };
 
-        let temperature = sht31.get_temperature_celcius(temperature_raw);
-        let humidity = sht31.get_humidity(humidity_raw);
+        let temperature = sht31.get_temperature(temperature_raw);
+        let humidity = sht31.get_relative_humidity(humidity_raw);
         info!("temperature: {temperature}, humidity: {humidity}");
 
         if let Ok(voc_raw) = sgp40.get_voc_data_with_compensation(humidity_raw, temperature_raw) {
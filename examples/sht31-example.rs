@@ -118,8 +118,8 @@ fn main() -> ExitCode {
             return ExitCode::from(EXIT_CODE_UNSUPPORTED_MODE);
         };
 
-        let temperature = sht31.get_temperature_celcius(temperature_raw);
-        let humidity = sht31.get_humidity(humidity_raw);
+        let temperature = sht31.get_temperature(temperature_raw);
+        let humidity = sht31.get_relative_humidity(humidity_raw);
         info!("temperature: {temperature}, humidity: {humidity}");
         
         let data_acquisition_delay = time::Duration::from_millis(2000);
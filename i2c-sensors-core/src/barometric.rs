@@ -0,0 +1,47 @@
+// -- altitude and sea level pressure from a plain f64 pressure in Pa, e.g. the pascal() of a
+// -- BMP388::get_pressure or BME280::compensate_pressure_float as is, temperatures in degree celsius
+// -- and altitudes in m; all of it assumes the troposphere of the international standard atmosphere
+// -- (ISA), i.e. up to 11000 m
+
+// -- ISA sea level pressure and temperature
+pub const ISA_SEA_LEVEL_PRESSURE: f64 = 101325.0;
+pub const ISA_SEA_LEVEL_TEMPERATURE: f64 = 288.15;
+// -- ISA temperature lapse rate in K/m
+pub const ISA_LAPSE_RATE: f64 = 0.0065;
+pub const ISA_TROPOPAUSE_ALTITUDE: f64 = 11000.0;
+
+const KELVIN_OFFSET: f64 = 273.15;
+// -- g M / (R L) with standard gravity, molar mass of dry air and the universal gas constant
+const ISA_EXPONENT: f64 = 9.80665 * 0.0289644 / (8.3144598 * ISA_LAPSE_RATE);
+
+// -- ISA altitude above the level of the given sea level pressure, e.g. the QNH of the nearest
+// -- airport or ISA_SEA_LEVEL_PRESSURE for pressure altitude; valid from -500 to 11000 m
+pub fn altitude(pressure: f64, sea_level_pressure: f64) -> f64 {
+    ISA_SEA_LEVEL_TEMPERATURE / ISA_LAPSE_RATE * (1.0 - libm::pow(pressure / sea_level_pressure, 1.0 / ISA_EXPONENT))
+}
+
+// -- altitude with the measured temperature in degree celsius instead of the ISA one, closer on
+// -- days warmer or colder than the standard atmosphere; valid up to a few 1000 m, the temperature
+// -- is taken for the whole air column
+pub fn hypsometric_altitude(pressure: f64, sea_level_pressure: f64, temperature: f64) -> f64 {
+    (libm::pow(sea_level_pressure / pressure, 1.0 / ISA_EXPONENT) - 1.0) * (temperature + KELVIN_OFFSET) / ISA_LAPSE_RATE
+}
+
+// -- QNH, the pressure reduced to sea level from the station altitude through the ISA; valid from
+// -- -500 to 11000 m
+pub fn sea_level_pressure(pressure: f64, altitude: f64) -> f64 {
+    pressure * libm::pow(1.0 - ISA_LAPSE_RATE * altitude / ISA_SEA_LEVEL_TEMPERATURE, -ISA_EXPONENT)
+}
+
+// -- pressure reduced to sea level from the station altitude with the measured temperature in
+// -- degree celsius, as weather services do for their maps; valid up to a few 1000 m
+pub fn sea_level_pressure_with_temperature(pressure: f64, altitude: f64, temperature: f64) -> f64 {
+    let temperature = temperature + KELVIN_OFFSET;
+    pressure * libm::pow(1.0 - ISA_LAPSE_RATE * altitude / (temperature + ISA_LAPSE_RATE * altitude), -ISA_EXPONENT)
+}
+
+// -- height above the point where the reference pressure was captured, e.g. at take-off or on the
+// -- ground floor; independent of the weather as long as it doesn't change in between
+pub fn relative_altitude(pressure: f64, reference_pressure: f64) -> f64 {
+    altitude(pressure, reference_pressure)
+}
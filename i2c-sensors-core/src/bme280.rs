@@ -0,0 +1,191 @@
+// -- lengths of the calibration and data blocks
+pub const BME280_LEN_TEMP_PRESS_CALIB_DATA: usize = 26;
+pub const BME280_LEN_HUMIDITY_CALIB_DATA: usize = 7;
+pub const BME280_LEN_P_T_H_DATA: usize = 8;
+
+// -- operating range, compensated values are clamped to it
+pub const BME280_TEMPERATURE_MIN: f64 = -40.0;
+pub const BME280_TEMPERATURE_MAX: f64 = 85.0;
+pub const BME280_PRESSURE_MIN: f64 = 30000.0;
+pub const BME280_PRESSURE_MAX: f64 = 110000.0;
+pub const BME280_HUMIDITY_MIN: f64 = 0.0;
+pub const BME280_HUMIDITY_MAX: f64 = 100.0;
+
+// -- shift values
+const BME280_12_BIT_SHIFT: u8 = 12;
+const BME280_8_BIT_SHIFT: u8 = 8;
+const BME280_4_BIT_SHIFT: u8 = 4;
+
+fn concat_bytes(msb: u8, lsb: u8) -> u16 {
+    ((msb as u16) << 8) | (lsb as u16)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CalibData
+{
+    // -- Calibration coefficients for the temperature sensor
+    dig_t1: u16,
+    dig_t2: i16,
+    dig_t3: i16,
+    // -- Calibration coefficients for the pressure sensor
+    dig_p1: u16,
+    dig_p2: i16,
+    dig_p3: i16,
+    dig_p4: i16,
+    dig_p5: i16,
+    dig_p6: i16,
+    dig_p7: i16,
+    dig_p8: i16,
+    dig_p9: i16,
+    // -- Calibration coefficients for the humidity sensor
+    dig_h1: u8,
+    dig_h2: i16,
+    dig_h3: u8,
+    dig_h4: i16,
+    dig_h5: i16,
+    dig_h6: i8,
+    // -- Variable to store the intermediate temperature coefficient
+    t_fine_float: f64,
+    t_fine_fixed: i32,
+}
+
+impl CalibData {
+
+    // -- from the temperature and pressure block at 0x88 and the humidity block at 0xe1
+    pub fn from_registers(temp_press_data: &[u8; BME280_LEN_TEMP_PRESS_CALIB_DATA],
+        humidity_data: &[u8; BME280_LEN_HUMIDITY_CALIB_DATA]) -> Self {
+        // -- temperature and pressure calibration data
+        let reg_data = temp_press_data;
+        let dig_t1 = concat_bytes(reg_data[1], reg_data[0]);
+        let dig_t2 = concat_bytes(reg_data[3], reg_data[2]) as i16;
+        let dig_t3 = concat_bytes(reg_data[5], reg_data[4]) as i16;
+        let dig_p1 = concat_bytes(reg_data[7], reg_data[6]);
+        let dig_p2 = concat_bytes(reg_data[9], reg_data[8]) as i16;
+        let dig_p3 = concat_bytes(reg_data[11], reg_data[10]) as i16;
+        let dig_p4 = concat_bytes(reg_data[13], reg_data[12]) as i16;
+        let dig_p5 = concat_bytes(reg_data[15], reg_data[14]) as i16;
+        let dig_p6 = concat_bytes(reg_data[17], reg_data[16]) as i16;
+        let dig_p7 = concat_bytes(reg_data[19], reg_data[18]) as i16;
+        let dig_p8 = concat_bytes(reg_data[21], reg_data[20]) as i16;
+        let dig_p9 = concat_bytes(reg_data[23], reg_data[22]) as i16;
+        let dig_h1 = reg_data[25];
+        // -- humidity calibration data
+        let reg_data = humidity_data;
+        let dig_h2 = concat_bytes(reg_data[1], reg_data[0]) as i16;
+        let dig_h3 = reg_data[2];
+        let dig_h4_msb = ((reg_data[3] as i8) as i16) * 16;
+        let dig_h4_lsb = (reg_data[4] & 0x0f) as i16;
+        let dig_h4 = dig_h4_msb | dig_h4_lsb;
+        let dig_h5_msb = ((reg_data[5] as i8) as i16) * 16;
+        let dig_h5_lsb = (reg_data[4] >> 4) as i16;
+        let dig_h5 = dig_h5_msb | dig_h5_lsb;
+        let dig_h6 = reg_data[6] as i8;
+        // -- create calibration structure
+        CalibData {
+            dig_t1, dig_t2, dig_t3,
+            dig_p1, dig_p2, dig_p3, dig_p4, dig_p5, dig_p6, dig_p7, dig_p8, dig_p9,
+            dig_h1, dig_h2, dig_h3, dig_h4, dig_h5, dig_h6,
+            t_fine_float: 0.0, t_fine_fixed: 0,
+        }
+    }
+
+    // -- also updates t_fine, pressure and humidity compensation depend on it
+    pub fn compensate_temperature_float(&mut self, uncomp_data: &UncompData) -> f64 {
+        let var1: f64 = ((uncomp_data.temperature as f64) / 16384.0) - ((self.dig_t1 as f64) / 1024.0);
+        let var1: f64 = var1 * (self.dig_t2 as f64);
+        let var2: f64 = ((uncomp_data.temperature as f64) / 131072.0) - ((self.dig_t1 as f64) / 8192.0);
+        let var2: f64 = (var2 * var2) * (self.dig_t3 as f64);
+        self.t_fine_float = var1 + var2;
+        let temperature = (var1 + var2) / 5120.0;
+        temperature.clamp(BME280_TEMPERATURE_MIN, BME280_TEMPERATURE_MAX)
+    }
+
+    pub fn compensate_temperature_fixed(&mut self, uncomp_data: &UncompData) -> f64 {
+        let var1a: i32 = ((uncomp_data.temperature >> 3) as i32) - ((self.dig_t1 as i32) << 1);
+        let var1: i32 = (var1a * (self.dig_t2 as i32)) >> 11;
+        let var2a: i32 = ((uncomp_data.temperature >> 4) as i32) - (self.dig_t1 as i32);
+        let var2: i32 = (((var2a * var2a) >> 12) *  (self.dig_t3 as i32)) >> 14;
+        self.t_fine_fixed = var1 + var2;
+        let temperature = (self.t_fine_fixed * 5 + 128) >> 8;
+        let temperature = temperature as f64 / 100.0;
+        temperature.clamp(BME280_TEMPERATURE_MIN, BME280_TEMPERATURE_MAX)
+    }
+
+    pub fn get_t_fine_float(&self) -> f64 {
+        self.t_fine_float
+    }
+
+    pub fn get_t_fine_fixed(&self) -> i32 {
+        self.t_fine_fixed
+    }
+
+    pub fn compensate_pressure_float(&self, uncomp_data: &UncompData) -> f64 {
+        let var1 = (self.t_fine_float / 2.0) - 64000.0;
+        let var2 = var1 * var1 * (self.dig_p6 as f64) / 32768.0;
+        let var2 = var2 + var1 * (self.dig_p5 as f64) * 2.0;
+        let var2 = (var2 / 4.0) + (self.dig_p4 as f64) * 65536.0;
+        let var3 = (self.dig_p3 as f64) * var1 * var1 / 524288.0;
+        let var1 = (var3 + (self.dig_p2 as f64) * var1) / 524288.0;
+        let var1 = (1.0 + var1 / 32768.0) * (self.dig_p1 as f64);
+        if var1 == 0.0 {
+            // -- avoid exception caused by division by zero
+            return BME280_PRESSURE_MIN
+        }
+        let pressure = 1048576.0 - (uncomp_data.pressure as f64);
+        let pressure = (pressure - (var2 / 4096.0)) * 6250.0 / var1;
+        let var1 = (self.dig_p9 as f64) * pressure * pressure / 2147483648.0;
+        let var2 = pressure * (self.dig_p8 as f64) / 32768.0;
+        let pressure = pressure + (var1 + var2 + (self.dig_p7 as f64)) / 16.0;
+        pressure.clamp(BME280_PRESSURE_MIN, BME280_PRESSURE_MAX)
+    }
+
+    pub fn compensate_humidity_float(&self, uncomp_data: &UncompData) -> f64 {
+        let var1 = self.t_fine_float - 76800.0;
+        let var2 = (self.dig_h4 as f64) * 64.0 + ((self.dig_h5 as f64) / 16384.0) * var1;
+        let var3 = (uncomp_data.humidity as f64) - var2;
+        let var4 = (self.dig_h2 as f64) / 65536.0;
+        let var5 = 1.0 + ((self.dig_h3 as f64) / 67108864.0) * var1;
+        let var6 = 1.0 + ((self.dig_h6 as f64) / 67108864.0) * var1 * var5;
+        let var6 = var3 * var4 * (var5 * var6);
+        let humidity = var6 * (1.0 - (self.dig_h1 as f64) * var6 / 524288.0);
+        humidity.clamp(BME280_HUMIDITY_MIN, BME280_HUMIDITY_MAX)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UncompData
+{
+    // -- Un-compensated pressure
+    pub pressure: u32,
+    // -- Un-compensated temperature
+    pub temperature: u32,
+    // -- Un-compensated humidity
+    pub humidity: u32,
+}
+
+impl UncompData {
+
+    // -- from the data block at 0xf7, pressure and temperature are 20 bit, humidity 16 bit
+    pub fn from_registers(reg_data: &[u8; BME280_LEN_P_T_H_DATA]) -> Self {
+        /* Store the parsed register values for pressure data */
+        let data_msb: u32 = (reg_data[0] as u32) << BME280_12_BIT_SHIFT;
+        let data_lsb: u32 = (reg_data[1] as u32) << BME280_4_BIT_SHIFT;
+        let data_xlsb: u32 = (reg_data[2] as u32) >> BME280_4_BIT_SHIFT;
+        let pressure = data_msb | data_lsb | data_xlsb;
+
+        /* Store the parsed register values for temperature data */
+        let data_msb: u32 = (reg_data[3] as u32) << BME280_12_BIT_SHIFT;
+        let data_lsb: u32 = (reg_data[4] as u32) << BME280_4_BIT_SHIFT;
+        let data_xlsb: u32 = (reg_data[5] as u32) >> BME280_4_BIT_SHIFT;
+        let temperature = data_msb | data_lsb | data_xlsb;
+
+        /* Store the parsed register values for humidity data */
+        let data_msb: u32 = (reg_data[6] as u32) << BME280_8_BIT_SHIFT;
+        let data_lsb: u32 = reg_data[7] as u32;
+        let humidity = data_msb | data_lsb;
+
+        UncompData {
+            pressure, temperature, humidity,
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use log::debug;
+
+use crate::units::Resistance;
+
+// -- lengths of the calibration blocks at 0x8a, 0xe1 and 0x00, they are parsed as one array
+pub const BME680_CALIB_DATA1_LEN: usize = 23;
+pub const BME680_CALIB_DATA2_LEN: usize = 14;
+pub const BME680_CALIB_DATA3_LEN: usize = 5;
+
+// -- Coefficient T2 LSB position
+const BME680_IDX_T2_LSB: usize = 0;
+// -- Coefficient T2 MSB position
+const BME680_IDX_T2_MSB: usize = 1;
+// -- Coefficient T3 position
+const BME680_IDX_T3: usize = 2;
+// -- Coefficient P1 LSB position
+const BME68X_IDX_P1_LSB: usize = 4;
+// -- Coefficient P1 MSB position
+const BME68X_IDX_P1_MSB: usize = 5;
+// -- Coefficient P2 LSB position
+const BME68X_IDX_P2_LSB: usize = 6;
+// -- Coefficient P2 MSB position
+const BME68X_IDX_P2_MSB: usize = 7;
+// -- Coefficient P3 position
+const BME68X_IDX_P3: usize = 8;
+// -- Coefficient P4 LSB position
+const BME68X_IDX_P4_LSB: usize = 10;
+// -- Coefficient P4 MSB position
+const BME68X_IDX_P4_MSB: usize = 11;
+// -- Coefficient P5 LSB position
+const BME68X_IDX_P5_LSB: usize = 12;
+// -- Coefficient P5 MSB position
+const BME68X_IDX_P5_MSB: usize = 13;
+// -- Coefficient P7 position
+const BME68X_IDX_P7: usize = 14;
+// -- Coefficient P6 position
+const BME68X_IDX_P6: usize = 15;
+// -- Coefficient P8 LSB position
+const BME68X_IDX_P8_LSB: usize = 18;
+// -- Coefficient P8 MSB position
+const BME68X_IDX_P8_MSB: usize = 19;
+// -- Coefficient P9 LSB position
+const BME68X_IDX_P9_LSB: usize = 20;
+// -- Coefficient P9 MSB position
+const BME68X_IDX_P9_MSB: usize = 21;
+// -- Coefficient P10 position
+const BME68X_IDX_P10: usize = 22;
+// -- Coefficient H2 MSB position
+const BME68X_IDX_H2_MSB: usize = 23;
+// -- Coefficient H2 LSB position
+const BME68X_IDX_H2_LSB: usize = 24;
+// -- Coefficient H1 LSB position
+const BME68X_IDX_H1_LSB: usize = 24;
+// -- Coefficient H1 MSB position
+const BME68X_IDX_H1_MSB: usize = 25;
+// -- Coefficient H3 position
+const BME68X_IDX_H3: usize = 26;
+// -- Coefficient H4 position
+const BME68X_IDX_H4: usize = 27;
+// -- Coefficient H5 position
+const BME68X_IDX_H5: usize = 28;
+// -- Coefficient H6 position
+const BME68X_IDX_H6: usize = 29;
+// -- Coefficient H7 position
+const BME68X_IDX_H7: usize = 30;
+// -- Coefficient T1 LSB position
+const BME680_IDX_T1_LSB: usize = 31;
+// -- Coefficient T1 MSB position
+const BME680_IDX_T1_MSB: usize = 32;
+// -- Coefficient GH2 LSB position
+const BME68X_IDX_GH2_LSB: usize = 33;
+// -- Coefficient GH2 MSB position
+const BME68X_IDX_GH2_MSB: usize = 34;
+// -- Coefficient GH1 position
+const BME68X_IDX_GH1: usize = 35;
+// -- Coefficient GH3 position
+const BME68X_IDX_GH3: usize = 36;
+// -- Coefficient res heat value position
+const BME68X_IDX_RES_HEAT_VAL: usize = 37;
+// -- Coefficient res heat range position
+const BME68X_IDX_RES_HEAT_RANGE: usize = 39;
+// -- Coefficient range switching error position
+const BME68X_IDX_RANGE_SW_ERR: usize = 41;
+
+// -- length of the pressure, temperature and humidity result block
+pub const BME680_MEAS_RESULT_LEN: usize = 8;
+
+// -- mask and bits for meas_status_0 register
+const BME680_MEAS_STATUS_0_NEW_DATA_BIT: u8 = 0x80;
+const BME680_MEAS_STATUS_0_GAS_MEASURING_BIT: u8 = 0x40;
+const BME680_MEAS_STATUS_0_MEASURING_BIT: u8 = 0x20;
+const BME680_MEAS_STATUS_0_GAS_MEAS_INDEX_MASK: u8 = 0x0f;
+
+// -- shift, bit, and mask values
+const BME680_12_BIT_SHIFT: u8 = 12;
+const BME680_8_BIT_SHIFT: u8 = 8;
+const BME680_6_BIT_SHIFT: u8 = 6;
+const BME680_4_BIT_SHIFT: u8 = 4;
+const BME680_2_BIT_SHIFT: u8 = 2;
+const BME680_4_BIT_MASK: u8 = 0xf;
+const BME680_BIT_H1_DATA_MASK: u8 = 0x0f;
+const BME680_RHRANGE_MASK: u8 = 0x30;
+const BME68X_RSERROR_MASK: u8 = 0xf0;
+const BME680_GAS_VALID_BIT: u8 = 0x20;
+const BME680_HEAT_STAB_BIT: u8 = 0x10;
+
+// -- list of gas ranges and corresponding constants used for the resistance calculation
+const GAS_RANGE_C1: [f64; 16] = [
+    1.0, 1.0, 1.0, 1.0, 1.0, 0.99, 1.0, 0.992,
+    1.0, 1.0, 0.998, 0.995, 1.0, 0.99, 1.0, 1.0
+];
+const GAS_RANGE_C2: [f64; 16] = [
+    8000000.0, 4000000.0, 2000000.0, 1000000.0, 499500.4995, 248262.1648, 125000.0, 63004.03226,
+    31281.28128, 15625.0, 7812.5, 3906.25, 1953.125, 976.5625, 488.28125, 244.140625
+];
+
+#[derive(Debug)]
+pub struct Bme680MeasuringStatus {
+    pub new_data: bool,
+    pub gas_measuring: bool,
+    pub measuring: bool,
+    pub gas_meas_index: u8,
+}
+
+impl Bme680MeasuringStatus {
+
+    pub fn from_register(reg_val: u8) -> Self {
+        // -- extract status values
+        let new_data = (reg_val & BME680_MEAS_STATUS_0_NEW_DATA_BIT) > 0;
+        let gas_measuring = (reg_val & BME680_MEAS_STATUS_0_GAS_MEASURING_BIT) > 0;
+        let measuring = (reg_val & BME680_MEAS_STATUS_0_MEASURING_BIT) > 0;
+        let gas_meas_index = reg_val & BME680_MEAS_STATUS_0_GAS_MEAS_INDEX_MASK;
+        Bme680MeasuringStatus {
+            new_data, gas_measuring, measuring, gas_meas_index,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Bme680MeasuringResult {
+    pub pressure_raw: u32,
+    pub temperature_raw: u32,
+    pub humidity_raw: u16,
+}
+
+impl Bme680MeasuringResult {
+
+    pub fn from_registers(reg_data: &[u8; BME680_MEAS_RESULT_LEN]) -> Self {
+        // -- store register values for pressure data
+        let data_msb = (reg_data[0] as u32) << BME680_12_BIT_SHIFT;
+        let data_lsb = (reg_data[1] as u32) << BME680_4_BIT_SHIFT;
+        let data_xlsb = (reg_data[2] as u32) >> BME680_4_BIT_SHIFT;
+        let pressure_raw = data_msb | data_lsb | data_xlsb;
+        // -- store register values for temperature data
+        let data_msb = (reg_data[3] as u32) << BME680_12_BIT_SHIFT;
+        let data_lsb = (reg_data[4] as u32) << BME680_4_BIT_SHIFT;
+        let data_xlsb = (reg_data[5] as u32) >> BME680_4_BIT_SHIFT;
+        let temperature_raw = data_msb | data_lsb | data_xlsb;
+        // -- store register values for humidity data
+        let data_msb = (reg_data[6] as u16) << BME680_8_BIT_SHIFT;
+        let data_lsb = reg_data[7] as u16;
+        let humidity_raw = data_msb | data_lsb;
+
+        Bme680MeasuringResult {
+            pressure_raw, temperature_raw, humidity_raw
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Bme680GasMeasuringResult {    
+    pub gas_res: Resistance,
+    pub gas_valid: bool,
+    pub heat_stab: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct CalibData
+{
+    // -- calibration coefficients for temperature
+    par_t1: f64,
+    par_t2: f64,
+    par_t3: f64,
+    // -- calibration coefficients for pressure
+    par_p1: f64,
+    par_p2: f64,
+    par_p3: f64,
+    par_p4: f64,
+    par_p5: f64,
+    par_p6: f64,
+    par_p7: f64,
+    par_p8: f64,
+    par_p9: f64,
+    par_p10: f64,
+    // -- calibration coefficients for gas
+    par_gh1: f64,
+    par_gh2: f64,
+    par_gh3: f64,
+    res_heat_range: f64,
+    res_heat_val: f64,
+    range_sw_err: f64,
+    // -- calibration coefficients for humidity
+    par_h1: f64,
+    par_h2: f64,
+    par_h3: f64,
+    par_h4: f64,
+    par_h5: f64,
+    par_h6: f64,
+    par_h7: f64,
+}
+
+fn concat_bytes(msb: u8, lsb: u8) -> u16 {
+    ((msb as u16) << 8) | (lsb as u16)
+}
+
+impl CalibData {
+
+    pub fn from_coefficients(coeff_array: &[u8]) -> Self {
+        // -- get calibration data for temperatire
+        let par_t1 = concat_bytes(coeff_array[BME680_IDX_T1_MSB], coeff_array[BME680_IDX_T1_LSB]) as f64;
+        let par_t2 = concat_bytes(coeff_array[BME680_IDX_T2_MSB], coeff_array[BME680_IDX_T2_LSB]) as f64;
+        let par_t3 = coeff_array[BME680_IDX_T3] as f64;
+        debug!("Read temperatire calibration data {par_t1} {par_t2} {par_t3} ");
+
+        // -- get calibration data for pressure
+        let par_p1 = concat_bytes(coeff_array[BME68X_IDX_P1_MSB], coeff_array[BME68X_IDX_P1_LSB]) as f64;
+        let par_p2 = (concat_bytes(coeff_array[BME68X_IDX_P2_MSB], coeff_array[BME68X_IDX_P2_LSB]) as i16) as f64;
+        let par_p3 = (coeff_array[BME68X_IDX_P3] as i8) as f64;
+        let par_p4 = (concat_bytes(coeff_array[BME68X_IDX_P4_MSB], coeff_array[BME68X_IDX_P4_LSB]) as i16) as f64;
+        let par_p5 = (concat_bytes(coeff_array[BME68X_IDX_P5_MSB], coeff_array[BME68X_IDX_P5_LSB]) as i16) as f64;
+        let par_p6 = (coeff_array[BME68X_IDX_P6] as i8) as f64;
+        let par_p7 = (coeff_array[BME68X_IDX_P7] as i8) as f64;
+        let par_p8 = (concat_bytes(coeff_array[BME68X_IDX_P8_MSB], coeff_array[BME68X_IDX_P8_LSB]) as i16) as f64;
+        let par_p9 = (concat_bytes(coeff_array[BME68X_IDX_P9_MSB], coeff_array[BME68X_IDX_P9_LSB]) as i16) as f64;
+        let par_p10 = coeff_array[BME68X_IDX_P10] as f64;
+        debug!("Read pressure calibration data {par_p1} {par_p2} {par_p3} {par_p4} {par_p5} {par_p6} {par_p7} {par_p8} {par_p9} {par_p10}");
+
+        // -- get calibration data for humidity
+        let par_h1 = (((coeff_array[BME68X_IDX_H1_MSB] as u16) << 4) |
+                        ((coeff_array[BME68X_IDX_H1_LSB] & BME680_BIT_H1_DATA_MASK) as u16)) as f64;
+        let par_h2 = (((coeff_array[BME68X_IDX_H2_MSB] as u16) << 4) |
+                        ((coeff_array[BME68X_IDX_H2_LSB] >> 4) as u16)) as f64;
+        let par_h3 = (coeff_array[BME68X_IDX_H3] as i8) as f64;
+        let par_h4 = (coeff_array[BME68X_IDX_H4] as i8) as f64;
+        let par_h5 = (coeff_array[BME68X_IDX_H5] as i8) as f64;
+        let par_h6 = coeff_array[BME68X_IDX_H6] as f64;
+        let par_h7 = (coeff_array[BME68X_IDX_H7] as i8) as f64;
+        debug!("Read humidity calibration data {par_h1} {par_h2} {par_h3} {par_h4} {par_h5} {par_h6} {par_h7}");
+
+
+        // -- get calibration data related to gas heater
+        let par_gh1 = (coeff_array[BME68X_IDX_GH1] as i8) as f64;
+        let par_gh2 = (concat_bytes(coeff_array[BME68X_IDX_GH2_MSB], coeff_array[BME68X_IDX_GH2_LSB]) as i16) as f64;
+        let par_gh3 = (coeff_array[BME68X_IDX_GH3] as i8) as f64;
+        let res_heat_range = ((coeff_array[BME68X_IDX_RES_HEAT_RANGE] & BME680_RHRANGE_MASK) / 16) as f64;
+        let res_heat_val = (coeff_array[BME68X_IDX_RES_HEAT_VAL] as i8) as f64;
+        let range_sw_err = (((coeff_array[BME68X_IDX_RANGE_SW_ERR] & BME68X_RSERROR_MASK) as i8) / 16) as f64;
+        debug!("Read gas heater calibration data {par_gh1} {par_gh2} {par_gh3} {res_heat_range} {res_heat_val} {range_sw_err}");
+
+        // -- return structured calibration data
+        CalibData {
+            par_t1, par_t2, par_t3,
+            par_p1, par_p2, par_p3, par_p4, par_p5,
+            par_p6, par_p7, par_p8, par_p9, par_p10,
+            par_gh1, par_gh2, par_gh3, res_heat_range,
+            res_heat_val, range_sw_err,
+            par_h1, par_h2, par_h3, par_h4, par_h5,
+            par_h6, par_h7,
+        }
+    }
+
+    pub fn temperature(&self, temperature_raw: u32) -> (f64, f64) {
+        let temperature_raw = temperature_raw as f64;
+        let par_t1 = self.par_t1;
+        let par_t2 = self.par_t2;
+        let par_t3 = self.par_t3;
+        let var1 = ((temperature_raw / 16384.0) - (par_t1 / 1024.0)) * par_t2;
+        let var2 = (((temperature_raw / 131072.0) - (par_t1 / 8192.0)) * ((temperature_raw / 131072.0) - (par_t1 / 8192.0))) * (par_t3 * 16.0);
+        let t_fine = var1 + var2;
+        let temp_comp = t_fine / 5120.0;
+        (temp_comp, t_fine)
+    }
+
+    pub fn pressure(&self, pressure_raw: u32, t_fine: f64) -> f64 {
+        let pressure_raw = pressure_raw as f64;
+        let par_p1 = self.par_p1;
+        let par_p2 = self.par_p2;
+        let par_p3 = self.par_p3;
+        let par_p4 = self.par_p4;
+        let par_p5 = self.par_p5;
+        let par_p6 = self.par_p6;
+        let par_p7 = self.par_p7;
+        let par_p8 = self.par_p8;
+        let par_p9 = self.par_p9;
+        let par_p10 = self.par_p10;
+        let var1 = (t_fine / 2.0) - 64000.0;
+        let var2 = var1 * var1 * (par_p6 / 131072.0);
+        let var2 = var2 + (var1 * par_p5 * 2.0);
+        let var2 = (var2 / 4.0) + (par_p4 * 65536.0);
+        let var1 = (((par_p3 * var1 * var1) / 16384.0) + (par_p2 * var1)) / 524288.0;
+        let var1 = (1.0 + (var1 / 32768.0)) * par_p1;
+        let press_comp = 1048576.0 - pressure_raw;
+        let press_comp = ((press_comp - (var2 / 4096.0)) * 6250.0) / var1;
+        let var1 = (par_p9 * press_comp * press_comp) / 2147483648.0;
+        let var2 = press_comp * (par_p8 / 32768.0);
+        let var3 = (press_comp / 256.0) * (press_comp / 256.0) * (press_comp / 256.0) * (par_p10 / 131072.0);
+        press_comp + (var1 + var2 + var3 + (par_p7 * 128.0)) / 16.0
+    }
+
+    pub fn humidity(&self, humidity_raw: u16, temperature: f64) -> f64 {
+        let humidity_raw = humidity_raw as f64;
+        let par_h1 = self.par_h1;
+        let par_h2 = self.par_h2;
+        let par_h3 = self.par_h3;
+        let par_h4 = self.par_h4;
+        let par_h5 = self.par_h5;
+        let par_h6 = self.par_h6;
+        let par_h7 = self.par_h7;
+
+        let var1 = humidity_raw - ((par_h1 * 16.0) + ((par_h3 / 2.0) * temperature));
+        let var2 = var1 * ((par_h2 / 262144.0) * (1.0 + ((par_h4 / 16384.0) * temperature) + ((par_h5 / 1048576.0) * temperature * temperature)));
+        let var3 = par_h6 / 16384.0;
+        let var4 = par_h7 / 2097152.0;
+        var2 + ((var3 + (var4 * temperature)) * var2 * var2)
+    }
+
+    pub fn res_heat(&self, amb_temp: f64, target_temp: f64) -> u8 {
+        let var1 = (self.par_gh1 / 16.0) + 49.0;
+        let var2 = ((self.par_gh2 / 32768.0) * 0.0005) + 0.00235;
+        let var3 = self.par_gh3  / 1024.0;
+        let var4 = var1 * (1.0 + (var2 * target_temp));
+        let var5 = var4 + (var3 * amb_temp);
+    
+        (3.4 * ((var5 * (4.0 / (4.0 + self.res_heat_range)) * (1.0/(1.0 +
+        (self.res_heat_val * 0.002)))) - 25.0)) as u8
+    }
+
+    pub fn gas_result(&self, data_msb: u8, data_lsb: u8) -> Bme680GasMeasuringResult {
+        let gas_adc = ((data_msb as u16) << BME680_2_BIT_SHIFT) | ((data_lsb as u16) >> BME680_6_BIT_SHIFT);
+        let gas_range = (data_lsb & BME680_4_BIT_MASK) as usize;
+        let gas_valid = (data_lsb & BME680_GAS_VALID_BIT) > 0;
+        let heat_stab = (data_lsb & BME680_HEAT_STAB_BIT) > 0;
+        let range_switching_error = self.range_sw_err;
+        let var1 = (1340.0 + (5.0 * range_switching_error)) * GAS_RANGE_C1[gas_range];
+        let gas_res = Resistance::from_ohms(var1 * GAS_RANGE_C2[gas_range] / (gas_adc as f64 - 512.0 + var1));
+        Bme680GasMeasuringResult {
+            gas_res, gas_valid, heat_stab,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+#[allow(unused_imports)]
+use log::{debug, warn};
+
+// -- length of the trimming coefficients block at 0x31
+pub const BMP388_LEN_TRIMMING_COEFFICIENTS: usize = 21;
+// -- length of the pressure and temperature data block at 0x04
+pub const BMP388_LEN_DATA: usize = 6;
+
+// -- fifo frame
+const BMP388_FIFO_SENSOR_FRAME_BIT: u8 = 0x80;
+const BMP388_FIFO_CONTROL_FRAME_BIT: u8 = 0x40;
+const BMP388_FIFO_CONTROL_FRAME_CONFIG_ERROR_BIT: u8 = 0x04;
+const BMP388_FIFO_CONTROL_FRAME_CONFIG_CHANGE_BIT: u8 = 0x08;
+const BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT: u8 = 0x20;
+const BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT: u8 = 0x10;
+const BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT: u8 = 0x04;
+
+pub const BMP388_FIFO_FRAMLE_LENGTH_HEADER: usize = 1;
+pub const BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME: usize = 4;
+pub const BMP388_FIFO_FRAMLE_LENGTH_DATA: usize = 3;
+// -- pressure and temperature frame followed by a sensor time frame
+pub const BMP388_FIFO_FRAMLE_LENGTH_MAX: usize = 11;
+
+#[derive(Debug)]
+pub struct FifoData {
+    pub pressure_raw: Option<u32>,
+    pub temperature_raw: Option<u32>,
+    pub sensor_time: Option<u32>,
+    pub config_change: bool,
+}
+
+// -- what the header of the next FIFO frame announces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FifoFrame {
+    ConfigError,
+    ConfigChange,
+    UnknownControl,
+    // -- neither pressure nor temperature is an empty frame
+    Sensor { pressure: bool, temperature: bool },
+    Unknown,
+}
+
+impl FifoFrame {
+
+    pub fn from_header(header: u8) -> Self {
+        if header & BMP388_FIFO_CONTROL_FRAME_BIT > 0 {
+            // -- either a config error or a config change
+            if header & BMP388_FIFO_CONTROL_FRAME_CONFIG_ERROR_BIT > 0 {
+                Self::ConfigError
+            } else if header & BMP388_FIFO_CONTROL_FRAME_CONFIG_CHANGE_BIT > 0 {
+                Self::ConfigChange
+            } else {
+                Self::UnknownControl
+            }
+        } else if header & BMP388_FIFO_SENSOR_FRAME_BIT > 0 {
+            let pressure = header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0;
+            let temperature = header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0;
+            Self::Sensor { pressure, temperature }
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+// -- header plus 3 bytes per sensor, the sensor time frame follows the last sensor frame in the FIFO
+pub fn sensor_frame_length(pressure: bool, temperature: bool, with_sensor_time: bool) -> usize {
+    let mut frame_length = BMP388_FIFO_FRAMLE_LENGTH_HEADER;
+    if pressure {
+        frame_length += BMP388_FIFO_FRAMLE_LENGTH_DATA;
+    }
+    if temperature {
+        frame_length += BMP388_FIFO_FRAMLE_LENGTH_DATA;
+    }
+    if with_sensor_time {
+        frame_length += BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME;
+    }
+    frame_length
+}
+
+// -- the bytes read for a sensor frame, temperature comes before pressure; values the bytes
+// -- read don't cover or the header doesn't flag are left out with a warning
+pub fn parse_sensor_frame(frame: &[u8], pressure: bool, temperature: bool, with_sensor_time: bool) -> FifoData {
+    // -- header is in byte 0
+    let header = frame.first().copied().unwrap_or_default();
+    let mut offset = BMP388_FIFO_FRAMLE_LENGTH_HEADER;
+    let temperature_raw = if temperature {
+        let temperature_raw = parse_sensor_value(frame, offset, header, BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT, "temperature");
+        offset += BMP388_FIFO_FRAMLE_LENGTH_DATA;
+        temperature_raw
+    } else {
+        None
+    };
+    let pressure_raw = if pressure {
+        let pressure_raw = parse_sensor_value(frame, offset, header, BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT, "pressure");
+        offset += BMP388_FIFO_FRAMLE_LENGTH_DATA;
+        pressure_raw
+    } else {
+        None
+    };
+    let sensor_time = if with_sensor_time {
+        // -- the sensor time frame has a header of its own
+        let sensor_time_header = frame.get(offset).copied().unwrap_or_default();
+        parse_sensor_value(frame, offset + BMP388_FIFO_FRAMLE_LENGTH_HEADER, sensor_time_header,
+            BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT, "sensor time")
+    } else {
+        None
+    };
+    FifoData {
+        pressure_raw, temperature_raw, sensor_time, config_change: false,
+    }
+}
+
+fn parse_sensor_value(frame: &[u8], offset: usize, header: u8, flag: u8, name: &str) -> Option<u32> {
+    let Some(data) = frame.get(offset..offset + BMP388_FIFO_FRAMLE_LENGTH_DATA) else {
+        warn!("Not enough bytes available for {name}");
+        return None
+    };
+    if header & flag == 0 {
+        warn!("Flag for {name} expected in header but not set: {header:#010b}");
+        return None
+    }
+    Some(concat_24_bits(data))
+}
+
+// -- xlsb, lsb, msb
+fn concat_24_bits(data: &[u8]) -> u32 {
+    (data[2] as u32) << 16 | (data[1] as u32) << 8 | (data[0] as u32)
+}
+
+fn concat_bytes(msb: u8, lsb: u8) -> u16 {
+    ((msb as u16) << 8) | (lsb as u16)
+}
+
+#[derive(Debug, Default)]
+pub struct DataRaw {
+    // -- Un-compensated pressure
+    pub pressure: u32,
+    // -- Un-compensated temperature
+    pub temperature: u32,
+}
+
+impl DataRaw {
+
+    pub fn from_registers(reg_data: &[u8; BMP388_LEN_DATA]) -> Self {
+        DataRaw {
+            pressure: concat_24_bits(&reg_data[0..3]),
+            temperature: concat_24_bits(&reg_data[3..6]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CalibData {
+    // -- Calibration coefficients for the pressure sensor
+    par_p1: f64,
+    par_p2: f64,
+    par_p3: f64,
+    par_p4: f64,
+    par_p5: f64,
+    par_p6: f64,
+    par_p7: f64,
+    par_p8: f64,
+    par_p9: f64,
+    par_p10: f64,
+    par_p11: f64,
+    // -- Calibration coefficients for the temperature sensor
+    par_t1: f64,
+    par_t2: f64,
+    par_t3: f64,
+}
+
+impl CalibData {
+
+    pub fn from_registers(reg_data: &[u8; BMP388_LEN_TRIMMING_COEFFICIENTS]) -> Self {
+        // -- temperature calibration coefficients
+        let par_t1 = concat_bytes(reg_data[1], reg_data[0]);
+        // let par_t1 = par_t1 as f64 / 0.00390625;
+        let par_t1 = par_t1 as f64 * 256.0; // == 1 / 0.00390625;
+        let par_t2 = concat_bytes(reg_data[3], reg_data[2]);
+        // let par_t2 = par_t2 as f64 / 1073741824.0;
+        let par_t2 = par_t2 as f64 * 0.000000000931323; // == 1 / 1073741824.0
+        let par_t3 = reg_data[4] as i8;
+        // let par_t3 = par_t3 as f64 / 281474976710656.0;
+        let par_t3 = par_t3 as f64 * 0.000000000000004; // == 1 / 281474976710656.0
+
+        // -- pressure calibration coefficients
+        let par_p1 = concat_bytes(reg_data[6], reg_data[5]) as i16;
+        //let par_p1 = (par_p1 - 16384) as f64 / 1048576.0;
+        let par_p1 = (par_p1 - 16384) as f64 * 0.000000953674316;
+        let par_p2 = concat_bytes(reg_data[8], reg_data[7]) as i16;
+        //let par_p2 = (par_p2 - 16384) as f64 / 536870912.0;
+        let par_p2 = (par_p2 - 16384) as f64 * 0.000000001862645;
+        let par_p3 = reg_data[9] as i8;
+        //let par_p3 = par_p3 as f64 / 4294967296.0;
+        let par_p3 = par_p3 as f64 * 0.000000000232831;
+        let par_p4 = reg_data[10] as i8;
+        //let par_p4 = (par_p4 as f64) / 137438953472.0;
+        let par_p4 = (par_p4 as f64) * 0.000000000007276;
+        let par_p5 = concat_bytes(reg_data[12], reg_data[11]);
+        //let par_p5 = (par_p5 as f64) / 0.125;
+        let par_p5 = (par_p5 as f64) * 8.0;
+        let par_p6 = concat_bytes(reg_data[14], reg_data[13]);
+        //let par_p6 = (par_p6 as f64) / 64.0;
+        let par_p6 = (par_p6 as f64) * 0.015625;
+        let par_p7 = reg_data[15] as i8;
+        //let par_p7 = (par_p7 as f64) / 256.0;
+        let par_p7 = (par_p7 as f64) * 0.00390625;
+        let par_p8 = reg_data[16] as i8;
+        //let par_p8 = (par_p8 as f64) / 32768.0;
+        let par_p8 = (par_p8 as f64) * 0.000030517578125;
+        let par_p9 = concat_bytes(reg_data[18], reg_data[17]) as i16;
+        //let par_p9 = (par_p9 as f64) / 281474976710656.0;
+        let par_p9 = (par_p9 as f64) * 0.000000000000004;
+        let par_p10 = reg_data[19] as i8;
+        //let par_p10 = (par_p10 as f64) / 281474976710656.0;
+        let par_p10 = (par_p10 as f64) * 0.000000000000004;
+        let par_p11 = reg_data[20] as i8;
+        //let par_p11 = (par_p11 as f64) / 36893488147419103232.0;
+        let par_p11 = (par_p11 as f64) * 0.00000000000000000002710505431213761;
+
+        // -- create calibration structure
+        CalibData {
+            par_t1, par_t2, par_t3,
+            par_p1, par_p2, par_p3, par_p4, par_p5, par_p6,
+            par_p7, par_p8, par_p9, par_p10, par_p11,
+        }
+    }
+
+    // -- degree celsius
+    pub fn temperature(&self, temperature_raw: u32) -> f64 {
+        let temperature_raw = temperature_raw as f64;
+        let partial_data1 = temperature_raw - self.par_t1;
+        let partial_data2 = partial_data1 * self.par_t2;
+        partial_data2 + ((partial_data1 * partial_data1) * self.par_t3)
+    }
+
+    // -- pascal, temperature in degree celsius
+    pub fn pressure(&self, pressure_raw: u32, temperature: f64) -> f64 {
+        let temperature_pow_2 = temperature * temperature;
+        let temperature_pow_3 = temperature_pow_2 * temperature;
+        let pressure_raw = pressure_raw as f64;
+        let partial_data1 = self.par_p6 * temperature;
+        let partial_data2 = self.par_p7 * temperature_pow_2;
+        let partial_data3 = self.par_p8 * temperature_pow_3;
+        let partial_out1 = self.par_p5 + partial_data1 + partial_data2 + partial_data3;
+        let partial_data1 = self.par_p2 * temperature;
+        let partial_data2 = self.par_p3 * temperature_pow_2;
+        let partial_data3 = self.par_p4 * temperature_pow_3;
+        let partial_out2 = pressure_raw * (self.par_p1 + partial_data1 + partial_data2 + partial_data3);
+        let partial_data1 = pressure_raw * pressure_raw;
+        let partial_data2 = self.par_p9 + self.par_p10 * temperature;
+        let partial_data3 = partial_data1 * partial_data2;
+        let partial_data4 = partial_data3 + partial_data1 * pressure_raw * self.par_p11;
+        partial_out1 + partial_out2 + partial_data4
+    }
+}
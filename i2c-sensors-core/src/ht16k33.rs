@@ -0,0 +1,250 @@
+// -- segment consts
+const SEGMENT_BIT_00_MASK: u16 = 0b00000000000001;
+const SEGMENT_BIT_00_BYTE: usize = 15;
+const SEGMENT_BIT_00_SHLF: usize = 0;
+const SEGMENT_BIT_01_MASK: u16 = 0b00000000000010;
+const SEGMENT_BIT_01_BYTE: usize = 1;
+const SEGMENT_BIT_01_SHLF: usize = 0;
+const SEGMENT_BIT_02_MASK: u16 = 0b00000000000100;
+const SEGMENT_BIT_02_BYTE: usize = 3;
+const SEGMENT_BIT_02_SHLF: usize = 0;
+const SEGMENT_BIT_03_MASK: u16 = 0b00000000001000;
+const SEGMENT_BIT_03_BYTE: usize = 5;
+const SEGMENT_BIT_03_SHLF: usize = 0;
+const SEGMENT_BIT_04_MASK: u16 = 0b00000000010000;
+const SEGMENT_BIT_04_BYTE: usize = 7;
+const SEGMENT_BIT_04_SHLF: usize = 0;
+const SEGMENT_BIT_05_MASK: u16 = 0b00000000100000;
+const SEGMENT_BIT_05_BYTE: usize = 9;
+const SEGMENT_BIT_05_SHLF: usize = 0;
+const SEGMENT_BIT_06_MASK: u16 = 0b00000001000000;
+const SEGMENT_BIT_06_BYTE: usize = 11;
+const SEGMENT_BIT_06_SHLF: usize = 0;
+const SEGMENT_BIT_07_MASK: u16 = 0b00000010000000;
+const SEGMENT_BIT_07_BYTE: usize = 1;
+const SEGMENT_BIT_07_SHLF: usize = 4;
+const SEGMENT_BIT_08_MASK: u16 = 0b00000100000000;
+const SEGMENT_BIT_08_BYTE: usize = 15;
+const SEGMENT_BIT_08_SHLF: usize = 4;
+const SEGMENT_BIT_09_MASK: u16 = 0b00001000000000;
+const SEGMENT_BIT_09_BYTE: usize = 3;
+const SEGMENT_BIT_09_SHLF: usize = 4;
+const SEGMENT_BIT_10_MASK: u16 = 0b00010000000000;
+const SEGMENT_BIT_10_BYTE: usize = 5;
+const SEGMENT_BIT_10_SHLF: usize = 4;
+const SEGMENT_BIT_11_MASK: u16 = 0b00100000000000;
+const SEGMENT_BIT_11_BYTE: usize = 7;
+const SEGMENT_BIT_11_SHLF: usize = 4;
+const SEGMENT_BIT_12_MASK: u16 = 0b01000000000000;
+const SEGMENT_BIT_12_BYTE: usize = 9;
+const SEGMENT_BIT_12_SHLF: usize = 4;
+const SEGMENT_BIT_13_MASK: u16 = 0b10000000000000;
+const SEGMENT_BIT_13_BYTE: usize = 11;
+const SEGMENT_BIT_13_SHLF: usize = 4;
+
+// -- the controller drives 16 rows of 8 commons
+pub const HT16K33_DISPLAY_RAM_LEN: usize = 16;
+
+// -- display ram of a 4 digit 14-segment display showing the first 4 chars of msg
+pub fn display_ram(msg: &str, set_colon: bool, set_dot: bool) -> [u8; HT16K33_DISPLAY_RAM_LEN] {
+    let segments = get_segments(msg);
+    let mut display_ram = [0; HT16K33_DISPLAY_RAM_LEN];
+    if set_colon {
+        self::set_colon(&mut display_ram)
+    }
+    if set_dot {
+        self::set_dot(&mut display_ram)
+    }
+    illuminate_char(&mut display_ram, segments[0], 0);
+    illuminate_char(&mut display_ram, segments[1], 1);
+    illuminate_char(&mut display_ram, segments[2], 2);
+    illuminate_char(&mut display_ram, segments[3], 3);
+    display_ram
+}
+
+fn set_colon(display_ram: &mut [u8; HT16K33_DISPLAY_RAM_LEN]) {
+    display_ram[0] |= 1;
+}
+
+fn set_dot(display_ram: &mut [u8; HT16K33_DISPLAY_RAM_LEN]) {
+    display_ram[2] |= 1;
+}
+
+fn illuminate_char(display_ram: &mut [u8; HT16K33_DISPLAY_RAM_LEN], segs_turn_on: u16, digit: u8) {
+    // -- digit cannot be bigger than 4
+    let digit = digit % 4;
+    // -- segment 0
+    if (segs_turn_on & SEGMENT_BIT_00_MASK) > 0 {
+        display_ram[SEGMENT_BIT_00_BYTE] |= (1 << digit) << SEGMENT_BIT_00_SHLF;
+    }
+    // -- segment 1
+    if (segs_turn_on & SEGMENT_BIT_01_MASK) > 0 {
+        display_ram[SEGMENT_BIT_01_BYTE] |= (1 << digit) << SEGMENT_BIT_01_SHLF;
+    }
+    // -- segment 2
+    if (segs_turn_on & SEGMENT_BIT_02_MASK) > 0 {
+        display_ram[SEGMENT_BIT_02_BYTE] |= (1 << digit) << SEGMENT_BIT_02_SHLF;
+    }
+    // -- segment 3
+    if (segs_turn_on & SEGMENT_BIT_03_MASK) > 0 {
+        display_ram[SEGMENT_BIT_03_BYTE] |= (1 << digit) << SEGMENT_BIT_03_SHLF;
+    }
+    // -- segment 4
+    if (segs_turn_on & SEGMENT_BIT_04_MASK) > 0 {
+        display_ram[SEGMENT_BIT_04_BYTE] |= (1 << digit) << SEGMENT_BIT_04_SHLF;
+    }
+    // -- segment 5
+    if (segs_turn_on & SEGMENT_BIT_05_MASK) > 0 {
+        display_ram[SEGMENT_BIT_05_BYTE] |= (1 << digit) << SEGMENT_BIT_05_SHLF;
+    }
+    // -- segment 6
+    if (segs_turn_on & SEGMENT_BIT_06_MASK) > 0 {
+        display_ram[SEGMENT_BIT_06_BYTE] |= (1 << digit) << SEGMENT_BIT_06_SHLF;
+    }
+    // -- segment 7
+    if (segs_turn_on & SEGMENT_BIT_07_MASK) > 0 {
+        display_ram[SEGMENT_BIT_07_BYTE] |= (1 << digit) << SEGMENT_BIT_07_SHLF;
+    }
+    // -- segment 8
+    if (segs_turn_on & SEGMENT_BIT_08_MASK) > 0 {
+        display_ram[SEGMENT_BIT_08_BYTE] |= (1 << digit) << SEGMENT_BIT_08_SHLF;
+    }
+    // -- segment 9
+    if (segs_turn_on & SEGMENT_BIT_09_MASK) > 0 {
+        display_ram[SEGMENT_BIT_09_BYTE] |= (1 << digit) << SEGMENT_BIT_09_SHLF;
+    }
+    // -- segment 10
+    if (segs_turn_on & SEGMENT_BIT_10_MASK) > 0 {
+        display_ram[SEGMENT_BIT_10_BYTE] |= (1 << digit) << SEGMENT_BIT_10_SHLF;
+    }
+    // -- segment 11
+    if (segs_turn_on & SEGMENT_BIT_11_MASK) > 0 {
+        display_ram[SEGMENT_BIT_11_BYTE] |= (1 << digit) << SEGMENT_BIT_11_SHLF;
+    }
+    // -- segment 12
+    if (segs_turn_on & SEGMENT_BIT_12_MASK) > 0 {
+        display_ram[SEGMENT_BIT_12_BYTE] |= (1 << digit) << SEGMENT_BIT_12_SHLF;
+    }
+    // -- segment 13
+    if (segs_turn_on & SEGMENT_BIT_13_MASK) > 0 {
+        display_ram[SEGMENT_BIT_13_BYTE] |= (1 << digit) << SEGMENT_BIT_13_SHLF;
+    }
+}
+
+pub fn get_segments(msg: &str) -> [u16;4] {
+    let mut segments: [u16;4] = [0;4];
+    let mut chars = msg.chars();
+    if let Some(digit0) = chars.next() {
+        segments[0] = get_segments_for_char(digit0);
+    };
+    if let Some(digit1) = chars.next() {
+        segments[1] = get_segments_for_char(digit1);
+    };
+    if let Some(digit2) = chars.next() {
+        segments[2] = get_segments_for_char(digit2);
+    };
+    if let Some(digit3) = chars.next() {
+        segments[3] = get_segments_for_char(digit3);
+    };        
+    segments
+}    
+
+pub fn get_segments_for_char(char: char) -> u16 {
+    match char {            
+        '!' => 0b00001000001000,
+        '"' => 0b00001000000010,
+        '#' => 0b01001101001110,
+        '$' => 0b01001101101101,
+        '%' => 0b10010000100100,
+        '&' => 0b00110011011001,
+        '\'' => 0b00001000000000,
+        '(' => 0b00000000111001,
+        ')' => 0b00000000001111,
+        '*' => 0b11111010000000,
+        '+' => 0b01001101000000,
+        ',' => 0b10000000000000,
+        '-' => 0b00000101000000,
+        '.' => 0b00000000000000,
+        '/' => 0b10010000000000,
+        '0' => 0b00000000111111,
+        '1' => 0b00010000000110,
+        '2' => 0b00000101011011,
+        '3' => 0b00000101001111,
+        '4' => 0b00000101100110,
+        '5' => 0b00000101101101,
+        '6' => 0b00000101111101,
+        '7' => 0b01010000000001,
+        '8' => 0b00000101111111,
+        '9' => 0b00000101100111,
+        ':' => 0b00000000000000,
+        ';' => 0b10001000000000,
+        '<' => 0b00110000000000,
+        '=' => 0b00000101001000,
+        '>' => 0b01000010000000,            
+        '?' => 0b01000100000011,
+        '@' => 0b00001100111011,
+        'A' => 0b00000101110111,
+        'B' => 0b01001100001111,
+        'C' => 0b00000000111001,
+        'D' => 0b01001000001111,
+        'E' => 0b00000101111001,
+        'F' => 0b00000101110001,
+        'G' => 0b00000100111101,
+        'H' => 0b00000101110110,
+        'I' => 0b01001000001001,
+        'J' => 0b00000000011110,
+        'K' => 0b00110001110000,
+        'L' => 0b00000000111000,
+        'M' => 0b00010010110110,
+        'N' => 0b00100010110110,
+        'O' => 0b00000000111111,
+        'P' => 0b00000101110011,
+        'Q' => 0b00100000111111,
+        'R' => 0b00100101110011,
+        'S' => 0b00000110001101,
+        'T' => 0b01001000000001,
+        'U' => 0b00000000111110,
+        'V' => 0b10010000110000,
+        'W' => 0b10100000110110,
+        'X' => 0b10110010000000,
+        'Y' => 0b01010010000000,
+        'Z' => 0b10010000001001,
+        '[' => 0b00000000111001,
+        '\\' => 0b00100010000000,
+        ']' => 0b00000000001111,
+        '^' => 0b10100000000000,
+        '_' => 0b00000000001000,
+        '`' => 0b00000010000000,
+        'a' => 0b00000101011111,
+        'b' => 0b00100001111000,
+        'c' => 0b00000101011000,
+        'd' => 0b10000100001110,
+        'e' => 0b00000001111001, 
+        'f' => 0b00000001110001,
+        'g' => 0b00000110001111,
+        'h' => 0b00000101110100,
+        'i' => 0b01000000000000,
+        'j' => 0b00000000001110,
+        'k' => 0b01111000000000,
+        'l' => 0b01001000000000,
+        'm' => 0b01000101010100,
+        'n' => 0b00100001010000,
+        'o' => 0b00000101011100,
+        'p' => 0b00010001110001,
+        'q' => 0b00100101100011,
+        'r' => 0b00000001010000,
+        's' => 0b00000110001101,
+        't' => 0b00000001111000,
+        'u' => 0b00000000011100,
+        'v' => 0b10000000010000,
+        'w' => 0b10100000010100,
+        'x' => 0b10110010000000,
+        'y' => 0b00001100001110,
+        'z' => 0b10010000001001,
+        '{' => 0b10000011001001,
+        '|' => 0b01001000000000,
+        '}' => 0b00110100001001,
+        '~' => 0b00000101010010,
+        _ => 0b00000000000000, 
+    }
+}
@@ -0,0 +1,14 @@
+// -- register parsing, compensation math, units and derived quantities of the i2c-sensors drivers, without std or alloc, so
+// -- firmware on a microcontroller converts readings exactly like the linux drivers built on top of it
+#![no_std]
+
+pub mod barometric;
+pub mod bme280;
+pub mod bme680;
+pub mod bmp388;
+pub mod ht16k33;
+pub mod psychrometrics;
+pub mod units;
+// -- kept line by line close to Sensirion's reference implementation
+#[allow(clippy::assign_op_pattern, clippy::manual_clamp, clippy::needless_return, clippy::new_without_default)]
+pub mod voc_algo;
@@ -0,0 +1,103 @@
+// -- derived humidity quantities from a plain f64 temperature in degree celsius and relative humidity
+// -- in percent, e.g. the celsius() and percent() of an SHT31, BME280 or BME680 reading; outside the
+// -- stated ranges the results are extrapolations, at 0 %RH dew point, frost point and humidex are
+// -- not defined and come out NaN
+
+// -- Magnus coefficients over water, Sonntag 1990, valid from -45 to 60 degree celsius
+const MAGNUS_WATER_A: f64 = 17.62;
+const MAGNUS_WATER_B: f64 = 243.12;
+// -- Magnus coefficients over ice, valid from -65 to 0.01 degree celsius
+const MAGNUS_ICE_A: f64 = 22.46;
+const MAGNUS_ICE_B: f64 = 272.62;
+// -- saturation vapour pressure at 0 degree celsius in hPa
+const MAGNUS_E0: f64 = 6.112;
+
+pub const MAGNUS_WATER_TEMPERATURE_MIN: f64 = -45.0;
+pub const MAGNUS_WATER_TEMPERATURE_MAX: f64 = 60.0;
+pub const MAGNUS_ICE_TEMPERATURE_MIN: f64 = -65.0;
+pub const MAGNUS_ICE_TEMPERATURE_MAX: f64 = 0.01;
+
+const KELVIN_OFFSET: f64 = 273.15;
+// -- molar mass ratio of water vapour and dry air, in g/kg
+const MIXING_RATIO_FACTOR: f64 = 621.97;
+// -- 100000 over the specific gas constant of water vapour, 461.5 J/(kg K), for hPa and K in, g/m3 out
+const ABSOLUTE_HUMIDITY_FACTOR: f64 = 216.7;
+
+// -- hPa, over water
+pub fn saturation_vapour_pressure(temperature: f64) -> f64 {
+    MAGNUS_E0 * libm::exp(MAGNUS_WATER_A * temperature / (MAGNUS_WATER_B + temperature))
+}
+
+// -- hPa, over water
+pub fn vapour_pressure(temperature: f64, humidity: f64) -> f64 {
+    humidity / 100.0 * saturation_vapour_pressure(temperature)
+}
+
+// -- degree celsius, Magnus formula over water, valid from -45 to 60 degree celsius and 1 to 100 %RH,
+// -- accurate to about 0.35 degree celsius
+pub fn dew_point(temperature: f64, humidity: f64) -> f64 {
+    let gamma = libm::log(humidity / 100.0) + MAGNUS_WATER_A * temperature / (MAGNUS_WATER_B + temperature);
+    MAGNUS_WATER_B * gamma / (MAGNUS_WATER_A - gamma)
+}
+
+// -- degree celsius, where the vapour deposits as ice, Magnus formula over ice, valid from -65 to
+// -- 0.01 degree celsius and 1 to 100 %RH; the relative humidity is over water as sensors report it
+pub fn frost_point(temperature: f64, humidity: f64) -> f64 {
+    let gamma = libm::log(vapour_pressure(temperature, humidity) / MAGNUS_E0);
+    MAGNUS_ICE_B * gamma / (MAGNUS_ICE_A - gamma)
+}
+
+// -- g/m3 of water vapour, valid from -45 to 60 degree celsius and 0 to 100 %RH
+pub fn absolute_humidity(temperature: f64, humidity: f64) -> f64 {
+    ABSOLUTE_HUMIDITY_FACTOR * vapour_pressure(temperature, humidity) / (temperature + KELVIN_OFFSET)
+}
+
+// -- g of water vapour per kg of dry air at the given pressure in Pa, e.g. of BME280 or BME680,
+// -- valid from -45 to 60 degree celsius and 0 to 100 %RH
+pub fn mixing_ratio(temperature: f64, humidity: f64, pressure: f64) -> f64 {
+    let vapour_pressure = vapour_pressure(temperature, humidity);
+    MIXING_RATIO_FACTOR * vapour_pressure / (pressure / 100.0 - vapour_pressure)
+}
+
+// -- degree celsius, Stull 2011 for sea level pressure, valid from -20 to 50 degree celsius and
+// -- 5 to 99 %RH, accurate to about 1 degree celsius, off in cold and dry air
+pub fn wet_bulb(temperature: f64, humidity: f64) -> f64 {
+    temperature * libm::atan(0.151977 * libm::sqrt(humidity + 8.313659))
+        + libm::atan(temperature + humidity)
+        - libm::atan(humidity - 1.676331)
+        + 0.00391838 * libm::pow(humidity, 1.5) * libm::atan(0.023101 * humidity)
+        - 4.686035
+}
+
+// -- degree celsius, apparent temperature after the US National Weather Service: Steadman's simple
+// -- formula up to a heat index of 80 degree fahrenheit, i.e. 26.7 degree celsius, above the Rothfusz
+// -- regression with its low and high humidity adjustments; meant for 27 to 50 degree celsius and
+// -- 40 to 100 %RH, below it returns about the temperature
+pub fn heat_index(temperature: f64, humidity: f64) -> f64 {
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let heat_index = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let regression = -42.379 + 2.04901523 * t + 10.14333127 * rh
+            - 0.22475541 * t * rh - 0.00683783 * t * t - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh + 0.00085282 * t * rh * rh - 0.00000199 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            regression - (13.0 - rh) / 4.0 * libm::sqrt((17.0 - libm::fabs(t - 95.0)) / 17.0)
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            regression + (rh - 85.0) / 10.0 * (87.0 - t) / 5.0
+        } else {
+            regression
+        }
+    };
+    (heat_index - 32.0) * 5.0 / 9.0
+}
+
+// -- dimensionless, felt like degree celsius, after Environment Canada from the dew point; reported
+// -- there from 20 degree celsius and a humidex of 25 on, below it stays close to the temperature
+pub fn humidex(temperature: f64, humidity: f64) -> f64 {
+    let dew_point = dew_point(temperature, humidity) + KELVIN_OFFSET;
+    let vapour_pressure = 6.11 * libm::exp(5417.7530 * (1.0 / 273.16 - 1.0 / dew_point));
+    temperature + 0.5555 * (vapour_pressure - 10.0)
+}
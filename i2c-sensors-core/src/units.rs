@@ -0,0 +1,189 @@
+use core::fmt;
+
+// -- conversion factors
+const KELVIN_OFFSET: f64 = 273.15;
+const PASCAL_PER_HECTOPASCAL: f64 = 100.0;
+const PASCAL_PER_KILOPASCAL: f64 = 1000.0;
+// -- conventional inch and millimeter of mercury
+const PASCAL_PER_INCH_OF_MERCURY: f64 = 3386.389;
+const PASCAL_PER_MILLIMETER_OF_MERCURY: f64 = 133.322387415;
+const PPB_PER_PPM: f64 = 1000.0;
+const OHM_PER_KILOOHM: f64 = 1000.0;
+
+// -- the value with the given or else the full precision, followed by the unit
+fn write_value(f: &mut fmt::Formatter, val: f64, unit: &str) -> fmt::Result {
+    match f.precision() {
+        Some(precision) => write!(f, "{val:.precision$} {unit}"),
+        None => write!(f, "{val} {unit}"),
+    }
+}
+
+// -- stored in degree celsius, the unit of the drivers
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Temperature(f64);
+
+impl Temperature {
+    pub const fn from_celsius(celsius: f64) -> Self {
+        Temperature(celsius)
+    }
+
+    pub fn from_fahrenheit(fahrenheit: f64) -> Self {
+        Temperature((fahrenheit - 32.0) * 5.0 / 9.0)
+    }
+
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        Temperature(kelvin - KELVIN_OFFSET)
+    }
+
+    pub fn celsius(&self) -> f64 {
+        self.0
+    }
+
+    pub fn fahrenheit(&self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    pub fn kelvin(&self) -> f64 {
+        self.0 + KELVIN_OFFSET
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, "°C")
+    }
+}
+
+// -- stored in pascal, the unit of the drivers
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Pressure(f64);
+
+impl Pressure {
+    pub const fn from_pascal(pascal: f64) -> Self {
+        Pressure(pascal)
+    }
+
+    pub fn from_hectopascal(hectopascal: f64) -> Self {
+        Pressure(hectopascal * PASCAL_PER_HECTOPASCAL)
+    }
+
+    pub fn from_inches_of_mercury(inches_of_mercury: f64) -> Self {
+        Pressure(inches_of_mercury * PASCAL_PER_INCH_OF_MERCURY)
+    }
+
+    pub fn from_millimeters_of_mercury(millimeters_of_mercury: f64) -> Self {
+        Pressure(millimeters_of_mercury * PASCAL_PER_MILLIMETER_OF_MERCURY)
+    }
+
+    pub fn pascal(&self) -> f64 {
+        self.0
+    }
+
+    // -- same as millibar
+    pub fn hectopascal(&self) -> f64 {
+        self.0 / PASCAL_PER_HECTOPASCAL
+    }
+
+    pub fn kilopascal(&self) -> f64 {
+        self.0 / PASCAL_PER_KILOPASCAL
+    }
+
+    pub fn inches_of_mercury(&self) -> f64 {
+        self.0 / PASCAL_PER_INCH_OF_MERCURY
+    }
+
+    pub fn millimeters_of_mercury(&self) -> f64 {
+        self.0 / PASCAL_PER_MILLIMETER_OF_MERCURY
+    }
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, "Pa")
+    }
+}
+
+// -- stored in percent, 0 to 100
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct RelativeHumidity(f64);
+
+impl RelativeHumidity {
+    pub const fn from_percent(percent: f64) -> Self {
+        RelativeHumidity(percent)
+    }
+
+    pub fn from_fraction(fraction: f64) -> Self {
+        RelativeHumidity(fraction * 100.0)
+    }
+
+    pub fn percent(&self) -> f64 {
+        self.0
+    }
+
+    // -- 0 to 1
+    pub fn fraction(&self) -> f64 {
+        self.0 / 100.0
+    }
+}
+
+impl fmt::Display for RelativeHumidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, "%RH")
+    }
+}
+
+// -- volume mixing ratio, stored in parts per billion
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Concentration(f64);
+
+impl Concentration {
+    pub const fn from_ppb(ppb: f64) -> Self {
+        Concentration(ppb)
+    }
+
+    pub fn from_ppm(ppm: f64) -> Self {
+        Concentration(ppm * PPB_PER_PPM)
+    }
+
+    pub fn ppb(&self) -> f64 {
+        self.0
+    }
+
+    pub fn ppm(&self) -> f64 {
+        self.0 / PPB_PER_PPM
+    }
+}
+
+impl fmt::Display for Concentration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, "ppb")
+    }
+}
+
+// -- stored in ohm
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Resistance(f64);
+
+impl Resistance {
+    pub const fn from_ohms(ohms: f64) -> Self {
+        Resistance(ohms)
+    }
+
+    pub fn from_kiloohms(kiloohms: f64) -> Self {
+        Resistance(kiloohms * OHM_PER_KILOOHM)
+    }
+
+    pub fn ohms(&self) -> f64 {
+        self.0
+    }
+
+    pub fn kiloohms(&self) -> f64 {
+        self.0 / OHM_PER_KILOOHM
+    }
+}
+
+impl fmt::Display for Resistance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, "Ω")
+    }
+}
@@ -188,10 +188,10 @@ impl VocAlgorithmParams {
         } else {            
             if sample >= 0.0 {
                 let shift = (VOCALGORITHM_SIGMOID_L - (5.0 * self.m_sigmoid_scaled_offset)) / 4.0;
-                return (VOCALGORITHM_SIGMOID_L + shift) / ((1.0 + x.exp()) - shift);
+                return (VOCALGORITHM_SIGMOID_L + shift) / ((1.0 + libm::exp(x)) - shift);
             } else {
                 return (self.m_sigmoid_scaled_offset / VOCALGORITHM_VOC_INDEX_OFFSET_DEFAULT) *
-                    (VOCALGORITHM_SIGMOID_L / (1.0 + x.exp()))
+                    (VOCALGORITHM_SIGMOID_L / (1.0 + libm::exp(x)))
             }
         }
     }
@@ -209,7 +209,7 @@ impl VocAlgorithmParams {
         if abs_delta < 0.0 {
             abs_delta = -abs_delta;
         }
-        let f1 = (VOCALGORITHM_LP_ALPHA * abs_delta).exp();
+        let f1 = libm::exp(VOCALGORITHM_LP_ALPHA * abs_delta);
         let tau_a = ((VOCALGORITHM_LP_TAU_SLOW - VOCALGORITHM_LP_TAU_FAST) * f1) + VOCALGORITHM_LP_TAU_FAST;
         let a3 = VOCALGORITHM_SAMPLING_INTERVAL / (VOCALGORITHM_SAMPLING_INTERVAL + tau_a);
         self.m_adaptive_lowpass_x3 = ((1.0 -a3) * self.m_adaptive_lowpass_x3) + (a3 * sample);
@@ -237,7 +237,7 @@ impl VocAlgorithmParams {
         } else if x > 50.0 {
             return 0.0;
         } else {            
-            return self.m_mean_variance_estimator_sigmoid_l / (1.0 + x.exp());            
+            return self.m_mean_variance_estimator_sigmoid_l / (1.0 + libm::exp(x));            
         }
     }
 
@@ -323,11 +323,11 @@ impl VocAlgorithmParams {
             };
 
             let _mult_a1 = additional_scaling * (VOCALGORITHM_MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING - self.m_mean_variance_estimator_gamma_variance);
-            let _sqrt_a = _mult_a1.sqrt();
+            let _sqrt_a = libm::sqrt(_mult_a1);
             
             let _mult_b1 = self.m_mean_variance_estimator_std * (self.m_mean_variance_estimator_std / (VOCALGORITHM_MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING * additional_scaling));
             let _mult_b2 = ((self.m_mean_variance_estimator_gamma_variance * delta_sgp) / additional_scaling) * delta_sgp;
-            let _sqrt_b = (_mult_b1 + _mult_b2).sqrt();
+            let _sqrt_b = libm::sqrt(_mult_b1 + _mult_b2);
             self.m_mean_variance_estimator_std = _sqrt_a * _sqrt_b;
             self.m_mean_variance_estimator_mean = self.m_mean_variance_estimator_mean + (self.m_mean_variance_estimator_gamma_mean * delta_sgp);                 
         }
@@ -0,0 +1,31 @@
+use i2c_sensors_core::barometric::{self, ISA_SEA_LEVEL_PRESSURE};
+
+// -- ISA pressure at 1000 m, where the ISA temperature is 8.5 degree celsius
+const PRESSURE_1000_M: f64 = 89874.6;
+
+#[test]
+fn isa_altitude_and_qnh_reduction() {
+    assert!((barometric::altitude(PRESSURE_1000_M, ISA_SEA_LEVEL_PRESSURE) - 1000.0).abs() < 0.5);
+    assert!((barometric::sea_level_pressure(PRESSURE_1000_M, 1000.0) - ISA_SEA_LEVEL_PRESSURE).abs() < 5.0);
+    // -- a higher QNH puts the same pressure higher up
+    assert!(barometric::altitude(PRESSURE_1000_M, 102000.0) > 1000.0);
+}
+
+#[test]
+fn measured_temperature_of_the_standard_atmosphere_gives_isa_values() {
+    let altitude = barometric::hypsometric_altitude(PRESSURE_1000_M, ISA_SEA_LEVEL_PRESSURE, 8.5);
+    assert!((altitude - 1000.0).abs() < 0.5, "{altitude}");
+    let sea_level_pressure = barometric::sea_level_pressure_with_temperature(PRESSURE_1000_M, 1000.0, 8.5);
+    assert!((sea_level_pressure - ISA_SEA_LEVEL_PRESSURE).abs() < 5.0, "{sea_level_pressure}");
+    // -- warm air is less dense, the same pressure difference spans more height
+    assert!(barometric::hypsometric_altitude(PRESSURE_1000_M, ISA_SEA_LEVEL_PRESSURE, 25.0) > altitude);
+}
+
+#[test]
+fn relative_altitude_against_reference() {
+    let reference_pressure = 97000.0;
+    assert_eq!(barometric::relative_altitude(reference_pressure, reference_pressure), 0.0);
+    // -- about 8.5 m per hPa near the ground
+    let height = barometric::relative_altitude(reference_pressure - 100.0, reference_pressure);
+    assert!((height - 8.6).abs() < 0.3, "{height}");
+}
@@ -0,0 +1,33 @@
+use i2c_sensors_core::bmp388::{self, FifoFrame};
+
+#[test]
+fn fifo_header_announces_the_frame() {
+    assert_eq!(FifoFrame::from_header(0x94), FifoFrame::Sensor { pressure: true, temperature: true });
+    assert_eq!(FifoFrame::from_header(0x90), FifoFrame::Sensor { pressure: false, temperature: true });
+    assert_eq!(FifoFrame::from_header(0x80), FifoFrame::Sensor { pressure: false, temperature: false });
+    assert_eq!(FifoFrame::from_header(0x44), FifoFrame::ConfigError);
+    assert_eq!(FifoFrame::from_header(0x48), FifoFrame::ConfigChange);
+    assert_eq!(FifoFrame::from_header(0x40), FifoFrame::UnknownControl);
+    assert_eq!(FifoFrame::from_header(0x00), FifoFrame::Unknown);
+}
+
+#[test]
+fn sensor_frame_with_sensor_time() {
+    assert_eq!(bmp388::sensor_frame_length(true, true, true), 11);
+    let frame = [0x94, 0x80, 0x2c, 0x80, 0x56, 0x34, 0x12, 0xa0, 0x03, 0x02, 0x01];
+    let fifo_data = bmp388::parse_sensor_frame(&frame, true, true, true);
+    assert_eq!(fifo_data.temperature_raw, Some(0x802c80));
+    assert_eq!(fifo_data.pressure_raw, Some(0x123456));
+    assert_eq!(fifo_data.sensor_time, Some(0x010203));
+    assert!(!fifo_data.config_change);
+}
+
+#[test]
+fn short_sensor_frame_keeps_what_was_read() {
+    // -- the sensor time frame was cut off
+    let frame = [0x84, 0x56, 0x34, 0x12, 0xa0, 0x03];
+    let fifo_data = bmp388::parse_sensor_frame(&frame, true, false, true);
+    assert_eq!(fifo_data.pressure_raw, Some(0x123456));
+    assert_eq!(fifo_data.temperature_raw, None);
+    assert_eq!(fifo_data.sensor_time, None);
+}
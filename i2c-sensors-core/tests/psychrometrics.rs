@@ -0,0 +1,37 @@
+use i2c_sensors_core::psychrometrics;
+
+#[test]
+fn dew_and_frost_point() {
+    assert!((psychrometrics::dew_point(25.0, 60.0) - 16.69).abs() < 0.01);
+    // -- saturated air is at its dew point
+    assert!((psychrometrics::dew_point(10.0, 100.0) - 10.0).abs() < 1e-9);
+    // -- below freezing the frost point lies above the dew point
+    let frost_point = psychrometrics::frost_point(-10.0, 80.0);
+    let dew_point = psychrometrics::dew_point(-10.0, 80.0);
+    assert!(frost_point > dew_point && frost_point < -10.0, "{frost_point} {dew_point}");
+    assert!(psychrometrics::dew_point(25.0, 0.0).is_nan());
+}
+
+#[test]
+fn absolute_humidity_and_mixing_ratio() {
+    assert!((psychrometrics::absolute_humidity(25.0, 60.0) - 13.78).abs() < 0.01);
+    assert!((psychrometrics::mixing_ratio(25.0, 60.0, 101325.0) - 11.86).abs() < 0.01);
+}
+
+#[test]
+fn wet_bulb_after_stull() {
+    // -- example of the paper
+    assert!((psychrometrics::wet_bulb(20.0, 50.0) - 13.7).abs() < 0.05);
+}
+
+#[test]
+fn heat_index_and_humidex() {
+    // -- NWS table, 90 degree fahrenheit at 70 %RH feel like 106
+    let heat_index = psychrometrics::heat_index(32.22, 70.0) * 9.0 / 5.0 + 32.0;
+    assert!((heat_index - 106.0).abs() < 1.0, "{heat_index}");
+    // -- mild air is about what it is
+    assert!((psychrometrics::heat_index(20.0, 50.0) - 20.0).abs() < 1.0);
+    // -- Environment Canada table, 30 degree celsius with a dew point of 15 give 34
+    let humidity = 100.0 * psychrometrics::saturation_vapour_pressure(15.0) / psychrometrics::saturation_vapour_pressure(30.0);
+    assert!((psychrometrics::humidex(30.0, humidity) - 34.0).abs() < 0.5);
+}
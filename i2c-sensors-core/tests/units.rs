@@ -0,0 +1,29 @@
+use i2c_sensors_core::units::{Concentration, Pressure, RelativeHumidity, Resistance, Temperature};
+
+#[test]
+fn temperature_conversions() {
+    let temperature = Temperature::from_celsius(25.0);
+    assert!((temperature.fahrenheit() - 77.0).abs() < 1e-9);
+    assert!((temperature.kelvin() - 298.15).abs() < 1e-9);
+    assert!((Temperature::from_fahrenheit(-40.0).celsius() + 40.0).abs() < 1e-9);
+    assert!((Temperature::from_kelvin(0.0).celsius() + 273.15).abs() < 1e-9);
+}
+
+#[test]
+fn pressure_conversions() {
+    // -- standard atmosphere
+    let pressure = Pressure::from_pascal(101325.0);
+    assert!((pressure.hectopascal() - 1013.25).abs() < 1e-9);
+    assert!((pressure.kilopascal() - 101.325).abs() < 1e-9);
+    assert!((pressure.inches_of_mercury() - 29.921).abs() < 1e-3);
+    assert!((pressure.millimeters_of_mercury() - 760.0).abs() < 1e-3);
+    assert!((Pressure::from_inches_of_mercury(29.92).hectopascal() - 1013.2).abs() < 0.1);
+    assert!(Pressure::from_hectopascal(1000.0) < pressure);
+}
+
+#[test]
+fn humidity_concentration_and_resistance() {
+    assert!((RelativeHumidity::from_percent(45.0).fraction() - 0.45).abs() < 1e-9);
+    assert!((Concentration::from_ppm(400.0).ppb() - 400000.0).abs() < 1e-9);
+    assert!((Resistance::from_kiloohms(12.5).ohms() - 12500.0).abs() < 1e-9);
+}
@@ -1,25 +1,27 @@
-use i2c_linux::I2c;
+use i2c_linux::{Functionality, I2c};
+use i2c_sensors_core::bme280::{CalibData, UncompData, BME280_LEN_HUMIDITY_CALIB_DATA, BME280_LEN_P_T_H_DATA,
+    BME280_LEN_TEMP_PRESS_CALIB_DATA};
 #[allow(unused_imports)]
 use log::{debug, info};
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
-use std::{thread, time};
-
-use crate::i2cio;
-
-const BME280_CHIP_ID: u8 = 0x60;
-const BME280_LEN_TEMP_PRESS_CALIB_DATA: usize = 26;
-const BME280_LEN_HUMIDITY_CALIB_DATA: usize = 7;
-const BME280_LEN_P_T_H_DATA: usize = 8;
-const BME280_STARTUP_DELAY_MS: u64 = 2;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::retry::Recoverable;
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{Pressure, RelativeHumidity, Temperature};
+use crate::spiio::{self, SpiBus, SpiDevice, SpiRegisterAccess};
+use crate::trace::{self, DriverCall};
+
+pub(crate) const BME280_CHIP_ID: u8 = 0x60;
+const BME280_STARTUP_DELAY_MS: u32 = 2;
 const BME280_SOFT_RESET_COMMAND: u8 = 0xb6;
-const BME280_TEMPERATURE_MIN: f64 = -40.0;
-const BME280_TEMPERATURE_MAX: f64 = 85.0;
-const BME280_PRESSURE_MIN: f64 = 30000.0;
-const BME280_PRESSURE_MAX: f64 = 110000.0;
-const BME280_HUMIDITY_MIN: f64 = 0.0;
-const BME280_HUMIDITY_MAX: f64 = 100.0;
+// -- a forced conversion with 16x oversampling of all three takes 113 ms at most
+const BME280_MEASURE_POLL_INTERVAL_MS: u32 = 10;
+const BME280_MEASURE_TIMEOUT_MS: u64 = 500;
 
 // -- masks for ctrl_hum, ctrl_meas, and config registers
 #[allow(dead_code)]
@@ -44,7 +46,7 @@ const BME280_SPI3W_MAS: u8 = 0x01;
 const BME280_STATUS_MEASURING: u8 = 0x08;
 
 // -- registers
-const BME280_REG_PART_ID: u8 = 0xd0;
+pub(crate) const BME280_REG_PART_ID: u8 = 0xd0;
 const BME280_REG_RESET: u8 = 0xe0;
 const BME280_REG_TEMP_PRESS_CALIB_DATA: u8 = 0x88;
 const BME280_REG_HUMIDITY_CALIB_DATA: u8 = 0xe1;
@@ -56,10 +58,9 @@ const BME280_REG_CTRL_MEAS: u8 = 0xf4;
 const BME280_REG_CONFIG: u8 = 0xf5;
 const BME280_REG_DATA: u8 = 0xf7;
 
-// -- shift values
-const BME280_12_BIT_SHIFT: u8 = 12;
-const BME280_8_BIT_SHIFT: u8 = 8;
-const BME280_4_BIT_SHIFT: u8 = 4;
+trace::register_names!(BME280_REG_PART_ID, BME280_REG_RESET, BME280_REG_TEMP_PRESS_CALIB_DATA,
+    BME280_REG_HUMIDITY_CALIB_DATA, BME280_REG_CTRL_HUM, BME280_REG_STATUS, BME280_REG_CTRL_MEAS,
+    BME280_REG_CONFIG, BME280_REG_DATA);
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Bme280DeviceAddress {
@@ -67,17 +68,19 @@ pub enum Bme280DeviceAddress {
     Secondary,    
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Bme280DeviceAddress {
     fn default() -> Self {
         Bme280DeviceAddress::Default
     }
 }
 
+
 impl Bme280DeviceAddress {
     const BME280_DEV_ADDR_DEFAULT: u16 = 0x77;
     const BME280_DEV_ADDR_SECONDARY: u16 = 0x76;    
 
-    fn value(&self) -> u16 {
+    pub(crate) fn value(&self) -> u16 {
         match *self {
             Self::Default => Self::BME280_DEV_ADDR_DEFAULT,
             Self::Secondary => Self::BME280_DEV_ADDR_SECONDARY,            
@@ -85,6 +88,7 @@ impl Bme280DeviceAddress {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme280SensorMode {
     Bme280PowerModeSleep,
     Bme280PowerModeForced,
@@ -116,6 +120,7 @@ impl fmt::Display for Bme280SensorMode {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme280OverSampling {
     NoOversampling,
     Oversampling1x,
@@ -163,6 +168,7 @@ impl fmt::Display for Bme280OverSampling {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme280TimeStandby {
     Ms0_5,
     Ms10,
@@ -215,6 +221,7 @@ impl fmt::Display for Bme280TimeStandby {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme280IrrFilter {
     FilterOff,
     Filter2x,
@@ -254,6 +261,7 @@ impl fmt::Display for Bme280IrrFilter {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme280Spi3w {
     Disable,
     Enable,
@@ -281,146 +289,210 @@ impl fmt::Display for Bme280Spi3w {
     }
 }
 
-#[derive(Debug)]
-struct CalibData
-{
-    // -- Calibration coefficients for the temperature sensor
-    dig_t1: u16,
-    dig_t2: i16,
-    dig_t3: i16,
-    // -- Calibration coefficients for the pressure sensor
-    dig_p1: u16,
-    dig_p2: i16,
-    dig_p3: i16,
-    dig_p4: i16,
-    dig_p5: i16,
-    dig_p6: i16,
-    dig_p7: i16,
-    dig_p8: i16,
-    dig_p9: i16,
-    // -- Calibration coefficients for the humidity sensor
-    dig_h1: u8,
-    dig_h2: i16,
-    dig_h3: u8,
-    dig_h4: i16,
-    dig_h5: i16,
-    dig_h6: i8,
-    // -- Variable to store the intermediate temperature coefficient
-    t_fine_float: f64,
-    t_fine_fixed: i32,
+// -- everything with_bus and the setters configure, written in one go by with_config and
+// -- apply_config; the default samples each quantity once and stays in sleep mode, so measure()
+// -- triggers forced conversions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bme280Config {
+    device_addr: Bme280DeviceAddress,
+    osr_h: Bme280OverSampling,
+    osr_p: Bme280OverSampling,
+    osr_t: Bme280OverSampling,
+    irr_filter: Bme280IrrFilter,
+    // -- inactive time between two conversions in normal mode
+    t_standby: Bme280TimeStandby,
+    // -- 3-wire spi interface, only of use when the sensor is wired for it
+    spi3w: Bme280Spi3w,
+    sensor_mode: Bme280SensorMode,
 }
 
-#[derive(Default)]
-struct UncompData
-{
-    // -- Un-compensated pressure
-    pressure: u32,
-    // -- Un-compensated temperature
-    temperature: u32,
-    // -- Un-compensated humidity
-    humidity: u32,
+impl Default for Bme280Config {
+    fn default() -> Self {
+        Bme280Config {
+            device_addr: Bme280DeviceAddress::Default,
+            osr_h: Bme280OverSampling::Oversampling1x,
+            osr_p: Bme280OverSampling::Oversampling1x,
+            osr_t: Bme280OverSampling::Oversampling1x,
+            irr_filter: Bme280IrrFilter::FilterOff,
+            t_standby: Bme280TimeStandby::Ms1000,
+            spi3w: Bme280Spi3w::Disable,
+            sensor_mode: Bme280SensorMode::Bme280PowerModeSleep,
+        }
+    }
 }
 
+impl Bme280Config {
+
+    pub fn with_device_addr(mut self, device_addr: Bme280DeviceAddress) -> Self {
+        self.device_addr = device_addr;
+        self
+    }
+
+    // -- NoOversampling skips the quantity, its register then reads 0x80000 or 0x8000
+    pub fn with_oversampling(mut self, osr_h: Bme280OverSampling, osr_p: Bme280OverSampling,
+        osr_t: Bme280OverSampling) -> Self {
+        self.osr_h = osr_h;
+        self.osr_p = osr_p;
+        self.osr_t = osr_t;
+        self
+    }
+
+    pub fn with_irr_filter(mut self, irr_filter: Bme280IrrFilter) -> Self {
+        self.irr_filter = irr_filter;
+        self
+    }
+
+    pub fn with_t_standby(mut self, t_standby: Bme280TimeStandby) -> Self {
+        self.t_standby = t_standby;
+        self
+    }
+
+    pub fn with_spi3w(mut self, spi3w: Bme280Spi3w) -> Self {
+        self.spi3w = spi3w;
+        self
+    }
+
+    pub fn with_sensor_mode(mut self, sensor_mode: Bme280SensorMode) -> Self {
+        self.sensor_mode = sensor_mode;
+        self
+    }
 
-pub struct BME280 {
+    pub fn get_device_addr(&self) -> Bme280DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    // -- checked before anything is written, a conversion needs at least one quantity to sample
+    pub fn validate(&self) -> Result<(), std::io::Error> {
+        let skipped = [&self.osr_h, &self.osr_p, &self.osr_t].iter()
+            .all(|osr| **osr == Bme280OverSampling::NoOversampling);
+        if skipped && self.sensor_mode != Bme280SensorMode::Bme280PowerModeSleep {
+            let errmsg = format!("{} with humidity, pressure and temperature skipped", self.sensor_mode);
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        Ok(())
+    }
+}
+
+pub struct BME280<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address.
     device_addr: Bme280DeviceAddress,
     // -- calibration data
     calib_data: CalibData,
     // -- uncompensated data
     uncomp_data: UncompData,
+    // -- adapter features, queried once
+    functionality: Functionality,
 }
 
 impl BME280 {
 
     pub fn new(i2c_bus_path: &Path, device_addr: Bme280DeviceAddress) -> Result<BME280, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr)
+    }
+
+    pub fn new_with_config(i2c_bus_path: &Path, config: &Bme280Config) -> Result<BME280, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_config(i2c, config)
+    }
+
+}
+
+impl BME280<SpiBus> {
+
+    pub fn new_spi(spi_path: &Path) -> Result<Self, std::io::Error> {
+        // -- get the spi device
+        let spi = spiio::get_device(spi_path)?;
+        Self::with_spi(spi)
+    }
+
+}
+
+impl<S: SpiDevice> BME280<SpiBus<S>> {
+
+    // -- the chip select line picks the device, the device address is kept at its default
+    pub fn with_spi(spi: S) -> Result<Self, std::io::Error> {
+        let bus = SpiBus::new(spi, SpiRegisterAccess::Plain);
+        Self::with_bus(bus, Bme280DeviceAddress::Default)
+    }
+
+}
+
+impl<B: I2cBus> BME280<B> {
+
+    pub fn with_bus(mut i2c: B, device_addr: Bme280DeviceAddress) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BME280", "with_bus", device_addr.value(), register_name);
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;  
+        let functionality = i2cio::functionality(&mut i2c)?;
         // -- check if device is available by reading chip id
         let chip_id = i2cio::read_byte(&mut i2c, BME280_REG_PART_ID)?;
         if chip_id != BME280_CHIP_ID {
-            let errmsg = format!("Found unknown chip id '{chip_id:#04x}', expected '{BME280_CHIP_ID:#04x}'");
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, errmsg))
+            return Err(Error::ChipId { expected: BME280_CHIP_ID.into(), found: chip_id.into() }.into())
         }
         debug!("Got chip id: {chip_id:#x}");
         // -- do a soft reset since it's in an unknown state
         Self::soft_reset(&mut i2c)?;
         // -- get calibration data
-        let calib_data = Self::get_calib_data(&mut i2c)?;
+        let calib_data = Self::get_calib_data(&mut i2c, functionality, device_addr.value())?;
         // -- return initialized structure
         Ok(BME280 {
             i2c,
             device_addr,
             calib_data,
             uncomp_data: Default::default(),
+            functionality,
         })
     }
 
+    // -- a config that doesn't validate fails before the bus is touched
+    pub fn with_config(i2c: B, config: &Bme280Config) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BME280", "with_config", config.device_addr.value(), register_name);
+        config.validate()?;
+        let mut bme280 = Self::with_bus(i2c, config.get_device_addr())?;
+        bme280.apply_config(config)?;
+        Ok(bme280)
+    }
+
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("BME280", call, self.device_addr.value(), register_name)
+    }
+
     #[allow(dead_code)]
     pub fn get_device_addr(&self) -> Bme280DeviceAddress {
         self.device_addr.clone()
     }
     
-    fn soft_reset(i2c: &mut I2c<File>) -> Result<(), std::io::Error> {
+    fn soft_reset(i2c: &mut B) -> Result<(), std::io::Error> {
         // -- initiate soft reset
         debug!("Initiating soft reset");
         i2cio::write_byte(i2c, BME280_REG_RESET, BME280_SOFT_RESET_COMMAND)?;
         // -- wait for the device to startup
-        let startup_delay = time::Duration::from_millis(BME280_STARTUP_DELAY_MS);
-        thread::sleep(startup_delay);
+        i2cio::delay(i2c, BME280_STARTUP_DELAY_MS);
         Ok(())
     }
 
-    fn concat_bytes(msb: u8, lsb: u8) -> u16 {
-        ((msb as u16) << 8) | (lsb as u16)
-    }
-
-    fn get_calib_data(i2c: &mut I2c<File>) -> Result<CalibData, std::io::Error> {
+    fn get_calib_data(i2c: &mut B, functionality: Functionality, device_addr: u16) -> Result<CalibData, std::io::Error> {
         // -- get temperature and pressure calibration data
-        let mut reg_data: [u8; BME280_LEN_TEMP_PRESS_CALIB_DATA] = [0; BME280_LEN_TEMP_PRESS_CALIB_DATA];
-        let _bytes_read = i2c.i2c_read_block_data(BME280_REG_TEMP_PRESS_CALIB_DATA, &mut reg_data)?;        
-        let dig_t1 = Self::concat_bytes(reg_data[1], reg_data[0]);
-        let dig_t2 = Self::concat_bytes(reg_data[3], reg_data[2]) as i16;
-        let dig_t3 = Self::concat_bytes(reg_data[5], reg_data[4]) as i16;
-        let dig_p1 = Self::concat_bytes(reg_data[7], reg_data[6]);
-        let dig_p2 = Self::concat_bytes(reg_data[9], reg_data[8]) as i16;
-        let dig_p3 = Self::concat_bytes(reg_data[11], reg_data[10]) as i16;
-        let dig_p4 = Self::concat_bytes(reg_data[13], reg_data[12]) as i16;
-        let dig_p5 = Self::concat_bytes(reg_data[15], reg_data[14]) as i16;
-        let dig_p6 = Self::concat_bytes(reg_data[17], reg_data[16]) as i16;
-        let dig_p7 = Self::concat_bytes(reg_data[19], reg_data[18]) as i16;
-        let dig_p8 = Self::concat_bytes(reg_data[21], reg_data[20]) as i16;
-        let dig_p9 = Self::concat_bytes(reg_data[23], reg_data[22]) as i16;
-        let dig_h1 = reg_data[25];
+        let mut temp_press_data: [u8; BME280_LEN_TEMP_PRESS_CALIB_DATA] = [0; BME280_LEN_TEMP_PRESS_CALIB_DATA];
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, BME280_REG_TEMP_PRESS_CALIB_DATA, &mut temp_press_data)?;
         // -- get humidity calibration data
-        let mut reg_data: [u8; BME280_LEN_HUMIDITY_CALIB_DATA] = [0; BME280_LEN_HUMIDITY_CALIB_DATA];
-        let _bytes_read = i2c.i2c_read_block_data(BME280_REG_HUMIDITY_CALIB_DATA, &mut reg_data)?;
-        let dig_h2 = Self::concat_bytes(reg_data[1], reg_data[0]) as i16;
-        let dig_h3 = reg_data[2];
-        let dig_h4_msb = ((reg_data[3] as i8) as i16) * 16;
-        let dig_h4_lsb = (reg_data[4] & 0x0f) as i16;
-        let dig_h4 = dig_h4_msb | dig_h4_lsb;
-        let dig_h5_msb = ((reg_data[5] as i8) as i16) * 16;
-        let dig_h5_lsb = (reg_data[4] >> 4) as i16;
-        let dig_h5 = dig_h5_msb | dig_h5_lsb;
-        let dig_h6 = reg_data[6] as i8;
+        let mut humidity_data: [u8; BME280_LEN_HUMIDITY_CALIB_DATA] = [0; BME280_LEN_HUMIDITY_CALIB_DATA];
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, BME280_REG_HUMIDITY_CALIB_DATA, &mut humidity_data)?;
         // -- create calibration structure
-        let calib_data = CalibData {
-            dig_t1, dig_t2, dig_t3,
-            dig_p1, dig_p2, dig_p3, dig_p4, dig_p5, dig_p6, dig_p7, dig_p8, dig_p9,
-            dig_h1, dig_h2, dig_h3, dig_h4, dig_h5, dig_h6,
-            t_fine_float: 0.0, t_fine_fixed: 0,
-        };
+        let calib_data = CalibData::from_registers(&temp_press_data, &humidity_data);
         debug!("Got calibration data: {calib_data:#?}");
         Ok(calib_data)
 
     }
 
     pub fn set_osr_humidity(&mut self, osr_h: Bme280OverSampling) -> Result<(), std::io::Error> {
+        let _call = self.call("set_osr_humidity");
         // -- write oversampling to ctr_hum
         let ctrl_hum = osr_h.value();
         debug!("Setting register BME280_REG_CTRL_HUM {BME280_REG_CTRL_HUM:#x} to value {ctrl_hum:#010b}");
@@ -433,6 +505,7 @@ impl BME280 {
     }
 
     pub fn set_osr_pressure_temperature(&mut self, osr_p : Bme280OverSampling, osr_t : Bme280OverSampling) -> Result<(), std::io::Error> {
+        let _call = self.call("set_osr_pressure_temperature");
         // -- read current value of ctrl_meas...
         let ctrl_meas = i2cio::read_byte(&mut self.i2c, BME280_REG_CTRL_MEAS)?;
         // -- ...keep mode bits, set pressure osr bits and temp osr bits...
@@ -443,6 +516,7 @@ impl BME280 {
     }
 
     pub fn set_sensor_config(&mut self, t_standby: Bme280TimeStandby, irr_filter: Bme280IrrFilter, spi3w_en: Bme280Spi3w) -> Result<(), std::io::Error> {
+        let _call = self.call("set_sensor_config");
         // -- set t_sb, filter bits, and spi3w_en bit
         let ctrl_config = (t_standby.value() << BME280_T_STANDBY_POS) | (irr_filter.value() << BME280_IRR_FILTER_POS) | spi3w_en.value();
         debug!("Setting register BME280_REG_CONFIG {BME280_REG_CONFIG:#x} to value {ctrl_config:#010b}");
@@ -451,6 +525,7 @@ impl BME280 {
     }
 
     pub fn set_sensor_mode(&mut self, sensor_mode : Bme280SensorMode) -> Result<(), std::io::Error> {
+        let _call = self.call("set_sensor_mode");
         // -- read current value of ctrl_meas...
         let ctrl_meas = i2cio::read_byte(&mut self.i2c, BME280_REG_CTRL_MEAS)?;
         // -- ...keep pressure osr bits and temp osr bits, set mode bits...
@@ -461,6 +536,7 @@ impl BME280 {
     }
 
     pub fn get_sensor_mode(&mut self) -> Result<Bme280SensorMode, std::io::Error> {
+        let _call = self.call("get_sensor_mode");
         // -- read current value of ctrl_meas...
         let ctrl_meas = i2cio::read_byte(&mut self.i2c, BME280_REG_CTRL_MEAS)?;
         let sensor_mode = match ctrl_meas & BME280_CTRL_MODE_MSK {
@@ -472,6 +548,7 @@ impl BME280 {
     }
 
     pub fn is_measuring(&mut self) -> Result<bool, std::io::Error> {
+        let _call = self.call("is_measuring");
         // -- get temperature and pressure calibration data
         let status = i2cio::read_byte(&mut self.i2c, BME280_REG_STATUS)?;
         let is_measuring = (status & BME280_STATUS_MEASURING) > 0;
@@ -479,118 +556,121 @@ impl BME280 {
     }
 
     pub fn get_sensor_data(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("get_sensor_data");
         // -- get temperature and pressure calibration data
         let mut reg_data: [u8; BME280_LEN_P_T_H_DATA] = [0; BME280_LEN_P_T_H_DATA];
-        let _bytes_read = self.i2c.i2c_read_block_data(BME280_REG_DATA, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), BME280_REG_DATA, &mut reg_data)?;
         debug!("Read {_bytes_read} bytes sensor data");
-
-        /* Store the parsed register values for pressure data */
-        let data_msb: u32 = (reg_data[0] as u32) << BME280_12_BIT_SHIFT;
-        let data_lsb: u32 = (reg_data[1] as u32) << BME280_4_BIT_SHIFT;
-        let data_xlsb: u32 = (reg_data[2] as u32) >> BME280_4_BIT_SHIFT;
-        self.uncomp_data.pressure = data_msb | data_lsb | data_xlsb;
-
-        /* Store the parsed register values for temperature data */
-        let data_msb: u32 = (reg_data[3] as u32) << BME280_12_BIT_SHIFT;
-        let data_lsb: u32 = (reg_data[4] as u32) << BME280_4_BIT_SHIFT;
-        let data_xlsb: u32 = (reg_data[5] as u32) >> BME280_4_BIT_SHIFT;
-        self.uncomp_data.temperature = data_msb | data_lsb | data_xlsb;
-
-        /* Store the parsed register values for humidity data */
-        let data_msb: u32 = (reg_data[6] as u32) << BME280_8_BIT_SHIFT;
-        let data_lsb: u32 = reg_data[7] as u32;
-        self.uncomp_data.humidity = data_msb | data_lsb;
-
+        self.uncomp_data = UncompData::from_registers(&reg_data);
         Ok(())
     }
 
     pub fn compensate_temperature_float(&mut self) -> f64 {
-        let var1: f64 = ((self.uncomp_data.temperature as f64) / 16384.0) - ((self.calib_data.dig_t1 as f64) / 1024.0);
-        let var1: f64 = var1 * (self.calib_data.dig_t2 as f64);
-        let var2: f64 = ((self.uncomp_data.temperature as f64) / 131072.0) - ((self.calib_data.dig_t1 as f64) / 8192.0);
-        let var2: f64 = (var2 * var2) * (self.calib_data.dig_t3 as f64);
-        self.calib_data.t_fine_float = var1 + var2;
-        let temperature = (var1 + var2) / 5120.0;
-        if temperature < BME280_TEMPERATURE_MIN {
-            BME280_TEMPERATURE_MIN
-        } else if temperature > BME280_TEMPERATURE_MAX {
-            BME280_TEMPERATURE_MAX
-        } else {
-            temperature
-        }
+        self.calib_data.compensate_temperature_float(&self.uncomp_data)
     }
 
     pub fn compensate_temperature_fixed(&mut self) -> f64 {
-        let var1a: i32 = ((self.uncomp_data.temperature >> 3) as i32) - ((self.calib_data.dig_t1 as i32) << 1);
-        let var1: i32 = (var1a * (self.calib_data.dig_t2 as i32)) >> 11;
-        let var2a: i32 = ((self.uncomp_data.temperature >> 4) as i32) - (self.calib_data.dig_t1 as i32);
-        let var2: i32 = (((var2a * var2a) >> 12) *  (self.calib_data.dig_t3 as i32)) >> 14;
-        self.calib_data.t_fine_fixed = var1 + var2;
-        let temperature = (self.calib_data.t_fine_fixed * 5 + 128) >> 8;
-        let temperature = temperature as f64 / 100.0;
-        if temperature < BME280_TEMPERATURE_MIN {
-            BME280_TEMPERATURE_MIN
-        } else if temperature > BME280_TEMPERATURE_MAX {
-            BME280_TEMPERATURE_MAX
-        } else {
-            temperature
-        }
+        self.calib_data.compensate_temperature_fixed(&self.uncomp_data)
     }
 
     #[allow(dead_code)]
     pub fn get_t_fine_float(&self) -> f64 {
-        self.calib_data.t_fine_float
+        self.calib_data.get_t_fine_float()
     }
 
     #[allow(dead_code)]
     pub fn get_t_fine_fixed(&self) -> i32 {
-        self.calib_data.t_fine_fixed
-    }
-
-    pub fn compensate_pressure_float(&self) -> f64 {        
-        let var1 = (self.calib_data.t_fine_float / 2.0) - 64000.0;
-        let var2 = var1 * var1 * (self.calib_data.dig_p6 as f64) / 32768.0;
-        let var2 = var2 + var1 * (self.calib_data.dig_p5 as f64) * 2.0;
-        let var2 = (var2 / 4.0) + (self.calib_data.dig_p4 as f64) * 65536.0;
-        let var3 = (self.calib_data.dig_p3 as f64) * var1 * var1 / 524288.0;
-        let var1 = (var3 + (self.calib_data.dig_p2 as f64) * var1) / 524288.0;
-        let var1 = (1.0 + var1 / 32768.0) * (self.calib_data.dig_p1 as f64);
-        if var1 == 0.0 {
-            // -- avoid exception caused by division by zero
-            return BME280_PRESSURE_MIN
-        } 
-        let pressure = 1048576.0 - (self.uncomp_data.pressure as f64);
-        let pressure = (pressure - (var2 / 4096.0)) * 6250.0 / var1;
-        let var1 = (self.calib_data.dig_p9 as f64) * pressure * pressure / 2147483648.0;
-        let var2 = pressure * (self.calib_data.dig_p8 as f64) / 32768.0;
-        let pressure = pressure + (var1 + var2 + (self.calib_data.dig_p7 as f64)) / 16.0;
-        if pressure < BME280_PRESSURE_MIN {
-            BME280_PRESSURE_MIN
-        }
-        else if pressure > BME280_PRESSURE_MAX {
-            BME280_PRESSURE_MAX
-        } else {
-            pressure
-        }
+        self.calib_data.get_t_fine_fixed()
     }
-    
-    pub fn compensate_humidity_float(&self) -> f64 {        
-         let var1 = self.calib_data.t_fine_float - 76800.0;
-        let var2 = (self.calib_data.dig_h4 as f64) * 64.0 + ((self.calib_data.dig_h5 as f64) / 16384.0) * var1;
-        let var3 = (self.uncomp_data.humidity as f64) - var2;
-        let var4 = (self.calib_data.dig_h2 as f64) / 65536.0;
-        let var5 = 1.0 + ((self.calib_data.dig_h3 as f64) / 67108864.0) * var1;
-        let var6 = 1.0 + ((self.calib_data.dig_h6 as f64) / 67108864.0) * var1 * var5;
-        let var6 = var3 * var4 * (var5 * var6);
-        let humidity = var6 * (1.0 - (self.calib_data.dig_h1 as f64) * var6 / 524288.0); 
-        if humidity > BME280_HUMIDITY_MAX {
-            BME280_HUMIDITY_MAX
-        }
-        else if humidity < BME280_HUMIDITY_MIN {
-            BME280_HUMIDITY_MIN
-        } else {
-            humidity
+
+    pub fn compensate_pressure_float(&self) -> f64 {
+        self.calib_data.compensate_pressure_float(&self.uncomp_data)
+    }
+
+    pub fn compensate_humidity_float(&self) -> f64 {
+        self.calib_data.compensate_humidity_float(&self.uncomp_data)
+    }
+
+    // -- the typed readings of the last get_sensor_data, temperature first since pressure
+    // -- and humidity compensation depend on its t_fine
+    pub fn get_temperature(&mut self) -> Temperature {
+        Temperature::from_celsius(self.compensate_temperature_float())
+    }
+
+    pub fn get_pressure(&self) -> Pressure {
+        Pressure::from_pascal(self.compensate_pressure_float())
+    }
+
+    pub fn get_humidity(&self) -> RelativeHumidity {
+        RelativeHumidity::from_percent(self.compensate_humidity_float())
+    }
+
+    // -- writes the whole config in sleep mode, where config writes aren't ignored, the sensor mode
+    // -- goes last together with the pressure and temperature oversampling; e.g. after a reset
+    pub fn apply_config(&mut self, config: &Bme280Config) -> Result<(), std::io::Error> {
+        let _call = self.call("apply_config");
+        config.validate()?;
+        if config.device_addr != self.device_addr {
+            let errmsg = format!("Config is for device address {:#04x}, not {:#04x}",
+                config.device_addr.value(), self.device_addr.value());
+            return Err(Error::InvalidConfig(errmsg).into())
         }
+        self.set_sensor_mode(Bme280SensorMode::Bme280PowerModeSleep)?;
+        self.set_sensor_config(config.t_standby.clone(), config.irr_filter.clone(), config.spi3w.clone())?;
+        // -- ctrl_hum becomes effective with the write to ctrl_meas below
+        let ctrl_hum = config.osr_h.value();
+        debug!("Setting register BME280_REG_CTRL_HUM {BME280_REG_CTRL_HUM:#x} to value {ctrl_hum:#010b}");
+        i2cio::write_byte(&mut self.i2c, BME280_REG_CTRL_HUM, ctrl_hum)?;
+        let ctrl_meas = (config.osr_p.value() << BME280_CTRL_PRESS_POS) | (config.osr_t.value() << BME280_CTRL_TEMP_POS)
+            | config.sensor_mode.value();
+        debug!("Setting register BME280_REG_CTRL_MEAS {BME280_REG_CTRL_MEAS:#x} to value {ctrl_meas:#010b}");
+        i2cio::write_byte(&mut self.i2c, BME280_REG_CTRL_MEAS, ctrl_meas)
+    }
+
+}
+
+// -- a brown-out clears the calibration image, read it again
+impl<B: I2cBus> Recoverable for BME280<B> {
+
+    fn recover(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("recover");
+        i2cio::set_slave(&mut self.i2c, self.device_addr.value())?;
+        self.calib_data = Self::get_calib_data(&mut self.i2c, self.functionality, self.device_addr.value())?;
+        Ok(())
+    }
+}
+
+// -- triggers a forced conversion unless the sensor runs in normal mode, it drops back to sleep
+// -- mode once the conversion is done
+impl<B: I2cBus> Sensor for BME280<B> {
+
+    fn name(&self) -> &'static str {
+        "BME280"
     }
 
-}
\ No newline at end of file
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        if !matches!(self.get_sensor_mode()?, Bme280SensorMode::Bme280PowerModeNormal) {
+            self.set_sensor_mode(Bme280SensorMode::Bme280PowerModeForced)?;
+            let started = i2cio::now(&self.i2c);
+            loop {
+                if matches!(self.get_sensor_mode()?, Bme280SensorMode::Bme280PowerModeSleep) {
+                    break
+                }
+                if i2cio::now(&self.i2c).saturating_sub(started) >= Duration::from_millis(BME280_MEASURE_TIMEOUT_MS) {
+                    return Err(Error::Timeout(format!("BME280 measurement not done after {BME280_MEASURE_TIMEOUT_MS} ms")).into())
+                }
+                i2cio::delay(&mut self.i2c, BME280_MEASURE_POLL_INTERVAL_MS);
+            }
+        }
+        self.get_sensor_data()?;
+        // -- temperature first, pressure and humidity compensation depend on its t_fine
+        let temperature = self.get_temperature();
+        Ok(Measurement {
+            temperature: Some(temperature),
+            humidity: Some(self.get_humidity()),
+            pressure: Some(self.get_pressure()),
+            ..Default::default()
+        })
+    }
+}
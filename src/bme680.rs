@@ -1,20 +1,30 @@
-use i2c_linux::I2c;
+use i2c_linux::{Functionality, I2c};
+use i2c_sensors_core::bme680::{CalibData, BME680_CALIB_DATA1_LEN, BME680_CALIB_DATA2_LEN, BME680_CALIB_DATA3_LEN,
+    BME680_MEAS_RESULT_LEN};
 #[allow(unused_imports)]
 use log::{debug, info};
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
-use std::{thread, time};
+use std::time::Duration;
 
-use crate::i2cio;
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::i2cio_async::{self, AsyncI2cBus};
+use crate::retry::Recoverable;
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{Pressure, RelativeHumidity, Temperature};
+use crate::spiio::{self, SpiBus, SpiDevice, SpiRegisterAccess};
+use crate::trace::{self, AsyncDriverCall, DriverCall};
 
+pub use i2c_sensors_core::bme680::{Bme680GasMeasuringResult, Bme680MeasuringResult, Bme680MeasuringStatus};
 
-const BME680_CHIP_ID: u8 = 0x61;
+
+pub(crate) const BME680_CHIP_ID: u8 = 0x61;
 
 // -- control, status and result registers
 const BME680_REG_MEAS_STATUS_0: u8 = 0x1d;
 const BME680_REG_MEAS_RESULT_BASE: u8 = 0x1f;
-#[allow(dead_code)]
 const BME680_REG_IDAC_HEAT_BASE: u8 = 0x50;
 const BME680_REG_RES_HEAT_BASE: u8 = 0x5a;
 const BME680_REG_GAS_WAIT_BASE: u8 = 0x64;
@@ -25,7 +35,7 @@ const BME680_REG_CTRL_GAS_0: u8 = 0x70;
 const BME680_REG_CTRL_GAS_1: u8 = 0x71;
 const BME680_REG_CTRL_HUM: u8 = 0x72;
 const BME680_REG_CTRL_MEAS: u8 = 0x74;
-const BME680_REG_CHIP_ID: u8 = 0xd0;
+pub(crate) const BME680_REG_CHIP_ID: u8 = 0xd0;
 const BME680_REG_RESET: u8 = 0xe0;
 
 // -- registers for calibration data
@@ -33,109 +43,31 @@ const BME680_REG_CALIB_DATA1_BASE: u8 = 0x8a;
 const BME680_REG_CALIB_DATA2_BASE: u8 = 0xe1;
 const BME680_REG_CALIB_DATA3_BASE: u8 = 0x00;
 
-const BME680_CALIB_DATA1_LEN: usize = 23;
-const BME680_CALIB_DATA2_LEN: usize = 14;
-const BME680_CALIB_DATA3_LEN: usize = 5;
-
-// -- Coefficient T2 LSB position
-const BME680_IDX_T2_LSB: usize = 0;
-// -- Coefficient T2 MSB position
-const BME680_IDX_T2_MSB: usize = 1;
-// -- Coefficient T3 position
-const BME680_IDX_T3: usize = 2;
-// -- Coefficient P1 LSB position
-const BME68X_IDX_P1_LSB: usize = 4;
-// -- Coefficient P1 MSB position
-const BME68X_IDX_P1_MSB: usize = 5;
-// -- Coefficient P2 LSB position
-const BME68X_IDX_P2_LSB: usize = 6;
-// -- Coefficient P2 MSB position
-const BME68X_IDX_P2_MSB: usize = 7;
-// -- Coefficient P3 position
-const BME68X_IDX_P3: usize = 8;
-// -- Coefficient P4 LSB position
-const BME68X_IDX_P4_LSB: usize = 10;
-// -- Coefficient P4 MSB position
-const BME68X_IDX_P4_MSB: usize = 11;
-// -- Coefficient P5 LSB position
-const BME68X_IDX_P5_LSB: usize = 12;
-// -- Coefficient P5 MSB position
-const BME68X_IDX_P5_MSB: usize = 13;
-// -- Coefficient P7 position
-const BME68X_IDX_P7: usize = 14;
-// -- Coefficient P6 position
-const BME68X_IDX_P6: usize = 15;
-// -- Coefficient P8 LSB position
-const BME68X_IDX_P8_LSB: usize = 18;
-// -- Coefficient P8 MSB position
-const BME68X_IDX_P8_MSB: usize = 19;
-// -- Coefficient P9 LSB position
-const BME68X_IDX_P9_LSB: usize = 20;
-// -- Coefficient P9 MSB position
-const BME68X_IDX_P9_MSB: usize = 21;
-// -- Coefficient P10 position
-const BME68X_IDX_P10: usize = 22;
-// -- Coefficient H2 MSB position
-const BME68X_IDX_H2_MSB: usize = 23;
-// -- Coefficient H2 LSB position
-const BME68X_IDX_H2_LSB: usize = 24;
-// -- Coefficient H1 LSB position
-const BME68X_IDX_H1_LSB: usize = 24;
-// -- Coefficient H1 MSB position
-const BME68X_IDX_H1_MSB: usize = 25;
-// -- Coefficient H3 position
-const BME68X_IDX_H3: usize = 26;
-// -- Coefficient H4 position
-const BME68X_IDX_H4: usize = 27;
-// -- Coefficient H5 position
-const BME68X_IDX_H5: usize = 28;
-// -- Coefficient H6 position
-const BME68X_IDX_H6: usize = 29;
-// -- Coefficient H7 position
-const BME68X_IDX_H7: usize = 30;
-// -- Coefficient T1 LSB position
-const BME680_IDX_T1_LSB: usize = 31;
-// -- Coefficient T1 MSB position
-const BME680_IDX_T1_MSB: usize = 32;
-// -- Coefficient GH2 LSB position
-const BME68X_IDX_GH2_LSB: usize = 33;
-// -- Coefficient GH2 MSB position
-const BME68X_IDX_GH2_MSB: usize = 34;
-// -- Coefficient GH1 position
-const BME68X_IDX_GH1: usize = 35;
-// -- Coefficient GH3 position
-const BME68X_IDX_GH3: usize = 36;
-// -- Coefficient res heat value position
-const BME68X_IDX_RES_HEAT_VAL: usize = 37;
-// -- Coefficient res heat range position
-const BME68X_IDX_RES_HEAT_RANGE: usize = 39;
-// -- Coefficient range switching error position
-const BME68X_IDX_RANGE_SW_ERR: usize = 41;
+trace::register_names!(BME680_REG_MEAS_STATUS_0, BME680_REG_MEAS_RESULT_BASE, BME680_REG_IDAC_HEAT_BASE,
+    BME680_REG_RES_HEAT_BASE, BME680_REG_GAS_WAIT_BASE, BME680_REG_GAS_ACD_MSB, BME680_REG_GAS_ACD_LSB_RANGE,
+    BME680_REG_CONFIG, BME680_REG_CTRL_GAS_0, BME680_REG_CTRL_GAS_1, BME680_REG_CTRL_HUM, BME680_REG_CTRL_MEAS,
+    BME680_REG_CHIP_ID, BME680_REG_RESET, BME680_REG_CALIB_DATA1_BASE, BME680_REG_CALIB_DATA2_BASE,
+    BME680_REG_CALIB_DATA3_BASE);
 
 // -- length values for block reads
-const BME680_MEAS_RESULT_LEN: usize = 8;
 #[allow(dead_code)]
 const BME680_IDAC_HEAT_BASE_LEN: usize = 10;
 const BME680_RES_HEAT_BASE_LEN: usize = 10;
 const BME680_GAS_WAIT_BASE_LEN: usize = 10;
 
-// -- mask and bits for meas_status_0 register
-const BME680_MEAS_STATUS_0_NEW_DATA_BIT: u8 = 0x80;
-const BME680_MEAS_STATUS_0_GAS_MEASURING_BIT: u8 = 0x40;
-const BME680_MEAS_STATUS_0_MEASURING_BIT: u8 = 0x20;
-const BME680_MEAS_STATUS_0_GAS_MEAS_INDEX_MASK: u8 = 0x0f;
-
 // -- other values
 const BME680_COMMAND_SOFT_RESET: u8 = 0xb6;
-const BME680_STARTUP_DELAY_MS: u64 = 2;
+const BME680_STARTUP_DELAY_MS: u32 = 2;
+// -- a forced conversion includes the gas wait of the heater, up to 4032 ms
+const BME680_MEASURE_POLL_INTERVAL_MS: u32 = 10;
+const BME680_MEASURE_TIMEOUT_MS: u64 = 5000;
+// -- heater limits of the datasheet, the gas wait register holds up to 63 ms times a factor of 64
+const BME680_HEATER_TEMPERATURE_MIN: f64 = 200.0;
+const BME680_HEATER_TEMPERATURE_MAX: f64 = 400.0;
+const BME680_GAS_WAIT_MS_MAX: u16 = 63;
+const BME680_HEATER_DURATION_MS_MAX: u16 = 4032;
 
 // -- shift, bit, and mask values
-const BME680_12_BIT_SHIFT: u8 = 12;
-const BME680_8_BIT_SHIFT: u8 = 8;
-const BME680_6_BIT_SHIFT: u8 = 6;
-const BME680_4_BIT_SHIFT: u8 = 4;
-const BME680_2_BIT_SHIFT: u8 = 2;
-const BME680_4_BIT_MASK: u8 = 0xf;
 const BME680_CTRL_MEAS_FORCED_MODE_BIT: u8 = 1;
 const BME680_CTRL_MEAS_PRESSURE_SHL: u8 = 2;
 const BME680_CTRL_MEAS_TEMPERATURE_SHL: u8 = 5;
@@ -145,22 +77,7 @@ const BME680_CTRL_GAS_0_HEATER_SHL: u8 = 3;
 const BME680_NB_CONV_NB_CONV_MASK: u8 = 0x0f;
 const BME680_NB_CONV_RUN_GAS_SHL: u8 = 4;
 const BME680_NB_CONV_RUN_GAS_MASK: u8 = 0xef;
-const BME680_BIT_H1_DATA_MASK: u8 = 0x0f;
-const BME680_RHRANGE_MASK: u8 = 0x30;
-const BME68X_RSERROR_MASK: u8 = 0xf0;
 const BME680_GAS_WAIT_MULT_FACT_SHL: u8 = 6;
-const BME680_GAS_VALID_BIT: u8 = 0x20;
-const BME680_HEAT_STAB_BIT: u8 = 0x10;
-
-// -- list of gas ranges and corresponding constants used for the resistance calculation
-const GAS_RANGE_C1: [f64; 16] = [
-    1.0, 1.0, 1.0, 1.0, 1.0, 0.99, 1.0, 0.992,
-    1.0, 1.0, 0.998, 0.995, 1.0, 0.99, 1.0, 1.0
-];
-const GAS_RANGE_C2: [f64; 16] = [
-    8000000.0, 4000000.0, 2000000.0, 1000000.0, 499500.4995, 248262.1648, 125000.0, 63004.03226,
-    31281.28128, 15625.0, 7812.5, 3906.25, 1953.125, 976.5625, 488.28125, 244.140625
-];
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Bme680DeviceAddress {
@@ -168,6 +85,7 @@ pub enum Bme680DeviceAddress {
     Secondary,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Bme680DeviceAddress {
     fn default() -> Self {
         Bme680DeviceAddress::Default
@@ -213,6 +131,7 @@ impl fmt::Display for Bme680SensorPowerMode {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme680OverSampling {
     NoOversampling,
     Oversampling1x,
@@ -257,6 +176,7 @@ impl fmt::Display for Bme680OverSampling {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme680IrrFilter {
     FilterOff,
     Coef3,
@@ -300,6 +220,7 @@ impl fmt::Display for Bme680IrrFilter {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme680HeaterProfile {
     SetPoint0, SetPoint1, SetPoint2, SetPoint3, SetPoint4,
     SetPoint5, SetPoint6, SetPoint7, SetPoint8, SetPoint9,
@@ -333,6 +254,7 @@ impl Bme680HeaterProfile {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bme680GasWaitMultiplicationFactor {
     X1,
     X4,
@@ -352,72 +274,118 @@ impl Bme680GasWaitMultiplicationFactor {
 
 }
 
-#[derive(Debug)]
-pub struct Bme680MeasuringStatus {
-    pub new_data: bool,
-    pub gas_measuring: bool,
-    pub measuring: bool,
-    pub gas_meas_index: u8,
+// -- everything with_bus and the setters configure, written in one go by with_config and
+// -- apply_config; the default samples each quantity once with the gas measurement off
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bme680Config {
+    device_addr: Bme680DeviceAddress,
+    humidity_osr: Bme680OverSampling,
+    pressure_osr: Bme680OverSampling,
+    temperature_osr: Bme680OverSampling,
+    irr_filter: Bme680IrrFilter,
+    // -- set point, target temperature in degree celsius and duration in ms, None for no gas measurement
+    heater: Option<(Bme680HeaterProfile, f64, u16)>,
+    // -- the heater resistance for the target temperature depends on it, in degree celsius
+    ambient_temperature: f64,
 }
 
-#[derive(Debug)]
-pub struct Bme680MeasuringResult {
-    pub pressure_raw: u32,
-    pub temperature_raw: u32,
-    pub humidity_raw: u16,
+impl Default for Bme680Config {
+    fn default() -> Self {
+        Bme680Config {
+            device_addr: Bme680DeviceAddress::Default,
+            humidity_osr: Bme680OverSampling::Oversampling1x,
+            pressure_osr: Bme680OverSampling::Oversampling1x,
+            temperature_osr: Bme680OverSampling::Oversampling1x,
+            irr_filter: Bme680IrrFilter::FilterOff,
+            heater: None,
+            ambient_temperature: 25.0,
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct Bme680GasMeasuringResult {    
-    pub gas_res: f64,
-    pub gas_valid: bool,
-    pub heat_stab: bool,
+impl Bme680Config {
+
+    pub fn with_device_addr(mut self, device_addr: Bme680DeviceAddress) -> Self {
+        self.device_addr = device_addr;
+        self
+    }
+
+    pub fn with_oversampling(mut self, humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling) -> Self {
+        self.humidity_osr = humidity_osr;
+        self.pressure_osr = pressure_osr;
+        self.temperature_osr = temperature_osr;
+        self
+    }
+
+    pub fn with_irr_filter(mut self, irr_filter: Bme680IrrFilter) -> Self {
+        self.irr_filter = irr_filter;
+        self
+    }
+
+    // -- turns the gas measurement on, the heater of the set point is driven to the target
+    // -- temperature for the duration, e.g. SetPoint0, 320 degree celsius for 150 ms
+    pub fn with_heater(mut self, heater_profile: Bme680HeaterProfile, target_temperature: f64, duration_ms: u16) -> Self {
+        self.heater = Some((heater_profile, target_temperature, duration_ms));
+        self
+    }
+
+    pub fn with_ambient_temperature(mut self, ambient_temperature: f64) -> Self {
+        self.ambient_temperature = ambient_temperature;
+        self
+    }
+
+    pub fn get_device_addr(&self) -> Bme680DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    // -- checked before anything is written
+    pub fn validate(&self) -> Result<(), std::io::Error> {
+        let skipped = [&self.humidity_osr, &self.pressure_osr, &self.temperature_osr].iter()
+            .all(|osr| **osr == Bme680OverSampling::NoOversampling);
+        if skipped && self.heater.is_none() {
+            let errmsg = "Humidity, pressure, temperature and gas measurement skipped".to_string();
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        if let Some((_, target_temperature, duration_ms)) = &self.heater {
+            if !(BME680_HEATER_TEMPERATURE_MIN..=BME680_HEATER_TEMPERATURE_MAX).contains(target_temperature) {
+                let errmsg = format!("Heater temperature {target_temperature} outside of \
+                    {BME680_HEATER_TEMPERATURE_MIN} to {BME680_HEATER_TEMPERATURE_MAX} degree celsius");
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+            if !(1..=BME680_HEATER_DURATION_MS_MAX).contains(duration_ms) {
+                let errmsg = format!("Heater duration {duration_ms} outside of 1 to {BME680_HEATER_DURATION_MS_MAX} ms");
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-struct CalibData
-{
-    // -- calibration coefficients for temperature
-    par_t1: f64,
-    par_t2: f64,
-    par_t3: f64,
-    // -- calibration coefficients for pressure
-    par_p1: f64,
-    par_p2: f64,
-    par_p3: f64,
-    par_p4: f64,
-    par_p5: f64,
-    par_p6: f64,
-    par_p7: f64,
-    par_p8: f64,
-    par_p9: f64,
-    par_p10: f64,
-    // -- calibration coefficients for gas
-    par_gh1: f64,
-    par_gh2: f64,
-    par_gh3: f64,
-    res_heat_range: f64,
-    res_heat_val: f64,
-    range_sw_err: f64,
-    // -- calibration coefficients for humidity
-    par_h1: f64,
-    par_h2: f64,
-    par_h3: f64,
-    par_h4: f64,
-    par_h5: f64,
-    par_h6: f64,
-    par_h7: f64,
+// -- the gas wait register value for the duration, rounded up to the step of the factor
+fn gas_wait(duration_ms: u16) -> (u8, Bme680GasWaitMultiplicationFactor) {
+    let mut milli_secs = duration_ms;
+    for mult_fact in [Bme680GasWaitMultiplicationFactor::X1, Bme680GasWaitMultiplicationFactor::X4,
+        Bme680GasWaitMultiplicationFactor::X16] {
+        if milli_secs <= BME680_GAS_WAIT_MS_MAX {
+            return (milli_secs as u8, mult_fact)
+        }
+        milli_secs = milli_secs.div_ceil(4);
+    }
+    (milli_secs.min(BME680_GAS_WAIT_MS_MAX) as u8, Bme680GasWaitMultiplicationFactor::X64)
 }
 
-pub struct BME680 {
+pub struct BME680<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address.
     device_addr: Bme680DeviceAddress,
     // -- chip id
     chip_id: u8,
     // -- calibration params
     calib_data: CalibData,
+    // -- adapter features, queried once
+    functionality: Functionality,
 }
 
 impl BME680 {
@@ -426,38 +394,99 @@ impl BME680 {
         humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
         temperature_osr: Bme680OverSampling, irr_filter: Bme680IrrFilter) -> Result<BME680, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr, humidity_osr, pressure_osr, temperature_osr, irr_filter)
+    }
+
+    pub fn new_with_config(i2c_bus_path: &Path, config: &Bme680Config) -> Result<BME680, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_config(i2c, config)
+    }
+
+}
+
+impl BME680<SpiBus> {
+
+    pub fn new_spi(spi_path: &Path,
+        humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling, irr_filter: Bme680IrrFilter) -> Result<Self, std::io::Error> {
+        // -- get the spi device
+        let spi = spiio::get_device(spi_path)?;
+        Self::with_spi(spi, humidity_osr, pressure_osr, temperature_osr, irr_filter)
+    }
+
+}
+
+impl<S: SpiDevice> BME680<SpiBus<S>> {
+
+    // -- the chip select line picks the device, the device address is kept at its default;
+    // -- the spi bus switches the memory page for the control and data registers
+    pub fn with_spi(spi: S,
+        humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling, irr_filter: Bme680IrrFilter) -> Result<Self, std::io::Error> {
+        let bus = SpiBus::new(spi, SpiRegisterAccess::Paged);
+        Self::with_bus(bus, Bme680DeviceAddress::Default, humidity_osr, pressure_osr, temperature_osr, irr_filter)
+    }
+
+}
+
+impl<B: I2cBus> BME680<B> {
+
+    pub fn with_bus(i2c: B, device_addr: Bme680DeviceAddress,
+        humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling, irr_filter: Bme680IrrFilter) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BME680", "with_bus", device_addr.value(), register_name);
+        let mut bme680 = Self::init(i2c, device_addr)?;
+        // -- set oversampling rates
+        bme680.set_humidity_osr(humidity_osr)?;
+        bme680.set_pressure_and_temperature_osr(pressure_osr, temperature_osr)?;
+        // -- set filter
+        bme680.set_irr_filter(irr_filter)?;
+        // -- return initialized structure
+        Ok(bme680)
+    }
+
+    // -- a config that doesn't validate fails before the bus is touched
+    pub fn with_config(i2c: B, config: &Bme680Config) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BME680", "with_config", config.device_addr.value(), register_name);
+        config.validate()?;
+        let mut bme680 = Self::init(i2c, config.get_device_addr())?;
+        bme680.apply_config(config)?;
+        Ok(bme680)
+    }
+
+    // -- chip id check, calibration data and soft reset, the device is left with its reset values
+    fn init(mut i2c: B, device_addr: Bme680DeviceAddress) -> Result<Self, std::io::Error> {
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;
+        let functionality = i2cio::functionality(&mut i2c)?;
         // -- check if device is available by reading chip id
         let chip_id = i2cio::read_byte(&mut i2c, BME680_REG_CHIP_ID)?;
         if chip_id != BME680_CHIP_ID {
-            let errmsg = format!("Found unknown chip id '{chip_id:#04x}', expected '{BME680_CHIP_ID:#04x}'");
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, errmsg))
+            return Err(Error::ChipId { expected: BME680_CHIP_ID.into(), found: chip_id.into() }.into())
         }
         debug!("Got chip id: {chip_id:#x}");
-        let calib_data = Self::get_calib_data(&mut i2c)?;
+        let calib_data = Self::get_calib_data(&mut i2c, functionality, device_addr.value())?;
         debug!("Got calibration data: {calib_data:#?}");
         let mut bme680 = BME680 {
             i2c,
             device_addr,
             chip_id,
             calib_data,
+            functionality,
             //uncomp_data: Default::default(),
         };
         // -- do a soft reset since it's in an unknown state
         bme680.soft_reset()?;
-        // -- set oversampling rates
-        bme680.set_humidity_osr(humidity_osr)?;
-        bme680.set_pressure_and_temperature_osr(pressure_osr, temperature_osr)?;
-        // -- set filter
-        bme680.set_irr_filter(irr_filter)?;
-        // -- get calibration data
-        //let calib_data = Self::get_calib_data(&mut i2c)?;
-        // -- return initialized structure
         Ok(bme680)
     }
 
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("BME680", call, self.device_addr.value(), register_name)
+    }
+
     #[allow(dead_code)]
     pub fn get_device_addr(&self) -> Bme680DeviceAddress {
         self.device_addr.clone()
@@ -465,155 +494,69 @@ impl BME680 {
 
     #[allow(dead_code)]
     pub fn get_chip_id(&self) -> u8 {
-        self.chip_id.clone()
+        self.chip_id
     }
 
     pub fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("soft_reset");
         const REG: u8 = BME680_REG_RESET;
         // -- initiate soft reset
         debug!("Initiating soft reset");
         i2cio::write_byte(&mut self.i2c, REG, BME680_COMMAND_SOFT_RESET)?;
         // -- wait for the device to startup
-        let startup_delay = time::Duration::from_millis(BME680_STARTUP_DELAY_MS);
-        thread::sleep(startup_delay);
+        i2cio::delay(&mut self.i2c, BME680_STARTUP_DELAY_MS);
         Ok(())
     }
 
-    fn concat_bytes(msb: u8, lsb: u8) -> u16 {
-        ((msb as u16) << 8) | (lsb as u16)
-    }
-
-    fn get_calib_data(i2c: &mut I2c<File>) -> Result<CalibData, std::io::Error> {
+    fn get_calib_data(i2c: &mut B, functionality: Functionality, device_addr: u16) -> Result<CalibData, std::io::Error> {
         // -- read calibration data block 1
         const REG_1: u8 = BME680_REG_CALIB_DATA1_BASE;
         const LEN_1: usize = BME680_CALIB_DATA1_LEN;
         let mut reg_data_1: [u8; LEN_1] = [0; LEN_1];
-        let _bytes_read = i2c.i2c_read_block_data(REG_1, &mut reg_data_1)?;
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, REG_1, &mut reg_data_1)?;
         debug!("Read {_bytes_read} bytes of calibration data, block 1");
         // -- read calibration data block 2
         const REG_2: u8 = BME680_REG_CALIB_DATA2_BASE;
         const LEN_2: usize = BME680_CALIB_DATA2_LEN;
         let mut reg_data_2: [u8; LEN_2] = [0; LEN_2];
-        let _bytes_read = i2c.i2c_read_block_data(REG_2, &mut reg_data_2)?;
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, REG_2, &mut reg_data_2)?;
         debug!("Read {_bytes_read} bytes of calibration data, block 2");
         // -- read calibration data block 3
         const REG_3: u8 = BME680_REG_CALIB_DATA3_BASE;
         const LEN_3: usize = BME680_CALIB_DATA3_LEN;
         let mut reg_data_3: [u8; LEN_3] = [0; LEN_3];
-        let _bytes_read = i2c.i2c_read_block_data(REG_3, &mut reg_data_3)?;
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, REG_3, &mut reg_data_3)?;
         debug!("Read {_bytes_read} bytes of calibration data, block 3");
         // -- concat arrays
         let coeff_array = [reg_data_1.as_slice(), reg_data_2.as_slice(), reg_data_3.as_slice()].concat();
-
-        // -- get calibration data for temperatire
-        let par_t1 = Self::concat_bytes(coeff_array[BME680_IDX_T1_MSB], coeff_array[BME680_IDX_T1_LSB]) as f64;
-        let par_t2 = Self::concat_bytes(coeff_array[BME680_IDX_T2_MSB], coeff_array[BME680_IDX_T2_LSB]) as f64;
-        let par_t3 = coeff_array[BME680_IDX_T3] as f64;
-        debug!("Read temperatire calibration data {par_t1} {par_t2} {par_t3} ");
-
-        // -- get calibration data for pressure
-        let par_p1 = Self::concat_bytes(coeff_array[BME68X_IDX_P1_MSB], coeff_array[BME68X_IDX_P1_LSB]) as f64;
-        let par_p2 = (Self::concat_bytes(coeff_array[BME68X_IDX_P2_MSB], coeff_array[BME68X_IDX_P2_LSB]) as i16) as f64;
-        let par_p3 = (coeff_array[BME68X_IDX_P3] as i8) as f64;
-        let par_p4 = (Self::concat_bytes(coeff_array[BME68X_IDX_P4_MSB], coeff_array[BME68X_IDX_P4_LSB]) as i16) as f64;
-        let par_p5 = (Self::concat_bytes(coeff_array[BME68X_IDX_P5_MSB], coeff_array[BME68X_IDX_P5_LSB]) as i16) as f64;
-        let par_p6 = (coeff_array[BME68X_IDX_P6] as i8) as f64;
-        let par_p7 = (coeff_array[BME68X_IDX_P7] as i8) as f64;
-        let par_p8 = (Self::concat_bytes(coeff_array[BME68X_IDX_P8_MSB], coeff_array[BME68X_IDX_P8_LSB]) as i16) as f64;
-        let par_p9 = (Self::concat_bytes(coeff_array[BME68X_IDX_P9_MSB], coeff_array[BME68X_IDX_P9_LSB]) as i16) as f64;
-        let par_p10 = coeff_array[BME68X_IDX_P10] as f64;
-        debug!("Read pressure calibration data {par_p1} {par_p2} {par_p3} {par_p4} {par_p5} {par_p6} {par_p7} {par_p8} {par_p9} {par_p10}");
-
-        // -- get calibration data for humidity
-        let par_h1 = (((coeff_array[BME68X_IDX_H1_MSB] as u16) << 4) |
-                        ((coeff_array[BME68X_IDX_H1_LSB] & BME680_BIT_H1_DATA_MASK) as u16)) as f64;
-        let par_h2 = (((coeff_array[BME68X_IDX_H2_MSB] as u16) << 4) |
-                        ((coeff_array[BME68X_IDX_H2_LSB] >> 4) as u16)) as f64;
-        let par_h3 = (coeff_array[BME68X_IDX_H3] as i8) as f64;
-        let par_h4 = (coeff_array[BME68X_IDX_H4] as i8) as f64;
-        let par_h5 = (coeff_array[BME68X_IDX_H5] as i8) as f64;
-        let par_h6 = coeff_array[BME68X_IDX_H6] as f64;
-        let par_h7 = (coeff_array[BME68X_IDX_H7] as i8) as f64;
-        debug!("Read humidity calibration data {par_h1} {par_h2} {par_h3} {par_h4} {par_h5} {par_h6} {par_h7}");
-
-
-        // -- get calibration data related to gas heater
-        let par_gh1 = (coeff_array[BME68X_IDX_GH1] as i8) as f64;
-        let par_gh2 = (Self::concat_bytes(coeff_array[BME68X_IDX_GH2_MSB], coeff_array[BME68X_IDX_GH2_LSB]) as i16) as f64;
-        let par_gh3 = (coeff_array[BME68X_IDX_GH3] as i8) as f64;
-        let res_heat_range = ((coeff_array[BME68X_IDX_RES_HEAT_RANGE] & BME680_RHRANGE_MASK) / 16) as f64;
-        let res_heat_val = (coeff_array[BME68X_IDX_RES_HEAT_VAL] as i8) as f64;
-        let range_sw_err = (((coeff_array[BME68X_IDX_RANGE_SW_ERR] & BME68X_RSERROR_MASK) as i8) / 16) as f64;
-        debug!("Read gas heater calibration data {par_gh1} {par_gh2} {par_gh3} {res_heat_range} {res_heat_val} {range_sw_err}");
-
-        // -- return structured calibration data
-        Ok(CalibData {
-            par_t1, par_t2, par_t3,
-            par_p1, par_p2, par_p3, par_p4, par_p5,
-            par_p6, par_p7, par_p8, par_p9, par_p10,
-            par_gh1, par_gh2, par_gh3, res_heat_range,
-            res_heat_val, range_sw_err,
-            par_h1, par_h2, par_h3, par_h4, par_h5,
-            par_h6, par_h7,
-        })
+        Ok(CalibData::from_coefficients(&coeff_array))
     }
 
     pub fn get_meas_status(&mut self) -> Result<Bme680MeasuringStatus, std::io::Error> {
+        let _call = self.call("get_meas_status");
         const REG: u8 = BME680_REG_MEAS_STATUS_0;
         // -- read current value
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
-        // -- extract status values
-        let new_data = (reg_val & BME680_MEAS_STATUS_0_NEW_DATA_BIT) > 0;
-        let gas_measuring = (reg_val & BME680_MEAS_STATUS_0_GAS_MEASURING_BIT) > 0;
-        let measuring = (reg_val & BME680_MEAS_STATUS_0_MEASURING_BIT) > 0;
-        let gas_meas_index = reg_val & BME680_MEAS_STATUS_0_GAS_MEAS_INDEX_MASK;
-        Ok(Bme680MeasuringStatus {
-            new_data, gas_measuring, measuring, gas_meas_index,
-        })
+        Ok(Bme680MeasuringStatus::from_register(reg_val))
     }
 
     pub fn get_meas_result(&mut self) -> Result<Bme680MeasuringResult, std::io::Error> {
+        let _call = self.call("get_meas_result");
         const REG: u8 = BME680_REG_MEAS_RESULT_BASE;
         const LEN: usize = BME680_MEAS_RESULT_LEN;
         let mut reg_data: [u8; LEN] = [0; LEN];
         // -- read current value and mask out run gas bit
-        let _bytes_read = self.i2c.i2c_read_block_data(REG, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), REG, &mut reg_data)?;
         debug!("Read {_bytes_read} bytes of resulting data after measuring");
-        // -- store register values for pressure data
-        let data_msb = (reg_data[0] as u32) << BME680_12_BIT_SHIFT;
-        let data_lsb = (reg_data[1] as u32) << BME680_4_BIT_SHIFT;
-        let data_xlsb = (reg_data[2] as u32) >> BME680_4_BIT_SHIFT;
-        let pressure_raw = data_msb | data_lsb | data_xlsb;
-        // -- store register values for temperature data
-        let data_msb = (reg_data[3] as u32) << BME680_12_BIT_SHIFT;
-        let data_lsb = (reg_data[4] as u32) << BME680_4_BIT_SHIFT;
-        let data_xlsb = (reg_data[5] as u32) >> BME680_4_BIT_SHIFT;
-        let temperature_raw = data_msb | data_lsb | data_xlsb;
-        // -- store register values for humidity data
-        let data_msb = (reg_data[6] as u16) << BME680_8_BIT_SHIFT;
-        let data_lsb = reg_data[7] as u16;
-        let humidity_raw = data_msb | data_lsb;
-
-        Ok(Bme680MeasuringResult {
-            pressure_raw, temperature_raw, humidity_raw
-        })
+        Ok(Bme680MeasuringResult::from_registers(&reg_data))
     }
 
     pub fn get_gas_meas_result(&mut self) -> Result<Bme680GasMeasuringResult, std::io::Error> {
+        let _call = self.call("get_gas_meas_result");
         // -- read current value
         let data_msb = i2cio::read_byte(&mut self.i2c, BME680_REG_GAS_ACD_MSB)?;
         let data_lsb = i2cio::read_byte(&mut self.i2c, BME680_REG_GAS_ACD_LSB_RANGE)?;
-        let gas_adc = ((data_msb as u16) << BME680_2_BIT_SHIFT) | ((data_lsb as u16) >> BME680_6_BIT_SHIFT);
-        let gas_range = (data_lsb & BME680_4_BIT_MASK) as usize;
-        let gas_valid = (data_lsb & BME680_GAS_VALID_BIT) > 0;
-        let heat_stab = (data_lsb & BME680_HEAT_STAB_BIT) > 0;
-        let range_switching_error = self.calib_data.range_sw_err;
-        let var1 = (1340.0 + (5.0 * range_switching_error)) * GAS_RANGE_C1[gas_range];
-        let gas_res = var1 * GAS_RANGE_C2[gas_range] / (gas_adc as f64 - 512.0 + var1);
-        let result = Bme680GasMeasuringResult {
-            gas_res, gas_valid, heat_stab,
-        };
-        Ok(result)
+        Ok(self.calib_data.gas_result(data_msb, data_lsb))
         // const LOOKUP_K1_RANGE: [f64; 16] = [
         //     0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, -0.8, 0.0, 0.0, -0.2, -0.5, 0.0, -1.0, 0.0, 0.0,
         // ];
@@ -628,64 +571,22 @@ impl BME680 {
         // Ok(gas_res)
     }
 
-    pub fn get_temperature(&self, temperature_raw: u32) -> (f64, f64) {
-        let temperature_raw = temperature_raw as f64;
-        let par_t1 = self.calib_data.par_t1;
-        let par_t2 = self.calib_data.par_t2;
-        let par_t3 = self.calib_data.par_t3;
-        let var1 = ((temperature_raw / 16384.0) - (par_t1 / 1024.0)) * par_t2;
-        let var2 = (((temperature_raw / 131072.0) - (par_t1 / 8192.0)) * ((temperature_raw / 131072.0) - (par_t1 / 8192.0))) * (par_t3 * 16.0);
-        let t_fine = var1 + var2;
-        let temp_comp = t_fine / 5120.0;
-        (temp_comp, t_fine)
-    }
-
-    pub fn get_pressure(&self, pressure_raw: u32, t_fine: f64) -> f64 {
-        let pressure_raw = pressure_raw as f64;
-        let par_p1 = self.calib_data.par_p1;
-        let par_p2 = self.calib_data.par_p2;
-        let par_p3 = self.calib_data.par_p3;
-        let par_p4 = self.calib_data.par_p4;
-        let par_p5 = self.calib_data.par_p5;
-        let par_p6 = self.calib_data.par_p6;
-        let par_p7 = self.calib_data.par_p7;
-        let par_p8 = self.calib_data.par_p8;
-        let par_p9 = self.calib_data.par_p9;
-        let par_p10 = self.calib_data.par_p10;
-        let var1 = (t_fine / 2.0) - 64000.0;
-        let var2 = var1 * var1 * (par_p6 / 131072.0);
-        let var2 = var2 + (var1 * par_p5 * 2.0);
-        let var2 = (var2 / 4.0) + (par_p4 * 65536.0);
-        let var1 = (((par_p3 * var1 * var1) / 16384.0) + (par_p2 * var1)) / 524288.0;
-        let var1 = (1.0 + (var1 / 32768.0)) * par_p1;
-        let press_comp = 1048576.0 - pressure_raw;
-        let press_comp = ((press_comp - (var2 / 4096.0)) * 6250.0) / var1;
-        let var1 = (par_p9 * press_comp * press_comp) / 2147483648.0;
-        let var2 = press_comp * (par_p8 / 32768.0);
-        let var3 = (press_comp / 256.0) * (press_comp / 256.0) * (press_comp / 256.0) * (par_p10 / 131072.0);
-        let press_comp = press_comp + (var1 + var2 + var3 + (par_p7 * 128.0)) / 16.0;
-        press_comp
-    }
-
-    pub fn get_humidity(&self, humidity_raw: u16, temperature: f64) -> f64 {
-        let humidity_raw = humidity_raw as f64;
-        let par_h1 = self.calib_data.par_h1;
-        let par_h2 = self.calib_data.par_h2;
-        let par_h3 = self.calib_data.par_h3;
-        let par_h4 = self.calib_data.par_h4;
-        let par_h5 = self.calib_data.par_h5;
-        let par_h6 = self.calib_data.par_h6;
-        let par_h7 = self.calib_data.par_h7;
-
-        let var1 = humidity_raw - ((par_h1 * 16.0) + ((par_h3 / 2.0) * temperature));
-        let var2 = var1 * ((par_h2 / 262144.0) * (1.0 + ((par_h4 / 16384.0) * temperature) + ((par_h5 / 1048576.0) * temperature * temperature)));
-        let var3 = par_h6 / 16384.0;
-        let var4 = par_h7 / 2097152.0;
-        let hum_comp = var2 + ((var3 + (var4 * temperature)) * var2 * var2);
-        hum_comp
+    // -- the temperature plus its t_fine, which the pressure compensation needs
+    pub fn get_temperature(&self, temperature_raw: u32) -> (Temperature, f64) {
+        let (temperature, t_fine) = self.calib_data.temperature(temperature_raw);
+        (Temperature::from_celsius(temperature), t_fine)
+    }
+
+    pub fn get_pressure(&self, pressure_raw: u32, t_fine: f64) -> Pressure {
+        Pressure::from_pascal(self.calib_data.pressure(pressure_raw, t_fine))
+    }
+
+    pub fn get_humidity(&self, humidity_raw: u16, temperature: Temperature) -> RelativeHumidity {
+        RelativeHumidity::from_percent(self.calib_data.humidity(humidity_raw, temperature.celsius()))
     }
 
     pub fn set_forced_mode(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("set_forced_mode");
         const REG: u8 = BME680_REG_CTRL_MEAS;
         // -- read current value, set forced mode bit
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
@@ -696,6 +597,7 @@ impl BME680 {
     }
 
     pub fn set_humidity_osr(&mut self, humidity_osr: Bme680OverSampling) -> Result<(), std::io::Error> {
+        let _call = self.call("set_humidity_osr");
         const REG: u8 = BME680_REG_CTRL_HUM;
         // -- set oversampling rate for humidity
         debug!("Setting humidity oversampling rate");
@@ -704,6 +606,7 @@ impl BME680 {
 
     pub fn set_pressure_and_temperature_osr(&mut self, pressure_osr: Bme680OverSampling,
         temperature_osr: Bme680OverSampling) -> Result<(), std::io::Error> {
+        let _call = self.call("set_pressure_and_temperature_osr");
         const REG: u8 = BME680_REG_CTRL_MEAS;
         // -- put bits for OSR in place, power mode implicit set to sleep (bit 0 and 1)
         let reg_val = temperature_osr.value() << BME680_CTRL_MEAS_TEMPERATURE_SHL
@@ -714,6 +617,7 @@ impl BME680 {
     }
 
     pub fn set_irr_filter(&mut self, irr_filter: Bme680IrrFilter) -> Result<(), std::io::Error> {
+        let _call = self.call("set_irr_filter");
         const REG: u8 = BME680_REG_CONFIG;
         let reg_val:u8 = irr_filter.value() <<  BME680_CONTROL_IIR_FILTER_SHL;
         // -- set infinite impulse response (IIR) filter
@@ -722,6 +626,7 @@ impl BME680 {
     }
 
     pub fn enable_heater(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("enable_heater");
         const REG: u8 = BME680_REG_CTRL_GAS_0;
         // -- read current value, set heater bit
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
@@ -732,6 +637,7 @@ impl BME680 {
     }
 
     pub fn disable_heater(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("disable_heater");
         const REG: u8 = BME680_REG_CTRL_GAS_0;
         // -- read current value, mask out heater bit
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
@@ -742,10 +648,11 @@ impl BME680 {
     }
 
     pub fn set_heater_profile(&mut self, heater_profile: Bme680HeaterProfile) -> Result<(), std::io::Error> {
+        let _call = self.call("set_heater_profile");
         const REG: u8 = BME680_REG_CTRL_GAS_1;
-        // -- read current value, mask out nb conv bits and set requested bits
+        // -- read current value, clear nb conv bits and set requested bits
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
-        let reg_val = reg_val & BME680_NB_CONV_NB_CONV_MASK;
+        let reg_val = reg_val & !BME680_NB_CONV_NB_CONV_MASK;
         let reg_val = reg_val | heater_profile.value();
         // -- write back register value
         debug!("Setting heater profile");
@@ -753,6 +660,7 @@ impl BME680 {
     }
 
     pub fn enable_run_gas(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("enable_run_gas");
         const REG: u8 = BME680_REG_CTRL_GAS_1;
         // -- read current value and set run gas bit
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
@@ -763,6 +671,7 @@ impl BME680 {
     }
 
     pub fn disable_run_gas(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("disable_run_gas");
         const REG: u8 = BME680_REG_CTRL_GAS_1;
         // -- read current value and mask out run gas bit
         let reg_val = i2cio::read_byte(&mut self.i2c, REG)?;
@@ -774,14 +683,7 @@ impl BME680 {
 
 
     pub fn calc_res_heat(&self, amb_temp: f64, target_temp: f64) -> u8 {
-        let var1 = ((self.calib_data.par_gh1 as f64) / 16.0) + 49.0;
-        let var2 = (((self.calib_data.par_gh2 as f64) / 32768.0) * 0.0005) + 0.00235;
-        let var3 = (self.calib_data.par_gh3 as f64)  / 1024.0;
-        let var4 = var1 * (1.0 + (var2 * target_temp));
-        let var5 = var4 + (var3 * amb_temp);
-        let res_heat = (3.4 * ((var5 * (4.0 / (4.0 + self.calib_data.res_heat_range)) * (1.0/(1.0 +
-        (self.calib_data.res_heat_val * 0.002)))) - 25.0)) as u8;
-        res_heat
+        self.calib_data.res_heat(amb_temp, target_temp)
     }
 
     // pub fn get_ldac_heat(&mut self) -> Result<Vec<u8>, std::io::Error> {
@@ -793,16 +695,17 @@ impl BME680 {
     // }
 
     pub fn get_res_heat(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        let _call = self.call("get_res_heat");
         const REG: u8 = BME680_REG_RES_HEAT_BASE;
         let mut reg_data: [u8; BME680_RES_HEAT_BASE_LEN] = [0; BME680_RES_HEAT_BASE_LEN];
         // -- read current value and mask out run gas bit
-        let _bytes_read = self.i2c.i2c_read_block_data(REG, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), REG, &mut reg_data)?;
         Ok(Vec::from(reg_data))
     }
 
     fn set_res_heat(&mut self, reg: u8, res_heat: u8) -> Result<(), std::io::Error> {
         if reg < BME680_REG_RES_HEAT_BASE || reg > BME680_REG_RES_HEAT_BASE + (BME680_RES_HEAT_BASE_LEN as u8) {
-            return Err(std::io::Error::other(format!("Invalid register for gas wait: {reg:#04x}")))
+            return Err(Error::InvalidConfig(format!("Invalid register for gas wait: {reg:#04x}")).into())
         }
         // -- write back register value
         debug!("Setting heater resistance {} to {res_heat:#010b}", reg - BME680_REG_RES_HEAT_BASE);
@@ -810,60 +713,71 @@ impl BME680 {
     }
 
     pub fn set_res_heat_0(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_0");
         const REG: u8 = BME680_REG_RES_HEAT_BASE;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_1(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_1");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 1;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_2(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_2");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 2;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_3(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_3");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 3;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_4(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_4");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 4;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_5(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_5");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 5;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_6(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_6");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 6;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_7(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_7");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 7;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_8(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_8");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 8;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn set_res_heat_9(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        let _call = self.call("set_res_heat_9");
         const REG: u8 = BME680_REG_RES_HEAT_BASE + 9;
         self.set_res_heat(REG, res_heat)
     }
 
     pub fn get_gas_wait(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        let _call = self.call("get_gas_wait");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE;
         let mut reg_data: [u8; BME680_GAS_WAIT_BASE_LEN] = [0; BME680_GAS_WAIT_BASE_LEN];
         // -- read current value and mask out run gas bit
-        let _bytes_read = self.i2c.i2c_read_block_data(REG, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), REG, &mut reg_data)?;
         Ok(Vec::from(reg_data))
     }
 
@@ -876,7 +790,7 @@ impl BME680 {
         let mult_fact = mult_fact.value();
         let reg_val = mult_fact << BME680_GAS_WAIT_MULT_FACT_SHL | milli_secs;
         if reg < BME680_REG_GAS_WAIT_BASE || reg > BME680_REG_GAS_WAIT_BASE + (BME680_GAS_WAIT_BASE_LEN as u8) {
-            return Err(std::io::Error::other(format!("Invalid register for gas wait: {reg:#04x}")))
+            return Err(Error::InvalidConfig(format!("Invalid register for gas wait: {reg:#04x}")).into())
         }
         // -- write back register value
         debug!("Setting gas wait {} to {reg_val:#010b} / {reg_val:#04x}", reg - BME680_REG_GAS_WAIT_BASE);
@@ -884,53 +798,517 @@ impl BME680 {
     }
 
     pub fn set_gas_wait_0(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_0");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_1(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_1");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 1;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_2(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_2");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 2;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_3(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_3");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 3;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_4(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_4");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 4;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_5(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_5");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 5;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_6(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_6");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 6;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_7(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_7");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 7;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_8(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_8");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 8;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
     pub fn set_gas_wait_9(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_gas_wait_9");
         const REG: u8 = BME680_REG_GAS_WAIT_BASE + 9;
         self.set_gas_wait(REG, milli_secs, mult_fact)
     }
 
-}
\ No newline at end of file
+    // -- writes the whole config, the oversampling write puts the sensor in sleep mode so nothing
+    // -- is measured with half of it; e.g. after a reset or with a new ambient temperature
+    pub fn apply_config(&mut self, config: &Bme680Config) -> Result<(), std::io::Error> {
+        let _call = self.call("apply_config");
+        config.validate()?;
+        if config.device_addr != self.device_addr {
+            let errmsg = format!("Config is for device address {:#04x}, not {:#04x}",
+                config.device_addr.value(), self.device_addr.value());
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        // -- ctrl_hum becomes effective with the write to ctrl_meas
+        self.set_humidity_osr(config.humidity_osr.clone())?;
+        self.set_pressure_and_temperature_osr(config.pressure_osr.clone(), config.temperature_osr.clone())?;
+        self.set_irr_filter(config.irr_filter.clone())?;
+        match &config.heater {
+            Some((heater_profile, target_temperature, duration_ms)) => {
+                let res_heat = self.calc_res_heat(config.ambient_temperature, *target_temperature);
+                self.set_res_heat(BME680_REG_RES_HEAT_BASE + heater_profile.value(), res_heat)?;
+                let (milli_secs, mult_fact) = gas_wait(*duration_ms);
+                self.set_gas_wait(BME680_REG_GAS_WAIT_BASE + heater_profile.value(), milli_secs, mult_fact)?;
+                self.set_heater_profile(heater_profile.clone())?;
+                self.enable_run_gas()
+            },
+            None => {
+                // -- back to the first set point, no stale profile is left selected
+                self.set_heater_profile(Bme680HeaterProfile::SetPoint0)?;
+                self.disable_run_gas()
+            },
+        }
+    }
+
+}
+
+// -- a brown-out clears the calibration image, read it again
+impl<B: I2cBus> Recoverable for BME680<B> {
+
+    fn recover(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("recover");
+        i2cio::set_slave(&mut self.i2c, self.device_addr.value())?;
+        self.calib_data = Self::get_calib_data(&mut self.i2c, self.functionality, self.device_addr.value())?;
+        Ok(())
+    }
+}
+
+// -- triggers a forced conversion, with the gas measurement if run gas is enabled; the gas
+// -- resistance is left out unless it is valid and the heater reached its target temperature
+impl<B: I2cBus> Sensor for BME680<B> {
+
+    fn name(&self) -> &'static str {
+        "BME680"
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        self.set_forced_mode()?;
+        let started = i2cio::now(&self.i2c);
+        loop {
+            if self.get_meas_status()?.new_data {
+                break
+            }
+            if i2cio::now(&self.i2c).saturating_sub(started) >= Duration::from_millis(BME680_MEASURE_TIMEOUT_MS) {
+                return Err(Error::Timeout(format!("BME680 measurement not done after {BME680_MEASURE_TIMEOUT_MS} ms")).into())
+            }
+            i2cio::delay(&mut self.i2c, BME680_MEASURE_POLL_INTERVAL_MS);
+        }
+        let meas_result = self.get_meas_result()?;
+        let (temperature, t_fine) = self.get_temperature(meas_result.temperature_raw);
+        let gas_result = self.get_gas_meas_result()?;
+        Ok(Measurement {
+            temperature: Some(temperature),
+            humidity: Some(self.get_humidity(meas_result.humidity_raw, temperature)),
+            pressure: Some(self.get_pressure(meas_result.pressure_raw, t_fine)),
+            gas_resistance: (gas_result.gas_valid && gas_result.heat_stab)
+                .then_some(gas_result.gas_res),
+            ..Default::default()
+        })
+    }
+}
+
+// -- async variant for executors polling many sensors, the startup wait yields instead of
+// -- blocking the thread, poll get_meas_status while the forced measurement converts
+pub struct BME680Async<B: AsyncI2cBus> {
+    // -- i2c bus
+    i2c: B,
+    // -- device address.
+    device_addr: Bme680DeviceAddress,
+    // -- chip id
+    chip_id: u8,
+    // -- calibration params
+    calib_data: CalibData,
+}
+
+impl<B: AsyncI2cBus> BME680Async<B> {
+
+    pub async fn with_bus(mut i2c: B, device_addr: Bme680DeviceAddress,
+        humidity_osr: Bme680OverSampling, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling, irr_filter: Bme680IrrFilter) -> Result<Self, std::io::Error> {
+        trace::async_driver_call("BME680", "with_bus", device_addr.value(), register_name).run(async move {
+            // -- set device address
+            i2cio_async::set_slave(&mut i2c, device_addr.value()).await?;
+            // -- check if device is available by reading chip id
+            let chip_id = i2cio_async::read_byte(&mut i2c, BME680_REG_CHIP_ID).await?;
+            if chip_id != BME680_CHIP_ID {
+                return Err(Error::ChipId { expected: BME680_CHIP_ID.into(), found: chip_id.into() }.into())
+            }
+            debug!("Got chip id: {chip_id:#x}");
+            let calib_data = Self::get_calib_data(&mut i2c).await?;
+            debug!("Got calibration data: {calib_data:#?}");
+            let mut bme680 = BME680Async {
+                i2c,
+                device_addr,
+                chip_id,
+                calib_data,
+            };
+            // -- do a soft reset since it's in an unknown state
+            bme680.soft_reset().await?;
+            // -- set oversampling rates
+            bme680.set_humidity_osr(humidity_osr).await?;
+            bme680.set_pressure_and_temperature_osr(pressure_osr, temperature_osr).await?;
+            // -- set filter
+            bme680.set_irr_filter(irr_filter).await?;
+            // -- return initialized structure
+            Ok(bme680)
+        }).await
+    }
+
+    // -- span of a public call, attached to its future, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> AsyncDriverCall {
+        trace::async_driver_call("BME680", call, self.device_addr.value(), register_name)
+    }
+
+    pub fn get_device_addr(&self) -> Bme680DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    pub fn get_chip_id(&self) -> u8 {
+        self.chip_id
+    }
+
+    pub async fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        self.call("soft_reset").run(async {
+            const REG: u8 = BME680_REG_RESET;
+            // -- initiate soft reset
+            debug!("Initiating soft reset");
+            i2cio_async::write_byte(&mut self.i2c, REG, BME680_COMMAND_SOFT_RESET).await?;
+            // -- wait for the device to startup
+            i2cio_async::delay(&mut self.i2c, BME680_STARTUP_DELAY_MS).await;
+            Ok(())
+        }).await
+    }
+
+    async fn get_calib_data(i2c: &mut B) -> Result<CalibData, std::io::Error> {
+        // -- read the three calibration data blocks
+        let mut reg_data_1: [u8; BME680_CALIB_DATA1_LEN] = [0; BME680_CALIB_DATA1_LEN];
+        i2cio_async::read_block(i2c, BME680_REG_CALIB_DATA1_BASE, &mut reg_data_1).await?;
+        let mut reg_data_2: [u8; BME680_CALIB_DATA2_LEN] = [0; BME680_CALIB_DATA2_LEN];
+        i2cio_async::read_block(i2c, BME680_REG_CALIB_DATA2_BASE, &mut reg_data_2).await?;
+        let mut reg_data_3: [u8; BME680_CALIB_DATA3_LEN] = [0; BME680_CALIB_DATA3_LEN];
+        i2cio_async::read_block(i2c, BME680_REG_CALIB_DATA3_BASE, &mut reg_data_3).await?;
+        // -- concat arrays
+        let coeff_array = [reg_data_1.as_slice(), reg_data_2.as_slice(), reg_data_3.as_slice()].concat();
+        Ok(CalibData::from_coefficients(&coeff_array))
+    }
+
+    pub async fn get_meas_status(&mut self) -> Result<Bme680MeasuringStatus, std::io::Error> {
+        self.call("get_meas_status").run(async {
+            let reg_val = i2cio_async::read_byte(&mut self.i2c, BME680_REG_MEAS_STATUS_0).await?;
+            Ok(Bme680MeasuringStatus::from_register(reg_val))
+        }).await
+    }
+
+    pub async fn get_meas_result(&mut self) -> Result<Bme680MeasuringResult, std::io::Error> {
+        self.call("get_meas_result").run(async {
+            let mut reg_data: [u8; BME680_MEAS_RESULT_LEN] = [0; BME680_MEAS_RESULT_LEN];
+            let _bytes_read = i2cio_async::read_block(&mut self.i2c, BME680_REG_MEAS_RESULT_BASE, &mut reg_data).await?;
+            debug!("Read {_bytes_read} bytes of resulting data after measuring");
+            Ok(Bme680MeasuringResult::from_registers(&reg_data))
+        }).await
+    }
+
+    pub async fn get_gas_meas_result(&mut self) -> Result<Bme680GasMeasuringResult, std::io::Error> {
+        self.call("get_gas_meas_result").run(async {
+            let data_msb = i2cio_async::read_byte(&mut self.i2c, BME680_REG_GAS_ACD_MSB).await?;
+            let data_lsb = i2cio_async::read_byte(&mut self.i2c, BME680_REG_GAS_ACD_LSB_RANGE).await?;
+            Ok(self.calib_data.gas_result(data_msb, data_lsb))
+        }).await
+    }
+
+    // -- the temperature plus its t_fine, which the pressure compensation needs
+    pub fn get_temperature(&self, temperature_raw: u32) -> (Temperature, f64) {
+        let (temperature, t_fine) = self.calib_data.temperature(temperature_raw);
+        (Temperature::from_celsius(temperature), t_fine)
+    }
+
+    pub fn get_pressure(&self, pressure_raw: u32, t_fine: f64) -> Pressure {
+        Pressure::from_pascal(self.calib_data.pressure(pressure_raw, t_fine))
+    }
+
+    pub fn get_humidity(&self, humidity_raw: u16, temperature: Temperature) -> RelativeHumidity {
+        RelativeHumidity::from_percent(self.calib_data.humidity(humidity_raw, temperature.celsius()))
+    }
+
+    // -- read, change and write back a control register
+    async fn update_register(&mut self, reg: u8, update: impl FnOnce(u8) -> u8) -> Result<(), std::io::Error> {
+        let reg_val = i2cio_async::read_byte(&mut self.i2c, reg).await?;
+        i2cio_async::write_byte(&mut self.i2c, reg, update(reg_val)).await
+    }
+
+    pub async fn set_forced_mode(&mut self) -> Result<(), std::io::Error> {
+        self.call("set_forced_mode").run(async {
+            debug!("Setting power mode forced");
+            self.update_register(BME680_REG_CTRL_MEAS, |reg_val| reg_val | BME680_CTRL_MEAS_FORCED_MODE_BIT).await
+        }).await
+    }
+
+    pub async fn set_humidity_osr(&mut self, humidity_osr: Bme680OverSampling) -> Result<(), std::io::Error> {
+        self.call("set_humidity_osr").run(async {
+            debug!("Setting humidity oversampling rate");
+            i2cio_async::write_byte(&mut self.i2c, BME680_REG_CTRL_HUM, humidity_osr.value()).await
+        }).await
+    }
+
+    pub async fn set_pressure_and_temperature_osr(&mut self, pressure_osr: Bme680OverSampling,
+        temperature_osr: Bme680OverSampling) -> Result<(), std::io::Error> {
+        self.call("set_pressure_and_temperature_osr").run(async {
+            // -- put bits for OSR in place, power mode implicit set to sleep (bit 0 and 1)
+            let reg_val = temperature_osr.value() << BME680_CTRL_MEAS_TEMPERATURE_SHL
+                | pressure_osr.value() <<  BME680_CTRL_MEAS_PRESSURE_SHL;
+            debug!("Setting pressure and temperature oversampling rate to {reg_val:#010b}");
+            i2cio_async::write_byte(&mut self.i2c, BME680_REG_CTRL_MEAS, reg_val).await
+        }).await
+    }
+
+    pub async fn set_irr_filter(&mut self, irr_filter: Bme680IrrFilter) -> Result<(), std::io::Error> {
+        self.call("set_irr_filter").run(async {
+            let reg_val:u8 = irr_filter.value() <<  BME680_CONTROL_IIR_FILTER_SHL;
+            debug!("Setting IRR filter");
+            i2cio_async::write_byte(&mut self.i2c, BME680_REG_CONFIG, reg_val).await
+        }).await
+    }
+
+    pub async fn enable_heater(&mut self) -> Result<(), std::io::Error> {
+        self.call("enable_heater").run(async {
+            debug!("Enabling heater");
+            self.update_register(BME680_REG_CTRL_GAS_0, |reg_val| reg_val | BME680_CTRL_GAS_0_HEATER_SHL).await
+        }).await
+    }
+
+    pub async fn disable_heater(&mut self) -> Result<(), std::io::Error> {
+        self.call("disable_heater").run(async {
+            debug!("Disabling heater");
+            self.update_register(BME680_REG_CTRL_GAS_0, |reg_val| reg_val & BME680_CTRL_GAS_0_HEATER_MASK).await
+        }).await
+    }
+
+    pub async fn set_heater_profile(&mut self, heater_profile: Bme680HeaterProfile) -> Result<(), std::io::Error> {
+        self.call("set_heater_profile").run(async {
+            debug!("Setting heater profile");
+            self.update_register(BME680_REG_CTRL_GAS_1,
+                |reg_val| (reg_val & !BME680_NB_CONV_NB_CONV_MASK) | heater_profile.value()).await
+        }).await
+    }
+
+    pub async fn enable_run_gas(&mut self) -> Result<(), std::io::Error> {
+        self.call("enable_run_gas").run(async {
+            debug!("Enable run gas");
+            self.update_register(BME680_REG_CTRL_GAS_1, |reg_val| reg_val | (1 <<  BME680_NB_CONV_RUN_GAS_SHL)).await
+        }).await
+    }
+
+    pub async fn disable_run_gas(&mut self) -> Result<(), std::io::Error> {
+        self.call("disable_run_gas").run(async {
+            debug!("Disable run gas");
+            self.update_register(BME680_REG_CTRL_GAS_1, |reg_val| reg_val & BME680_NB_CONV_RUN_GAS_MASK).await
+        }).await
+    }
+
+    pub fn calc_res_heat(&self, amb_temp: f64, target_temp: f64) -> u8 {
+        self.calib_data.res_heat(amb_temp, target_temp)
+    }
+
+    pub async fn get_res_heat(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        self.call("get_res_heat").run(async {
+            let mut reg_data: [u8; BME680_RES_HEAT_BASE_LEN] = [0; BME680_RES_HEAT_BASE_LEN];
+            let _bytes_read = i2cio_async::read_block(&mut self.i2c, BME680_REG_RES_HEAT_BASE, &mut reg_data).await?;
+            Ok(Vec::from(reg_data))
+        }).await
+    }
+
+    async fn set_res_heat(&mut self, reg: u8, res_heat: u8) -> Result<(), std::io::Error> {
+        if reg < BME680_REG_RES_HEAT_BASE || reg > BME680_REG_RES_HEAT_BASE + (BME680_RES_HEAT_BASE_LEN as u8) {
+            return Err(Error::InvalidConfig(format!("Invalid register for gas wait: {reg:#04x}")).into())
+        }
+        debug!("Setting heater resistance {} to {res_heat:#010b}", reg - BME680_REG_RES_HEAT_BASE);
+        i2cio_async::write_byte(&mut self.i2c, reg, res_heat).await
+    }
+
+    pub async fn set_res_heat_0(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_0").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_1(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_1").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 1;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_2(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_2").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 2;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_3(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_3").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 3;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_4(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_4").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 4;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_5(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_5").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 5;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_6(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_6").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 6;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_7(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_7").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 7;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_8(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_8").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 8;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn set_res_heat_9(&mut self, res_heat: u8) -> Result<(), std::io::Error> {
+        self.call("set_res_heat_9").run(async {
+            const REG: u8 = BME680_REG_RES_HEAT_BASE + 9;
+            self.set_res_heat(REG, res_heat).await
+        }).await
+    }
+
+    pub async fn get_gas_wait(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        self.call("get_gas_wait").run(async {
+            let mut reg_data: [u8; BME680_GAS_WAIT_BASE_LEN] = [0; BME680_GAS_WAIT_BASE_LEN];
+            let _bytes_read = i2cio_async::read_block(&mut self.i2c, BME680_REG_GAS_WAIT_BASE, &mut reg_data).await?;
+            Ok(Vec::from(reg_data))
+        }).await
+    }
+
+    async fn set_gas_wait(&mut self, reg: u8, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        let milli_secs = milli_secs.min(64);
+        let reg_val = mult_fact.value() << BME680_GAS_WAIT_MULT_FACT_SHL | milli_secs;
+        if reg < BME680_REG_GAS_WAIT_BASE || reg > BME680_REG_GAS_WAIT_BASE + (BME680_GAS_WAIT_BASE_LEN as u8) {
+            return Err(Error::InvalidConfig(format!("Invalid register for gas wait: {reg:#04x}")).into())
+        }
+        debug!("Setting gas wait {} to {reg_val:#010b} / {reg_val:#04x}", reg - BME680_REG_GAS_WAIT_BASE);
+        i2cio_async::write_byte(&mut self.i2c, reg, reg_val).await
+    }
+
+    pub async fn set_gas_wait_0(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_0").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_1(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_1").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 1;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_2(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_2").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 2;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_3(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_3").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 3;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_4(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_4").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 4;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_5(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_5").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 5;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_6(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_6").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 6;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_7(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_7").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 7;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_8(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_8").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 8;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+
+    pub async fn set_gas_wait_9(&mut self, milli_secs: u8, mult_fact: Bme680GasWaitMultiplicationFactor) -> Result<(), std::io::Error> {
+        self.call("set_gas_wait_9").run(async {
+            const REG: u8 = BME680_REG_GAS_WAIT_BASE + 9;
+            self.set_gas_wait(REG, milli_secs, mult_fact).await
+        }).await
+    }
+}
@@ -1,19 +1,28 @@
-use i2c_linux::I2c;
+use i2c_linux::{Functionality, I2c};
+use i2c_sensors_core::bmp388::{self, CalibData, FifoFrame, BMP388_FIFO_FRAMLE_LENGTH_MAX, BMP388_LEN_DATA,
+    BMP388_LEN_TRIMMING_COEFFICIENTS};
 #[allow(unused_imports)]
 use log::{debug, info, warn};
 use std::fs::File;
 use std::path::Path;
-use std::{thread, time};
+use std::time::Duration;
 
-use crate::i2cio;
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::retry::Recoverable;
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{Pressure, Temperature};
+use crate::spiio::{self, SpiBus, SpiDevice, SpiRegisterAccess};
+use crate::trace::{self, DriverCall};
 
 use super::bmp388_enums::*;
 
+pub use i2c_sensors_core::bmp388::{DataRaw, FifoData};
+
 // -- chip id
-const BMP388_CHIP_ID: u8 = 0x50;
+pub(crate) const BMP388_CHIP_ID: u8 = 0x50;
 
 // -- length of multi-byte registers
-const BMP388_LEN_TRIMMING_COEFFICIENTS: usize = 21;
 const BMP388_LEN_PRESSURE_DATA: usize = 3;
 const BMP388_LEN_TEMPERATURE_DATA: usize = 3;
 #[allow(dead_code)]
@@ -24,31 +33,20 @@ const BMP388_LEN_FIFO_LENGTH: usize = 2;
 const BMP388_LEN_FIFO_WATERMARK: usize = 2;
 
 // -- registers
-const BMP388_REG_CHIP_ID: u8 = 0x00;
-#[allow(dead_code)]
+pub(crate) const BMP388_REG_CHIP_ID: u8 = 0x00;
 const BMP388_REG_ERRORS: u8 = 0x02;
 const BMP388_REG_STATUS: u8 = 0x03;
 const BMP388_REG_PRESSURE_DATA: u8 = 0x04;
 const BMP388_REG_TEMPERATURE_DATA: u8 = 0x07;
-#[allow(dead_code)]
 const BMP388_REG_SENSOR_TIME: u8 = 0x0C;
-#[allow(dead_code)]
 const BMP388_REG_EVENT: u8 = 0x10;
-#[allow(dead_code)]
 const BMP388_REG_INT_STATUS: u8 = 0x11;
-#[allow(dead_code)]
 const BMP388_REG_FIFO_LENGTH: u8 = 0x12;
-#[allow(dead_code)]
 const BMP388_REG_FIFO_DATA: u8 = 0x14;
-#[allow(dead_code)]
 const BMP388_REG_FIFO_WATERMARK: u8 = 0x15;
-#[allow(dead_code)]
 const BMP388_REG_FIFO_CONFIG_1: u8 = 0x17;
-#[allow(dead_code)]
 const BMP388_REG_FIFO_CONFIG_2: u8 = 0x18;
-#[allow(dead_code)]
 const BMP388_REG_INT_CONTROL: u8 = 0x19;
-#[allow(dead_code)]
 const BMP388_REG_IF_CONF: u8 = 0x1a;
 const BMP388_REG_POWER_CONTROL: u8 = 0x1b;
 const BMP388_REG_OVERSAMPLING_RATE: u8 = 0x1c;
@@ -57,13 +55,23 @@ const BMP388_REG_CONFIG: u8 = 0x1f;
 const BMP388_REG_TRIMMING_COEFFICIENTS: u8 = 0x31;
 const BMP388_REG_CMD: u8 = 0x7e;
 
+trace::register_names!(BMP388_REG_CHIP_ID, BMP388_REG_ERRORS, BMP388_REG_STATUS, BMP388_REG_PRESSURE_DATA,
+    BMP388_REG_TEMPERATURE_DATA, BMP388_REG_SENSOR_TIME, BMP388_REG_EVENT, BMP388_REG_INT_STATUS,
+    BMP388_REG_FIFO_LENGTH, BMP388_REG_FIFO_DATA, BMP388_REG_FIFO_WATERMARK, BMP388_REG_FIFO_CONFIG_1,
+    BMP388_REG_FIFO_CONFIG_2, BMP388_REG_INT_CONTROL, BMP388_REG_IF_CONF, BMP388_REG_POWER_CONTROL,
+    BMP388_REG_OVERSAMPLING_RATE, BMP388_REG_OUTPUT_DATA_RATE, BMP388_REG_CONFIG,
+    BMP388_REG_TRIMMING_COEFFICIENTS, BMP388_REG_CMD);
+
 // -- commands
 #[allow(dead_code)]
 const BMP388_CMD_FIFO_FLUSH: u8 = 0xb0;
 const BMP388_CMD_SOFT_RESET: u8 = 0xb6;
 
 // -- other constants
-const BMP388_STARTUP_DELAY_MS: u64 = 2;
+const BMP388_STARTUP_DELAY_MS: u32 = 2;
+// -- a forced conversion with 32x pressure and temperature oversampling takes about 130 ms
+const BMP388_MEASURE_POLL_INTERVAL_MS: u32 = 10;
+const BMP388_MEASURE_TIMEOUT_MS: u64 = 500;
 
 const BMP388_PRESSURE_SENSOR_ENABLED_BIT: u8 = 0x1;
 const BMP388_TEMPERATURE_SENSOR_ENABLED_BIT: u8 = 0x2;
@@ -84,66 +92,163 @@ const BMP388_FIFO_PRESSURE_ENABLE_BIT: u8 = 3;
 const BMP388_FIFO_TEMPERATURE_ENABLE_BIT: u8 = 4;
 // -- fifo config 2
 const BMP388_FIFO_DATA_SELECT_BIT: u8 = 3;
+const BMP388_FIFO_SUBSAMPLING_MAX: i8 = 7;
+
+// -- conversion time in normal mode in us, datasheet section 3.9.2, has to fit in the ODR period
+const BMP388_CONVERSION_TIME_BASE_US: u32 = 234;
+const BMP388_CONVERSION_TIME_PRESSURE_US: u32 = 392;
+const BMP388_CONVERSION_TIME_TEMPERATURE_US: u32 = 163;
+const BMP388_CONVERSION_TIME_PER_SAMPLE_US: u32 = 2020;
+const BMP388_ODR_PERIOD_BASE_US: u32 = 5000;
+
+// -- FIFO settings of a config, as enable_fifo takes them
+#[derive(Clone, Debug, PartialEq)]
+struct Bmp388FifoConfig {
+    stop_on_full: Bmp388FifoStopOnFull,
+    with_pressure: Bmp388FifoWithPressureData,
+    with_temperature: Bmp388FifoWithTemperatureData,
+    with_sensor_time: Bmp388FifoWithSensorTime,
+    data_filtered: Bmp388FifoDataFiltered,
+    subsampling: i8,
+}
 
-// -- fifo frame
-const BMP388_FIFO_SENSOR_FRAME_BIT: u8 = 0x80;
-const BMP388_FIFO_CONTROL_FRAME_BIT: u8 = 0x40;
-const BMP388_FIFO_CONTROL_FRAME_CONFIG_ERROR_BIT: u8 = 0x04;
-const BMP388_FIFO_CONTROL_FRAME_CONFIG_CHANGE_BIT: u8 = 0x08;
-const BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT: u8 = 0x20;
-const BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT: u8 = 0x10;
-const BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT: u8 = 0x04;
-
-const BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME: usize = 4;
-const BMP388_FIFO_FRAMLE_LENGTH_PRESSURE: usize = 4;
-const BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE: usize = 4;
-const BMP388_FIFO_FRAMLE_LENGTH_PRESSURE_TEMPERATURE: usize = 7;
-
-#[derive(Debug)]
-pub struct FifoData {
-    pub pressure_raw: Option<u32>,
-    pub temperature_raw: Option<u32>,
-    pub sensor_time: Option<u32>,
-    pub config_change: bool,
+// -- everything with_bus and the setters configure, written in one go by with_config and
+// -- apply_config; the default samples pressure and temperature once at 200 Hz, stays in sleep
+// -- mode so measure() triggers forced conversions, and leaves the FIFO off
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bmp388Config {
+    device_addr: Bmp388DeviceAddress,
+    osr_p: Bmp388OverSamplingPr,
+    osr_t: Bmp388OverSamplingTp,
+    irr_filter: Bmp388IrrFilter,
+    odr: Bmp388OutputDataRate,
+    pwr_mode: Bmp388SensorPowerMode,
+    enable_pressure: Bmp388StatusPressureSensor,
+    enable_temperature: Bmp388StatusTemperatureSensor,
+    fifo: Option<Bmp388FifoConfig>,
 }
 
-#[derive(Debug)]
-struct CalibData {
-    // -- Calibration coefficients for the pressure sensor
-    par_p1: f64,
-    par_p2: f64,
-    par_p3: f64,
-    par_p4: f64,
-    par_p5: f64,
-    par_p6: f64,
-    par_p7: f64,
-    par_p8: f64,
-    par_p9: f64,
-    par_p10: f64,
-    par_p11: f64,
-    // -- Calibration coefficients for the temperature sensor
-    par_t1: f64,
-    par_t2: f64,
-    par_t3: f64,
+impl Default for Bmp388Config {
+    fn default() -> Self {
+        Bmp388Config {
+            device_addr: Bmp388DeviceAddress::Default,
+            osr_p: Bmp388OverSamplingPr::UltraLowX1,
+            osr_t: Bmp388OverSamplingTp::X1,
+            irr_filter: Bmp388IrrFilter::Off,
+            odr: Bmp388OutputDataRate::Ax200Hz,
+            pwr_mode: Bmp388SensorPowerMode::Sleep,
+            enable_pressure: Bmp388StatusPressureSensor::Enabled,
+            enable_temperature: Bmp388StatusTemperatureSensor::Enabled,
+            fifo: None,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct DataRaw {
-    // -- Un-compensated pressure
-    pub pressure: u32,
-    // -- Un-compensated temperature
-    pub temperature: u32,
+impl Bmp388Config {
+
+    pub fn with_device_addr(mut self, device_addr: Bmp388DeviceAddress) -> Self {
+        self.device_addr = device_addr;
+        self
+    }
+
+    pub fn with_oversampling(mut self, osr_p: Bmp388OverSamplingPr, osr_t: Bmp388OverSamplingTp) -> Self {
+        self.osr_p = osr_p;
+        self.osr_t = osr_t;
+        self
+    }
+
+    pub fn with_irr_filter(mut self, irr_filter: Bmp388IrrFilter) -> Self {
+        self.irr_filter = irr_filter;
+        self
+    }
+
+    pub fn with_output_data_rate(mut self, odr: Bmp388OutputDataRate) -> Self {
+        self.odr = odr;
+        self
+    }
+
+    pub fn with_sensor_mode(mut self, pwr_mode: Bmp388SensorPowerMode,
+        enable_pressure: Bmp388StatusPressureSensor, enable_temperature: Bmp388StatusTemperatureSensor) -> Self {
+        self.pwr_mode = pwr_mode;
+        self.enable_pressure = enable_pressure;
+        self.enable_temperature = enable_temperature;
+        self
+    }
+
+    // -- same arguments as enable_fifo, the FIFO is flushed whenever the config is applied
+    pub fn with_fifo(mut self, stop_on_full: Bmp388FifoStopOnFull,
+        with_pressure: Bmp388FifoWithPressureData, with_temperature: Bmp388FifoWithTemperatureData,
+        with_sensor_time: Bmp388FifoWithSensorTime, data_filtered: Bmp388FifoDataFiltered, subsampling: i8) -> Self {
+        self.fifo = Some(Bmp388FifoConfig {
+            stop_on_full, with_pressure, with_temperature, with_sensor_time, data_filtered, subsampling,
+        });
+        self
+    }
+
+    pub fn get_device_addr(&self) -> Bmp388DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    // -- conversion time of one pressure and temperature measurement in normal mode
+    fn conversion_time_us(&self) -> u32 {
+        let mut conversion_time = BMP388_CONVERSION_TIME_BASE_US;
+        if self.enable_pressure == Bmp388StatusPressureSensor::Enabled {
+            conversion_time += BMP388_CONVERSION_TIME_PRESSURE_US + (BMP388_CONVERSION_TIME_PER_SAMPLE_US << self.osr_p.value());
+        }
+        if self.enable_temperature == Bmp388StatusTemperatureSensor::Enabled {
+            conversion_time += BMP388_CONVERSION_TIME_TEMPERATURE_US + (BMP388_CONVERSION_TIME_PER_SAMPLE_US << self.osr_t.value());
+        }
+        conversion_time
+    }
+
+    // -- checked before anything is written, the sensor itself flags a conf_err in normal mode
+    // -- if the conversion doesn't fit in the ODR period
+    pub fn validate(&self) -> Result<(), std::io::Error> {
+        let pressure_enabled = self.enable_pressure == Bmp388StatusPressureSensor::Enabled;
+        let temperature_enabled = self.enable_temperature == Bmp388StatusTemperatureSensor::Enabled;
+        if self.pwr_mode != Bmp388SensorPowerMode::Sleep && !pressure_enabled && !temperature_enabled {
+            let errmsg = format!("{} with pressure and temperature disabled", self.pwr_mode);
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        if self.pwr_mode == Bmp388SensorPowerMode::Normal {
+            let conversion_time = self.conversion_time_us();
+            let odr_period = BMP388_ODR_PERIOD_BASE_US << self.odr.value();
+            if conversion_time > odr_period {
+                let errmsg = format!("Conversion time of {conversion_time} us exceeds the ODR period of {odr_period} us");
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+        }
+        if let Some(fifo) = &self.fifo {
+            let with_pressure = fifo.with_pressure == Bmp388FifoWithPressureData::Enabled;
+            let with_temperature = fifo.with_temperature == Bmp388FifoWithTemperatureData::Enabled;
+            if !with_pressure && !with_temperature {
+                let errmsg = "FIFO without pressure and temperature data".to_string();
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+            if (with_pressure && !pressure_enabled) || (with_temperature && !temperature_enabled) {
+                let errmsg = "FIFO with data of a disabled sensor".to_string();
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+            if !(0..=BMP388_FIFO_SUBSAMPLING_MAX).contains(&fifo.subsampling) {
+                let errmsg = format!("FIFO subsampling {} outside of 0 to {BMP388_FIFO_SUBSAMPLING_MAX}", fifo.subsampling);
+                return Err(Error::InvalidConfig(errmsg).into())
+            }
+        }
+        Ok(())
+    }
 }
 
-pub struct BMP388 {
+pub struct BMP388<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address.
     device_addr: Bmp388DeviceAddress,
     // -- calibration data
     calib_data: CalibData,
     // -- is sensor time enabled for FIFO data?
     with_sensor_time: Bmp388FifoWithSensorTime,
+    // -- adapter features, queried once
+    functionality: Functionality,
 }
 
 impl BMP388 {
@@ -152,31 +257,93 @@ impl BMP388 {
         osr_p: Bmp388OverSamplingPr, osr_t: Bmp388OverSamplingTp,
         irr_filter: Bmp388IrrFilter, odr: Bmp388OutputDataRate) -> Result<BMP388, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr, osr_p, osr_t, irr_filter, odr)
+    }
+
+    pub fn new_with_config(i2c_bus_path: &Path, config: &Bmp388Config) -> Result<BMP388, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_config(i2c, config)
+    }
+
+}
+
+impl BMP388<SpiBus> {
+
+    pub fn new_spi(spi_path: &Path,
+        osr_p: Bmp388OverSamplingPr, osr_t: Bmp388OverSamplingTp,
+        irr_filter: Bmp388IrrFilter, odr: Bmp388OutputDataRate) -> Result<Self, std::io::Error> {
+        // -- get the spi device
+        let spi = spiio::get_device(spi_path)?;
+        Self::with_spi(spi, osr_p, osr_t, irr_filter, odr)
+    }
+
+}
+
+impl<S: SpiDevice> BMP388<SpiBus<S>> {
+
+    // -- the chip select line picks the device, the device address is kept at its default;
+    // -- the spi bus skips the dummy byte the BMP388 sends ahead of read data
+    pub fn with_spi(spi: S,
+        osr_p: Bmp388OverSamplingPr, osr_t: Bmp388OverSamplingTp,
+        irr_filter: Bmp388IrrFilter, odr: Bmp388OutputDataRate) -> Result<Self, std::io::Error> {
+        let bus = SpiBus::new(spi, SpiRegisterAccess::DummyByte);
+        Self::with_bus(bus, Bmp388DeviceAddress::Default, osr_p, osr_t, irr_filter, odr)
+    }
+
+}
+
+impl<B: I2cBus> BMP388<B> {
+
+    pub fn with_bus(i2c: B, device_addr: Bmp388DeviceAddress,
+        osr_p: Bmp388OverSamplingPr, osr_t: Bmp388OverSamplingTp,
+        irr_filter: Bmp388IrrFilter, odr: Bmp388OutputDataRate) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BMP388", "with_bus", device_addr.value(), register_name);
+        let mut bmp388 = Self::init(i2c, device_addr)?;
+        bmp388.set_osr_pressure_temperature(osr_p, osr_t)?;
+        bmp388.set_irr_filter(irr_filter)?;
+        bmp388.set_output_data_rate(odr)?;
+        Ok(bmp388)
+    }
+
+    // -- a config that doesn't validate fails before the bus is touched
+    pub fn with_config(i2c: B, config: &Bmp388Config) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("BMP388", "with_config", config.device_addr.value(), register_name);
+        config.validate()?;
+        let mut bmp388 = Self::init(i2c, config.get_device_addr())?;
+        bmp388.apply_config(config)?;
+        Ok(bmp388)
+    }
+
+    // -- chip id check, soft reset and calibration data, the device is left with its reset values
+    fn init(mut i2c: B, device_addr: Bmp388DeviceAddress) -> Result<Self, std::io::Error> {
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;
+        let functionality = i2cio::functionality(&mut i2c)?;
         // -- check if device is available by reading chip id
         let chip_id = i2cio::read_byte(&mut i2c, BMP388_REG_CHIP_ID)?;
         if chip_id != BMP388_CHIP_ID {
-            let errmsg = format!("Found unknown chip id '{chip_id:#04x}', expected '{BMP388_CHIP_ID:#04x}'");
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, errmsg))
+            return Err(Error::ChipId { expected: BMP388_CHIP_ID.into(), found: chip_id.into() }.into())
         }
         debug!("Got chip id: {chip_id:#x}");
         // -- do a soft reset since it's in an unknown state
         Self::soft_reset(&mut i2c)?;
         // -- get calibration data
-        let calib_data = Self::get_calib_data(&mut i2c)?;
+        let calib_data = Self::get_calib_data(&mut i2c, functionality, device_addr.value())?;
         // -- return initialized structure
-        let mut bmp388 = BMP388 {
+        Ok(BMP388 {
             i2c,
             device_addr,
             calib_data,
             with_sensor_time: Bmp388FifoWithSensorTime::Disabled,
-        };
-        bmp388.set_osr_pressure_temperature(osr_p, osr_t)?;
-        bmp388.set_irr_filter(irr_filter)?;
-        bmp388.set_output_data_rate(odr)?;
-        Ok(bmp388)
+            functionality,
+        })
+    }
+
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("BMP388", call, self.device_addr.value(), register_name)
     }
 
     #[allow(dead_code)]
@@ -184,17 +351,17 @@ impl BMP388 {
         self.device_addr.clone()
     }
 
-    fn soft_reset(i2c: &mut I2c<File>) -> Result<(), std::io::Error> {
+    fn soft_reset(i2c: &mut B) -> Result<(), std::io::Error> {
         // -- initiate soft reset
         debug!("Initiating soft reset");
         i2cio::write_byte(i2c, BMP388_REG_CMD, BMP388_CMD_SOFT_RESET)?;
         // -- wait for the device to startup
-        let startup_delay = time::Duration::from_millis(BMP388_STARTUP_DELAY_MS);
-        thread::sleep(startup_delay);
+        i2cio::delay(i2c, BMP388_STARTUP_DELAY_MS);
         Ok(())
     }
 
     pub fn set_output_data_rate(&mut self, subdiv_factor: Bmp388OutputDataRate) -> Result<(), std::io::Error> {
+        let _call = self.call("set_output_data_rate");
         let reg_val = subdiv_factor.value();
         debug!("Setting register BMP388_REG_OUTPUT_DATA_RATE {BMP388_REG_OUTPUT_DATA_RATE:#x} to value {reg_val:#010b}");
         // -- write it back
@@ -202,6 +369,7 @@ impl BMP388 {
     }
 
     pub fn set_irr_filter(&mut self, irr_filter: Bmp388IrrFilter) -> Result<(), std::io::Error> {
+        let _call = self.call("set_irr_filter");
         let reg_val = irr_filter.value();
         debug!("Setting register BMP388_REG_CONFIG {BMP388_REG_CONFIG:#x} to value {reg_val:#010b}");
         // -- write it back
@@ -210,6 +378,7 @@ impl BMP388 {
 
     pub fn set_sensor_mode(&mut self, pwr_mode : Bmp388SensorPowerMode,
         enable_pressure: Bmp388StatusPressureSensor, enable_temperature: Bmp388StatusTemperatureSensor) -> Result<(), std::io::Error> {
+        let _call = self.call("set_sensor_mode");
         let reg_val = pwr_mode.value() << BMP388_POWER_MODE_LOW_BIT | enable_temperature.value() << 1 | enable_pressure.value();
         debug!("Setting register BMP388_REG_POWER_CONTROL {BMP388_REG_POWER_CONTROL:#x} to value {reg_val:#010b}");
         // -- write it back
@@ -217,6 +386,7 @@ impl BMP388 {
     }
 
     pub fn get_sensor_mode(&mut self) -> Result<(Bmp388SensorPowerMode, Bmp388StatusPressureSensor, Bmp388StatusTemperatureSensor), std::io::Error> {
+        let _call = self.call("get_sensor_mode");
         // -- read current value of BMP388_REG_POWER_CONTROL
         let reg_val = i2cio::read_byte(&mut self.i2c, BMP388_REG_POWER_CONTROL)?;
         debug!("Got register BMP388_REG_POWER_CONTROL {BMP388_REG_POWER_CONTROL:#x} value {reg_val:#010b}");
@@ -238,6 +408,7 @@ impl BMP388 {
 
     pub fn get_status(&mut self)
         -> Result<(Bmp388StatusCommandDecoder, Bmp388StatusPressureData, Bmp388StatusTemperatureData), std::io::Error> {
+        let _call = self.call("get_status");
         // -- read current value of BMP388_REG_POWER_CONTROL
         let reg_val = i2cio::read_byte(&mut self.i2c, BMP388_REG_STATUS)?;
         let cmd_decoder_ready = match (reg_val & BMP388_STATUS_CMD_READY_MASK) > 0 {
@@ -262,6 +433,7 @@ impl BMP388 {
     }
 
     pub fn is_data_ready(&mut self) -> Result<bool, std::io::Error> {
+        let _call = self.call("is_data_ready");
         let int_status = self.get_int_status()?;
         Ok(int_status & BMP388_INT_STATUS_DATA_READY_BIT > 0)
     }
@@ -270,6 +442,7 @@ impl BMP388 {
         with_pressure: Bmp388FifoWithPressureData, with_temperature: Bmp388FifoWithTemperatureData, 
         with_sensor_time: Bmp388FifoWithSensorTime, data_filtered: Bmp388FifoDataFiltered, subsampling: i8) 
         -> Result<(), std::io::Error> {
+        let _call = self.call("enable_fifo");
         debug!("Enabling FIFO");
         // -- flush fifo on enable to get rid of old data
         self.flush_fifo()?;
@@ -282,7 +455,7 @@ impl BMP388 {
         let reg_config_2 = data_select_bit | subsampling;
         debug!("Setting register BMP388_REG_FIFO_CONFIG_2 {BMP388_REG_FIFO_CONFIG_2:#x} to value {reg_config_2:#010b}");
         i2cio::write_byte(&mut self.i2c, BMP388_REG_FIFO_CONFIG_2, reg_config_2)?;
-        let enable_fifo_bit = 1 as u8;
+        let enable_fifo_bit = 1_u8;
         let stop_on_full_bit = stop_on_full.value() << BMP388_FIFO_STOP_ON_FULL_BIT;
         let sensor_time_enabled_bit = with_sensor_time.value() << BMP388_FIFO_SENSOR_TIME_ENABLE_BIT;
         let pressure_enabled_bit = with_pressure.value() << BMP388_FIFO_PRESSURE_ENABLE_BIT;
@@ -297,503 +470,217 @@ impl BMP388 {
     }
 
     pub fn disable_fifo(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("disable_fifo");
         debug!("Disabling FIFO");
         let reg_config_1 = BMP388_FIFO_DISABLE_FIFO;
         i2cio::write_byte(&mut self.i2c, BMP388_REG_FIFO_CONFIG_1, reg_config_1)
     }
 
     pub fn flush_fifo(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("flush_fifo");
         // -- initiate flush
         debug!("Flushing FIFO");
         i2cio::write_byte(&mut self.i2c, BMP388_REG_CMD, BMP388_CMD_FIFO_FLUSH)
     }
 
     pub fn get_fifo_length(&mut self) -> Result<u16, std::io::Error> {
+        let _call = self.call("get_fifo_length");
         // -- read current FIFO length
         debug!("Reading FIFO length");
         i2cio::read_word(&mut self.i2c, BMP388_REG_FIFO_LENGTH)
     }
 
     pub fn get_fifo_data(&mut self) -> Result<u8, std::io::Error> {
+        let _call = self.call("get_fifo_data");
         // -- read next FIFO data
         debug!("Reading FIFO data");
         i2cio::read_byte(&mut self.i2c, BMP388_REG_FIFO_DATA)
     }
 
     pub fn get_fifo_watermark(&mut self) -> Result<u16, std::io::Error> {
+        let _call = self.call("get_fifo_watermark");
         // -- read FIFO watermark
         debug!("Reading FIFO watermark");
         i2cio::read_word(&mut self.i2c, BMP388_REG_FIFO_WATERMARK)
     }
 
-    fn read_fifo_frame_temperature(&mut self) -> Result<Option<u32>, std::io::Error> {
-        // -- use i2c block read to read 4 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame temperature");
-        let temperatore_raw = if bytes_read == FRAME_LENGTH {
-            // -- header is in byte 0, temperature flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 {
-                let temperature_xlsb = read_buf[1] as u32;
-                let temperature_lsb = read_buf[2] as u32;
-                let temperature_msb = read_buf[3] as u32;
-                let temperatore_raw = temperature_msb << 16 | temperature_lsb << 8 | temperature_xlsb;
-                Some(temperatore_raw)
-            } else {
-                warn!("Temperature flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for temperature");
-            None
-        };
-        Ok(temperatore_raw)
-    }
-
-    fn read_fifo_frame_temperature_with_time(&mut self) -> Result<(Option<u32>, Option<u32>), std::io::Error> {
-        // -- use i2c block read to read 8 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE + BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame temperature with sensor time ");
-        // -- read temperature bytes if the bytes read allow it
-        let temperatore_raw = if bytes_read >= BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE {
-            // -- header is in byte 0, temperature flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 {
-                let temperature_xlsb = read_buf[1] as u32;
-                let temperature_lsb = read_buf[2] as u32;
-                let temperature_msb = read_buf[3] as u32;
-                let temperatore_raw = temperature_msb << 16 | temperature_lsb << 8 | temperature_xlsb;
-                Some(temperatore_raw)
-            } else {
-                warn!("Temperature flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for temperature");
-            None
-        };
-        // -- read sensor time bytes if the bytes read allow it
-        let sensor_time = if bytes_read == FRAME_LENGTH {
-            // -- sensor time header is in byte 0, time flag is expected to be set
-            let sensor_time_header = read_buf[4];
-            if sensor_time_header & BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT > 0 {
-                let sensor_time_xlsb = read_buf[5] as u32;
-                let sensor_time_lsb = read_buf[6] as u32;
-                let sensor_time_msb = read_buf[7] as u32;
-                let sensor_time = sensor_time_msb << 16 | sensor_time_lsb << 8 | sensor_time_xlsb;
-                Some(sensor_time)
-            } else {
-                warn!("Sensor time flag expected in header but not set: {sensor_time_header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for sensor time");
-            None
-        };
-        Ok((temperatore_raw, sensor_time))
-
-    }
-
-    fn read_fifo_frame_pressure(&mut self) -> Result<Option<u32>, std::io::Error> {
-        // -- use i2c block read to read 4 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_PRESSURE;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame pressure");
-        // -- read pressure bytes if the bytes read allow it
-        let pressure_raw = if bytes_read == FRAME_LENGTH {
-            // -- header is in byte 0, pressure flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                let pressure_xlsb = read_buf[1] as u32;
-                let pressure_lsb = read_buf[2] as u32;
-                let pressure_msb = read_buf[3] as u32;
-                let pressure_raw = pressure_msb << 16 | pressure_lsb << 8 | pressure_xlsb;
-                Some(pressure_raw)
-            } else {
-                warn!("Pressure flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for pressure");
-            None
-        };
-        Ok(pressure_raw)
-    }
-
-    fn read_fifo_frame_pressure_with_time(&mut self) -> Result<(Option<u32>, Option<u32>), std::io::Error> {
-        // -- use i2c block read to read 8 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_PRESSURE + BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame pressure with sensor time");
-        // -- read pressure bytes if the bytes read allow it
-        let pressure_raw = if bytes_read >= BMP388_FIFO_FRAMLE_LENGTH_PRESSURE {
-            // -- header is in byte 0, pressure flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                let pressure_xlsb = read_buf[1] as u32;
-                let pressure_lsb = read_buf[2] as u32;
-                let pressure_msb = read_buf[3] as u32;
-                let pressure_raw = pressure_msb << 16 | pressure_lsb << 8 | pressure_xlsb;
-                Some(pressure_raw)
-            } else {
-                warn!("Pressure flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for pressure");
-            None
-        };
-        // -- read sensor time bytes if the bytes read allow it
-        let sensor_time = if bytes_read == FRAME_LENGTH {
-            // -- sensor time header is in byte 0, time flag is expected to be set
-            let sensor_time_header = read_buf[4];
-            if sensor_time_header & BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT > 0 {
-                let sensor_time_xlsb = read_buf[5] as u32;
-                let sensor_time_lsb = read_buf[6] as u32;
-                let sensor_time_msb = read_buf[7] as u32;
-                let sensor_time = sensor_time_msb << 16 | sensor_time_lsb << 8 | sensor_time_xlsb;
-                Some(sensor_time)
-            } else {
-                warn!("Sensor time flag expected in header but not set: {sensor_time_header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for sensor time");
-            None
-        };
-        Ok((pressure_raw, sensor_time))
-    }
-
-    fn read_fifo_frame_pressure_temperature(&mut self) -> Result<(Option<u32>, Option<u32>), std::io::Error> {
-        // -- use i2c block read to read 7 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_PRESSURE_TEMPERATURE;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame pressure and temperature");
-        // -- read temperature bytes if the bytes read allow it
-        let temperatore_raw = if bytes_read >= BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE {
-            // -- header is in byte 0, temperature flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 {
-                let temperature_xlsb = read_buf[1] as u32;
-                let temperature_lsb = read_buf[2] as u32;
-                let temperature_msb = read_buf[3] as u32;
-                let temperatore_raw = temperature_msb << 16 | temperature_lsb << 8 | temperature_xlsb;
-                Some(temperatore_raw)
-            } else {
-                warn!("Temperature flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for temperature");
-            None
-        };
-        // -- read pressure bytes if the bytes read allow it
-        let pressure_raw = if bytes_read == FRAME_LENGTH {
-            // -- header is in byte 0, pressure flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                let pressure_xlsb = read_buf[4] as u32;
-                let pressure_lsb = read_buf[5] as u32;
-                let pressure_msb = read_buf[6] as u32;
-                let pressure_raw = pressure_msb << 16 | pressure_lsb << 8 | pressure_xlsb;
-                Some(pressure_raw)
-            } else {
-                warn!("Pressure flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for pressure");
-            None
-        };
-        Ok((pressure_raw, temperatore_raw))
-    }
-
-    fn read_fifo_frame_pressure_temperature_with_time(&mut self) -> Result<(Option<u32>, Option<u32>, Option<u32>), std::io::Error> {
-        // -- use i2c block read to read 11 byte frame
-        const FRAME_LENGTH: usize = BMP388_FIFO_FRAMLE_LENGTH_PRESSURE_TEMPERATURE + BMP388_FIFO_FRAMLE_LENGTH_SENSOR_TIME;
-        let mut read_buf: [u8; FRAME_LENGTH] = [0; FRAME_LENGTH];
-        let bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_FIFO_DATA, &mut read_buf)?;
-        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame pressure and temperature with sensor time");
-        // -- read temperature bytes if the bytes read allow it
-        let temperatore_raw = if bytes_read >= BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE {
-            // -- header is in byte 0, temperature flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 {
-                let temperature_xlsb = read_buf[1] as u32;
-                let temperature_lsb = read_buf[2] as u32;
-                let temperature_msb = read_buf[3] as u32;
-                let temperatore_raw = temperature_msb << 16 | temperature_lsb << 8 | temperature_xlsb;
-                Some(temperatore_raw)
-            } else {
-                warn!("Temperature flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for temperature");
-            None
-        };
-        // -- read pressure bytes if the bytes read allow it
-        let pressure_raw = if bytes_read >= BMP388_FIFO_FRAMLE_LENGTH_PRESSURE_TEMPERATURE {
-            // -- header is in byte 0, pressure flag is expected to be set
-            let header = read_buf[0];
-            if header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                let pressure_xlsb = read_buf[4] as u32;
-                let pressure_lsb = read_buf[5] as u32;
-                let pressure_msb = read_buf[6] as u32;
-                let pressure_raw = pressure_msb << 16 | pressure_lsb << 8 | pressure_xlsb;
-                Some(pressure_raw)
-            } else {
-                warn!("Pressure flag expected in header but not set: {header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for pressure");
-            None
-        };
-        // -- read sensor time bytes if the bytes read allow it
-        let sensor_time = if bytes_read == FRAME_LENGTH {
-            // -- sensor time header is in byte 0, time flag is expected to be set
-            let sensor_time_header = read_buf[7];
-            if sensor_time_header & BMP388_FIFO_SENSOR_FRAME_SENSOR_TIME_BIT > 0 {
-                let sensor_time_xlsb = read_buf[8] as u32;
-                let sensor_time_lsb = read_buf[9] as u32;
-                let sensor_time_msb = read_buf[10] as u32;
-                let sensor_time = sensor_time_msb << 16 | sensor_time_lsb << 8 | sensor_time_xlsb;
-                Some(sensor_time)
-            } else {
-                warn!("Sensor time flag expected in header but not set: {sensor_time_header:#010b}");
-                None
-            }
-        } else {
-            warn!("Not enough bytes available for sensor time");
-            None
-        };
-        Ok((pressure_raw, temperatore_raw, sensor_time))
+    fn read_fifo_sensor_frame(&mut self, pressure: bool, temperature: bool, with_sensor_time: bool) -> Result<FifoData, std::io::Error> {
+        // -- use i2c block read to read the frame, 4 to 11 bytes
+        let frame_length = bmp388::sensor_frame_length(pressure, temperature, with_sensor_time);
+        let mut read_buf: [u8; BMP388_FIFO_FRAMLE_LENGTH_MAX] = [0; BMP388_FIFO_FRAMLE_LENGTH_MAX];
+        let bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), BMP388_REG_FIFO_DATA, &mut read_buf[..frame_length])?;
+        debug!("Read {bytes_read} bytes from FIFO data register for FIFO frame of {frame_length} bytes");
+        Ok(bmp388::parse_sensor_frame(&read_buf[..bytes_read.min(frame_length)], pressure, temperature, with_sensor_time))
     }
 
     pub fn read_next_fifo_data_frame(&mut self, ) -> Result<FifoData, std::io::Error> {
+        let _call = self.call("read_next_fifo_data_frame");
         // -- read FIFO data frame
         debug!("Reading FIFO data frame");
         // -- peek header to determine what frame to read
         let header = self.get_fifo_data()?;
-        if header & BMP388_FIFO_CONTROL_FRAME_BIT > 0 {
-            // -- either a config error or a config change
-            if header & BMP388_FIFO_CONTROL_FRAME_CONFIG_ERROR_BIT > 0 {
-                let _data_word = self.i2c.smbus_read_word_data(BMP388_REG_FIFO_DATA)?;
-                return Err(std::io::Error::other("FIFO configuration error"))
-            } else if header & BMP388_FIFO_CONTROL_FRAME_CONFIG_CHANGE_BIT > 0 {
-                let _data_word = self.i2c.smbus_read_word_data(BMP388_REG_FIFO_DATA)?;
-                return Ok(FifoData {
+        match FifoFrame::from_header(header) {
+            FifoFrame::ConfigError => {
+                let _data_word = i2cio::read_word(&mut self.i2c, BMP388_REG_FIFO_DATA)?;
+                Err(Error::Fifo("FIFO configuration error".to_string()).into())
+            },
+            FifoFrame::ConfigChange => {
+                let _data_word = i2cio::read_word(&mut self.i2c, BMP388_REG_FIFO_DATA)?;
+                Ok(FifoData {
                     pressure_raw: None, temperature_raw: None, sensor_time: None, config_change: true,
                 })
-            } else {
-                return Err(std::io::Error::other(format!("Unknown FIFO control header: {header:#010b}")))
-            }
-        }
-        else if header & BMP388_FIFO_SENSOR_FRAME_BIT > 0 {
-            let fifo_length = self.get_fifo_length()? as usize;
-            if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 && header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                if self.with_sensor_time == Bmp388FifoWithSensorTime::Enabled 
-                    && fifo_length == BMP388_FIFO_FRAMLE_LENGTH_PRESSURE_TEMPERATURE {
-                    let (pressure_raw, temperature_raw, sensor_time) = self.read_fifo_frame_pressure_temperature_with_time()?;
-                    return Ok(FifoData {
-                        pressure_raw: pressure_raw, temperature_raw, sensor_time, config_change: false,
-                    })
-                } else {
-                    let (pressure_raw, temperature_raw) = self.read_fifo_frame_pressure_temperature()?;
-                    return Ok(FifoData {
-                        pressure_raw: pressure_raw, temperature_raw, sensor_time: None, config_change: false,
-                    })
-                }
-            } else if header & BMP388_FIFO_SENSOR_FRAME_TEMPERATURE_BIT > 0 {
-                if self.with_sensor_time == Bmp388FifoWithSensorTime::Enabled
-                    && fifo_length == BMP388_FIFO_FRAMLE_LENGTH_TEMPERATURE {
-                    let (temperature_raw, sensor_time) = self.read_fifo_frame_temperature_with_time()?;
-                    return Ok(FifoData {
-                        pressure_raw: None, temperature_raw, sensor_time, config_change: false,
-                    })
-                } else {
-                    let temperature_raw = self.read_fifo_frame_temperature()?;
-                    return Ok(FifoData {
-                        pressure_raw: None, temperature_raw, sensor_time: None, config_change: false,
-                    })
-                }
-            } else if header & BMP388_FIFO_SENSOR_FRAME_PRESSURE_BIT > 0 {
-                if self.with_sensor_time == Bmp388FifoWithSensorTime::Enabled 
-                    && fifo_length == BMP388_FIFO_FRAMLE_LENGTH_PRESSURE {
-                    let (pressure_raw, sensor_time) = self.read_fifo_frame_pressure_with_time()?;
+            },
+            FifoFrame::UnknownControl => Err(Error::Fifo(format!("Unknown FIFO control header: {header:#010b}")).into()),
+            FifoFrame::Sensor { pressure, temperature } => {
+                let fifo_length = self.get_fifo_length()? as usize;
+                if !pressure && !temperature {
+                    // -- empty frame
+                    let _data_word = i2cio::read_word(&mut self.i2c, BMP388_REG_FIFO_DATA)?;
                     return Ok(FifoData {
-                        pressure_raw, temperature_raw: None, sensor_time, config_change: false,
-                    })
-                } else {
-                    let pressure_raw = self.read_fifo_frame_pressure()?;
-                    return Ok(FifoData {
-                        pressure_raw, temperature_raw: None, sensor_time: None, config_change: false,
+                        pressure_raw: None, temperature_raw: None, sensor_time: None, config_change: false,
                     })
                 }
-            } else {
-                // -- empty frame
-                let _data_word = self.i2c.smbus_read_word_data(BMP388_REG_FIFO_DATA)?;
-                return Ok(FifoData {
-                    pressure_raw: None, temperature_raw: None, sensor_time: None, config_change: false,
-                })
-            }
+                // -- the sensor time frame follows the last sensor frame
+                let with_sensor_time = self.with_sensor_time == Bmp388FifoWithSensorTime::Enabled
+                    && fifo_length == bmp388::sensor_frame_length(pressure, temperature, false);
+                self.read_fifo_sensor_frame(pressure, temperature, with_sensor_time)
+            },
+            FifoFrame::Unknown => Err(Error::Fifo(format!("Unknown FIFO header: {header:#010b}")).into()),
         }
-        return Err(std::io::Error::other(format!("Unknown FIFO header: {header:#010b}")))
-    }
-
-    fn concat_bytes(msb: u8, lsb: u8) -> u16 {
-        ((msb as u16) << 8) | (lsb as u16)
     }
 
-    fn get_calib_data(i2c: &mut I2c<File>) -> Result<CalibData, std::io::Error> {
+    fn get_calib_data(i2c: &mut B, functionality: Functionality, device_addr: u16) -> Result<CalibData, std::io::Error> {
         // -- get temperature and pressure calibration data
         let mut reg_data: [u8; BMP388_LEN_TRIMMING_COEFFICIENTS] = [0; BMP388_LEN_TRIMMING_COEFFICIENTS];
-        let _bytes_read = i2c.i2c_read_block_data(BMP388_REG_TRIMMING_COEFFICIENTS, &mut reg_data)?;
-        // -- temperature calibration coefficients
-        let par_t1 = Self::concat_bytes(reg_data[1], reg_data[0]);
-        // let par_t1 = par_t1 as f64 / 0.00390625;
-        let par_t1 = par_t1 as f64 * 256.0; // == 1 / 0.00390625;
-        let par_t2 = Self::concat_bytes(reg_data[3], reg_data[2]);
-        // let par_t2 = par_t2 as f64 / 1073741824.0;
-        let par_t2 = par_t2 as f64 * 0.000000000931323; // == 1 / 1073741824.0
-        let par_t3 = reg_data[4] as i8;
-        // let par_t3 = par_t3 as f64 / 281474976710656.0;
-        let par_t3 = par_t3 as f64 * 0.000000000000004; // == 1 / 281474976710656.0
-
-        // -- pressure calibration coefficients
-        let par_p1 = Self::concat_bytes(reg_data[6], reg_data[5]) as i16;
-        //let par_p1 = (par_p1 - 16384) as f64 / 1048576.0;
-        let par_p1 = (par_p1 - 16384) as f64 * 0.000000953674316;
-        let par_p2 = Self::concat_bytes(reg_data[8], reg_data[7]) as i16;
-        //let par_p2 = (par_p2 - 16384) as f64 / 536870912.0;
-        let par_p2 = (par_p2 - 16384) as f64 * 0.000000001862645;
-        let par_p3 = reg_data[9] as i8;
-        //let par_p3 = par_p3 as f64 / 4294967296.0;
-        let par_p3 = par_p3 as f64 * 0.000000000232831;
-        let par_p4 = reg_data[10] as i8;
-        //let par_p4 = (par_p4 as f64) / 137438953472.0;
-        let par_p4 = (par_p4 as f64) * 0.000000000007276;
-        let par_p5 = Self::concat_bytes(reg_data[12], reg_data[11]);
-        //let par_p5 = (par_p5 as f64) / 0.125;
-        let par_p5 = (par_p5 as f64) * 8.0;
-        let par_p6 = Self::concat_bytes(reg_data[14], reg_data[13]);
-        //let par_p6 = (par_p6 as f64) / 64.0;
-        let par_p6 = (par_p6 as f64) * 0.015625;
-        let par_p7 = reg_data[15] as i8;
-        //let par_p7 = (par_p7 as f64) / 256.0;
-        let par_p7 = (par_p7 as f64) * 0.00390625;
-        let par_p8 = reg_data[16] as i8;
-        //let par_p8 = (par_p8 as f64) / 32768.0;
-        let par_p8 = (par_p8 as f64) * 0.000030517578125;
-        let par_p9 = Self::concat_bytes(reg_data[18], reg_data[17]) as i16;
-        //let par_p9 = (par_p9 as f64) / 281474976710656.0;
-        let par_p9 = (par_p9 as f64) * 0.000000000000004;
-        let par_p10 = reg_data[19] as i8;
-        //let par_p10 = (par_p10 as f64) / 281474976710656.0;
-        let par_p10 = (par_p10 as f64) * 0.000000000000004;
-        let par_p11 = reg_data[20] as i8;
-        //let par_p11 = (par_p11 as f64) / 36893488147419103232.0;
-        let par_p11 = (par_p11 as f64) * 0.00000000000000000002710505431213761;
-
+        let _bytes_read = i2cio::read_block(i2c, functionality, device_addr, BMP388_REG_TRIMMING_COEFFICIENTS, &mut reg_data)?;
         // -- create calibration structure
-        let calib_data = CalibData {
-            par_t1, par_t2, par_t3,
-            par_p1, par_p2, par_p3, par_p4, par_p5, par_p6,
-            par_p7, par_p8, par_p9, par_p10, par_p11,
-        };
+        let calib_data = CalibData::from_registers(&reg_data);
         debug!("Got calibration data: {calib_data:#?}");
         Ok(calib_data)
 
     }
 
     pub fn get_data_raw(&mut self) -> Result<DataRaw, std::io::Error> {
+        let _call = self.call("get_data_raw");
         // -- get temperature and pressure data
-        const DATA_LEN: usize = BMP388_LEN_PRESSURE_DATA + BMP388_LEN_TEMPERATURE_DATA;
-        let mut reg_data: [u8; DATA_LEN] = [0; DATA_LEN];
-        let _bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_PRESSURE_DATA, &mut reg_data)?;
+        let mut reg_data: [u8; BMP388_LEN_DATA] = [0; BMP388_LEN_DATA];
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), BMP388_REG_PRESSURE_DATA, &mut reg_data)?;
         debug!("Got {_bytes_read} bytes of raw data");
-        let data_xlsb = reg_data[0] as u32;
-        let data_lsb = (reg_data[1] as u32) << 8;
-        let data_msb = (reg_data[2] as u32) << 16;
-        let pressure = data_msb | data_lsb | data_xlsb;
-        let data_xlsb = reg_data[3] as u32;
-        let data_lsb = (reg_data[4] as u32) << 8;
-        let data_msb = (reg_data[5] as u32) << 16;
-        let temperature = data_msb | data_lsb | data_xlsb;
         // -- create raw data structure
-        let data_raw = DataRaw {
-            pressure,
-            temperature,
-        };
+        let data_raw = DataRaw::from_registers(&reg_data);
         debug!("Got data raw: {data_raw:#?}");
         Ok(data_raw)
     }
 
     pub fn get_pressure_raw(&mut self) -> Result<u32, std::io::Error> {
+        let _call = self.call("get_pressure_raw");
         // -- get temperature and pressure data
         let mut reg_data: [u8; BMP388_LEN_PRESSURE_DATA] = [0; BMP388_LEN_PRESSURE_DATA];
-        let _bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_PRESSURE_DATA, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), BMP388_REG_PRESSURE_DATA, &mut reg_data)?;
         let pressure = (reg_data[2] as u32) << 16 | (reg_data[1] as u32) << 8 | (reg_data[0] as u32);
         debug!("Got raw pressure: {pressure}");
         Ok(pressure)
     }
 
     pub fn get_temperature_raw(&mut self) -> Result<u32, std::io::Error> {
+        let _call = self.call("get_temperature_raw");
         // -- get temperature and pressure data
         let mut reg_data: [u8; BMP388_LEN_TEMPERATURE_DATA] = [0; BMP388_LEN_TEMPERATURE_DATA];
-        let _bytes_read = self.i2c.i2c_read_block_data(BMP388_REG_TEMPERATURE_DATA, &mut reg_data)?;
+        let _bytes_read = i2cio::read_block(&mut self.i2c, self.functionality, self.device_addr.value(), BMP388_REG_TEMPERATURE_DATA, &mut reg_data)?;
         let temperature = (reg_data[2] as u32) << 16 | (reg_data[1] as u32) << 8 | (reg_data[0] as u32);
         debug!("Got raw temperature: {temperature}");
         Ok(temperature)
     }
 
     pub fn set_osr_pressure_temperature(&mut self, osr_p: Bmp388OverSamplingPr, osr_t : Bmp388OverSamplingTp) -> Result<(), std::io::Error> {
+        let _call = self.call("set_osr_pressure_temperature");
         // -- write oversampling for pressure and temperature
         let reg_val = osr_t.value() << 3 | osr_p.value();
         debug!("Setting register BMP388_REG_OVERSAMPLING_RATE {BMP388_REG_OVERSAMPLING_RATE:#x} to value {reg_val:#010b} / {osr_p} for pressure, {osr_t} for temperature");
         i2cio::write_byte(&mut self.i2c, BMP388_REG_OVERSAMPLING_RATE, reg_val)
     }
 
-    pub fn get_pressure_and_temperature(&self, data_raw: &DataRaw) -> (f64, f64) {
+    pub fn get_pressure_and_temperature(&self, data_raw: &DataRaw) -> (Pressure, Temperature) {
         let temperature = self.get_temperature(data_raw.temperature);
         let pressure = self.get_pressure(data_raw.pressure, temperature);
         (pressure, temperature)
     }
 
-    pub fn get_temperature(&self, temperature_raw: u32) -> f64 {
-        let temperature_raw = temperature_raw as f64;
-        let partial_data1 = temperature_raw - self.calib_data.par_t1;
-        let partial_data2 = partial_data1 * self.calib_data.par_t2;
-        let temperature = partial_data2 + ((partial_data1 * partial_data1) * self.calib_data.par_t3);
-        temperature
-    }
-
-    pub fn get_pressure(&self, pressure_raw: u32, temperature: f64) -> f64 {
-        let temperature_pow_2 = temperature.powi(2);
-        let temperature_pow_3 = temperature.powi(3);
-        let pressure_raw = pressure_raw as f64;
-        let partial_data1 = self.calib_data.par_p6 * temperature;
-        let partial_data2 = self.calib_data.par_p7 * temperature_pow_2;
-        let partial_data3 = self.calib_data.par_p8 * temperature_pow_3;
-        let partial_out1 = self.calib_data.par_p5 + partial_data1 + partial_data2 + partial_data3;
-        let partial_data1 = self.calib_data.par_p2 * temperature;
-        let partial_data2 = self.calib_data.par_p3 * temperature_pow_2;
-        let partial_data3 = self.calib_data.par_p4 * temperature_pow_3;
-        let partial_out2 = pressure_raw * (self.calib_data.par_p1 + partial_data1 + partial_data2 + partial_data3);
-        let partial_data1 = pressure_raw.powi(2);
-        let partial_data2 = self.calib_data.par_p9 + self.calib_data.par_p10 * temperature;
-        let partial_data3 = partial_data1 * partial_data2;
-        let partial_data4 = partial_data3 + pressure_raw.powi(3) * self.calib_data.par_p11;
-        let pressure = partial_out1 + partial_out2 + partial_data4;
-        pressure
-    }
-
-}
\ No newline at end of file
+    pub fn get_temperature(&self, temperature_raw: u32) -> Temperature {
+        Temperature::from_celsius(self.calib_data.temperature(temperature_raw))
+    }
+
+    pub fn get_pressure(&self, pressure_raw: u32, temperature: Temperature) -> Pressure {
+        Pressure::from_pascal(self.calib_data.pressure(pressure_raw, temperature.celsius()))
+    }
+
+    // -- writes the whole config in sleep mode, the power mode goes last so a normal mode
+    // -- conversion only ever runs with all of it; e.g. after a reset
+    pub fn apply_config(&mut self, config: &Bmp388Config) -> Result<(), std::io::Error> {
+        let _call = self.call("apply_config");
+        config.validate()?;
+        if config.device_addr != self.device_addr {
+            let errmsg = format!("Config is for device address {:#04x}, not {:#04x}",
+                config.device_addr.value(), self.device_addr.value());
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        self.set_sensor_mode(Bmp388SensorPowerMode::Sleep, config.enable_pressure.clone(), config.enable_temperature.clone())?;
+        self.set_osr_pressure_temperature(config.osr_p.clone(), config.osr_t.clone())?;
+        self.set_irr_filter(config.irr_filter.clone())?;
+        self.set_output_data_rate(config.odr.clone())?;
+        match &config.fifo {
+            Some(fifo) => self.enable_fifo(fifo.stop_on_full.clone(), fifo.with_pressure.clone(),
+                fifo.with_temperature.clone(), fifo.with_sensor_time.clone(), fifo.data_filtered.clone(), fifo.subsampling)?,
+            None => self.disable_fifo()?,
+        }
+        self.set_sensor_mode(config.pwr_mode.clone(), config.enable_pressure.clone(), config.enable_temperature.clone())
+    }
+
+}
+
+// -- a brown-out clears the calibration image, read it again
+impl<B: I2cBus> Recoverable for BMP388<B> {
+
+    fn recover(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("recover");
+        i2cio::set_slave(&mut self.i2c, self.device_addr.value())?;
+        self.calib_data = Self::get_calib_data(&mut self.i2c, self.functionality, self.device_addr.value())?;
+        Ok(())
+    }
+}
+
+// -- triggers a forced conversion of both pressure and temperature unless the sensor runs in
+// -- normal mode, it drops back to sleep mode once the conversion is done
+impl<B: I2cBus> Sensor for BMP388<B> {
+
+    fn name(&self) -> &'static str {
+        "BMP388"
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        let (sensor_mode, _, _) = self.get_sensor_mode()?;
+        if !matches!(sensor_mode, Bmp388SensorPowerMode::Normal) {
+            self.set_sensor_mode(Bmp388SensorPowerMode::Forced,
+                Bmp388StatusPressureSensor::Enabled, Bmp388StatusTemperatureSensor::Enabled)?;
+            let started = i2cio::now(&self.i2c);
+            loop {
+                let (_, pressure_data, temperature_data) = self.get_status()?;
+                if matches!((pressure_data, temperature_data), (Bmp388StatusPressureData::Ready, Bmp388StatusTemperatureData::Ready)) {
+                    break
+                }
+                if i2cio::now(&self.i2c).saturating_sub(started) >= Duration::from_millis(BMP388_MEASURE_TIMEOUT_MS) {
+                    return Err(Error::Timeout(format!("BMP388 measurement not done after {BMP388_MEASURE_TIMEOUT_MS} ms")).into())
+                }
+                i2cio::delay(&mut self.i2c, BMP388_MEASURE_POLL_INTERVAL_MS);
+            }
+        }
+        let data_raw = self.get_data_raw()?;
+        let (pressure, temperature) = self.get_pressure_and_temperature(&data_raw);
+        Ok(Measurement {
+            temperature: Some(temperature),
+            pressure: Some(pressure),
+            ..Default::default()
+        })
+    }
+}
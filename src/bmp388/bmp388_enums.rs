@@ -6,6 +6,7 @@ pub enum Bmp388DeviceAddress {
     Secondary,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Bmp388DeviceAddress {
     fn default() -> Self {
         Self::Default
@@ -24,7 +25,7 @@ impl Bmp388DeviceAddress {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388SensorPowerMode {
     Sleep,
     Forced,
@@ -55,7 +56,7 @@ impl fmt::Display for Bmp388SensorPowerMode {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388StatusPressureSensor {
     Disabled,
     Enabled,
@@ -79,7 +80,7 @@ impl fmt::Display for Bmp388StatusPressureSensor {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388StatusTemperatureSensor {
     Disabled,
     Enabled,
@@ -104,6 +105,7 @@ impl fmt::Display for Bmp388StatusTemperatureSensor {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388OverSamplingPr {
     UltraLowX1, LowX2, StandardX4,
     HighX8, UltraHighX16, HighestX32,
@@ -143,6 +145,7 @@ impl fmt::Display for Bmp388OverSamplingPr {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388OverSamplingTp {
     X1, X2, X4, X8, X16, X32,
 }
@@ -181,6 +184,7 @@ impl fmt::Display for Bmp388OverSamplingTp {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388OutputDataRate {
     Ax200Hz, Bx100Hz, Cx50Hz, Dx25Hz, Ex12_5Hz, 
     Fx6_25Hz, Gx3_1Hz, Hx1_5Hz, Ix0_78Hz, Jx0_39Hz, 
@@ -213,6 +217,7 @@ impl Bmp388OutputDataRate {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388IrrFilter {
     Off, Coef1, Coef3, Coef7, Coef15, Coef31, Coef63, Coef127,
 }
@@ -286,7 +291,7 @@ impl fmt::Display for Bmp388StatusTemperatureData {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388FifoStopOnFull {
     Disabled,
     Enabled,
@@ -301,7 +306,7 @@ impl Bmp388FifoStopOnFull {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388FifoWithPressureData {
     Disabled,
     Enabled,
@@ -316,7 +321,7 @@ impl Bmp388FifoWithPressureData {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388FifoWithTemperatureData {
     Disabled,
     Enabled,
@@ -331,7 +336,7 @@ impl Bmp388FifoWithTemperatureData {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388FifoWithSensorTime {
     Disabled,
     Enabled,
@@ -346,7 +351,7 @@ impl Bmp388FifoWithSensorTime {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Bmp388FifoDataFiltered {
     Unfiltered,
     Filtered,
@@ -0,0 +1,205 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+// -- how long to back off between two attempts to get the lock
+const BUS_LOCK_RETRY_INTERVAL_MS: u32 = 1;
+
+// -- opt-in advisory lock (flock) shared by all processes that use the same lock path,
+// -- held for every single transfer and across multi-message transactions
+pub struct LockedBus<B: I2cBus = I2c<File>> {
+    // -- the guarded bus
+    bus: B,
+    // -- the locked file, either the device node or a dedicated lock file
+    lock_file: File,
+    lock_path: PathBuf,
+    // -- how long to wait for a busy bus
+    timeout: Duration,
+    // -- nesting depth of the transactions holding the lock
+    depth: usize,
+}
+
+impl LockedBus {
+
+    // -- lock the device node itself, every cooperating program opening the bus this way shares the lock
+    pub fn open(i2c_bus_path: &Path, timeout: Duration) -> Result<LockedBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        let lock_file = File::open(i2c_bus_path)?;
+        Ok(Self::with_lock_file(i2c, lock_file, i2c_bus_path, timeout))
+    }
+
+}
+
+impl<B: I2cBus> LockedBus<B> {
+
+    // -- lock a dedicated lock file, e.g. /run/lock/i2c-1.lock, created if it does not exist
+    pub fn new(bus: B, lock_path: &Path, timeout: Duration) -> Result<Self, std::io::Error> {
+        let lock_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path)?;
+        Ok(Self::with_lock_file(bus, lock_file, lock_path, timeout))
+    }
+
+    fn with_lock_file(bus: B, lock_file: File, lock_path: &Path, timeout: Duration) -> Self {
+        LockedBus {
+            bus,
+            lock_file,
+            lock_path: lock_path.to_path_buf(),
+            timeout,
+            depth: 0,
+        }
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn flock(&self, operation: libc::c_int) -> Result<(), std::io::Error> {
+        // -- SAFETY: the descriptor is owned by lock_file and stays open for the call
+        let res = unsafe { libc::flock(self.lock_file.as_raw_fd(), operation) };
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    fn acquire(&mut self) -> Result<(), std::io::Error> {
+        if self.depth > 0 {
+            self.depth += 1;
+            return Ok(())
+        }
+        let started = self.bus.now();
+        loop {
+            match self.flock(libc::LOCK_EX | libc::LOCK_NB) {
+                Ok(()) => break,
+                Err(err) if err.raw_os_error() == Some(libc::EWOULDBLOCK) => {
+                    if self.bus.now().saturating_sub(started) >= self.timeout {
+                        let errmsg = format!("I2C bus '{}' is still locked by another process after {} ms",
+                            self.lock_path.display(), self.timeout.as_millis());
+                        return Err(Error::Timeout(errmsg).into())
+                    }
+                    self.bus.delay_ms(BUS_LOCK_RETRY_INTERVAL_MS);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        debug!("Locked I2C bus '{}' after {} us", self.lock_path.display(), self.bus.now().saturating_sub(started).as_micros());
+        self.depth = 1;
+        Ok(())
+    }
+
+    fn release(&mut self) -> Result<(), std::io::Error> {
+        match self.depth {
+            0 => Ok(()),
+            1 => {
+                self.depth = 0;
+                self.flock(libc::LOCK_UN)
+            }
+            _ => {
+                self.depth -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn locked<R>(&mut self, op: impl FnOnce(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+        self.acquire()?;
+        let result = op(&mut self.bus);
+        let release_result = self.release();
+        let result = result?;
+        release_result?;
+        Ok(result)
+    }
+}
+
+impl<B: I2cBus> I2cBus for LockedBus<B> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        // -- the slave address is per file descriptor so setting it needs no lock
+        self.bus.set_slave(dev_addr)
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.locked(|bus| bus.smbus_read_byte_data(register))
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.locked(|bus| bus.smbus_read_word_data(register))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.locked(|bus| bus.smbus_write_byte(data))
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.locked(|bus| bus.smbus_write_byte_data(register, data))
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.locked(|bus| bus.smbus_write_word_data(register, data))
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.locked(|bus| bus.smbus_write_block_data(register, data))
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.locked(|bus| bus.i2c_read_block_data(register, data))
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.locked(|bus| bus.i2c_transfer(messages))
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.bus.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.bus.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.acquire()?;
+        if let Err(err) = self.bus.begin_transaction() {
+            self.release()?;
+            return Err(err)
+        }
+        Ok(())
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        let result = self.bus.end_transaction();
+        self.release()?;
+        result
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.bus.retry_policy()
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        self.bus.record(event)
+    }
+}
@@ -0,0 +1,251 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+// -- counters of one device's bus traffic, bytes are the data moved by the smbus calls plus
+// -- the full payload of raw messages; delays and setting the slave address are not counted
+#[derive(Clone, Debug, Default)]
+pub struct BusStats {
+    reads: u64,
+    writes: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+    errors: HashMap<std::io::ErrorKind, u64>,
+    retries: u64,
+    crc_failures: u64,
+    bus_time: Duration,
+}
+
+impl BusStats {
+
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    pub fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    // -- failed transfers by error kind
+    pub fn errors(&self) -> &HashMap<std::io::ErrorKind, u64> {
+        &self.errors
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    pub fn crc_failures(&self) -> u64 {
+        self.crc_failures
+    }
+
+    // -- time spent in transfers, failed ones included
+    pub fn bus_time(&self) -> Duration {
+        self.bus_time
+    }
+}
+
+impl fmt::Display for BusStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "reads {}, writes {}, bytes read {}, bytes written {}, errors {}, retries {}, crc failures {}, bus time {} us",
+            self.reads, self.writes, self.bytes_read, self.bytes_written, self.error_count(), self.retries,
+            self.crc_failures, self.bus_time.as_micros())
+    }
+}
+
+// -- hands out snapshots of a stats bus the driver owns, clones share the counters
+#[derive(Clone)]
+pub struct BusStatsHandle {
+    stats: Arc<Mutex<BusStats>>,
+}
+
+impl BusStatsHandle {
+
+    pub fn snapshot(&self) -> BusStats {
+        lock(&self.stats).clone()
+    }
+
+    pub fn reset(&self) {
+        *lock(&self.stats) = BusStats::default();
+    }
+}
+
+// -- counters are still good after a panic elsewhere, keep counting
+fn lock(stats: &Mutex<BusStats>) -> MutexGuard<'_, BusStats> {
+    stats.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// -- counts the traffic of the driver it is handed to; wrap the bus of every driver instance
+// -- to tell the devices apart, and wrap it before a retry bus so retries are counted, e.g.
+// -- RetryBus::new(StatsBus::new(shared_bus.device()), policy)
+pub struct StatsBus<B: I2cBus = I2c<File>> {
+    // -- the counted bus
+    bus: B,
+    // -- counters shared with the handles
+    stats: Arc<Mutex<BusStats>>,
+}
+
+impl StatsBus {
+
+    pub fn open(i2c_bus_path: &Path) -> Result<StatsBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Ok(Self::new(i2c))
+    }
+
+}
+
+impl<B: I2cBus> StatsBus<B> {
+
+    pub fn new(bus: B) -> Self {
+        StatsBus {
+            bus,
+            stats: Arc::new(Mutex::new(BusStats::default())),
+        }
+    }
+
+    pub fn handle(&self) -> BusStatsHandle {
+        BusStatsHandle {
+            stats: self.stats.clone(),
+        }
+    }
+
+    pub fn release(self) -> B {
+        self.bus
+    }
+
+    fn counted<R>(&mut self, is_read: bool, op: impl FnOnce(&mut B) -> Result<R, std::io::Error>,
+        bytes: impl FnOnce(&R) -> u64) -> Result<R, std::io::Error> {
+        let started = self.bus.now();
+        let result = op(&mut self.bus);
+        let elapsed = self.bus.now().saturating_sub(started);
+        let mut stats = lock(&self.stats);
+        stats.bus_time += elapsed;
+        if is_read {
+            stats.reads += 1;
+        } else {
+            stats.writes += 1;
+        }
+        match &result {
+            Ok(val) if is_read => stats.bytes_read += bytes(val),
+            Ok(val) => stats.bytes_written += bytes(val),
+            Err(err) => *stats.errors.entry(err.kind()).or_insert(0) += 1,
+        }
+        result
+    }
+}
+
+impl<B: I2cBus> I2cBus for StatsBus<B> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.bus.set_slave(dev_addr)
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.counted(true, |bus| bus.smbus_read_byte_data(register), |_| 1)
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.counted(true, |bus| bus.smbus_read_word_data(register), |_| 2)
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.counted(false, |bus| bus.smbus_write_byte(data), |_| 1)
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.counted(false, |bus| bus.smbus_write_byte_data(register, data), |_| 1)
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.counted(false, |bus| bus.smbus_write_word_data(register, data), |_| 2)
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        let len = data.len() as u64;
+        self.counted(false, |bus| bus.smbus_write_block_data(register, data), |_| len)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.counted(true, |bus| bus.i2c_read_block_data(register, data), |bytes_read| *bytes_read as u64)
+    }
+
+    // -- every message counts as a read or a write of its own
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        let started = self.bus.now();
+        let result = self.bus.i2c_transfer(messages);
+        let elapsed = self.bus.now().saturating_sub(started);
+        let mut stats = lock(&self.stats);
+        stats.bus_time += elapsed;
+        for message in messages.iter() {
+            match message {
+                Message::Read { .. } => stats.reads += 1,
+                Message::Write { .. } => stats.writes += 1,
+            }
+        }
+        match &result {
+            Ok(()) => {
+                for message in messages.iter() {
+                    match message {
+                        Message::Read { .. } => stats.bytes_read += message.len() as u64,
+                        Message::Write { .. } => stats.bytes_written += message.len() as u64,
+                    }
+                }
+            }
+            Err(err) => *stats.errors.entry(err.kind()).or_insert(0) += 1,
+        }
+        result
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.bus.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.bus.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.end_transaction()
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.bus.retry_policy()
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        match event {
+            BusEvent::Retry => lock(&self.stats).retries += 1,
+            BusEvent::CrcFailure => lock(&self.stats).crc_failures += 1,
+        }
+        self.bus.record(event)
+    }
+}
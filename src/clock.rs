@@ -0,0 +1,153 @@
+use i2c_linux::{Functionality, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use std::thread;
+
+use crate::i2cio::{BusEvent, I2cBus, RetryPolicy};
+
+// -- where the drivers get the time and wait from, the system clock unless the bus brings its own
+pub trait Clock {
+    // -- monotonic time since an arbitrary but fixed start
+    fn now(&self) -> Duration;
+
+    fn delay_ms(&mut self, milli_secs: u32);
+}
+
+// -- monotonic time since the first query in this process, waits put the thread to sleep
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+static SYSTEM_CLOCK_START: OnceLock<Instant> = OnceLock::new();
+
+impl Clock for SystemClock {
+
+    fn now(&self) -> Duration {
+        SYSTEM_CLOCK_START.get_or_init(Instant::now).elapsed()
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        let delay = Duration::from_millis(milli_secs as u64);
+        thread::sleep(delay);
+    }
+}
+
+// -- time that only moves when someone waits or advances it, e.g. to run hours of sensor
+// -- warm-up in a test within milliseconds; clones share the time
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl VirtualClock {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Clock for VirtualClock {
+
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.advance(Duration::from_millis(milli_secs as u64));
+    }
+}
+
+// -- hands the waits and time queries of the driver it is given to a clock of choice,
+// -- e.g. a virtual clock in tests or the scheduler of an embedding application
+pub struct ClockedBus<B: I2cBus, C: Clock> {
+    // -- the bus doing the transfers
+    bus: B,
+    // -- the clock doing the waits
+    clock: C,
+}
+
+impl<B: I2cBus, C: Clock> ClockedBus<B, C> {
+
+    pub fn new(bus: B, clock: C) -> Self {
+        ClockedBus {
+            bus,
+            clock,
+        }
+    }
+
+    pub fn release(self) -> (B, C) {
+        (self.bus, self.clock)
+    }
+}
+
+impl<B: I2cBus, C: Clock> I2cBus for ClockedBus<B, C> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.bus.set_slave(dev_addr)
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.bus.smbus_read_byte_data(register)
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.bus.smbus_read_word_data(register)
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_byte(data)
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_byte_data(register, data)
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_word_data(register, data)
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_block_data(register, data)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.bus.i2c_read_block_data(register, data)
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.bus.i2c_transfer(messages)
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.clock.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.end_transaction()
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.bus.retry_policy()
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        self.bus.record(event)
+    }
+}
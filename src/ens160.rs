@@ -4,11 +4,17 @@ use log::{debug, error, log_enabled, info, Level};
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 
-use crate::i2cio;
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::retry::Recoverable;
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{Concentration, RelativeHumidity, Temperature};
+use crate::trace::{self, DriverCall};
 
-const ENS160_PART_ID: u16 = 0x160;
-const ENS160_REG_PART_ID: u8 = 0x00;
+pub(crate) const ENS160_PART_ID: u16 = 0x160;
+pub(crate) const ENS160_REG_PART_ID: u8 = 0x00;
 const ENS160_REG_OP_MODE: u8 = 0x10;
 const ENS160_REG_TEMP_IN: u8 = 0x13;
 const ENS160_REG_RH_IN: u8 = 0x15;
@@ -17,6 +23,9 @@ const ENS160_REG_DATA_AQI: u8 = 0x21;
 const ENS160_REG_DATA_TVOC: u8 = 0x22;
 const ENS160_REG_DATA_ECO2: u8 = 0x24;
 
+trace::register_names!(ENS160_REG_PART_ID, ENS160_REG_OP_MODE, ENS160_REG_TEMP_IN, ENS160_REG_RH_IN,
+    ENS160_REG_DEVICE_STATUS, ENS160_REG_DATA_AQI, ENS160_REG_DATA_TVOC, ENS160_REG_DATA_ECO2);
+
 #[allow(dead_code)]
 const ENS160_OP_MODE_DEEP_SLEEP: u8 = 0x00;
 
@@ -24,6 +33,9 @@ const ENS160_OP_MODE_DEEP_SLEEP: u8 = 0x00;
 const ENS160_OP_MODE_IDLE: u8 = 0x01;
 const ENS160_OP_MODE_OPERATIONAL: u8 = 0x02;
 
+// -- the validity flag is updated with every measurement, once per second
+const ENS160_VALIDITY_POLL_INTERVAL_MS: u32 = 1000;
+
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Ens160DeviceAddress {
@@ -31,17 +43,19 @@ pub enum Ens160DeviceAddress {
     Secondary,     
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Ens160DeviceAddress {
     fn default() -> Self {
         Ens160DeviceAddress::Default
     }
 }
 
+
 impl Ens160DeviceAddress {
     const DEVICE_ADDR_DEFAULT: u16 = 0x53;
     const DEVICE_ADDR_SECONDARY: u16 = 0x52;
     
-    fn value(&self) -> u16 {
+    pub(crate) fn value(&self) -> u16 {
         match *self {
             Self::Default => Self::DEVICE_ADDR_DEFAULT,
             Self::Secondary => Self::DEVICE_ADDR_SECONDARY,
@@ -97,8 +111,7 @@ impl Ens160AirQualityIndex {
     const ENS160_AQI_GOOD: u8 = 2;
     const ENS160_AQI_FAIRMODERATE: u8 = 3;
     const ENS160_AQI_POOR: u8 = 4;
-    #[allow(dead_code)]
-    const ENS160_AQI_BADUNHEALTHY: u8 = 5;    
+    const ENS160_AQI_BADUNHEALTHY: u8 = 5;
 
     fn from(val: u8) -> Self {
         match val {
@@ -109,6 +122,16 @@ impl Ens160AirQualityIndex {
             _ => Self::BadUnhealthy,
         }
     }
+
+    pub fn value(&self) -> u8 {
+        match *self {
+            Self::Excellent => Self::ENS160_AQI_EXCELLENT,
+            Self::Good => Self::ENS160_AQI_GOOD,
+            Self::FairModerate => Self::ENS160_AQI_FAIRMODERATE,
+            Self::Poor => Self::ENS160_AQI_POOR,
+            Self::BadUnhealthy => Self::ENS160_AQI_BADUNHEALTHY,
+        }
+    }
 }
 
 impl fmt::Display for Ens160AirQualityIndex {
@@ -155,9 +178,9 @@ impl fmt::Display for Ens160EquivalentCO2 {
     }
 }
 
-pub struct ENS160 {
+pub struct ENS160<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address.
     device_addr: Ens160DeviceAddress,
     // -- part id    
@@ -168,14 +191,22 @@ impl ENS160 {
 
     pub fn new(i2c_bus_path: &Path, device_addr: Ens160DeviceAddress) -> Result<ENS160, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr)
+    }
+
+}
+
+impl<B: I2cBus> ENS160<B> {
+
+    pub fn with_bus(mut i2c: B, device_addr: Ens160DeviceAddress) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("ENS160", "with_bus", device_addr.value(), register_name);
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;
         // -- check if device is available by reading part id
         let part_id = Self::read_part_id(&mut i2c)?;
         if part_id != ENS160_PART_ID {
-            let errmsg = format!("Found unknown part id '{part_id:#06x}', expected '{ENS160_PART_ID:#06x}'");
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, errmsg))
+            return Err(Error::ChipId { expected: ENS160_PART_ID, found: part_id }.into())
         }
         debug!("ENS160 part id: {part_id:#06x}");
         // -- check if op mode is operational and if not make it so
@@ -194,16 +225,21 @@ impl ENS160 {
             part_id,
         })
     }
+
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("ENS160", call, self.device_addr.value(), register_name)
+    }
     
-    fn read_part_id(i2c: &mut I2c<File>) -> Result<u16, std::io::Error> {
+    fn read_part_id(i2c: &mut B) -> Result<u16, std::io::Error> {
         i2cio::read_word(i2c, ENS160_REG_PART_ID)
     }
 
-    fn read_op_mode(i2c: &mut I2c<File>) -> Result<u8, std::io::Error> {
+    fn read_op_mode(i2c: &mut B) -> Result<u8, std::io::Error> {
         i2cio::read_byte(i2c, ENS160_REG_OP_MODE)
     }
 
-    fn set_op_mode_operational(i2c: &mut I2c<File>) -> Result<(), std::io::Error> {
+    fn set_op_mode_operational(i2c: &mut B) -> Result<(), std::io::Error> {
         i2cio::write_byte(i2c, ENS160_REG_OP_MODE, ENS160_OP_MODE_OPERATIONAL)
     }
 
@@ -223,6 +259,7 @@ impl ENS160 {
     }
 
     pub fn get_validity(&mut self) -> Result<Ens160Validity, std::io::Error> {
+        let _call = self.call("get_validity");
         let device_status = self.get_device_status()?;
         let validity_code = (device_status & 0b00001100) >> 2;
         let validity = Ens160Validity::from(validity_code);
@@ -230,50 +267,113 @@ impl ENS160 {
         Ok(validity)
     }
 
+    // -- poll the validity on the bus clock until the output is valid, warm-up takes 3 minutes and
+    // -- the initial start-up of a new sensor 1 hour; a timeout error if that takes longer than given
+    pub fn wait_for_valid_output(&mut self, timeout: Duration) -> Result<(), std::io::Error> {
+        let _call = self.call("wait_for_valid_output");
+        let started = i2cio::now(&self.i2c);
+        loop {
+            if matches!(self.get_validity()?, Ens160Validity::OperatingOk) {
+                return Ok(())
+            }
+            if i2cio::now(&self.i2c).saturating_sub(started) >= timeout {
+                return Err(Error::Timeout(format!("ENS160 output still not valid after {} ms", timeout.as_millis())).into())
+            }
+            i2cio::delay(&mut self.i2c, ENS160_VALIDITY_POLL_INTERVAL_MS);
+        }
+    }
+
     pub fn get_air_quality_index(&mut self) -> Result<Ens160AirQualityIndex, std::io::Error> {
+        let _call = self.call("get_air_quality_index");
         let aqi_code = i2cio::read_byte(&mut self.i2c, ENS160_REG_DATA_AQI)?;
         let aqi = Ens160AirQualityIndex::from(aqi_code);
-        debug!("END160 Air Quality Index: {aqi_code} => {aqi}");
+        debug!("ENS160 Air Quality Index: {aqi_code} => {aqi}");
         Ok(aqi)
     }
 
-    pub fn get_total_volatile_organic_compounds(&mut self) -> Result<u16, std::io::Error> {
+    pub fn get_total_volatile_organic_compounds(&mut self) -> Result<Concentration, std::io::Error> {
+        let _call = self.call("get_total_volatile_organic_compounds");
         let data_tvoc = i2cio::read_word(&mut self.i2c, ENS160_REG_DATA_TVOC)?;
-        debug!("END160 TVOC Concentration (ppb): {data_tvoc}");
-        Ok(data_tvoc)
+        debug!("ENS160 TVOC Concentration (ppb): {data_tvoc}");
+        Ok(Concentration::from_ppb(data_tvoc as f64))
     }
 
     pub fn get_equivalent_co2(&mut self) -> Result<Ens160EquivalentCO2, std::io::Error> {
+        let _call = self.call("get_equivalent_co2");
         let eco2_code = i2cio::read_word(&mut self.i2c, ENS160_REG_DATA_ECO2)?;
         let eco2 = Ens160EquivalentCO2::from(eco2_code);
-        debug!("END160 Equivalent CO2 Concentration (ppm): {eco2_code} => {eco2}");
+        debug!("ENS160 Equivalent CO2 Concentration (ppm): {eco2_code} => {eco2}");
         Ok(eco2)
     }
     
-    pub fn get_relative_humidity(&mut self) -> Result<f64, std::io::Error> {
+    pub fn get_relative_humidity(&mut self) -> Result<RelativeHumidity, std::io::Error> {
+        let _call = self.call("get_relative_humidity");
         let rh_word = i2cio::read_word(&mut self.i2c, ENS160_REG_RH_IN)?;
-        debug!("END160 Relative humidity: {rh_word:#06x}");
+        debug!("ENS160 Relative humidity: {rh_word:#06x}");
         let rh = (rh_word as f64) / 512.0;
-        Ok(rh)
+        Ok(RelativeHumidity::from_percent(rh))
     }
 
-    pub fn set_relative_humidity(&mut self, rh: f64) -> Result<(), std::io::Error> {
-        let rh_word = (rh * 512.0) as u16;
-        debug!("END160 Setting relative humidity: {rh_word:#06x}");
+    pub fn set_relative_humidity(&mut self, rh: RelativeHumidity) -> Result<(), std::io::Error> {
+        let _call = self.call("set_relative_humidity");
+        let rh_word = (rh.percent() * 512.0) as u16;
+        debug!("ENS160 Setting relative humidity: {rh_word:#06x}");
         i2cio::write_word(&mut self.i2c, ENS160_REG_RH_IN, rh_word)
     }
 
-    pub fn get_temperature(&mut self) -> Result<f64, std::io::Error> {
+    pub fn get_temperature(&mut self) -> Result<Temperature, std::io::Error> {
+        let _call = self.call("get_temperature");
         let temperature_word = i2cio::read_word(&mut self.i2c, ENS160_REG_TEMP_IN)?;
-        debug!("END160 Temperature: {temperature_word:#06x}");
-        let temperature = (temperature_word as f64) / 64.0 - 273.15;
-        Ok(temperature)
+        debug!("ENS160 Temperature: {temperature_word:#06x}");
+        // -- in 1/64 kelvin
+        let temperature = (temperature_word as f64) / 64.0;
+        Ok(Temperature::from_kelvin(temperature))
     }
 
-    pub fn set_temperature(&mut self, temperature: f64) -> Result<(), std::io::Error> {
-        let temperature_word =  ((temperature + 273.15) * 64.0) as u16;
-        debug!("END160 Setting temperature: {temperature_word:#06x}");
+    pub fn set_temperature(&mut self, temperature: Temperature) -> Result<(), std::io::Error> {
+        let _call = self.call("set_temperature");
+        let temperature_word =  (temperature.kelvin() * 64.0) as u16;
+        debug!("ENS160 Setting temperature: {temperature_word:#06x}");
         i2cio::write_word(&mut self.i2c, ENS160_REG_TEMP_IN, temperature_word)
     }
 
-}
\ No newline at end of file
+}
+
+// -- after a power glitch the sensor falls back to deep sleep, re-enter the operational mode
+impl<B: I2cBus> Recoverable for ENS160<B> {
+
+    fn recover(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("recover");
+        i2cio::set_slave(&mut self.i2c, self.device_addr.value())?;
+        let op_mode = Self::read_op_mode(&mut self.i2c)?;
+        debug!("ENS160 op mode: {op_mode:#04x}");
+        if op_mode != ENS160_OP_MODE_OPERATIONAL {
+            debug!("Setting ENS160 op mode to operational");
+            Self::set_op_mode_operational(&mut self.i2c)?;
+        }
+        Ok(())
+    }
+}
+
+// -- reads the outputs of the last measurement, the sensor measures once per second on its own;
+// -- eCO2 in ppm rather than the rating of get_equivalent_co2, check get_validity during warm-up
+impl<B: I2cBus> Sensor for ENS160<B> {
+
+    fn name(&self) -> &'static str {
+        "ENS160"
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        let aqi = self.get_air_quality_index()?;
+        let tvoc = self.get_total_volatile_organic_compounds()?;
+        let eco2 = i2cio::read_word(&mut self.i2c, ENS160_REG_DATA_ECO2)?;
+        debug!("ENS160 Equivalent CO2 Concentration (ppm): {eco2}");
+        Ok(Measurement {
+            tvoc: Some(tvoc),
+            eco2: Some(Concentration::from_ppm(eco2 as f64)),
+            aqi: Some(aqi.value()),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+// -- what went wrong, for callers that want to react instead of parsing messages; the drivers keep
+// -- returning std::io::Error with this error inside, Error::from(io_err) gets it back out, e.g.
+// -- match Error::from(err) { Error::ChipId { found, .. } => ..., Error::Nack { .. } => ..., _ => ... }
+#[derive(Debug)]
+pub enum Error {
+    // -- the bus transfer itself failed
+    Bus(std::io::Error),
+    // -- nobody acknowledged, the device is absent, powered down or at another address; the errno
+    // -- the adapter reported it with, ENXIO or EREMOTEIO, unless it came from embedded-hal
+    Nack { errno: Option<i32> },
+    // -- a kernel driver owns the address, named if sysfs tells which one
+    AddressInUse { device_addr: u16, driver: Option<String> },
+    // -- a device answered but its chip, part or device id is not the expected one
+    ChipId { expected: u16, found: u16 },
+    // -- a data word did not match its checksum
+    Crc { expected: u8, found: u8 },
+    // -- a setting the device or the driver does not accept
+    InvalidConfig(String),
+    // -- the device flagged its FIFO configuration as invalid or sent a frame the driver can't parse
+    Fifo(String),
+    // -- the bus or the device did not answer in time, with what was waited for
+    Timeout(String),
+    // -- the adapter lacks what the transfer needs and there is no fallback
+    Unsupported(String),
+    // -- the device has no valid data yet, e.g. during warm-up
+    NotReady,
+}
+
+impl Error {
+
+    // -- the io error kind the error travels as
+    pub fn kind(&self) -> std::io::ErrorKind {
+        match self {
+            Self::Bus(err) => err.kind(),
+            Self::Nack { .. } => std::io::ErrorKind::NotFound,
+            Self::AddressInUse { .. } => std::io::ErrorKind::ResourceBusy,
+            Self::ChipId { .. } => std::io::ErrorKind::InvalidData,
+            Self::Crc { .. } => std::io::ErrorKind::InvalidData,
+            Self::InvalidConfig(_) => std::io::ErrorKind::InvalidInput,
+            Self::Fifo(_) => std::io::ErrorKind::InvalidData,
+            Self::Timeout(_) => std::io::ErrorKind::TimedOut,
+            Self::Unsupported(_) => std::io::ErrorKind::Unsupported,
+            Self::NotReady => std::io::ErrorKind::WouldBlock,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bus(err) => write!(f, "Bus error: {err}"),
+            Self::Nack { .. } => write!(f, "Device did not acknowledge"),
+            Self::AddressInUse { device_addr, driver: Some(driver) } =>
+                write!(f, "Address {device_addr:#04x} is in use by kernel driver '{driver}', unbind it or read the device through its IIO driver"),
+            Self::AddressInUse { device_addr, driver: None } =>
+                write!(f, "Address {device_addr:#04x} is in use by a kernel driver"),
+            Self::ChipId { expected, found } => write!(f, "Found unknown chip id '{found:#06x}', expected '{expected:#06x}'"),
+            Self::Crc { expected, found } => write!(f, "Expected CRC {expected:#04x}, received CRC {found:#04x}"),
+            Self::InvalidConfig(msg) => write!(f, "Invalid configuration: {msg}"),
+            Self::Fifo(msg) => write!(f, "FIFO error: {msg}"),
+            Self::Timeout(msg) => write!(f, "Timed out: {msg}"),
+            Self::Unsupported(msg) => write!(f, "Not supported: {msg}"),
+            Self::NotReady => write!(f, "Device not ready"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bus(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// -- unwraps an error raised by a driver, classifies anything else the bus reported
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = err.into_inner().expect("checked above");
+            return *inner.downcast::<Error>().expect("checked above")
+        }
+        match (err.raw_os_error(), err.kind()) {
+            // -- ENXIO from adapters that report an unacknowledged address, EREMOTEIO from the others
+            (Some(errno @ (libc::ENXIO | libc::EREMOTEIO)), _) => Self::Nack { errno: Some(errno) },
+            (_, std::io::ErrorKind::TimedOut) => Self::Timeout(err.to_string()),
+            _ => Self::Bus(err),
+        }
+    }
+}
+
+// -- bus errors and NACKs with an errno go back unchanged so retries still see the errno, the others
+// -- travel inside an io error of the matching kind
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Bus(err) => err,
+            Error::Nack { errno: Some(errno) } => std::io::Error::from_raw_os_error(errno),
+            err => std::io::Error::new(err.kind(), err),
+        }
+    }
+}
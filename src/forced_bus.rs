@@ -0,0 +1,106 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use crate::i2cio::{self, I2cBus};
+
+// -- read-only access to addresses a kernel driver owns, for diagnostics like dumping the registers of
+// -- a sensor the iio driver misreads; uses I2C_SLAVE_FORCE, which bypasses the kernel's check that
+// -- nobody else uses the address. RISKS: the kernel driver keeps running and its transfers interleave
+// -- with ours, so it may read a register pointer we moved, and reading clears read-to-clear flags,
+// -- FIFOs and interrupt status behind the driver's back; it is not told and its cached state may
+// -- go stale. Every write is refused, plain writes and smbus writes alike, only the register pointer
+// -- write of a combined write/read transfer goes through, but even an smbus read moves the pointer.
+// -- Prefer unbinding the driver or reading through iio::IioSensor where that is an option
+pub struct ForcedBus<I: AsRawFd = File> {
+    i2c: I2c<I>,
+}
+
+impl ForcedBus {
+
+    pub fn open(i2c_bus_path: &Path) -> Result<ForcedBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Ok(Self::new(i2c))
+    }
+
+}
+
+impl<I: AsRawFd> ForcedBus<I> {
+
+    pub fn new(i2c: I2c<I>) -> Self {
+        ForcedBus {
+            i2c,
+        }
+    }
+
+    pub fn release(self) -> I2c<I> {
+        self.i2c
+    }
+}
+
+fn read_only(what: &str) -> std::io::Error {
+    let errmsg = format!("Refusing {what} on a forced bus, it is for read-only diagnostics");
+    std::io::Error::new(std::io::ErrorKind::PermissionDenied, errmsg)
+}
+
+impl<I: AsRawFd> I2cBus for ForcedBus<I> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        let driver = i2cio::adapter_nr(self.i2c.as_raw_fd())
+            .and_then(|adapter_nr| i2cio::bound_driver(Path::new(i2cio::I2C_DEVICES_PATH), adapter_nr, dev_addr));
+        if let Some(driver) = driver {
+            warn!("Forcing access to address {dev_addr:#04x} owned by kernel driver '{driver}'");
+        }
+        self.i2c.smbus_set_slave_address(dev_addr, true)
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.i2c.smbus_read_byte_data(register)
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.i2c.smbus_read_word_data(register)
+    }
+
+    fn smbus_write_byte(&mut self, _data: u8) -> Result<(), std::io::Error> {
+        Err(read_only("smbus write byte"))
+    }
+
+    fn smbus_write_byte_data(&mut self, _register: u8, _data: u8) -> Result<(), std::io::Error> {
+        Err(read_only("smbus write byte data"))
+    }
+
+    fn smbus_write_word_data(&mut self, _register: u8, _data: u16) -> Result<(), std::io::Error> {
+        Err(read_only("smbus write word data"))
+    }
+
+    fn smbus_write_block_data(&mut self, _register: u8, _data: &[u8]) -> Result<(), std::io::Error> {
+        Err(read_only("smbus write block data"))
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.i2c.i2c_read_block_data(register, data)
+    }
+
+    // -- reads, and single register bytes written right before a read
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        for (index, message) in messages.iter().enumerate() {
+            let Message::Write { .. } = message else {
+                continue
+            };
+            let is_read_next = matches!(messages.get(index + 1), Some(Message::Read { .. }));
+            if message.len() != 1 || !is_read_next {
+                return Err(read_only("a plain i2c write"))
+            }
+        }
+        self.i2c.i2c_transfer(messages)
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.i2c.i2c_functionality()
+    }
+}
@@ -0,0 +1,247 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+use i2c_linux::Message;
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::i2cio::I2cBus;
+use crate::i2cio_async::AsyncI2cBus;
+
+// -- largest block the smbus block write is allowed to carry
+const SMBUS_BLOCK_MAX: usize = 32;
+
+fn seven_bit_addr(addr: u16) -> Result<u8, std::io::Error> {
+    if addr > 0x7f {
+        let errmsg = format!("Address '{addr:#06x}' is not a 7 bit address");
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, errmsg))
+    }
+    Ok(addr as u8)
+}
+
+fn map_err<E: Error>(err: E) -> std::io::Error {
+    let kind = err.kind();
+    debug!("embedded-hal i2c error: {kind:?}");
+    match kind {
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => crate::error::Error::Nack { errno: None }.into(),
+        ErrorKind::NoAcknowledge(_) =>
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("I2C error: {kind}")),
+        _ => std::io::Error::other(format!("I2C error: {kind}")),
+    }
+}
+
+// -- smbus block writes carry the byte count after the command
+fn smbus_block(register: u8, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    if data.len() > SMBUS_BLOCK_MAX {
+        let errmsg = format!("Block of {} bytes exceeds the SMBus limit of {SMBUS_BLOCK_MAX} bytes", data.len());
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, errmsg))
+    }
+    let mut write_buf = Vec::with_capacity(data.len() + 2);
+    write_buf.push(register);
+    write_buf.push(data.len() as u8);
+    write_buf.extend_from_slice(data);
+    Ok(write_buf)
+}
+
+// -- embedded-hal transactions address a single device, none for an empty transfer
+fn transfer_addr(messages: &[Message]) -> Result<Option<u8>, std::io::Error> {
+    let Some(first) = messages.first() else {
+        return Ok(None)
+    };
+    let address = first.address();
+    if messages.iter().any(|message| message.address() != address) {
+        let errmsg = "Messages of one transfer must address the same device";
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, errmsg))
+    }
+    Ok(Some(seven_bit_addr(address)?))
+}
+
+fn operations<'a>(messages: &'a mut [Message]) -> Vec<Operation<'a>> {
+    messages.iter_mut()
+        .map(|message| match message {
+            Message::Read { data, .. } => Operation::Read(data),
+            Message::Write { data, .. } => Operation::Write(data),
+        })
+        .collect()
+}
+
+// -- adapts any embedded-hal 1.0 i2c bus plus delay to the bus trait the drivers use,
+// -- e.g. linux-embedded-hal, bit-banged buses or the embedded-hal mock transports
+pub struct HalBus<I2C, D, C = SystemClock> {
+    // -- embedded-hal i2c bus
+    i2c: I2C,
+    // -- embedded-hal delay
+    delay: D,
+    // -- where the time comes from, embedded-hal has no notion of it
+    clock: C,
+    // -- device address set by the driver
+    device_addr: u8,
+}
+
+impl<I2C: I2c, D: DelayNs> HalBus<I2C, D> {
+
+    pub fn new(i2c: I2C, delay: D) -> Self {
+        Self::with_clock(i2c, delay, SystemClock)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs, C: Clock> HalBus<I2C, D, C> {
+
+    // -- take the time from a clock of choice, e.g. one running on a board timer;
+    // -- the waits still go to the embedded-hal delay
+    pub fn with_clock(i2c: I2C, delay: D, clock: C) -> Self {
+        HalBus {
+            i2c,
+            delay,
+            clock,
+            device_addr: 0,
+        }
+    }
+
+    pub fn release(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs, C: Clock> I2cBus for HalBus<I2C, D, C> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.device_addr = seven_bit_addr(dev_addr)?;
+        Ok(())
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        let mut read_buf: [u8; 1] = [0; 1];
+        self.i2c.write_read(self.device_addr, &[register], &mut read_buf).map_err(map_err)?;
+        Ok(read_buf[0])
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        // -- smbus words are transferred least significant byte first
+        let mut read_buf: [u8; 2] = [0; 2];
+        self.i2c.write_read(self.device_addr, &[register], &mut read_buf).map_err(map_err)?;
+        Ok(u16::from_le_bytes(read_buf))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.i2c.write(self.device_addr, &[data]).map_err(map_err)
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.i2c.write(self.device_addr, &[register, data]).map_err(map_err)
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        let [data_lsb, data_msb] = data.to_le_bytes();
+        self.i2c.write(self.device_addr, &[register, data_lsb, data_msb]).map_err(map_err)
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        let write_buf = smbus_block(register, data)?;
+        self.i2c.write(self.device_addr, &write_buf).map_err(map_err)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.i2c.write_read(self.device_addr, &[register], data).map_err(map_err)?;
+        Ok(data.len())
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        let Some(address) = transfer_addr(messages)? else {
+            return Ok(())
+        };
+        let mut operations = operations(messages);
+        self.i2c.transaction(address, &mut operations).map_err(map_err)
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.delay.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.clock.now()
+    }
+}
+
+// -- the same adapter for embedded-hal-async buses and delays, e.g. embassy or tokio based
+// -- transports, for the async driver variants
+pub struct AsyncHalBus<I2C, D> {
+    // -- embedded-hal-async i2c bus
+    i2c: I2C,
+    // -- embedded-hal-async delay
+    delay: D,
+    // -- device address set by the driver
+    device_addr: u8,
+}
+
+impl<I2C: embedded_hal_async::i2c::I2c, D: embedded_hal_async::delay::DelayNs> AsyncHalBus<I2C, D> {
+
+    pub fn new(i2c: I2C, delay: D) -> Self {
+        AsyncHalBus {
+            i2c,
+            delay,
+            device_addr: 0,
+        }
+    }
+
+    pub fn release(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<I2C: embedded_hal_async::i2c::I2c, D: embedded_hal_async::delay::DelayNs> AsyncI2cBus for AsyncHalBus<I2C, D> {
+
+    async fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.device_addr = seven_bit_addr(dev_addr)?;
+        Ok(())
+    }
+
+    async fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        let mut read_buf: [u8; 1] = [0; 1];
+        self.i2c.write_read(self.device_addr, &[register], &mut read_buf).await.map_err(map_err)?;
+        Ok(read_buf[0])
+    }
+
+    async fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        // -- smbus words are transferred least significant byte first
+        let mut read_buf: [u8; 2] = [0; 2];
+        self.i2c.write_read(self.device_addr, &[register], &mut read_buf).await.map_err(map_err)?;
+        Ok(u16::from_le_bytes(read_buf))
+    }
+
+    async fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.i2c.write(self.device_addr, &[data]).await.map_err(map_err)
+    }
+
+    async fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.i2c.write(self.device_addr, &[register, data]).await.map_err(map_err)
+    }
+
+    async fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        let [data_lsb, data_msb] = data.to_le_bytes();
+        self.i2c.write(self.device_addr, &[register, data_lsb, data_msb]).await.map_err(map_err)
+    }
+
+    async fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        let write_buf = smbus_block(register, data)?;
+        self.i2c.write(self.device_addr, &write_buf).await.map_err(map_err)
+    }
+
+    async fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.i2c.write_read(self.device_addr, &[register], data).await.map_err(map_err)?;
+        Ok(data.len())
+    }
+
+    async fn i2c_transfer(&mut self, messages: &mut [Message<'_>]) -> Result<(), std::io::Error> {
+        let Some(address) = transfer_addr(messages)? else {
+            return Ok(())
+        };
+        let mut operations = operations(messages);
+        self.i2c.transaction(address, &mut operations).await.map_err(map_err)
+    }
+
+    async fn delay_ms(&mut self, milli_secs: u32) {
+        self.delay.delay_ms(milli_secs).await
+    }
+}
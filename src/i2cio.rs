@@ -1,60 +1,439 @@
-use i2c_linux::{    
-    I2c, Message, ReadFlags, WriteFlags,
+use i2c_linux::{
+    Functionality, I2c, Message, ReadFlags, WriteFlags,
 };
-use std::fs::File;
+use log::{debug, warn};
+use std::fs::{self, File};
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::Path;
-use std::{thread, time};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::trace::{self, Direction};
+
+// -- longest block the smbus ioctls move in one go
+const SMBUS_BLOCK_MAX: usize = 32;
+// -- longest block a one byte count allows, SMBus 3 blocks end there as well
+const SMBUS3_BLOCK_MAX: usize = 255;
+
+// -- where the kernel lists the i2c devices it instantiated, e.g. 1-0077, with a driver link once bound
+pub const I2C_DEVICES_PATH: &str = "/sys/bus/i2c/devices";
+
+// -- the bus operations the drivers rely on, implemented for the linux i2c-dev bus below
+// -- and open for test rigs, simulators and other transports to implement
+pub trait I2cBus {
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error>;
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error>;
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error>;
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error>;
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error>;
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error>;
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error>;
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error>;
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error>;
+
+    // -- drivers wait and tell the time through their bus so transports can bring their own clock
+    fn delay_ms(&mut self, milli_secs: u32) {
+        SystemClock.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        SystemClock.now()
+    }
+
+    // -- mark the start and end of a multi-message sequence that other bus users must not
+    // -- interleave with, no-ops unless the transport guards the bus
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    // -- the policy transactions on this bus are retried with, none unless the transport retries
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        None
+    }
+
+    // -- the adapter's features as reported by I2C_FUNCS, everything unless the transport knows better
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        Ok(Functionality::all())
+    }
+
+    // -- note what the bus can't see by itself, ignored unless the transport keeps statistics
+    fn record(&mut self, _event: BusEvent) {
+    }
+}
+
+// -- events reported to the bus by the retry logic and the drivers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BusEvent {
+    // -- a failed transfer or transaction is tried again
+    Retry,
+    // -- a data word failed its crc check
+    CrcFailure,
+}
+
+// -- kinds of bus errors worth another attempt, combined into a mask in the retry policy
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetryErrorClass {
+    // -- EREMOTEIO, the device did not acknowledge, e.g. a glitch on a long cable run
+    RemoteIo,
+    // -- ETIMEDOUT, the adapter or a bus lock gave up waiting
+    TimedOut,
+    // -- EIO, the adapter reported a generic transfer error
+    Io,
+    // -- EAGAIN, e.g. lost arbitration on a multi-master bus
+    Again,
+    // -- every error, including a missing device or a wrong chip id
+    Any,
+}
+
+impl RetryErrorClass {
+    const REMOTE_IO: u8 = 0x01;
+    const TIMED_OUT: u8 = 0x02;
+    const IO: u8 = 0x04;
+    const AGAIN: u8 = 0x08;
+    const ANY: u8 = 0xff;
+
+    fn value(&self) -> u8 {
+        match *self {
+            Self::RemoteIo => Self::REMOTE_IO,
+            Self::TimedOut => Self::TIMED_OUT,
+            Self::Io => Self::IO,
+            Self::Again => Self::AGAIN,
+            Self::Any => Self::ANY,
+        }
+    }
+
+    fn classify(err: &std::io::Error) -> u8 {
+        match (err.raw_os_error(), err.kind()) {
+            (Some(libc::EREMOTEIO), _) => Self::REMOTE_IO,
+            (Some(libc::ETIMEDOUT), _) | (_, std::io::ErrorKind::TimedOut) => Self::TIMED_OUT,
+            (Some(libc::EIO), _) => Self::IO,
+            (Some(libc::EAGAIN), _) | (_, std::io::ErrorKind::WouldBlock) => Self::AGAIN,
+            _ => 0,
+        }
+    }
+}
+
+// -- how often and how patiently to retry transient bus errors, the default makes three
+// -- attempts on EREMOTEIO and ETIMEDOUT with 10 ms, then 20 ms between them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    // -- attempts in total, the first one included
+    attempts: u32,
+    // -- wait before the second attempt
+    backoff_ms: u32,
+    // -- the wait is multiplied by this factor after every further attempt
+    backoff_factor: u32,
+    // -- mask of the retried error classes
+    error_classes: u8,
+    // -- consecutive failed operations after which a driver is re-initialized, if at all
+    recover_after: Option<u32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(3, 10).with_backoff_factor(2)
+    }
+}
+
+impl RetryPolicy {
+
+    pub fn new(attempts: u32, backoff_ms: u32) -> Self {
+        RetryPolicy {
+            attempts: attempts.max(1),
+            backoff_ms,
+            backoff_factor: 1,
+            error_classes: RetryErrorClass::REMOTE_IO | RetryErrorClass::TIMED_OUT,
+            recover_after: None,
+        }
+    }
+
+    pub fn with_backoff_factor(mut self, backoff_factor: u32) -> Self {
+        self.backoff_factor = backoff_factor.max(1);
+        self
+    }
+
+    pub fn with_error_classes(mut self, error_classes: &[RetryErrorClass]) -> Self {
+        self.error_classes = error_classes.iter().fold(0, |mask, class| mask | class.value());
+        self
+    }
+
+    // -- see retry::Recovering, re-runs the driver's init sequence after that many failures in a row
+    pub fn with_recover_after(mut self, failures: u32) -> Self {
+        self.recover_after = Some(failures.max(1));
+        self
+    }
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn get_recover_after(&self) -> Option<u32> {
+        self.recover_after
+    }
+
+    pub fn is_retryable(&self, err: &std::io::Error) -> bool {
+        self.error_classes == RetryErrorClass::ANY
+            || (self.error_classes & RetryErrorClass::classify(err)) != 0
+    }
+}
+
+impl<I: AsRawFd> I2cBus for I2c<I> {
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        I2c::smbus_set_slave_address(self, dev_addr, false).map_err(|err| {
+            if err.raw_os_error() != Some(libc::EBUSY) {
+                return err
+            }
+            let driver = adapter_nr(self.as_raw_fd())
+                .and_then(|adapter_nr| bound_driver(Path::new(I2C_DEVICES_PATH), adapter_nr, dev_addr));
+            Error::AddressInUse { device_addr: dev_addr, driver }.into()
+        })
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        I2c::smbus_read_byte_data(self, register)
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        I2c::smbus_read_word_data(self, register)
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        I2c::smbus_write_byte(self, data)
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        I2c::smbus_write_byte_data(self, register, data)
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        I2c::smbus_write_word_data(self, register, data)
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        I2c::smbus_write_block_data(self, register, data)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        I2c::i2c_read_block_data(self, register, data)
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        I2c::i2c_transfer(self, messages)
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        I2c::i2c_functionality(self)
+    }
+}
 
 pub fn get_bus(bus_path: &Path)  -> Result<I2c<File>, std::io::Error> {
     I2c::from_path(bus_path)
 }
 
-pub fn set_slave(i2c: &mut I2c<File>, dev_addr: u16) -> Result<(), std::io::Error> {
-    i2c.smbus_set_slave_address(dev_addr, false)
-} 
+// -- the number of the adapter an open /dev/i2c-N belongs to
+pub(crate) fn adapter_nr(fd: RawFd) -> Option<u32> {
+    let bus_path = fs::read_link(format!("/proc/self/fd/{fd}")).ok()?;
+    bus_path.file_name()?.to_str()?.strip_prefix("i2c-")?.parse().ok()
+}
+
+// -- the kernel driver bound to an address, if any, read from a directory laid out like /sys/bus/i2c/devices
+pub fn bound_driver(devices_path: &Path, adapter_nr: u32, dev_addr: u16) -> Option<String> {
+    let driver_path = fs::read_link(devices_path.join(format!("{adapter_nr}-{dev_addr:04x}")).join("driver")).ok()?;
+    Some(driver_path.file_name()?.to_str()?.to_string())
+}
+
+pub fn set_slave<B: I2cBus>(i2c: &mut B, dev_addr: u16) -> Result<(), std::io::Error> {
+    i2c.set_slave(dev_addr)
+}
+
+// -- run f, and run it again as long as the policy allows for the error it returned
+pub fn retry<B: I2cBus, R>(i2c: &mut B, policy: &RetryPolicy, mut f: impl FnMut(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+    let mut backoff_ms = policy.backoff_ms;
+    let mut attempt = 1;
+    loop {
+        match f(i2c) {
+            Err(err) if attempt < policy.attempts && policy.is_retryable(&err) => {
+                warn!("I2C transfer failed in attempt {attempt} of {}, retrying in {backoff_ms} ms: {err}", policy.attempts);
+                i2c.record(BusEvent::Retry);
+                i2c.delay_ms(backoff_ms);
+                backoff_ms = backoff_ms.saturating_mul(policy.backoff_factor);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
 
-pub fn read_20_bits(i2c: &mut I2c<File>, register: u8) -> Result<i32, std::io::Error> {
+// -- a transaction is retried as a whole if the bus has a retry policy
+pub fn transaction<B: I2cBus, R>(i2c: &mut B, mut f: impl FnMut(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+    match i2c.retry_policy() {
+        Some(policy) => retry(i2c, &policy, |i2c| transaction_once(i2c, &mut f)),
+        None => transaction_once(i2c, f),
+    }
+}
+
+fn transaction_once<B: I2cBus, R>(i2c: &mut B, f: impl FnOnce(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+    i2c.begin_transaction()?;
+    let result = f(i2c);
+    // -- always end the transaction, the first error wins
+    let end_result = i2c.end_transaction();
+    let result = result?;
+    end_result?;
+    Ok(result)
+}
+
+// -- query the adapter once, e.g. when a driver is created, and hand the result to the block transfers below
+pub fn functionality<B: I2cBus>(i2c: &mut B) -> Result<Functionality, std::io::Error> {
+    let functionality = i2c.functionality()?;
+    debug!("I2C adapter functionality: {functionality:?}");
+    Ok(functionality)
+}
+
+fn adapter_lacks(what: &str) -> std::io::Error {
+    Error::Unsupported(format!("adapter lacks {what}")).into()
+}
+
+// -- smbus i2c block read if the adapter has it and the block fits, else an equivalent combined
+// -- write/read transfer, else one smbus byte read per register
+pub fn read_block<B: I2cBus>(i2c: &mut B, functionality: Functionality, device_addr: u16, register: u8, data: &mut [u8])
+    -> Result<usize, std::io::Error> {
+    let bytes_read = read_block_untraced(i2c, functionality, device_addr, register, data)
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &data[..bytes_read.min(data.len())]);
+    Ok(bytes_read)
+}
+
+fn read_block_untraced<B: I2cBus>(i2c: &mut B, functionality: Functionality, device_addr: u16, register: u8, data: &mut [u8])
+    -> Result<usize, std::io::Error> {
+    if functionality.contains(Functionality::SMBUS_READ_I2C_BLOCK) && data.len() <= SMBUS_BLOCK_MAX {
+        return i2c.i2c_read_block_data(register, data)
+    }
+    if functionality.contains(Functionality::I2C) {
+        debug!("Reading {} bytes from register {register:#04x} with a combined transfer", data.len());
+        let register = [register];
+        let write_message = Message::Write { address: device_addr, data: &register, flags: WriteFlags::empty() };
+        let read_message = Message::Read { address: device_addr, data, flags: ReadFlags::empty() };
+        let mut messages = [write_message, read_message];
+        i2c.i2c_transfer(&mut messages)?;
+        return Ok(messages[1].len())
+    }
+    if functionality.contains(Functionality::SMBUS_READ_BYTE_DATA) {
+        debug!("Reading {} bytes from register {register:#04x} byte by byte", data.len());
+        for (offset, val) in data.iter_mut().enumerate() {
+            *val = i2c.smbus_read_byte_data(register.wrapping_add(offset as u8))?;
+        }
+        return Ok(data.len())
+    }
+    Err(adapter_lacks("i2c block reads, plain i2c transfers and smbus byte reads"))
+}
+
+pub fn read_20_bits<B: I2cBus>(i2c: &mut B, functionality: Functionality, device_addr: u16, register: u8) -> Result<i32, std::io::Error> {
     let mut val: [u8; 3] = [0, 0, 0];
-    let _bytes_read = i2c.i2c_read_block_data(register, &mut val)?;
+    let _bytes_read = read_block(i2c, functionality, device_addr, register, &mut val)?;
     let val: i32 = (val[2] >> 4) as i32 + ((val[1] as i32) << 4) + ((val[0] as i32) << 12);
     Ok(val)
 }
 
-pub fn read_word(i2c: &mut I2c<File>, register: u8) -> Result<u16, std::io::Error> {
-    i2c.smbus_read_word_data(register)
+pub fn read_word<B: I2cBus>(i2c: &mut B, register: u8) -> Result<u16, std::io::Error> {
+    let val = i2c.smbus_read_word_data(register)
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &val.to_le_bytes());
+    Ok(val)
 }
 
-pub fn read_byte(i2c: &mut I2c<File>, register: u8) -> Result<u8, std::io::Error> {
-    i2c.smbus_read_byte_data(register)
+pub fn read_byte<B: I2cBus>(i2c: &mut B, register: u8) -> Result<u8, std::io::Error> {
+    let val = i2c.smbus_read_byte_data(register)
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &[val]);
+    Ok(val)
 }
 
-pub fn read_bytes(i2c: &mut I2c<File>, device_addr: u16, data: &mut [u8]) -> Result<(), std::io::Error> {
-    let read_message = Message::Read { address: device_addr, data: data, flags: ReadFlags::empty() };
+pub fn read_bytes<B: I2cBus>(i2c: &mut B, device_addr: u16, data: &mut [u8]) -> Result<(), std::io::Error> {
+    let read_message = Message::Read { address: device_addr, data, flags: ReadFlags::empty() };
     let mut messages = [read_message];
     i2c.i2c_transfer(&mut messages)
+        .inspect_err(|err| trace::failed(Direction::Read, None, err))?;
+    trace::transfer(Direction::Read, None, data);
+    Ok(())
 }
 
-pub fn write_byte_single(i2c: &mut I2c<File>, data: u8) -> Result<(), std::io::Error> {
+pub fn write_byte_single<B: I2cBus>(i2c: &mut B, data: u8) -> Result<(), std::io::Error> {
     i2c.smbus_write_byte(data)
+        .inspect_err(|err| trace::failed(Direction::Write, None, err))?;
+    trace::transfer(Direction::Write, None, &[data]);
+    Ok(())
 }
 
-pub fn write_byte(i2c: &mut I2c<File>, register: u8, data: u8) -> Result<(), std::io::Error> {
+pub fn write_byte<B: I2cBus>(i2c: &mut B, register: u8, data: u8) -> Result<(), std::io::Error> {
     i2c.smbus_write_byte_data(register, data)
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), &[data]);
+    Ok(())
 }
 
-pub fn write_bytes<const LEN: usize>(i2c: &mut I2c<File>, device_addr: u16, data: [u8; LEN]) -> Result<(), std::io::Error> {
+pub fn write_bytes<B: I2cBus, const LEN: usize>(i2c: &mut B, device_addr: u16, data: [u8; LEN]) -> Result<(), std::io::Error> {
     let data = &data;
-    let write_message = Message::Write { address: device_addr, data: data, flags: WriteFlags::empty() };
+    let write_message = Message::Write { address: device_addr, data, flags: WriteFlags::empty() };
     let mut messages = [write_message];
     i2c.i2c_transfer(&mut messages)
+        .inspect_err(|err| trace::failed(Direction::Write, None, err))?;
+    trace::transfer(Direction::Write, None, data);
+    Ok(())
 }
 
-pub fn write_word(i2c: &mut I2c<File>, register: u8, data: u16) -> Result<(), std::io::Error> {
+// -- smbus block write if the adapter has it, else the same bytes, register and count first, as a plain write
+pub fn write_block<B: I2cBus>(i2c: &mut B, functionality: Functionality, device_addr: u16, register: u8, data: &[u8])
+    -> Result<(), std::io::Error> {
+    write_block_untraced(i2c, functionality, device_addr, register, data)
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), data);
+    Ok(())
+}
+
+fn write_block_untraced<B: I2cBus>(i2c: &mut B, functionality: Functionality, device_addr: u16, register: u8, data: &[u8])
+    -> Result<(), std::io::Error> {
+    if data.len() > SMBUS3_BLOCK_MAX {
+        let errmsg = format!("block of {} bytes exceeds the {SMBUS3_BLOCK_MAX} bytes of an smbus block", data.len());
+        return Err(Error::InvalidConfig(errmsg).into())
+    }
+    if functionality.contains(Functionality::SMBUS_WRITE_BLOCK_DATA) && data.len() <= SMBUS_BLOCK_MAX {
+        return i2c.smbus_write_block_data(register, data)
+    }
+    if functionality.contains(Functionality::I2C) {
+        debug!("Writing {} bytes to register {register:#04x} with a plain transfer", data.len());
+        let mut block = vec![register, data.len() as u8];
+        block.extend_from_slice(data);
+        let write_message = Message::Write { address: device_addr, data: &block, flags: WriteFlags::empty() };
+        let mut messages = [write_message];
+        return i2c.i2c_transfer(&mut messages)
+    }
+    Err(adapter_lacks("smbus block writes and plain i2c transfers"))
+}
+
+pub fn write_word<B: I2cBus>(i2c: &mut B, register: u8, data: u16) -> Result<(), std::io::Error> {
     i2c.smbus_write_word_data(register, data)
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), &data.to_le_bytes());
+    Ok(())
 }
 
-pub fn delay(milli_secs: u32) {    
-    let delay = time::Duration::from_millis(milli_secs as u64);
-    thread::sleep(delay);
+pub fn delay<B: I2cBus>(i2c: &mut B, milli_secs: u32) {
+    i2c.delay_ms(milli_secs)
 }
 
+pub fn now<B: I2cBus>(i2c: &B) -> Duration {
+    i2c.now()
+}
@@ -0,0 +1,132 @@
+use i2c_linux::{
+    Message, ReadFlags, WriteFlags,
+};
+
+use crate::i2cio::BusEvent;
+use crate::trace::{self, Direction};
+
+// -- async counterpart of the bus trait for the async driver variants, waits yield to the
+// -- executor instead of blocking the thread, see hal::AsyncHalBus for embedded-hal-async buses
+#[allow(async_fn_in_trait)]
+pub trait AsyncI2cBus {
+    async fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error>;
+
+    async fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error>;
+
+    async fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error>;
+
+    async fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error>;
+
+    async fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error>;
+
+    async fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error>;
+
+    async fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error>;
+
+    async fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error>;
+
+    async fn i2c_transfer(&mut self, messages: &mut [Message<'_>]) -> Result<(), std::io::Error>;
+
+    // -- there is no executor independent timer so every transport brings its own delay source
+    async fn delay_ms(&mut self, milli_secs: u32);
+
+    // -- same contract as the blocking bus, no-ops unless the transport guards the bus
+    async fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    async fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    // -- same contract as the blocking bus, ignored unless the transport keeps statistics
+    fn record(&mut self, _event: BusEvent) {
+    }
+}
+
+pub async fn set_slave<B: AsyncI2cBus>(i2c: &mut B, dev_addr: u16) -> Result<(), std::io::Error> {
+    i2c.set_slave(dev_addr).await
+}
+
+pub async fn transaction<B: AsyncI2cBus, R>(i2c: &mut B, f: impl AsyncFnOnce(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+    i2c.begin_transaction().await?;
+    let result = f(i2c).await;
+    // -- always end the transaction, the first error wins
+    let end_result = i2c.end_transaction().await;
+    let result = result?;
+    end_result?;
+    Ok(result)
+}
+
+// -- the transfers are traced like the blocking ones, the driver call span is attached to the future
+pub async fn read_word<B: AsyncI2cBus>(i2c: &mut B, register: u8) -> Result<u16, std::io::Error> {
+    let val = i2c.smbus_read_word_data(register).await
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &val.to_le_bytes());
+    Ok(val)
+}
+
+pub async fn read_byte<B: AsyncI2cBus>(i2c: &mut B, register: u8) -> Result<u8, std::io::Error> {
+    let val = i2c.smbus_read_byte_data(register).await
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &[val]);
+    Ok(val)
+}
+
+pub async fn read_block<B: AsyncI2cBus>(i2c: &mut B, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+    let bytes_read = i2c.i2c_read_block_data(register, data).await
+        .inspect_err(|err| trace::failed(Direction::Read, Some(register), err))?;
+    trace::transfer(Direction::Read, Some(register), &data[..bytes_read.min(data.len())]);
+    Ok(bytes_read)
+}
+
+pub async fn read_bytes<B: AsyncI2cBus>(i2c: &mut B, device_addr: u16, data: &mut [u8]) -> Result<(), std::io::Error> {
+    let read_message = Message::Read { address: device_addr, data, flags: ReadFlags::empty() };
+    let mut messages = [read_message];
+    i2c.i2c_transfer(&mut messages).await
+        .inspect_err(|err| trace::failed(Direction::Read, None, err))?;
+    trace::transfer(Direction::Read, None, data);
+    Ok(())
+}
+
+pub async fn write_byte_single<B: AsyncI2cBus>(i2c: &mut B, data: u8) -> Result<(), std::io::Error> {
+    i2c.smbus_write_byte(data).await
+        .inspect_err(|err| trace::failed(Direction::Write, None, err))?;
+    trace::transfer(Direction::Write, None, &[data]);
+    Ok(())
+}
+
+pub async fn write_byte<B: AsyncI2cBus>(i2c: &mut B, register: u8, data: u8) -> Result<(), std::io::Error> {
+    i2c.smbus_write_byte_data(register, data).await
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), &[data]);
+    Ok(())
+}
+
+pub async fn write_bytes<B: AsyncI2cBus, const LEN: usize>(i2c: &mut B, device_addr: u16, data: [u8; LEN]) -> Result<(), std::io::Error> {
+    let data = &data;
+    let write_message = Message::Write { address: device_addr, data, flags: WriteFlags::empty() };
+    let mut messages = [write_message];
+    i2c.i2c_transfer(&mut messages).await
+        .inspect_err(|err| trace::failed(Direction::Write, None, err))?;
+    trace::transfer(Direction::Write, None, data);
+    Ok(())
+}
+
+pub async fn write_block<B: AsyncI2cBus>(i2c: &mut B, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+    i2c.smbus_write_block_data(register, data).await
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), data);
+    Ok(())
+}
+
+pub async fn write_word<B: AsyncI2cBus>(i2c: &mut B, register: u8, data: u16) -> Result<(), std::io::Error> {
+    i2c.smbus_write_word_data(register, data).await
+        .inspect_err(|err| trace::failed(Direction::Write, Some(register), err))?;
+    trace::transfer(Direction::Write, Some(register), &data.to_le_bytes());
+    Ok(())
+}
+
+pub async fn delay<B: AsyncI2cBus>(i2c: &mut B, milli_secs: u32) {
+    i2c.delay_ms(milli_secs).await
+}
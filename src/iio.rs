@@ -0,0 +1,273 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{Pressure, RelativeHumidity, Resistance, Temperature};
+
+// -- where the kernel lists the devices bound to its industrial io drivers
+pub const IIO_DEVICES_PATH: &str = "/sys/bus/iio/devices";
+
+// -- chips with a kernel iio driver, bme280 and bmp388 are handled by the bmp280 driver
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IioChip {
+    Bme280,
+    Bme680,
+    Bmp388,
+    Tmp117,
+}
+
+impl IioChip {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Bme280 => "BME280",
+            Self::Bme680 => "BME680",
+            Self::Bmp388 => "BMP388",
+            Self::Tmp117 => "TMP117",
+        }
+    }
+
+    // -- what the kernel drivers put into the name attribute
+    fn device_names(&self) -> &'static [&'static str] {
+        match *self {
+            Self::Bme280 => &["bme280"],
+            Self::Bme680 => &["bme680"],
+            Self::Bmp388 => &["bmp380", "bmp388"],
+            Self::Tmp117 => &["tmp117"],
+        }
+    }
+
+    // -- what the chip measures
+    pub fn channels(&self) -> &'static [IioChannel] {
+        match *self {
+            Self::Bme280 => &[IioChannel::Temperature, IioChannel::Pressure, IioChannel::Humidity],
+            Self::Bme680 => &[IioChannel::Temperature, IioChannel::Pressure, IioChannel::Humidity, IioChannel::GasResistance],
+            Self::Bmp388 => &[IioChannel::Temperature, IioChannel::Pressure],
+            Self::Tmp117 => &[IioChannel::Temperature],
+        }
+    }
+}
+
+impl fmt::Display for IioChip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IioChannel {
+    Temperature,
+    Pressure,
+    Humidity,
+    GasResistance,
+}
+
+impl IioChannel {
+    // -- channel part of the attribute names
+    fn value(&self) -> &'static str {
+        match *self {
+            Self::Temperature => "temp",
+            Self::Pressure => "pressure",
+            Self::Humidity => "humidityrelative",
+            Self::GasResistance => "resistance",
+        }
+    }
+
+    // -- from the iio units, millidegree celsius, kilopascal, milli percent and ohm, to
+    // -- the units of the userspace drivers, degree celsius, pascal, percent and ohm
+    fn factor(&self) -> f64 {
+        match *self {
+            Self::Temperature => 0.001,
+            Self::Pressure => 1000.0,
+            Self::Humidity => 0.001,
+            Self::GasResistance => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for IioChannel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+// -- a sensor claimed by a kernel iio driver, read through sysfs instead of /dev/i2c-N; the values
+// -- are in the units of the userspace drivers so either backend can feed the same code
+pub struct IioSensor {
+    chip: IioChip,
+    // -- e.g. /sys/bus/iio/devices/iio:device0
+    device_path: PathBuf,
+}
+
+impl IioSensor {
+
+    // -- the first device of the chip under /sys/bus/iio/devices
+    pub fn new(chip: IioChip) -> Result<IioSensor, std::io::Error> {
+        Self::find(Path::new(IIO_DEVICES_PATH), chip, None)
+    }
+
+    // -- the device of the chip at the given address, for more than one sensor of a kind
+    pub fn with_device_addr(chip: IioChip, device_addr: u16) -> Result<IioSensor, std::io::Error> {
+        Self::find(Path::new(IIO_DEVICES_PATH), chip, Some(device_addr))
+    }
+
+    // -- like new and with_device_addr but below any directory laid out like /sys/bus/iio/devices
+    pub fn find(devices_path: &Path, chip: IioChip, device_addr: Option<u16>) -> Result<IioSensor, std::io::Error> {
+        let mut device_paths = fs::read_dir(devices_path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>();
+        // -- read_dir order is arbitrary, keep the result stable
+        device_paths.sort();
+        for device_path in device_paths {
+            let Ok(name) = fs::read_to_string(device_path.join("name")) else {
+                continue
+            };
+            if !chip.device_names().contains(&name.trim()) {
+                continue
+            }
+            if device_addr.is_some() && device_addr != Self::i2c_device_addr(&device_path) {
+                continue
+            }
+            debug!("Found {chip} at '{}'", device_path.display());
+            return Ok(IioSensor {
+                chip,
+                device_path,
+            })
+        }
+        let errmsg = match device_addr {
+            Some(device_addr) => format!("No IIO device for {chip} at address {device_addr:#04x} in '{}'", devices_path.display()),
+            None => format!("No IIO device for {chip} in '{}'", devices_path.display()),
+        };
+        Err(std::io::Error::new(std::io::ErrorKind::NotFound, errmsg))
+    }
+
+    // -- the device link of an i2c device points to e.g. .../i2c-1/1-0077
+    fn i2c_device_addr(device_path: &Path) -> Option<u16> {
+        let target = fs::read_link(device_path.join("device")).ok()?;
+        let (_bus, device_addr) = target.file_name()?.to_str()?.split_once('-')?;
+        u16::from_str_radix(device_addr, 16).ok()
+    }
+
+    pub fn get_chip(&self) -> IioChip {
+        self.chip
+    }
+
+    pub fn get_device_path(&self) -> &Path {
+        &self.device_path
+    }
+
+    fn attribute_path(&self, channel: IioChannel, attribute: &str) -> PathBuf {
+        self.device_path.join(format!("in_{}_{attribute}", channel.value()))
+    }
+
+    fn read_attribute(&self, channel: IioChannel, attribute: &str) -> Result<Option<String>, std::io::Error> {
+        match fs::read_to_string(self.attribute_path(channel, attribute)) {
+            Ok(val) => Ok(Some(val.trim().to_string())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, channel: IioChannel, attribute: &str, val: &str) -> Result<T, std::io::Error> {
+        val.parse().map_err(|_| {
+            let errmsg = format!("Invalid value '{val}' in '{}'", self.attribute_path(channel, attribute).display());
+            std::io::Error::new(std::io::ErrorKind::InvalidData, errmsg)
+        })
+    }
+
+    fn lacks(&self, channel: IioChannel, what: &str) -> std::io::Error {
+        let errmsg = format!("IIO device '{}' of {} has no {channel} {what}", self.device_path.display(), self.chip);
+        std::io::Error::new(std::io::ErrorKind::Unsupported, errmsg)
+    }
+
+    // -- the processed value if the driver provides one, else (raw + offset) * scale
+    pub fn get_value(&self, channel: IioChannel) -> Result<f64, std::io::Error> {
+        let val: f64 = match self.read_attribute(channel, "input")? {
+            Some(input) => self.parse(channel, "input", &input)?,
+            None => {
+                let raw = self.read_attribute(channel, "raw")?.ok_or_else(|| self.lacks(channel, "channel"))?;
+                let raw: f64 = self.parse(channel, "raw", &raw)?;
+                let offset: f64 = match self.read_attribute(channel, "offset")? {
+                    Some(offset) => self.parse(channel, "offset", &offset)?,
+                    None => 0.0,
+                };
+                let scale: f64 = match self.read_attribute(channel, "scale")? {
+                    Some(scale) => self.parse(channel, "scale", &scale)?,
+                    None => 1.0,
+                };
+                (raw + offset) * scale
+            }
+        };
+        Ok(val * channel.factor())
+    }
+
+    pub fn get_temperature(&self) -> Result<Temperature, std::io::Error> {
+        self.get_value(IioChannel::Temperature).map(Temperature::from_celsius)
+    }
+
+    pub fn get_pressure(&self) -> Result<Pressure, std::io::Error> {
+        self.get_value(IioChannel::Pressure).map(Pressure::from_pascal)
+    }
+
+    pub fn get_humidity(&self) -> Result<RelativeHumidity, std::io::Error> {
+        self.get_value(IioChannel::Humidity).map(RelativeHumidity::from_percent)
+    }
+
+    pub fn get_gas_resistance(&self) -> Result<Resistance, std::io::Error> {
+        self.get_value(IioChannel::GasResistance).map(Resistance::from_ohms)
+    }
+
+    // -- same order as BMP388::get_pressure_and_temperature
+    pub fn get_pressure_and_temperature(&self) -> Result<(Pressure, Temperature), std::io::Error> {
+        Ok((self.get_pressure()?, self.get_temperature()?))
+    }
+
+    pub fn get_oversampling(&self, channel: IioChannel) -> Result<u32, std::io::Error> {
+        let ratio = self.read_attribute(channel, "oversampling_ratio")?
+            .ok_or_else(|| self.lacks(channel, "oversampling"))?;
+        self.parse(channel, "oversampling_ratio", &ratio)
+    }
+
+    pub fn get_oversampling_available(&self, channel: IioChannel) -> Result<Vec<u32>, std::io::Error> {
+        let available = self.read_attribute(channel, "oversampling_ratio_available")?
+            .ok_or_else(|| self.lacks(channel, "oversampling"))?;
+        available.split_whitespace()
+            .map(|ratio| self.parse(channel, "oversampling_ratio_available", ratio))
+            .collect()
+    }
+
+    // -- writing needs the permissions of the sysfs attribute, usually root
+    pub fn set_oversampling(&mut self, channel: IioChannel, ratio: u32) -> Result<(), std::io::Error> {
+        let available = self.get_oversampling_available(channel)?;
+        if !available.contains(&ratio) {
+            let errmsg = format!("{} {channel} oversampling ratio {ratio} is not one of {available:?}", self.chip);
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        fs::write(self.attribute_path(channel, "oversampling_ratio"), ratio.to_string())
+    }
+}
+
+// -- every channel of the chip, the kernel driver converts on each read
+impl Sensor for IioSensor {
+
+    fn name(&self) -> &'static str {
+        self.chip.name()
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let mut measurement = Measurement::default();
+        for channel in self.chip.channels() {
+            let val = self.get_value(*channel)?;
+            match channel {
+                IioChannel::Temperature => measurement.temperature = Some(Temperature::from_celsius(val)),
+                IioChannel::Pressure => measurement.pressure = Some(Pressure::from_pascal(val)),
+                IioChannel::Humidity => measurement.humidity = Some(RelativeHumidity::from_percent(val)),
+                IioChannel::GasResistance => measurement.gas_resistance = Some(Resistance::from_ohms(val)),
+            }
+        }
+        Ok(measurement)
+    }
+}
@@ -1,13 +1,34 @@
 pub mod bme280;
 pub mod bme680;
 pub mod bmp388;
+pub mod bus_lock;
+pub mod bus_stats;
+pub mod clock;
 pub mod ens160;
+pub mod error;
+pub mod forced_bus;
+pub mod hal;
 pub mod ht16k33;
 #[allow(dead_code)]
 pub mod i2cio;
+pub mod i2cio_async;
+pub mod iio;
+pub mod mock;
+pub mod recording;
+pub mod retry;
+pub mod scanner;
+pub mod sensirion_crc;
+pub mod sensor;
 pub mod sgp40;
+pub mod shared_bus;
 pub mod sht31;
+pub mod spiio;
+pub mod tca9548a;
 pub mod tmp117;
-pub mod voc_algo;
+pub mod trace;
+pub use i2c_sensors_core::barometric;
+pub use i2c_sensors_core::psychrometrics;
+pub use i2c_sensors_core::units;
+pub use i2c_sensors_core::voc_algo;
 
 
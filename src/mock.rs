@@ -0,0 +1,519 @@
+use i2c_linux::{Functionality, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::i2cio::{BusEvent, I2cBus};
+use crate::i2cio_async::AsyncI2cBus;
+use crate::spiio::SpiDevice;
+
+// -- one bus operation the driver is expected to issue, reads carry the canned response
+#[derive(Clone, Debug, PartialEq)]
+pub enum MockOp {
+    SetSlave { device_addr: u16 },
+    SmbusReadByteData { register: u8, data: u8 },
+    SmbusReadWordData { register: u8, data: u16 },
+    SmbusWriteByte { data: u8 },
+    SmbusWriteByteData { register: u8, data: u8 },
+    SmbusWriteWordData { register: u8, data: u16 },
+    SmbusWriteBlockData { register: u8, data: Vec<u8> },
+    I2cReadBlockData { register: u8, data: Vec<u8> },
+    MessageRead { device_addr: u16, data: Vec<u8> },
+    MessageWrite { device_addr: u16, data: Vec<u8> },
+}
+
+impl MockOp {
+    // -- compare what the driver asked for, read data is only compared by length
+    fn matches(&self, issued: &MockOp) -> bool {
+        match (self, issued) {
+            (MockOp::SmbusReadByteData { register, .. }, MockOp::SmbusReadByteData { register: issued_register, .. })
+                | (MockOp::SmbusReadWordData { register, .. }, MockOp::SmbusReadWordData { register: issued_register, .. }) =>
+                register == issued_register,
+            (MockOp::I2cReadBlockData { register, data }, MockOp::I2cReadBlockData { register: issued_register, data: issued_data }) =>
+                register == issued_register && data.len() <= issued_data.len(),
+            (MockOp::MessageRead { device_addr, data }, MockOp::MessageRead { device_addr: issued_addr, data: issued_data }) =>
+                device_addr == issued_addr && data.len() == issued_data.len(),
+            _ => self == issued,
+        }
+    }
+}
+
+impl fmt::Display for MockOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MockOp::SetSlave { device_addr } =>
+                write!(f, "set_slave {device_addr:#04x}"),
+            MockOp::SmbusReadByteData { register, data } =>
+                write!(f, "smbus_read_byte_data reg {register:#04x} -> {data:#04x}"),
+            MockOp::SmbusReadWordData { register, data } =>
+                write!(f, "smbus_read_word_data reg {register:#04x} -> {data:#06x}"),
+            MockOp::SmbusWriteByte { data } =>
+                write!(f, "smbus_write_byte {data:#04x}"),
+            MockOp::SmbusWriteByteData { register, data } =>
+                write!(f, "smbus_write_byte_data reg {register:#04x} <- {data:#04x}"),
+            MockOp::SmbusWriteWordData { register, data } =>
+                write!(f, "smbus_write_word_data reg {register:#04x} <- {data:#06x}"),
+            MockOp::SmbusWriteBlockData { register, data } =>
+                write!(f, "smbus_write_block_data reg {register:#04x} <- {data:02x?}"),
+            MockOp::I2cReadBlockData { register, data } =>
+                write!(f, "i2c_read_block_data reg {register:#04x} -> {data:02x?}"),
+            MockOp::MessageRead { device_addr, data } =>
+                write!(f, "message_read {device_addr:#04x} -> {data:02x?}"),
+            MockOp::MessageWrite { device_addr, data } =>
+                write!(f, "message_write {device_addr:#04x} <- {data:02x?}"),
+        }
+    }
+}
+
+// -- a scripted operation, optionally failing with the given error kind
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockTransaction {
+    op: MockOp,
+    error: Option<std::io::ErrorKind>,
+}
+
+impl MockTransaction {
+
+    pub fn new(op: MockOp) -> Self {
+        MockTransaction {
+            op,
+            error: None,
+        }
+    }
+
+    pub fn set_slave(device_addr: u16) -> Self {
+        Self::new(MockOp::SetSlave { device_addr })
+    }
+
+    pub fn read_byte(register: u8, data: u8) -> Self {
+        Self::new(MockOp::SmbusReadByteData { register, data })
+    }
+
+    pub fn read_word(register: u8, data: u16) -> Self {
+        Self::new(MockOp::SmbusReadWordData { register, data })
+    }
+
+    pub fn write_byte_single(data: u8) -> Self {
+        Self::new(MockOp::SmbusWriteByte { data })
+    }
+
+    pub fn write_byte(register: u8, data: u8) -> Self {
+        Self::new(MockOp::SmbusWriteByteData { register, data })
+    }
+
+    pub fn write_word(register: u8, data: u16) -> Self {
+        Self::new(MockOp::SmbusWriteWordData { register, data })
+    }
+
+    pub fn write_block(register: u8, data: &[u8]) -> Self {
+        Self::new(MockOp::SmbusWriteBlockData { register, data: data.to_vec() })
+    }
+
+    pub fn read_block(register: u8, data: &[u8]) -> Self {
+        Self::new(MockOp::I2cReadBlockData { register, data: data.to_vec() })
+    }
+
+    pub fn read_bytes(device_addr: u16, data: &[u8]) -> Self {
+        Self::new(MockOp::MessageRead { device_addr, data: data.to_vec() })
+    }
+
+    pub fn write_bytes(device_addr: u16, data: &[u8]) -> Self {
+        Self::new(MockOp::MessageWrite { device_addr, data: data.to_vec() })
+    }
+
+    // -- the operation is expected but the bus reports an error instead of completing it
+    pub fn with_error(mut self, kind: std::io::ErrorKind) -> Self {
+        self.error = Some(kind);
+        self
+    }
+}
+
+impl fmt::Display for MockTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+        if let Some(kind) = self.error {
+            write!(f, " !! {kind:?}")?;
+        }
+        Ok(())
+    }
+}
+
+struct MockState<T> {
+    script: Vec<T>,
+    // -- index of the next expected transaction
+    position: usize,
+    // -- sum of all requested delays
+    delay_ms: u64,
+    // -- what the drivers reported to the bus, e.g. crc failures
+    events: Vec<BusEvent>,
+}
+
+fn diff<T: fmt::Display, I: fmt::Display>(state: &MockState<T>, issued: Option<&I>) -> String {
+    let mut lines = Vec::new();
+    for (index, transaction) in state.script.iter().enumerate() {
+        if index < state.position {
+            lines.push(format!("  {index:3} {transaction}"));
+        } else if index == state.position {
+            lines.push(format!("- {index:3} {transaction}"));
+            if let Some(issued) = issued {
+                lines.push(format!("+ {index:3} {issued}"));
+            }
+        } else {
+            lines.push(format!("  {index:3} {transaction} (pending)"));
+        }
+    }
+    if state.position >= state.script.len() {
+        if let Some(issued) = issued {
+            lines.push(format!("+ {:3} {issued}", state.position));
+        }
+    }
+    lines.join("\n")
+}
+
+// -- bus for driver tests that walks an ordered script of expected transactions, panics with
+// -- a diff of script and issued operation on any mismatch; clones share the script so the
+// -- test keeps a handle after moving the bus into the driver, e.g.
+// -- let mock = MockBus::new(&[...]); let sht31 = SHT31::with_bus(mock.clone(), ...); mock.done();
+// -- the same script drives the async driver variants, every operation completes immediately;
+// -- waits complete immediately too and advance the time the bus reports
+#[derive(Clone)]
+pub struct MockBus {
+    state: Rc<RefCell<MockState<MockTransaction>>>,
+    // -- adapter features reported to the drivers
+    functionality: Functionality,
+}
+
+impl MockBus {
+
+    pub fn new(script: &[MockTransaction]) -> Self {
+        let state = MockState {
+            script: script.to_vec(),
+            position: 0,
+            delay_ms: 0,
+            events: Vec::new(),
+        };
+        MockBus {
+            state: Rc::new(RefCell::new(state)),
+            functionality: Functionality::all(),
+        }
+    }
+
+    // -- report a limited adapter, e.g. one without smbus block transfers, everything by default
+    pub fn with_functionality(mut self, functionality: Functionality) -> Self {
+        self.functionality = functionality;
+        self
+    }
+
+    // -- append further expectations, e.g. after checking the driver initialization
+    pub fn expect(&self, script: &[MockTransaction]) {
+        self.state.borrow_mut().script.extend_from_slice(script);
+    }
+
+    // -- panics unless every scripted transaction was issued
+    pub fn done(&self) {
+        let state = self.state.borrow();
+        if state.position < state.script.len() {
+            panic!("Mock bus script not finished, {} transaction(s) never issued\n{}",
+                state.script.len() - state.position, diff::<_, MockOp>(&state, None));
+        }
+    }
+
+    // -- total time the driver asked to wait
+    pub fn delay_total_ms(&self) -> u64 {
+        self.state.borrow().delay_ms
+    }
+
+    // -- events recorded on the bus so far, in order
+    pub fn events(&self) -> Vec<BusEvent> {
+        self.state.borrow().events.clone()
+    }
+
+    // -- check the issued operation against the script and hand out the scripted one
+    fn next(&mut self, issued: MockOp) -> Result<MockOp, std::io::Error> {
+        let mut state = self.state.borrow_mut();
+        let expected = state.script.get(state.position).cloned();
+        match expected {
+            Some(expected) if expected.op.matches(&issued) => {
+                state.position += 1;
+                match expected.error {
+                    Some(kind) => Err(std::io::Error::new(kind, format!("Mock bus scripted error for {}", expected.op))),
+                    None => Ok(expected.op),
+                }
+            }
+            Some(_) => panic!("Mock bus transaction mismatch at {} (- expected, + issued)\n{}",
+                state.position, diff(&state, Some(&issued))),
+            None => panic!("Mock bus script exhausted, unexpected transaction (+ issued)\n{}",
+                diff(&state, Some(&issued))),
+        }
+    }
+
+    fn read_into(data: &mut [u8], canned: &[u8]) -> usize {
+        data[..canned.len()].copy_from_slice(canned);
+        canned.len()
+    }
+}
+
+impl I2cBus for MockBus {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.next(MockOp::SetSlave { device_addr: dev_addr })?;
+        Ok(())
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        match self.next(MockOp::SmbusReadByteData { register, data: 0 })? {
+            MockOp::SmbusReadByteData { data, .. } => Ok(data),
+            _ => unreachable!(),
+        }
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        match self.next(MockOp::SmbusReadWordData { register, data: 0 })? {
+            MockOp::SmbusReadWordData { data, .. } => Ok(data),
+            _ => unreachable!(),
+        }
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.next(MockOp::SmbusWriteByte { data })?;
+        Ok(())
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.next(MockOp::SmbusWriteByteData { register, data })?;
+        Ok(())
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.next(MockOp::SmbusWriteWordData { register, data })?;
+        Ok(())
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.next(MockOp::SmbusWriteBlockData { register, data: data.to_vec() })?;
+        Ok(())
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        match self.next(MockOp::I2cReadBlockData { register, data: vec![0; data.len()] })? {
+            MockOp::I2cReadBlockData { data: canned, .. } => Ok(Self::read_into(data, &canned)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        for message in messages.iter_mut() {
+            match message {
+                Message::Read { address, data, .. } => {
+                    match self.next(MockOp::MessageRead { device_addr: *address, data: vec![0; data.len()] })? {
+                        MockOp::MessageRead { data: canned, .. } => Self::read_into(data, &canned),
+                        _ => unreachable!(),
+                    };
+                }
+                Message::Write { address, data, .. } => {
+                    self.next(MockOp::MessageWrite { device_addr: *address, data: data.to_vec() })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.state.borrow_mut().delay_ms += milli_secs as u64;
+    }
+
+    // -- time only moves with the waits the driver asks for
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.state.borrow().delay_ms)
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        Ok(self.functionality)
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        self.state.borrow_mut().events.push(event);
+    }
+}
+
+impl AsyncI2cBus for MockBus {
+
+    async fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        I2cBus::set_slave(self, dev_addr)
+    }
+
+    async fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        I2cBus::smbus_read_byte_data(self, register)
+    }
+
+    async fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        I2cBus::smbus_read_word_data(self, register)
+    }
+
+    async fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        I2cBus::smbus_write_byte(self, data)
+    }
+
+    async fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        I2cBus::smbus_write_byte_data(self, register, data)
+    }
+
+    async fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        I2cBus::smbus_write_word_data(self, register, data)
+    }
+
+    async fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        I2cBus::smbus_write_block_data(self, register, data)
+    }
+
+    async fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        I2cBus::i2c_read_block_data(self, register, data)
+    }
+
+    async fn i2c_transfer(&mut self, messages: &mut [Message<'_>]) -> Result<(), std::io::Error> {
+        I2cBus::i2c_transfer(self, messages)
+    }
+
+    async fn delay_ms(&mut self, milli_secs: u32) {
+        I2cBus::delay_ms(self, milli_secs)
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        I2cBus::record(self, event)
+    }
+}
+
+// -- one spi transfer the driver is expected to issue, tx is compared byte by byte and
+// -- rx is the canned response clocked in at the same time
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockSpiTransfer {
+    tx: Vec<u8>,
+    rx: Vec<u8>,
+    error: Option<std::io::ErrorKind>,
+}
+
+impl MockSpiTransfer {
+
+    pub fn new(tx: &[u8], rx: &[u8]) -> Self {
+        assert_eq!(tx.len(), rx.len(), "Mock spi transfer needs tx and rx of the same length");
+        MockSpiTransfer {
+            tx: tx.to_vec(),
+            rx: rx.to_vec(),
+            error: None,
+        }
+    }
+
+    // -- the device answers with data once the command bytes, e.g. address and dummy byte, are out
+    pub fn read(command: &[u8], data: &[u8]) -> Self {
+        let mut tx = command.to_vec();
+        tx.resize(command.len() + data.len(), 0);
+        let mut rx = vec![0; command.len()];
+        rx.extend_from_slice(data);
+        Self::new(&tx, &rx)
+    }
+
+    pub fn write(tx: &[u8]) -> Self {
+        Self::new(tx, &vec![0; tx.len()])
+    }
+
+    // -- the transfer is expected but the controller reports an error instead of completing it
+    pub fn with_error(mut self, kind: std::io::ErrorKind) -> Self {
+        self.error = Some(kind);
+        self
+    }
+}
+
+impl fmt::Display for MockSpiTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "spi_transfer {:02x?} -> {:02x?}", self.tx, self.rx)?;
+        if let Some(kind) = self.error {
+            write!(f, " !! {kind:?}")?;
+        }
+        Ok(())
+    }
+}
+
+// -- what the driver clocked out, shown in the diff next to the scripted transfer
+struct MockSpiIssued<'a>(&'a [u8]);
+
+impl fmt::Display for MockSpiIssued<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "spi_transfer {:02x?}", self.0)
+    }
+}
+
+// -- spi device for driver tests, the counterpart of MockBus for spiio::SpiBus, e.g.
+// -- let mock = MockSpi::new(&[...]); let bme280 = BME280::with_spi(mock.clone())?; mock.done();
+#[derive(Clone)]
+pub struct MockSpi {
+    state: Rc<RefCell<MockState<MockSpiTransfer>>>,
+}
+
+impl MockSpi {
+
+    pub fn new(script: &[MockSpiTransfer]) -> Self {
+        let state = MockState {
+            script: script.to_vec(),
+            position: 0,
+            delay_ms: 0,
+            events: Vec::new(),
+        };
+        MockSpi {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    // -- append further expectations, e.g. after checking the driver initialization
+    pub fn expect(&self, script: &[MockSpiTransfer]) {
+        self.state.borrow_mut().script.extend_from_slice(script);
+    }
+
+    // -- panics unless every scripted transfer was issued
+    pub fn done(&self) {
+        let state = self.state.borrow();
+        if state.position < state.script.len() {
+            panic!("Mock spi script not finished, {} transfer(s) never issued\n{}",
+                state.script.len() - state.position, diff::<_, MockSpiIssued>(&state, None));
+        }
+    }
+
+    // -- total time the driver asked to wait
+    pub fn delay_total_ms(&self) -> u64 {
+        self.state.borrow().delay_ms
+    }
+}
+
+impl SpiDevice for MockSpi {
+
+    fn transfer(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<(), std::io::Error> {
+        let mut state = self.state.borrow_mut();
+        let issued = MockSpiIssued(tx);
+        let expected = state.script.get(state.position).cloned();
+        match expected {
+            Some(expected) if expected.tx == tx && expected.rx.len() == rx.len() => {
+                state.position += 1;
+                if let Some(kind) = expected.error {
+                    return Err(std::io::Error::new(kind, format!("Mock spi scripted error for {expected}")))
+                }
+                rx.copy_from_slice(&expected.rx);
+                Ok(())
+            }
+            Some(_) => panic!("Mock spi transfer mismatch at {} (- expected, + issued)\n{}",
+                state.position, diff(&state, Some(&issued))),
+            None => panic!("Mock spi script exhausted, unexpected transfer (+ issued)\n{}",
+                diff(&state, Some(&issued))),
+        }
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.state.borrow_mut().delay_ms += milli_secs as u64;
+    }
+
+    // -- time only moves with the waits the driver asks for
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.state.borrow().delay_ms)
+    }
+}
@@ -0,0 +1,457 @@
+use chrono::Local;
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+// -- marks a comment line in a recording
+const RECORDING_COMMENT: char = '#';
+// -- placeholder for transactions without register
+const RECORDING_NO_REGISTER: &str = "--";
+// -- header comment holding the adapter functionality, e.g. '# functionality 0x0eff0009'
+const RECORDING_FUNCTIONALITY: &str = "functionality";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordedOp {
+    SetSlave,
+    SmbusReadByteData,
+    SmbusReadWordData,
+    SmbusWriteByte,
+    SmbusWriteByteData,
+    SmbusWriteWordData,
+    SmbusWriteBlockData,
+    I2cReadBlockData,
+    MessageRead,
+    MessageWrite,
+}
+
+impl RecordedOp {
+    fn value(&self) -> &'static str {
+        match *self {
+            RecordedOp::SetSlave => "set_slave",
+            RecordedOp::SmbusReadByteData => "smbus_read_byte_data",
+            RecordedOp::SmbusReadWordData => "smbus_read_word_data",
+            RecordedOp::SmbusWriteByte => "smbus_write_byte",
+            RecordedOp::SmbusWriteByteData => "smbus_write_byte_data",
+            RecordedOp::SmbusWriteWordData => "smbus_write_word_data",
+            RecordedOp::SmbusWriteBlockData => "smbus_write_block_data",
+            RecordedOp::I2cReadBlockData => "i2c_read_block_data",
+            RecordedOp::MessageRead => "message_read",
+            RecordedOp::MessageWrite => "message_write",
+        }
+    }
+
+    // -- whether the recorded bytes were sent by the device
+    pub fn is_read(&self) -> bool {
+        matches!(*self, RecordedOp::SmbusReadByteData | RecordedOp::SmbusReadWordData
+            | RecordedOp::I2cReadBlockData | RecordedOp::MessageRead)
+    }
+}
+
+impl fmt::Display for RecordedOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl FromStr for RecordedOp {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ops = [
+            RecordedOp::SetSlave, RecordedOp::SmbusReadByteData, RecordedOp::SmbusReadWordData,
+            RecordedOp::SmbusWriteByte, RecordedOp::SmbusWriteByteData, RecordedOp::SmbusWriteWordData,
+            RecordedOp::SmbusWriteBlockData, RecordedOp::I2cReadBlockData, RecordedOp::MessageRead,
+            RecordedOp::MessageWrite,
+        ];
+        ops.into_iter()
+            .find(|op| op.value() == s)
+            .ok_or_else(|| invalid_data(format!("Unknown recorded operation '{s}'")))
+    }
+}
+
+// -- one line of a recording:
+// -- <micro seconds since start> <device address> <operation> <R|W> <register|--> <hex bytes>
+// -- smbus words are kept as on the wire, least significant byte first
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedTransaction {
+    pub timestamp_us: u64,
+    pub device_addr: u16,
+    pub op: RecordedOp,
+    pub register: Option<u8>,
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for RecordedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = if self.op.is_read() { 'R' } else { 'W' };
+        write!(f, "{} {:#04x} {} {direction} ", self.timestamp_us, self.device_addr, self.op)?;
+        match self.register {
+            Some(register) => write!(f, "{register:#04x}")?,
+            None => write!(f, "{RECORDING_NO_REGISTER}")?,
+        }
+        for byte in self.data.iter() {
+            write!(f, " {byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecordedTransaction {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace();
+        let mut next_field = |name: &str| fields.next()
+            .ok_or_else(|| invalid_data(format!("Recorded transaction '{s}' lacks the {name}")));
+        let timestamp_us = next_field("timestamp")?;
+        let timestamp_us = timestamp_us.parse::<u64>()
+            .map_err(|err| invalid_data(format!("Invalid timestamp '{timestamp_us}': {err}")))?;
+        let device_addr = parse_hex(next_field("device address")?)? as u16;
+        let op = next_field("operation")?.parse::<RecordedOp>()?;
+        // -- the direction is informative only, it follows from the operation
+        let _direction = next_field("direction")?;
+        let register = match next_field("register")? {
+            RECORDING_NO_REGISTER => None,
+            register => Some(parse_hex(register)? as u8),
+        };
+        let data = fields
+            .map(|byte| parse_hex(byte).map(|byte| byte as u8))
+            .collect::<Result<Vec<u8>, std::io::Error>>()?;
+        Ok(RecordedTransaction {
+            timestamp_us,
+            device_addr,
+            op,
+            register,
+            data,
+        })
+    }
+}
+
+fn invalid_data(errmsg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, errmsg)
+}
+
+fn parse_hex(s: &str) -> Result<u32, std::io::Error> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    u32::from_str_radix(digits, 16)
+        .map_err(|err| invalid_data(format!("Invalid hex value '{s}': {err}")))
+}
+
+// -- passes every transaction through to the wrapped bus and writes it to the recording,
+// -- only transactions the bus completed are recorded
+pub struct RecordingBus<B: I2cBus = I2c<File>, W: Write = LineWriter<File>> {
+    bus: B,
+    writer: W,
+    started: Duration,
+    // -- device address set by the driver
+    device_addr: u16,
+}
+
+impl RecordingBus {
+
+    pub fn create(i2c_bus_path: &Path, recording_path: &Path) -> Result<RecordingBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        let file = File::create(recording_path)?;
+        let mut recording_bus = RecordingBus::new(i2c, LineWriter::new(file));
+        writeln!(recording_bus.writer, "{RECORDING_COMMENT} bus {}", i2c_bus_path.display())?;
+        Ok(recording_bus)
+    }
+
+}
+
+impl<B: I2cBus, W: Write> RecordingBus<B, W> {
+
+    pub fn new(mut bus: B, mut writer: W) -> Self {
+        // -- the header is a comment, a failing writer shows up with the first transaction
+        let _ = writeln!(writer, "{RECORDING_COMMENT} i2c-sensors recording started {}", Local::now().to_rfc3339());
+        // -- the drivers pick their transfers by the adapter functionality, the replay has to report the same
+        match bus.functionality() {
+            Ok(functionality) => {
+                let _ = writeln!(writer, "{RECORDING_COMMENT} {RECORDING_FUNCTIONALITY} {:#010x}", functionality.bits());
+            }
+            Err(err) => warn!("Recording without adapter functionality: {err}"),
+        }
+        let started = bus.now();
+        RecordingBus {
+            bus,
+            writer,
+            started,
+            device_addr: 0,
+        }
+    }
+
+    pub fn release(self) -> (B, W) {
+        (self.bus, self.writer)
+    }
+
+    fn record(&mut self, device_addr: u16, op: RecordedOp, register: Option<u8>, data: &[u8]) -> Result<(), std::io::Error> {
+        let transaction = RecordedTransaction {
+            timestamp_us: self.bus.now().saturating_sub(self.started).as_micros() as u64,
+            device_addr,
+            op,
+            register,
+            data: data.to_vec(),
+        };
+        writeln!(self.writer, "{transaction}")
+    }
+}
+
+impl<B: I2cBus, W: Write> I2cBus for RecordingBus<B, W> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.bus.set_slave(dev_addr)?;
+        self.device_addr = dev_addr;
+        self.record(dev_addr, RecordedOp::SetSlave, None, &[])
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        let data = self.bus.smbus_read_byte_data(register)?;
+        self.record(self.device_addr, RecordedOp::SmbusReadByteData, Some(register), &[data])?;
+        Ok(data)
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        let data = self.bus.smbus_read_word_data(register)?;
+        self.record(self.device_addr, RecordedOp::SmbusReadWordData, Some(register), &data.to_le_bytes())?;
+        Ok(data)
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_byte(data)?;
+        self.record(self.device_addr, RecordedOp::SmbusWriteByte, None, &[data])
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_byte_data(register, data)?;
+        self.record(self.device_addr, RecordedOp::SmbusWriteByteData, Some(register), &[data])
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_word_data(register, data)?;
+        self.record(self.device_addr, RecordedOp::SmbusWriteWordData, Some(register), &data.to_le_bytes())
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.bus.smbus_write_block_data(register, data)?;
+        self.record(self.device_addr, RecordedOp::SmbusWriteBlockData, Some(register), data)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        let bytes_read = self.bus.i2c_read_block_data(register, data)?;
+        self.record(self.device_addr, RecordedOp::I2cReadBlockData, Some(register), &data[..bytes_read])?;
+        Ok(bytes_read)
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.bus.i2c_transfer(messages)?;
+        // -- one line per message, in transfer order
+        for message in messages.iter() {
+            match message {
+                Message::Read { address, data, .. } =>
+                    self.record(*address, RecordedOp::MessageRead, None, data)?,
+                Message::Write { address, data, .. } =>
+                    self.record(*address, RecordedOp::MessageWrite, None, data)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.bus.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.bus.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.end_transaction()
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.bus.retry_policy()
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        self.bus.record(event)
+    }
+}
+
+// -- serves a recording back to a driver, writes must match the recording byte by byte,
+// -- delays return right away since the recording already happened
+pub struct ReplayBus {
+    transactions: Vec<RecordedTransaction>,
+    // -- index of the next transaction to replay
+    position: usize,
+    // -- device address set by the driver
+    device_addr: u16,
+    // -- adapter features of the recording, everything if it does not tell
+    functionality: Functionality,
+}
+
+impl ReplayBus {
+
+    pub fn open(recording_path: &Path) -> Result<ReplayBus, std::io::Error> {
+        let file = File::open(recording_path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<ReplayBus, std::io::Error> {
+        let mut transactions = Vec::new();
+        let mut functionality = Functionality::all();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix(RECORDING_COMMENT) {
+                if let Some(bits) = comment.trim().strip_prefix(RECORDING_FUNCTIONALITY) {
+                    functionality = Functionality::from_bits_truncate(parse_hex(bits.trim())?);
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            transactions.push(line.parse::<RecordedTransaction>()?);
+        }
+        debug!("Loaded {} recorded transactions, adapter functionality {functionality:?}", transactions.len());
+        Ok(Self::new(transactions).with_functionality(functionality))
+    }
+
+    pub fn new(transactions: Vec<RecordedTransaction>) -> ReplayBus {
+        ReplayBus {
+            transactions,
+            position: 0,
+            device_addr: 0,
+            functionality: Functionality::all(),
+        }
+    }
+
+    // -- report a limited adapter, from_reader takes it from the recording header
+    pub fn with_functionality(mut self, functionality: Functionality) -> Self {
+        self.functionality = functionality;
+        self
+    }
+
+    // -- number of recorded transactions not yet replayed
+    pub fn remaining(&self) -> usize {
+        self.transactions.len() - self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn next(&mut self, device_addr: u16, op: RecordedOp, register: Option<u8>) -> Result<&RecordedTransaction, std::io::Error> {
+        let position = self.position;
+        let Some(transaction) = self.transactions.get(position) else {
+            let errmsg = format!("Replay exhausted, driver issued {op} at {device_addr:#04x} after the last recorded transaction");
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, errmsg))
+        };
+        if transaction.op != op || transaction.device_addr != device_addr || transaction.register != register {
+            let register = register.map_or(RECORDING_NO_REGISTER.to_string(), |register| format!("{register:#04x}"));
+            let errmsg = format!("Replay mismatch at transaction {position}: recorded '{transaction}', driver issued {op} at {device_addr:#04x} register {register}");
+            return Err(invalid_data(errmsg))
+        }
+        self.position += 1;
+        Ok(transaction)
+    }
+
+    fn replay_write(&mut self, device_addr: u16, op: RecordedOp, register: Option<u8>, data: &[u8]) -> Result<(), std::io::Error> {
+        let position = self.position;
+        let transaction = self.next(device_addr, op, register)?;
+        if transaction.data != data {
+            let errmsg = format!("Replay mismatch at transaction {position}: recorded '{transaction}', driver wrote {data:02x?}");
+            return Err(invalid_data(errmsg))
+        }
+        Ok(())
+    }
+
+    fn replay_read(&mut self, device_addr: u16, op: RecordedOp, register: Option<u8>, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        let position = self.position;
+        let transaction = self.next(device_addr, op, register)?;
+        if transaction.data.len() > data.len() {
+            let errmsg = format!("Replay mismatch at transaction {position}: recorded '{transaction}', driver reads only {} bytes", data.len());
+            return Err(invalid_data(errmsg))
+        }
+        data[..transaction.data.len()].copy_from_slice(&transaction.data);
+        Ok(transaction.data.len())
+    }
+}
+
+impl I2cBus for ReplayBus {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.replay_write(dev_addr, RecordedOp::SetSlave, None, &[])?;
+        self.device_addr = dev_addr;
+        Ok(())
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        let mut data: [u8; 1] = [0; 1];
+        self.replay_read(self.device_addr, RecordedOp::SmbusReadByteData, Some(register), &mut data)?;
+        Ok(data[0])
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        let mut data: [u8; 2] = [0; 2];
+        self.replay_read(self.device_addr, RecordedOp::SmbusReadWordData, Some(register), &mut data)?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.replay_write(self.device_addr, RecordedOp::SmbusWriteByte, None, &[data])
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.replay_write(self.device_addr, RecordedOp::SmbusWriteByteData, Some(register), &[data])
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.replay_write(self.device_addr, RecordedOp::SmbusWriteWordData, Some(register), &data.to_le_bytes())
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.replay_write(self.device_addr, RecordedOp::SmbusWriteBlockData, Some(register), data)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.replay_read(self.device_addr, RecordedOp::I2cReadBlockData, Some(register), data)
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        for message in messages.iter_mut() {
+            match message {
+                Message::Read { address, data, .. } => {
+                    self.replay_read(*address, RecordedOp::MessageRead, None, data)?;
+                }
+                Message::Write { address, data, .. } =>
+                    self.replay_write(*address, RecordedOp::MessageWrite, None, data)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, _milli_secs: u32) {
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        Ok(self.functionality)
+    }
+}
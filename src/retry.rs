@@ -0,0 +1,214 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+// -- retries transient errors of every single transfer per policy, transactions are retried
+// -- as a whole by i2cio::transaction instead of message by message; wrap the bus handed to
+// -- one driver to apply the policy per driver, or use i2cio::retry for a single sequence
+pub struct RetryBus<B: I2cBus = I2c<File>> {
+    // -- the retried bus
+    bus: B,
+    // -- how to retry
+    policy: RetryPolicy,
+    // -- nesting depth of the running transactions
+    depth: usize,
+}
+
+impl RetryBus {
+
+    pub fn open(i2c_bus_path: &Path, policy: RetryPolicy) -> Result<RetryBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Ok(Self::new(i2c, policy))
+    }
+
+}
+
+impl<B: I2cBus> RetryBus<B> {
+
+    pub fn new(bus: B, policy: RetryPolicy) -> Self {
+        RetryBus {
+            bus,
+            policy,
+            depth: 0,
+        }
+    }
+
+    pub fn get_policy(&self) -> RetryPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: RetryPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn release(self) -> B {
+        self.bus
+    }
+
+    fn retried<R>(&mut self, mut op: impl FnMut(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+        if self.depth > 0 {
+            // -- repeating one message of a transaction would break its sequence
+            return op(&mut self.bus)
+        }
+        i2cio::retry(&mut self.bus, &self.policy, op)
+    }
+}
+
+impl<B: I2cBus> I2cBus for RetryBus<B> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.bus.set_slave(dev_addr)
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.retried(|bus| bus.smbus_read_byte_data(register))
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.retried(|bus| bus.smbus_read_word_data(register))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.retried(|bus| bus.smbus_write_byte(data))
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.retried(|bus| bus.smbus_write_byte_data(register, data))
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.retried(|bus| bus.smbus_write_word_data(register, data))
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.retried(|bus| bus.smbus_write_block_data(register, data))
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.retried(|bus| bus.i2c_read_block_data(register, data))
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.retried(|bus| bus.i2c_transfer(messages))
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.bus.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.bus.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.bus.begin_transaction()?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        self.depth = self.depth.saturating_sub(1);
+        self.bus.end_transaction()
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        Some(self.policy)
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.retried(|bus| bus.functionality())
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        self.bus.record(event)
+    }
+}
+
+// -- re-run the parts of a driver's init sequence a device loses on a brown-out or a hung bus,
+// -- e.g. re-read calibration data or re-enter the operational mode
+pub trait Recoverable {
+    fn recover(&mut self) -> Result<(), std::io::Error>;
+}
+
+// -- owns a driver and re-initializes it once the policy's number of failed operations in a
+// -- row is reached, only errors of the policy's error classes count, e.g.
+// -- let mut bme280 = Recovering::new(BME280::with_bus(RetryBus::new(i2c, policy), addr)?, policy);
+// -- bme280.run(|bme280| bme280.get_sensor_data())?;
+pub struct Recovering<D, H = fn(&mut D) -> Result<(), std::io::Error>> {
+    // -- the guarded driver
+    driver: D,
+    // -- when to recover
+    policy: RetryPolicy,
+    // -- the recovery hook
+    hook: H,
+    // -- failed operations since the last success or recovery
+    failures: u32,
+}
+
+impl<D: Recoverable> Recovering<D> {
+
+    pub fn new(driver: D, policy: RetryPolicy) -> Self {
+        Self::with_hook(driver, policy, D::recover)
+    }
+
+}
+
+impl<D, H: FnMut(&mut D) -> Result<(), std::io::Error>> Recovering<D, H> {
+
+    // -- recover with a hook of its own, e.g. to restore a configuration only the caller knows
+    pub fn with_hook(driver: D, policy: RetryPolicy, hook: H) -> Self {
+        Recovering {
+            driver,
+            policy,
+            hook,
+            failures: 0,
+        }
+    }
+
+    pub fn run<R>(&mut self, op: impl FnOnce(&mut D) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+        let err = match op(&mut self.driver) {
+            Ok(result) => {
+                self.failures = 0;
+                return Ok(result)
+            }
+            Err(err) => err,
+        };
+        if !self.policy.is_retryable(&err) {
+            return Err(err)
+        }
+        self.failures += 1;
+        let Some(recover_after) = self.policy.get_recover_after() else {
+            return Err(err)
+        };
+        if self.failures >= recover_after {
+            warn!("Re-initializing driver after {} failed operations in a row", self.failures);
+            self.failures = 0;
+            if let Err(recover_err) = (self.hook)(&mut self.driver) {
+                warn!("Re-initializing driver failed: {recover_err}");
+            }
+        }
+        Err(err)
+    }
+
+    pub fn get_failures(&self) -> u32 {
+        self.failures
+    }
+
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    pub fn release(self) -> D {
+        self.driver
+    }
+}
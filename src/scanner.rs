@@ -0,0 +1,200 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::bme280::{self, Bme280DeviceAddress};
+use crate::bme680;
+use crate::bmp388;
+use crate::ens160::{self, Ens160DeviceAddress};
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::sensirion_crc;
+use crate::sht31::{self, SHT31DeviceAddress};
+use crate::tmp117::{self, Tmp117DeviceAddress};
+
+// -- the full 7 bit range without the reserved addresses, like i2cdetect
+pub const SCAN_ADDR_FIRST: u16 = 0x08;
+pub const SCAN_ADDR_LAST: u16 = 0x77;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScanChip {
+    Bme280,
+    Bme680,
+    Bmp388,
+    Ens160,
+    Sht31,
+    Tmp117,
+}
+
+impl ScanChip {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Bme280 => "BME280",
+            Self::Bme680 => "BME680",
+            Self::Bmp388 => "BMP388",
+            Self::Ens160 => "ENS160",
+            Self::Sht31 => "SHT31",
+            Self::Tmp117 => "TMP117",
+        }
+    }
+}
+
+impl fmt::Display for ScanChip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// -- a supported chip found on the bus
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanResult {
+    // -- 7 bit device address
+    addr: u16,
+    // -- the identified chip
+    chip: ScanChip,
+    // -- chip, part or device id as read, the SHT31 status register as it has no id
+    id: u16,
+    // -- revision, only the TMP117 reports one with its id
+    revision: Option<u8>,
+}
+
+impl ScanResult {
+    pub fn get_addr(&self) -> u16 {
+        self.addr
+    }
+
+    pub fn get_chip(&self) -> ScanChip {
+        self.chip
+    }
+
+    pub fn get_id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn get_revision(&self) -> Option<u8> {
+        self.revision
+    }
+}
+
+impl fmt::Display for ScanResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.chip, self.revision) {
+            (ScanChip::Sht31, _) => write!(f, "{:#04x}: {} (status {:#06x})", self.addr, self.chip, self.id),
+            (_, Some(revision)) => write!(f, "{:#04x}: {} (id {:#06x}, revision {revision})", self.addr, self.chip, self.id),
+            (_, None) => write!(f, "{:#04x}: {} (id {:#06x})", self.addr, self.chip, self.id),
+        }
+    }
+}
+
+pub fn scan_bus(i2c_bus_path: &Path) -> Result<Vec<ScanResult>, std::io::Error> {
+    // -- get the bus
+    let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+    scan(&mut i2c, SCAN_ADDR_FIRST..=SCAN_ADDR_LAST)
+}
+
+// -- only the addresses the supported chips can be strapped to are probed, each with the
+// -- identity read of its driver, addresses which don't answer or answer with an unknown id
+// -- are skipped; the probes are reads except for the SHT31 status command; only the TMP117
+// -- reports a revision, the BME680 variant and the ENS160 firmware version are not read, the
+// -- latter takes a command in idle mode
+pub fn scan<B: I2cBus>(i2c: &mut B, addrs: RangeInclusive<u16>) -> Result<Vec<ScanResult>, std::io::Error> {
+    let mut results = Vec::new();
+    for addr in addrs {
+        let Some(probe) = probe_for(addr) else {
+            continue
+        };
+        // -- addresses a kernel driver owns can't be probed, skip them instead of failing the scan
+        if let Err(err) = i2cio::set_slave(i2c, addr) {
+            match Error::from(err) {
+                err @ Error::AddressInUse { .. } => {
+                    info!("Skipping {addr:#04x}: {err}");
+                    continue
+                }
+                err => return Err(err.into()),
+            }
+        }
+        match probe(i2c, addr) {
+            Ok(Some(result)) => {
+                debug!("Found {result}");
+                results.push(result);
+            }
+            Ok(None) => debug!("Unknown chip at {addr:#04x}"),
+            Err(err) => debug!("No answer at {addr:#04x}: {err}"),
+        }
+    }
+    Ok(results)
+}
+
+type Probe<B> = fn(&mut B, u16) -> Result<Option<ScanResult>, std::io::Error>;
+
+fn probe_for<B: I2cBus>(addr: u16) -> Option<Probe<B>> {
+    let bosch = [Bme280DeviceAddress::Default, Bme280DeviceAddress::Secondary];
+    let ens160 = [Ens160DeviceAddress::Default, Ens160DeviceAddress::Secondary];
+    let sht31 = [SHT31DeviceAddress::Default, SHT31DeviceAddress::Secondary];
+    let tmp117 = [Tmp117DeviceAddress::Default, Tmp117DeviceAddress::Alt1,
+        Tmp117DeviceAddress::Alt2, Tmp117DeviceAddress::Alt3];
+    if bosch.iter().any(|dev_addr| dev_addr.value() == addr) {
+        Some(probe_bosch)
+    } else if ens160.iter().any(|dev_addr| dev_addr.value() == addr) {
+        Some(probe_ens160)
+    } else if sht31.iter().any(|dev_addr| dev_addr.value() == addr) {
+        Some(probe_sht31)
+    } else if tmp117.iter().any(|dev_addr| dev_addr.value() == addr) {
+        Some(probe_tmp117)
+    } else {
+        None
+    }
+}
+
+// -- BME280, BME680 and BMP388 share their addresses, the BMP388 keeps its id at 0x00
+fn probe_bosch<B: I2cBus>(i2c: &mut B, addr: u16) -> Result<Option<ScanResult>, std::io::Error> {
+    let chip_id = i2cio::read_byte(i2c, bme280::BME280_REG_PART_ID)?;
+    debug!("Chip id at {addr:#04x} register {:#04x}: {chip_id:#04x}", bme280::BME280_REG_PART_ID);
+    match chip_id {
+        bme280::BME280_CHIP_ID => return Ok(Some(ScanResult { addr, chip: ScanChip::Bme280, id: chip_id as u16, revision: None })),
+        bme680::BME680_CHIP_ID => return Ok(Some(ScanResult { addr, chip: ScanChip::Bme680, id: chip_id as u16, revision: None })),
+        _ => {}
+    }
+    let chip_id = i2cio::read_byte(i2c, bmp388::BMP388_REG_CHIP_ID)?;
+    debug!("Chip id at {addr:#04x} register {:#04x}: {chip_id:#04x}", bmp388::BMP388_REG_CHIP_ID);
+    if chip_id != bmp388::BMP388_CHIP_ID {
+        return Ok(None)
+    }
+    Ok(Some(ScanResult { addr, chip: ScanChip::Bmp388, id: chip_id as u16, revision: None }))
+}
+
+fn probe_ens160<B: I2cBus>(i2c: &mut B, addr: u16) -> Result<Option<ScanResult>, std::io::Error> {
+    let part_id = i2cio::read_word(i2c, ens160::ENS160_REG_PART_ID)?;
+    if part_id != ens160::ENS160_PART_ID {
+        return Ok(None)
+    }
+    Ok(Some(ScanResult { addr, chip: ScanChip::Ens160, id: part_id, revision: None }))
+}
+
+// -- the SHT31 has no id register, a status read that passes the crc check identifies it
+fn probe_sht31<B: I2cBus>(i2c: &mut B, addr: u16) -> Result<Option<ScanResult>, std::io::Error> {
+    let cmd_msb: u8 = (sht31::SHT31_COMMAND_READ_STATUS >> 8) as u8;
+    let cmd_lsb: u8 = (sht31::SHT31_COMMAND_READ_STATUS & 0xff) as u8;
+    let mut read_buf: [u8; 3] = [0; 3];
+    i2cio::transaction(i2c, |i2c| {
+        i2cio::write_byte(i2c, cmd_msb, cmd_lsb)?;
+        i2cio::read_bytes(i2c, addr, &mut read_buf)
+    })?;
+    if sensirion_crc::crc8(&read_buf[..2]) != read_buf[2] {
+        return Ok(None)
+    }
+    let status = (read_buf[0] as u16) << 8 | read_buf[1] as u16;
+    Ok(Some(ScanResult { addr, chip: ScanChip::Sht31, id: status, revision: None }))
+}
+
+fn probe_tmp117<B: I2cBus>(i2c: &mut B, addr: u16) -> Result<Option<ScanResult>, std::io::Error> {
+    // -- TMP117 sends most significant byte first so a swap is required
+    let reg_val = i2cio::read_word(i2c, tmp117::TMP117_REG_DEVICE_ID)?.swap_bytes();
+    let (device_id, device_rev) = tmp117::device_id_and_revision(reg_val);
+    if device_id != tmp117::TMP117_DEVICE_ID {
+        return Ok(None)
+    }
+    Ok(Some(ScanResult { addr, chip: ScanChip::Tmp117, id: device_id, revision: Some(device_rev) }))
+}
@@ -0,0 +1,82 @@
+#[allow(unused_imports)]
+use log::{debug, warn};
+
+use crate::error::Error;
+use crate::i2cio::{BusEvent, I2cBus};
+use crate::i2cio_async::AsyncI2cBus;
+
+// -- see data sheets of SHT3x and SGP40: crc-8, polynomial 0x31, init 0xff, no final xor
+const CRC8_POLYNOMIAL: u8 = 0x31;
+const CRC8_INIT: u8 = 0xff;
+
+// -- Sensirion sensors send every data word as msb, lsb, crc
+const WORD_LEN: usize = 3;
+
+// -- what to do when a word doesn't match its crc
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CrcPolicy {
+    // -- fail the read with a crc error, the word is dropped
+    #[default]
+    Error,
+    // -- log a warning and hand out the word anyway
+    Warn,
+    // -- don't check at all
+    Ignore,
+}
+
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = CRC8_INIT;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            if (crc & 0x80) != 0 {
+                crc = (crc << 1) ^ CRC8_POLYNOMIAL;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+// -- checks every word of a response against the crc that follows it
+pub fn check(policy: CrcPolicy, read_buf: &[u8]) -> Result<(), std::io::Error> {
+    check_recording(policy, read_buf, |_| {})
+}
+
+// -- like check, also counts every failed word on the bus for its statistics
+pub fn check_on_bus<B: I2cBus>(i2c: &mut B, policy: CrcPolicy, read_buf: &[u8]) -> Result<(), std::io::Error> {
+    check_recording(policy, read_buf, |event| i2c.record(event))
+}
+
+// -- check_on_bus for the async drivers
+pub fn check_on_async_bus<B: AsyncI2cBus>(i2c: &mut B, policy: CrcPolicy, read_buf: &[u8]) -> Result<(), std::io::Error> {
+    check_recording(policy, read_buf, |event| i2c.record(event))
+}
+
+// -- one pass over the words, every failed one goes to record and the first fails the read, none
+// -- are checked when ignored
+fn check_recording(policy: CrcPolicy, read_buf: &[u8], mut record: impl FnMut(BusEvent)) -> Result<(), std::io::Error> {
+    if policy == CrcPolicy::Ignore {
+        return Ok(())
+    }
+    let mut first_failure = None;
+    for word in read_buf.chunks_exact(WORD_LEN) {
+        let expected = crc8(&word[..2]);
+        let found = word[2];
+        if found == expected {
+            continue
+        }
+        record(BusEvent::CrcFailure);
+        match policy {
+            CrcPolicy::Error => {
+                first_failure.get_or_insert(Error::Crc { expected, found });
+            },
+            _ => warn!("Expected CRC {expected:#04x}, received CRC {found:#04x}"),
+        }
+    }
+    match first_failure {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::psychrometrics;
+use crate::units::{Concentration, Pressure, RelativeHumidity, Resistance, Temperature};
+
+// -- one reading of any of the drivers, each sensor fills in what it measures and leaves the rest
+// -- None; quantities carry their unit, e.g. pressure.hectopascal() or temperature.fahrenheit()
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub temperature: Option<Temperature>,
+    pub humidity: Option<RelativeHumidity>,
+    pub pressure: Option<Pressure>,
+    // -- BME680 heater plate resistance
+    pub gas_resistance: Option<Resistance>,
+    // -- 1 to 500, 100 is the average of the last 24 hours, from the Sensirion VOC algorithm
+    pub voc_index: Option<f64>,
+    // -- total volatile organic compounds
+    pub tvoc: Option<Concentration>,
+    // -- equivalent CO2
+    pub eco2: Option<Concentration>,
+    // -- air quality index 1 to 5 according to UBA, 1 is excellent
+    pub aqi: Option<u8>,
+}
+
+// -- psychrometrics of the temperature and humidity, None unless the sensor measures both, e.g. SHT31,
+// -- BME280, BME680 or the IIO BME280 and BME680; the valid ranges are those of psychrometrics
+impl Measurement {
+
+    fn temperature_and_humidity(&self) -> Option<(f64, f64)> {
+        Some((self.temperature?.celsius(), self.humidity?.percent()))
+    }
+
+    pub fn dew_point(&self) -> Option<Temperature> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(Temperature::from_celsius(psychrometrics::dew_point(temperature, humidity)))
+    }
+
+    pub fn frost_point(&self) -> Option<Temperature> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(Temperature::from_celsius(psychrometrics::frost_point(temperature, humidity)))
+    }
+
+    // -- g/m3
+    pub fn absolute_humidity(&self) -> Option<f64> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(psychrometrics::absolute_humidity(temperature, humidity))
+    }
+
+    // -- g/kg, needs the pressure too, e.g. of BME280 or BME680
+    pub fn mixing_ratio(&self) -> Option<f64> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(psychrometrics::mixing_ratio(temperature, humidity, self.pressure?.pascal()))
+    }
+
+    pub fn wet_bulb(&self) -> Option<Temperature> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(Temperature::from_celsius(psychrometrics::wet_bulb(temperature, humidity)))
+    }
+
+    pub fn heat_index(&self) -> Option<Temperature> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(Temperature::from_celsius(psychrometrics::heat_index(temperature, humidity)))
+    }
+
+    pub fn humidex(&self) -> Option<f64> {
+        let (temperature, humidity) = self.temperature_and_humidity()?;
+        Some(psychrometrics::humidex(temperature, humidity))
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = [
+            self.temperature.map(|temperature| format!("{temperature:.2}")),
+            self.humidity.map(|humidity| format!("{humidity:.2}")),
+            self.pressure.map(|pressure| format!("{pressure:.1}")),
+            self.gas_resistance.map(|gas_resistance| format!("{gas_resistance:.0}")),
+            self.voc_index.map(|voc_index| format!("VOC index {voc_index:.0}")),
+            self.tvoc.map(|tvoc| format!("TVOC {:.0} ppb", tvoc.ppb())),
+            self.eco2.map(|eco2| format!("eCO2 {:.0} ppm", eco2.ppm())),
+            self.aqi.map(|aqi| format!("AQI {aqi}")),
+        ];
+        let fields: Vec<String> = fields.into_iter().flatten().collect();
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+// -- common interface of the drivers, so a set of different sensors can be read in one loop, e.g.
+// -- let mut sensors: Vec<Box<dyn Sensor>> = vec![Box::new(bme280), Box::new(sht31), Box::new(ens160)];
+// -- for sensor in sensors.iter_mut() { info!("{}: {}", sensor.name(), sensor.measure()?) }
+pub trait Sensor {
+    // -- the chip, e.g. BME280
+    fn name(&self) -> &'static str;
+    // -- take a reading, triggering a conversion first where the sensor needs one
+    fn measure(&mut self) -> Result<Measurement, std::io::Error>;
+}
@@ -3,23 +3,67 @@ use i2c_linux::I2c;
 use log::{debug, error, log_enabled, info, warn, Level};
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 
-use crate::{i2cio, voc_algo::VocAlgorithmParams};
+use crate::{i2cio::{self, I2cBus}, i2cio_async::{self, AsyncI2cBus}, retry::Recoverable,
+    sensirion_crc::{self, CrcPolicy}, sensor::{Measurement, Sensor}, trace::{self, AsyncDriverCall, DriverCall},
+    voc_algo::VocAlgorithmParams};
 
 const DEVICE_ADDR_DEFAULT: u16 = 0x59;
 
+// -- the sensor takes commands, it has no registers
+trace::register_names!();
+
 // -- the soft reset time is actually up to or less than 0.6ms
 const SGP40_SOFT_RESET_DELAY_MS: u32 = 1;
 const SGP40_DATA_READY_DELAY_MS: u32 = 30;
+// -- the voc algorithm is tuned for one sample per second
+const SGP40_VOC_SAMPLING_INTERVAL_MS: u64 = 1000;
+
+// -- see data sheet: subcommand 0x26 0x0f plus default compensation values with CRCs
+const SGP40_COMMAND_NO_COMPENSATION: [u8; 8] = [0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93];
+// -- the default compensation values, 50 %RH and 25 °C
+const SGP40_DEFAULT_HUMIDITY_RAW: u16 = 0x8000;
+const SGP40_DEFAULT_TEMPERATURE_RAW: u16 = 0x6666;
+
+
+// -- see data sheet: subcommand 0x26 0x0f plus compensation values with CRCs
+fn compensation_frame(humidity_raw: u16, temperature_raw: u16) -> [u8; 8] {
+    let humidity_raw_msb: u8 = (humidity_raw >> 8) as u8;
+    let humidity_raw_lsb: u8 = (humidity_raw & 0xff) as u8;
+    let temperature_raw_msb: u8 = (temperature_raw >> 8) as u8;
+    let temperature_raw_lsb: u8 = (temperature_raw & 0xff) as u8;
+    let mut data: [u8; 8] = [0x26, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    data[2] = humidity_raw_msb;
+    data[3] = humidity_raw_lsb;
+    data[4] = sensirion_crc::crc8(&[humidity_raw_msb, humidity_raw_lsb]);
+    data[5] = temperature_raw_msb;
+    data[6] = temperature_raw_lsb;
+    data[7] = sensirion_crc::crc8(&[temperature_raw_msb, temperature_raw_lsb]);
+    data
+}
 
+// -- raw voc word followed by its crc
+fn parse_voc(read_buf: &[u8; 3]) -> u16 {
+    let voc_raw_msb = read_buf[0];
+    let voc_raw_lsb = read_buf[1];
+    (voc_raw_msb as u16) << 8 | (voc_raw_lsb as u16)
+}
 
-pub struct SGP40 {
+pub struct SGP40<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address
     device_addr: u16,
     // -- voc
     voc_algo: VocAlgorithmParams,
+    // -- what to do with voc words that fail their crc check
+    crc_policy: CrcPolicy,
+    // -- bus time of the last paced sample
+    last_sample: Option<Duration>,
+    // -- compensation of measure, in SHT31 ticks
+    humidity_raw: u16,
+    temperature_raw: u16,
 }
 
 impl SGP40 {
@@ -27,11 +71,26 @@ impl SGP40 {
     pub fn new(i2c_bus_path: &Path) -> Result<SGP40,std::io::Error> {
         // -- get the bus
         let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c)
+    }
+
+}
+
+impl<B: I2cBus> SGP40<B> {
+
+    pub fn with_bus(mut i2c: B) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("SGP40", "with_bus", DEVICE_ADDR_DEFAULT, register_name);
+        // -- set device address, the soft reset is a plain byte write and relies on it
+        i2cio::set_slave(&mut i2c, DEVICE_ADDR_DEFAULT)?;
         // -- create SGP40 object
         let mut sgp40 = SGP40 {
-            i2c: i2c,
+            i2c,
             device_addr: DEVICE_ADDR_DEFAULT,
             voc_algo: VocAlgorithmParams::new(),
+            crc_policy: CrcPolicy::Error,
+            last_sample: None,
+            humidity_raw: SGP40_DEFAULT_HUMIDITY_RAW,
+            temperature_raw: SGP40_DEFAULT_TEMPERATURE_RAW,
         };
         // -- do a soft reset since it's in an unknown state
         debug!("Soft-resetting SGP40");
@@ -40,89 +99,206 @@ impl SGP40 {
         Ok(sgp40)
     }
 
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("SGP40", call, self.device_addr, register_name)
+    }
+
     pub fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("soft_reset");
         // -- see data sheet: subcommand 0x00 0x06 for soft reset
         let data: u8 = 0x06;
         debug!("Sending SGP40 data: {:#}", data);
         i2cio::write_byte_single(&mut self.i2c, data)?;
         // -- wait for the device to startup
-        i2cio::delay(SGP40_SOFT_RESET_DELAY_MS);
+        i2cio::delay(&mut self.i2c, SGP40_SOFT_RESET_DELAY_MS);
         Ok(())
     }
 
     pub fn get_voc_data_no_compensation(&mut self) -> Result<u16, std::io::Error> {
-        // -- see data sheet: subcommand 0x26 0x0f plus default compensation values with CRCs
-        let data: [u8; 8] = [0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93];
+        let _call = self.call("get_voc_data_no_compensation");
+        let data = SGP40_COMMAND_NO_COMPENSATION;
         debug!("Sending SGP40 data: {:#?}", data);
-        i2cio::write_bytes(&mut self.i2c, self.device_addr, data)?;
-        // -- wait for the sensor data
-        i2cio::delay(SGP40_DATA_READY_DELAY_MS);
-        // -- read response
+        let device_addr = self.device_addr;
         let mut read_buf: [u8; 3] = [0; 3];
-        i2cio::read_bytes(&mut self.i2c, self.device_addr, &mut read_buf)?;
-        let voc_raw_msb = read_buf[0];
-        let voc_raw_lsb = read_buf[1];
-        let voc_raw_crc = read_buf[2];
-        let calc_crc = Self::calc_crc(&[voc_raw_msb, voc_raw_lsb]);
-        let voc_raw = (voc_raw_msb as u16) << 8 | (voc_raw_lsb as u16);
-        if voc_raw_crc != calc_crc {
-            warn!("Expected CRC {:#04x}, received CRC {:#04x}", calc_crc, voc_raw_crc);
-        }
-        Ok(voc_raw)
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_bytes(i2c, device_addr, data)?;
+            // -- wait for the sensor data
+            i2cio::delay(i2c, SGP40_DATA_READY_DELAY_MS);
+            // -- read response
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_voc(&read_buf))
     }
 
     pub fn get_voc_data_with_compensation(&mut self,
         humidity_raw: u16, temperature_raw: u16) -> Result<u16, std::io::Error> {
-        let humidity_raw_msb: u8 = (humidity_raw >> 8) as u8;
-        let humidity_raw_lsb: u8 = (humidity_raw & 0xff) as u8;
-        let temperature_raw_msb: u8 = (temperature_raw >> 8) as u8;
-        let temperature_raw_lsb: u8 = (temperature_raw & 0xff) as u8;
-        // -- see data sheet: subcommand 0x26 0x0f plus compensation values with CRCs
-        let mut data: [u8; 8] = [0x26, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-        data[2] = humidity_raw_msb;
-        data[3] = humidity_raw_lsb;
-        data[4] = Self::calc_crc(&[humidity_raw_msb, humidity_raw_lsb]);
-        data[5] = temperature_raw_msb;
-        data[6] = temperature_raw_lsb;
-        data[7] = Self::calc_crc(&[temperature_raw_msb, temperature_raw_lsb]);
+        let _call = self.call("get_voc_data_with_compensation");
+        let data = compensation_frame(humidity_raw, temperature_raw);
         debug!("Sending SGP40 data: {:#?}", data);
-        i2cio::write_bytes(&mut self.i2c, self.device_addr, data)?;
-        // -- wait for the sensor data
-        i2cio::delay(SGP40_DATA_READY_DELAY_MS);
-        // -- read response
+        let device_addr = self.device_addr;
         let mut read_buf: [u8; 3] = [0; 3];
-        i2cio::read_bytes(&mut self.i2c, self.device_addr, &mut read_buf)?;
-        let voc_raw_msb = read_buf[0];
-        let voc_raw_lsb = read_buf[1];
-        let voc_raw_crc = read_buf[2];
-        let calc_crc = Self::calc_crc(&[voc_raw_msb, voc_raw_lsb]);
-        let voc_raw = (voc_raw_msb as u16) << 8 | (voc_raw_lsb as u16);
-        if voc_raw_crc != calc_crc {
-            warn!("Expected CRC {:#04x}, received CRC {:#04x}", calc_crc, voc_raw_crc);
-        }
-        Ok(voc_raw)
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_bytes(i2c, device_addr, data)?;
+            // -- wait for the sensor data
+            i2cio::delay(i2c, SGP40_DATA_READY_DELAY_MS);
+            // -- read response
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_voc(&read_buf))
+    }
+
+    // -- checked by default, a corrupted word fails the read instead of reaching the voc algorithm
+    pub fn set_crc_policy(&mut self, crc_policy: CrcPolicy) {
+        self.crc_policy = crc_policy;
     }
 
     pub fn process_voc(&mut self, voc_raw: u16) -> f64 {
         self.voc_algo.process(voc_raw)
     }
 
-    fn calc_crc<const LEN: usize>(data: &[u8; LEN]) -> u8 {
-        let mut crc: u8 = 0xff;
-        for i in 0..data.len() {
-            crc ^= data[i];
-            let mut b = 8;
-            while b > 0 {
-                println!("b: {}", b);
-                if (crc & 0x80) > 0 {
-                    crc = (crc << 1) ^ 0x31;
-                } else {
-                    crc = crc << 1;
-                }
-                b -= 1;
+    // -- humidity and temperature measure compensates with, raw as the SHT31 returns them,
+    // -- 50 %RH and 25 °C until set
+    pub fn set_compensation(&mut self, humidity_raw: u16, temperature_raw: u16) {
+        self.humidity_raw = humidity_raw;
+        self.temperature_raw = temperature_raw;
+    }
+
+    // -- measure and run the voc algorithm at its one second pace, waits on the bus clock for
+    // -- the rest of the interval if called early
+    pub fn get_voc_index(&mut self, humidity_raw: u16, temperature_raw: u16) -> Result<f64, std::io::Error> {
+        let _call = self.call("get_voc_index");
+        if let Some(last_sample) = self.last_sample {
+            let interval = Duration::from_millis(SGP40_VOC_SAMPLING_INTERVAL_MS);
+            let since = i2cio::now(&self.i2c).saturating_sub(last_sample);
+            if since < interval {
+                i2cio::delay(&mut self.i2c, (interval - since).as_millis() as u32);
             }
         }
-        crc
+        self.last_sample = Some(i2cio::now(&self.i2c));
+        let voc_raw = self.get_voc_data_with_compensation(humidity_raw, temperature_raw)?;
+        Ok(self.process_voc(voc_raw))
+    }
+
+}
+
+// -- the sensor ends up in an unknown state after a bus hang, reset it
+impl<B: I2cBus> Recoverable for SGP40<B> {
+
+    fn recover(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("recover");
+        i2cio::set_slave(&mut self.i2c, self.device_addr)?;
+        self.soft_reset()
+    }
+}
+
+// -- the voc index at the one second pace of get_voc_index, compensated with set_compensation
+impl<B: I2cBus> Sensor for SGP40<B> {
+
+    fn name(&self) -> &'static str {
+        "SGP40"
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        let voc_index = self.get_voc_index(self.humidity_raw, self.temperature_raw)?;
+        Ok(Measurement {
+            voc_index: Some(voc_index),
+            ..Default::default()
+        })
+    }
+}
+
+// -- async variant for executors polling many sensors, the 30 ms measurement wait yields
+// -- instead of blocking the thread
+pub struct SGP40Async<B: AsyncI2cBus> {
+    // -- i2c bus
+    i2c: B,
+    // -- device address
+    device_addr: u16,
+    // -- voc
+    voc_algo: VocAlgorithmParams,
+    // -- what to do with voc words that fail their crc check
+    crc_policy: CrcPolicy,
+}
+
+impl<B: AsyncI2cBus> SGP40Async<B> {
+
+    pub async fn with_bus(mut i2c: B) -> Result<Self, std::io::Error> {
+        trace::async_driver_call("SGP40", "with_bus", DEVICE_ADDR_DEFAULT, register_name).run(async move {
+            // -- set device address, the soft reset is a plain byte write and relies on it
+            i2cio_async::set_slave(&mut i2c, DEVICE_ADDR_DEFAULT).await?;
+            // -- create SGP40 object
+            let mut sgp40 = SGP40Async {
+                i2c,
+                device_addr: DEVICE_ADDR_DEFAULT,
+                voc_algo: VocAlgorithmParams::new(),
+                crc_policy: CrcPolicy::Error,
+            };
+            // -- do a soft reset since it's in an unknown state
+            debug!("Soft-resetting SGP40");
+            sgp40.soft_reset().await?;
+            // -- ready to measure steady
+            Ok(sgp40)
+        }).await
+    }
+
+    // -- span of a public call, attached to its future, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> AsyncDriverCall {
+        trace::async_driver_call("SGP40", call, self.device_addr, register_name)
+    }
+
+    pub async fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        self.call("soft_reset").run(async {
+            // -- see data sheet: subcommand 0x00 0x06 for soft reset
+            let data: u8 = 0x06;
+            debug!("Sending SGP40 data: {:#}", data);
+            i2cio_async::write_byte_single(&mut self.i2c, data).await?;
+            // -- wait for the device to startup
+            i2cio_async::delay(&mut self.i2c, SGP40_SOFT_RESET_DELAY_MS).await;
+            Ok(())
+        }).await
     }
 
-}
\ No newline at end of file
+    async fn measure(&mut self, data: [u8; 8]) -> Result<u16, std::io::Error> {
+        debug!("Sending SGP40 data: {:#?}", data);
+        let device_addr = self.device_addr;
+        let mut read_buf: [u8; 3] = [0; 3];
+        // -- keep other bus users from slipping in between command and response
+        i2cio_async::transaction(&mut self.i2c, async |i2c| {
+            i2cio_async::write_bytes(i2c, device_addr, data).await?;
+            // -- wait for the sensor data
+            i2cio_async::delay(i2c, SGP40_DATA_READY_DELAY_MS).await;
+            // -- read response
+            i2cio_async::read_bytes(i2c, device_addr, &mut read_buf).await
+        }).await?;
+        sensirion_crc::check_on_async_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_voc(&read_buf))
+    }
+
+    pub async fn get_voc_data_no_compensation(&mut self) -> Result<u16, std::io::Error> {
+        self.call("get_voc_data_no_compensation").run(async {
+            self.measure(SGP40_COMMAND_NO_COMPENSATION).await
+        }).await
+    }
+
+    pub async fn get_voc_data_with_compensation(&mut self,
+        humidity_raw: u16, temperature_raw: u16) -> Result<u16, std::io::Error> {
+        self.call("get_voc_data_with_compensation").run(async {
+            self.measure(compensation_frame(humidity_raw, temperature_raw)).await
+        }).await
+    }
+
+    // -- checked by default, a corrupted word fails the read instead of reaching the voc algorithm
+    pub fn set_crc_policy(&mut self, crc_policy: CrcPolicy) {
+        self.crc_policy = crc_policy;
+    }
+
+    pub fn process_voc(&mut self, voc_raw: u16) -> f64 {
+        self.voc_algo.process(voc_raw)
+    }
+}
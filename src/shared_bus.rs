@@ -0,0 +1,240 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+struct SharedBusState<B> {
+    // -- the bus all devices share
+    bus: B,
+    // -- device address the bus currently points to
+    current_addr: Option<u16>,
+    // -- device holding the bus for a transaction
+    owner: Option<usize>,
+    // -- nesting depth of the owner's transactions
+    depth: usize,
+}
+
+struct SharedBusShared<B> {
+    state: Mutex<SharedBusState<B>>,
+    // -- signalled when a transaction ends and the bus is free again
+    released: Condvar,
+    // -- id of the next device handed out
+    next_id: AtomicUsize,
+}
+
+// -- one adapter shared by many drivers, possibly across threads
+pub struct SharedBus<B: I2cBus = I2c<File>, C: Clock = SystemClock> {
+    shared: Arc<SharedBusShared<B>>,
+    // -- every device waits on a copy of it, outside the bus lock
+    clock: C,
+}
+
+impl SharedBus {
+
+    pub fn open(i2c_bus_path: &Path) -> Result<SharedBus, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Ok(Self::new(i2c))
+    }
+
+}
+
+impl<B: I2cBus> SharedBus<B> {
+
+    pub fn new(bus: B) -> Self {
+        Self::with_clock(bus, SystemClock)
+    }
+}
+
+impl<B: I2cBus, C: Clock + Clone> SharedBus<B, C> {
+
+    // -- wait and take the time from a clock of choice, e.g. a VirtualClock in tests
+    pub fn with_clock(bus: B, clock: C) -> Self {
+        let state = SharedBusState {
+            bus,
+            current_addr: None,
+            owner: None,
+            depth: 0,
+        };
+        let shared = SharedBusShared {
+            state: Mutex::new(state),
+            released: Condvar::new(),
+            next_id: AtomicUsize::new(0),
+        };
+        SharedBus {
+            shared: Arc::new(shared),
+            clock,
+        }
+    }
+
+    // -- hand out a bus handle for one driver, e.g. SHT31::with_bus(shared_bus.device(), ...)
+    pub fn device(&self) -> SharedBusDevice<B, C> {
+        SharedBusDevice {
+            shared: Arc::clone(&self.shared),
+            id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+            device_addr: None,
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<B: I2cBus, C: Clock + Clone> Clone for SharedBus<B, C> {
+    fn clone(&self) -> Self {
+        SharedBus {
+            shared: Arc::clone(&self.shared),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+// -- a driver's view of the shared bus, remembers its device address and re-applies it
+// -- under the bus lock before every transfer; from begin_transaction until the matching
+// -- end_transaction the bus belongs to this device and all others wait
+pub struct SharedBusDevice<B: I2cBus = I2c<File>, C: Clock = SystemClock> {
+    shared: Arc<SharedBusShared<B>>,
+    id: usize,
+    device_addr: Option<u16>,
+    clock: C,
+}
+
+impl<B: I2cBus, C: Clock> SharedBusDevice<B, C> {
+
+    // -- lock the bus, waiting for the transaction of another device to end
+    fn lock(&self) -> Result<MutexGuard<'_, SharedBusState<B>>, std::io::Error> {
+        let poisoned = |_| std::io::Error::other("Shared bus lock poisoned by a panicking thread");
+        let state = self.shared.state.lock().map_err(poisoned)?;
+        self.shared.released
+            .wait_while(state, |state| state.owner.is_some_and(|owner| owner != self.id))
+            .map_err(poisoned)
+    }
+
+    // -- give up one level of the transaction and hand the bus on when the outermost ends
+    fn release(&self, state: &mut SharedBusState<B>) {
+        if state.owner != Some(self.id) {
+            return;
+        }
+        state.depth -= 1;
+        if state.depth == 0 {
+            state.owner = None;
+            self.shared.released.notify_all();
+        }
+    }
+
+    fn transaction<R>(&mut self, op: impl FnOnce(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+        let mut state = self.lock()?;
+        if let Some(device_addr) = self.device_addr {
+            if state.current_addr != Some(device_addr) {
+                debug!("Shared bus switching to device address {device_addr:#04x}");
+                // -- forget the current address first, a failed switch leaves it unknown
+                state.current_addr = None;
+                state.bus.set_slave(device_addr)?;
+                state.current_addr = Some(device_addr);
+            }
+        }
+        op(&mut state.bus)
+    }
+}
+
+impl<B: I2cBus, C: Clock> I2cBus for SharedBusDevice<B, C> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.device_addr = Some(dev_addr);
+        // -- switch right away so a bad address is reported to the caller
+        self.transaction(|_| Ok(()))
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.transaction(|bus| bus.smbus_read_byte_data(register))
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.transaction(|bus| bus.smbus_read_word_data(register))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_byte(data))
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_byte_data(register, data))
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_word_data(register, data))
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_block_data(register, data))
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.transaction(|bus| bus.i2c_read_block_data(register, data))
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.i2c_transfer(messages))
+    }
+
+    // -- waits and time go to the clock of the shared bus without locking the bus, other devices
+    // -- keep transferring while this one waits
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.clock.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        let mut state = self.lock()?;
+        // -- keep the bus until the matching end_transaction, nested transactions only count up
+        state.owner = Some(self.id);
+        state.depth += 1;
+        let result = state.bus.begin_transaction();
+        if result.is_err() {
+            // -- no end_transaction follows a failed begin
+            self.release(&mut state);
+        }
+        result
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        let mut state = self.lock()?;
+        let result = state.bus.end_transaction();
+        self.release(&mut state);
+        result
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.lock().ok()?.bus.retry_policy()
+    }
+
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        self.lock()?.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        if let Ok(mut state) = self.lock() {
+            state.bus.record(event)
+        }
+    }
+}
+
+impl<B: I2cBus, C: Clock> Drop for SharedBusDevice<B, C> {
+    fn drop(&mut self) {
+        // -- a driver dropped mid-transaction, e.g. by a panic, must not keep the bus forever
+        let mut state = self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.owner == Some(self.id) {
+            state.owner = None;
+            state.depth = 0;
+            self.shared.released.notify_all();
+        }
+    }
+}
@@ -1,23 +1,28 @@
-use i2c_linux::{
-    I2c, Message, ReadFlags,
-};
+use i2c_linux::I2c;
 #[allow(unused_imports)]
 use log::{debug, error, log_enabled, info, Level};
 use std::fs::File;
 use std::path::Path;
-use std::{thread, time};
 
-use crate::i2cio;
+use crate::i2cio::{self, I2cBus};
+use crate::i2cio_async::{self, AsyncI2cBus};
+use crate::sensirion_crc::{self, CrcPolicy};
+use crate::sensor::{Measurement, Sensor};
+use crate::units::{RelativeHumidity, Temperature};
+use crate::trace::{self, AsyncDriverCall, DriverCall};
 
 const SHT31_COMMAND_FETCH_DATA: u16 = 0xe000;
-const SHT31_COMMAND_READ_STATUS: u16 = 0xf32d;
+pub(crate) const SHT31_COMMAND_READ_STATUS: u16 = 0xf32d;
 const SHT31_COMMAND_RESET_STATUS: u16 = 0x3041;
 const SHT31_COMMAND_SOFT_RESET: u16 = 0x30a2;
 const SHT31_COMMAND_STOP_CONTINUOUS_MODE: u16 = 0x3093;
 
+// -- the sensor takes commands, it has no registers
+trace::register_names!();
+
 // -- the soft reset time is actually 1.5ms
-const SHT31_SOFT_RESET_DELAY_MS: u64 = 2;
-const SHT31_NO_CLOCK_STRETCH_READ_DELAY_MS: u64 = 5;
+const SHT31_SOFT_RESET_DELAY_MS: u32 = 2;
+const SHT31_NO_CLOCK_STRETCH_READ_DELAY_MS: u32 = 5;
 
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq)]
@@ -26,6 +31,7 @@ pub enum SHT31DeviceAddress {
     Secondary,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for SHT31DeviceAddress {
     fn default() -> Self {
         SHT31DeviceAddress::Default
@@ -36,7 +42,7 @@ impl SHT31DeviceAddress {
     const DEVICE_ADDR_DEFAULT: u16 = 0x44;
     const DEVICE_ADDR_SECONDARY: u16 = 0x45;
 
-    fn value(&self) -> u16 {
+    pub(crate) fn value(&self) -> u16 {
         match *self {
             Self::Default => Self::DEVICE_ADDR_DEFAULT,
             Self::Secondary => Self::DEVICE_ADDR_SECONDARY,
@@ -144,25 +150,55 @@ impl SHT31ContinuousAcquisition {
     }
 }
 
-pub struct SHT31 {
+// -- temperature and humidity words, each followed by its crc
+fn parse_data(read_buf: &[u8; 6]) -> (u16, u16) {
+    let temperature_msb = read_buf[0] as u16;
+    let temperature_lsb = read_buf[1] as u16;
+    let temperature_raw = temperature_msb << 8 | temperature_lsb;
+    let humidity_msb = read_buf[3] as u16;
+    let humidity_lsb = read_buf[4] as u16;
+    let humidity_raw = humidity_msb << 8 | humidity_lsb;
+    (temperature_raw, humidity_raw)
+}
+
+fn temperature(temperature_raw: u16) -> Temperature {
+    Temperature::from_celsius(-45.0 + (temperature_raw as f64 * 175.0) / 65535.0)
+}
+
+fn relative_humidity(humidity_raw: u16) -> RelativeHumidity {
+    RelativeHumidity::from_percent((humidity_raw as f64 * 100.0) / 65535.0)
+}
+
+pub struct SHT31<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address
     device_addr: SHT31DeviceAddress,
-    // -- measuring mode
+    // -- what to do with words that fail their crc check
+    crc_policy: CrcPolicy,
 }
 
 impl SHT31 {
 
     pub fn new(i2c_bus_path: &Path, device_addr: SHT31DeviceAddress) -> Result<SHT31, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr)
+    }
+
+}
+
+impl<B: I2cBus> SHT31<B> {
+
+    pub fn with_bus(mut i2c: B, device_addr: SHT31DeviceAddress) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("SHT31", "with_bus", device_addr.value(), register_name);
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;
         // -- create SHT31 object
         let mut sht31 = SHT31 {
             i2c,
             device_addr,
+            crc_policy: CrcPolicy::Error,
         };
         // -- read status register
         debug!("Reading SHT31 status register");
@@ -175,7 +211,7 @@ impl SHT31 {
         }
         // -- stop continuous mode
         debug!("Stopping SHT31 continuous mode");
-        if let Err(_) = sht31.stop_continuous_mode() {
+        if sht31.stop_continuous_mode().is_err() {
             // -- do a soft reset since it's in an unknown state
             debug!("Soft-resetting SHT31");
             sht31.soft_reset()?;
@@ -184,18 +220,27 @@ impl SHT31 {
         Ok(sht31)
     }
 
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("SHT31", call, self.device_addr.value(), register_name)
+    }
+
     pub fn get_status(&mut self) -> Result<u16, std::io::Error> {
+        let _call = self.call("get_status");
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (SHT31_COMMAND_READ_STATUS >> 8) as u8;
         let cmd_lsb: u8 = (SHT31_COMMAND_READ_STATUS & 0xff) as u8;
         // -- send MSB as command and LSB as data
         debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
-        i2cio::write_byte(&mut self.i2c, cmd_msb, cmd_lsb)?;
         // -- read response
         let mut read_buf: [u8; 3] = [0; 3];
-        let read_message = Message::Read { address: self.device_addr.value(), data: &mut read_buf, flags: ReadFlags::empty() };
-        let mut messages = [read_message];
-        self.i2c.i2c_transfer(&mut messages)?;
+        let device_addr = self.device_addr.value();
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_byte(i2c, cmd_msb, cmd_lsb)?;
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
         let reg_msb = read_buf[0] as u16;
         let reg_lsb = read_buf[1] as u16;
         let reg_val = reg_msb << 8 | reg_lsb;
@@ -203,6 +248,7 @@ impl SHT31 {
     }
 
     pub fn reset_status(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("reset_status");
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (SHT31_COMMAND_RESET_STATUS >> 8) as u8;
         let cmd_lsb: u8 = (SHT31_COMMAND_RESET_STATUS & 0xff) as u8;
@@ -212,6 +258,7 @@ impl SHT31 {
     }
 
     pub fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("soft_reset");
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (SHT31_COMMAND_SOFT_RESET >> 8) as u8;
         let cmd_lsb: u8 = (SHT31_COMMAND_SOFT_RESET & 0xff) as u8;
@@ -219,61 +266,56 @@ impl SHT31 {
         debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
         i2cio::write_byte(&mut self.i2c, cmd_msb, cmd_lsb)?;
         // -- wait for the device to startup
-        let startup_delay = time::Duration::from_millis(SHT31_SOFT_RESET_DELAY_MS);
-        thread::sleep(startup_delay);
+        i2cio::delay(&mut self.i2c, SHT31_SOFT_RESET_DELAY_MS);
         Ok(())
     }
 
     pub fn get_data_single(&mut self, acquisition_mode: SHT31SingleShotAcquisition) 
          -> Result<(u16, u16), std::io::Error> { 
-            let acquisition_mode = acquisition_mode.value();
+        let _call = self.call("get_data_single");
+        let acquisition_mode = acquisition_mode.value();
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (acquisition_mode >> 8) as u8;
         let cmd_lsb: u8 = (acquisition_mode & 0xff) as u8;
         // -- send MSB as command and LSB as data
         debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
-        i2cio::write_byte(&mut self.i2c, cmd_msb, cmd_lsb)?;
         // -- read response
         let mut read_buf: [u8; 6] = [0; 6];
-        let read_message = Message::Read { address: self.device_addr.value(), data: &mut read_buf, flags: ReadFlags::empty() };
-        let mut messages = [read_message];
-        self.i2c.i2c_transfer(&mut messages)?;
-        let temperature_msb = read_buf[0] as u16;
-        let temperature_lsb = read_buf[1] as u16;
-        let temperature_raw = temperature_msb << 8 | temperature_lsb;
-        let humidity_msb = read_buf[3] as u16;
-        let humidity_lsb = read_buf[4] as u16;
-        let humidity_raw = humidity_msb << 8 | humidity_lsb;
-        Ok((temperature_raw, humidity_raw))
+        let device_addr = self.device_addr.value();
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_byte(i2c, cmd_msb, cmd_lsb)?;
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_data(&read_buf))
     }
 
     pub fn get_data_single_no_clock_stretch(&mut self, acquisition_mode: SHT31SingleShotAcquisitionNoClockStretch) 
         -> Result<(u16, u16), std::io::Error> { 
+        let _call = self.call("get_data_single_no_clock_stretch");
         let acquisition_mode = acquisition_mode.value();
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (acquisition_mode >> 8) as u8;
         let cmd_lsb: u8 = (acquisition_mode & 0xff) as u8;
         // -- send MSB as command and LSB as data
         debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
-        i2cio::write_byte(&mut self.i2c, cmd_msb, cmd_lsb)?;
-        // -- no clock stretch requires a delay before reading values 
-        let startup_delay = time::Duration::from_millis(SHT31_NO_CLOCK_STRETCH_READ_DELAY_MS);
-        thread::sleep(startup_delay);
         // -- read response
         let mut read_buf: [u8; 6] = [0; 6];
-        let read_message = Message::Read { address: self.device_addr.value(), data: &mut read_buf, flags: ReadFlags::empty() };
-        let mut messages = [read_message];
-        self.i2c.i2c_transfer(&mut messages)?;
-        let temperature_msb = read_buf[0] as u16;
-        let temperature_lsb = read_buf[1] as u16;
-        let temperature_raw = temperature_msb << 8 | temperature_lsb;
-        let humidity_msb = read_buf[3] as u16;
-        let humidity_lsb = read_buf[4] as u16;
-        let humidity_raw = humidity_msb << 8 | humidity_lsb;
-        Ok((temperature_raw, humidity_raw))
+        let device_addr = self.device_addr.value();
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_byte(i2c, cmd_msb, cmd_lsb)?;
+            // -- no clock stretch requires a delay before reading values
+            i2cio::delay(i2c, SHT31_NO_CLOCK_STRETCH_READ_DELAY_MS);
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_data(&read_buf))
     }
 
     pub fn start_continuous_mode(&mut self, acquisition_mode: SHT31ContinuousAcquisition) -> Result<(), std::io::Error> {
+        let _call = self.call("start_continuous_mode");
         let acquisition_mode = acquisition_mode.value();
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (acquisition_mode >> 8) as u8;
@@ -284,6 +326,7 @@ impl SHT31 {
     }
 
     pub fn stop_continuous_mode(&mut self) -> Result<(), std::io::Error> {
+        let _call = self.call("stop_continuous_mode");
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (SHT31_COMMAND_STOP_CONTINUOUS_MODE >> 8) as u8;
         let cmd_lsb: u8 = (SHT31_COMMAND_STOP_CONTINUOUS_MODE & 0xff) as u8;
@@ -294,36 +337,205 @@ impl SHT31 {
 
     pub fn get_data_continuous(&mut self) 
          -> Result<(u16, u16), std::io::Error> {
+        let _call = self.call("get_data_continuous");
         // -- SHT31 expects most significant byte first
         let cmd_msb: u8 = (SHT31_COMMAND_FETCH_DATA >> 8) as u8;
         let cmd_lsb: u8 = (SHT31_COMMAND_FETCH_DATA & 0xff) as u8;
         // -- send MSB as command and LSB as data
         debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
-        i2cio::write_byte(&mut self.i2c, cmd_msb, cmd_lsb)?;
         // -- read response
         let mut read_buf: [u8; 6] = [0; 6];
-        let read_message = Message::Read { address: self.device_addr.value(), data: &mut read_buf, flags: ReadFlags::empty() };
-        let mut messages = [read_message];
-        self.i2c.i2c_transfer(&mut messages)?;
-        let temperature_msb = read_buf[0] as u16;
-        let temperature_lsb = read_buf[1] as u16;
-        let temperature_raw = temperature_msb << 8 | temperature_lsb;
-        let humidity_msb = read_buf[3] as u16;
-        let humidity_lsb = read_buf[4] as u16;
-        let humidity_raw = humidity_msb << 8 | humidity_lsb;
-        Ok((temperature_raw, humidity_raw))
+        let device_addr = self.device_addr.value();
+        // -- keep other bus users from slipping in between command and response
+        i2cio::transaction(&mut self.i2c, |i2c| {
+            i2cio::write_byte(i2c, cmd_msb, cmd_lsb)?;
+            i2cio::read_bytes(i2c, device_addr, &mut read_buf)
+        })?;
+        sensirion_crc::check_on_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+        Ok(parse_data(&read_buf))
+    }
+
+    // -- checked by default, a corrupted word fails the read instead of reaching the conversion
+    pub fn set_crc_policy(&mut self, crc_policy: CrcPolicy) {
+        self.crc_policy = crc_policy;
+    }
+
+    pub fn get_temperature(&self, temperature_raw: u16) -> Temperature {
+        temperature(temperature_raw)
     }
 
-    pub fn get_temperature_celcius(&self, temperature_raw: u16) -> f64 {
-        -45.0 + (temperature_raw as f64 * 175.0) / 65535.0
+    pub fn get_relative_humidity(&self, humidity_raw: u16) -> RelativeHumidity {
+        relative_humidity(humidity_raw)
     }
 
-    pub fn get_temperature_fahrenheit(&self, temperature_raw: u16) -> f64 {
-        -49.0 + (temperature_raw as f64 * 315.0) / 65535.0
+}
+
+// -- a single shot with high repeatability, stop the continuous mode first if it was started
+impl<B: I2cBus> Sensor for SHT31<B> {
+
+    fn name(&self) -> &'static str {
+        "SHT31"
     }
 
-    pub fn get_humidity(&self, humidity_raw: u16) -> f64 {
-        (humidity_raw as f64 * 100.0) / 65535.0
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        let (temperature_raw, humidity_raw) = self.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh)?;
+        Ok(Measurement {
+            temperature: Some(temperature(temperature_raw)),
+            humidity: Some(relative_humidity(humidity_raw)),
+            ..Default::default()
+        })
     }
+}
+
+// -- async variant for executors polling many sensors, the data ready wait of the no clock
+// -- stretch read yields instead of blocking the thread
+pub struct SHT31Async<B: AsyncI2cBus> {
+    // -- i2c bus
+    i2c: B,
+    // -- device address
+    device_addr: SHT31DeviceAddress,
+    // -- what to do with words that fail their crc check
+    crc_policy: CrcPolicy,
+}
 
-}
\ No newline at end of file
+impl<B: AsyncI2cBus> SHT31Async<B> {
+
+    pub async fn with_bus(mut i2c: B, device_addr: SHT31DeviceAddress) -> Result<Self, std::io::Error> {
+        trace::async_driver_call("SHT31", "with_bus", device_addr.value(), register_name).run(async move {
+            // -- set device address
+            i2cio_async::set_slave(&mut i2c, device_addr.value()).await?;
+            // -- create SHT31 object
+            let mut sht31 = SHT31Async {
+                i2c,
+                device_addr,
+                crc_policy: CrcPolicy::Error,
+            };
+            // -- read status register
+            debug!("Reading SHT31 status register");
+            let status_reg_val = sht31.get_status().await?;
+            debug!("SHT31 status register value: {status_reg_val:#010b}");
+            if status_reg_val != 0 {
+                // -- reset status register
+                debug!("Resetting status register SHT31");
+                sht31.reset_status().await?;
+            }
+            // -- stop continuous mode
+            debug!("Stopping SHT31 continuous mode");
+            if sht31.stop_continuous_mode().await.is_err() {
+                // -- do a soft reset since it's in an unknown state
+                debug!("Soft-resetting SHT31");
+                sht31.soft_reset().await?;
+            }
+            // -- ready to measure steady
+            Ok(sht31)
+        }).await
+    }
+
+    // -- span of a public call, attached to its future, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> AsyncDriverCall {
+        trace::async_driver_call("SHT31", call, self.device_addr.value(), register_name)
+    }
+
+    async fn send_command(&mut self, command: u16) -> Result<(), std::io::Error> {
+        // -- SHT31 expects most significant byte first, send MSB as command and LSB as data
+        let cmd_msb: u8 = (command >> 8) as u8;
+        let cmd_lsb: u8 = (command & 0xff) as u8;
+        debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
+        i2cio_async::write_byte(&mut self.i2c, cmd_msb, cmd_lsb).await
+    }
+
+    async fn read_response<const LEN: usize>(&mut self, command: u16, delay_ms: u32) -> Result<[u8; LEN], std::io::Error> {
+        let cmd_msb: u8 = (command >> 8) as u8;
+        let cmd_lsb: u8 = (command & 0xff) as u8;
+        debug!("Sending SHT31 command: {cmd_msb:#04x} {cmd_lsb:#04x}");
+        let mut read_buf: [u8; LEN] = [0; LEN];
+        let device_addr = self.device_addr.value();
+        // -- keep other bus users from slipping in between command and response
+        i2cio_async::transaction(&mut self.i2c, async |i2c| {
+            i2cio_async::write_byte(i2c, cmd_msb, cmd_lsb).await?;
+            if delay_ms > 0 {
+                i2cio_async::delay(i2c, delay_ms).await;
+            }
+            i2cio_async::read_bytes(i2c, device_addr, &mut read_buf).await
+        }).await?;
+        Ok(read_buf)
+    }
+
+    pub async fn get_status(&mut self) -> Result<u16, std::io::Error> {
+        self.call("get_status").run(async {
+            let read_buf: [u8; 3] = self.read_response(SHT31_COMMAND_READ_STATUS, 0).await?;
+            sensirion_crc::check_on_async_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+            let reg_msb = read_buf[0] as u16;
+            let reg_lsb = read_buf[1] as u16;
+            Ok(reg_msb << 8 | reg_lsb)
+        }).await
+    }
+
+    pub async fn reset_status(&mut self) -> Result<(), std::io::Error> {
+        self.call("reset_status").run(async {
+            self.send_command(SHT31_COMMAND_RESET_STATUS).await
+        }).await
+    }
+
+    pub async fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        self.call("soft_reset").run(async {
+            self.send_command(SHT31_COMMAND_SOFT_RESET).await?;
+            // -- wait for the device to startup
+            i2cio_async::delay(&mut self.i2c, SHT31_SOFT_RESET_DELAY_MS).await;
+            Ok(())
+        }).await
+    }
+
+    pub async fn get_data_single(&mut self, acquisition_mode: SHT31SingleShotAcquisition)
+        -> Result<(u16, u16), std::io::Error> {
+        self.call("get_data_single").run(async {
+            let read_buf = self.read_response(acquisition_mode.value(), 0).await?;
+            sensirion_crc::check_on_async_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+            Ok(parse_data(&read_buf))
+        }).await
+    }
+
+    pub async fn get_data_single_no_clock_stretch(&mut self, acquisition_mode: SHT31SingleShotAcquisitionNoClockStretch)
+        -> Result<(u16, u16), std::io::Error> {
+        self.call("get_data_single_no_clock_stretch").run(async {
+            // -- no clock stretch requires a delay before reading values
+            let read_buf = self.read_response(acquisition_mode.value(), SHT31_NO_CLOCK_STRETCH_READ_DELAY_MS).await?;
+            sensirion_crc::check_on_async_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+            Ok(parse_data(&read_buf))
+        }).await
+    }
+
+    pub async fn start_continuous_mode(&mut self, acquisition_mode: SHT31ContinuousAcquisition) -> Result<(), std::io::Error> {
+        self.call("start_continuous_mode").run(async {
+            self.send_command(acquisition_mode.value()).await
+        }).await
+    }
+
+    pub async fn stop_continuous_mode(&mut self) -> Result<(), std::io::Error> {
+        self.call("stop_continuous_mode").run(async {
+            self.send_command(SHT31_COMMAND_STOP_CONTINUOUS_MODE).await
+        }).await
+    }
+
+    pub async fn get_data_continuous(&mut self) -> Result<(u16, u16), std::io::Error> {
+        self.call("get_data_continuous").run(async {
+            let read_buf = self.read_response(SHT31_COMMAND_FETCH_DATA, 0).await?;
+            sensirion_crc::check_on_async_bus(&mut self.i2c, self.crc_policy, &read_buf)?;
+            Ok(parse_data(&read_buf))
+        }).await
+    }
+
+    // -- checked by default, a corrupted word fails the read instead of reaching the conversion
+    pub fn set_crc_policy(&mut self, crc_policy: CrcPolicy) {
+        self.crc_policy = crc_policy;
+    }
+
+    pub fn get_temperature(&self, temperature_raw: u16) -> Temperature {
+        temperature(temperature_raw)
+    }
+
+    pub fn get_relative_humidity(&self, humidity_raw: u16) -> RelativeHumidity {
+        relative_humidity(humidity_raw)
+    }
+}
@@ -0,0 +1,207 @@
+use i2c_linux::Message;
+#[allow(unused_imports)]
+use log::{debug, warn};
+use spidev::{SpiModeFlags, Spidev, SpidevOptions, SpidevTransfer};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::i2cio::I2cBus;
+
+// -- bit 7 of the first byte selects read (1) or write (0), the other 7 bits address the register
+const SPI_READ: u8 = 0x80;
+const SPI_WRITE_MASK: u8 = 0x7f;
+
+// -- the page select register and its spi_mem_page bit on paged devices
+const SPI_REG_STATUS: u8 = 0x73;
+const SPI_MEM_PAGE_BIT: u8 = 0x10;
+const SPI_MEM_PAGE_SHIFT_LEFT: u8 = 4;
+
+// -- all Bosch sensors support mode 0 and mode 3 and up to 10 MHz
+const SPI_DEFAULT_SPEED_HZ: u32 = 1_000_000;
+
+// -- the full-duplex transfer the spi transport relies on, implemented for linux spidev below
+// -- and open for test rigs and other spi controllers to implement
+pub trait SpiDevice {
+    // -- clock out tx while clocking in rx, both have the same length and share one chip select
+    fn transfer(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<(), std::io::Error>;
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        SystemClock.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        SystemClock.now()
+    }
+}
+
+impl SpiDevice for Spidev {
+    fn transfer(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<(), std::io::Error> {
+        let mut transfer = SpidevTransfer::read_write(tx, rx);
+        Spidev::transfer(self, &mut transfer)
+    }
+}
+
+pub fn get_device(spi_path: &Path) -> Result<Spidev, std::io::Error> {
+    let mut spi = Spidev::open(spi_path)?;
+    let options = SpidevOptions::new()
+        .bits_per_word(8)
+        .max_speed_hz(SPI_DEFAULT_SPEED_HZ)
+        .mode(SpiModeFlags::SPI_MODE_0)
+        .build();
+    spi.configure(&options)?;
+    Ok(spi)
+}
+
+// -- how a device maps the 8 bit register addresses the drivers use onto 7 bit spi addresses
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpiRegisterAccess {
+    // -- bit 7 is dropped, e.g. BME280
+    Plain,
+    // -- like plain but every read starts with a dummy byte, e.g. BMP388
+    DummyByte,
+    // -- bit 7 selects one of two memory pages through spi_mem_page, registers 0x80 - 0xff
+    // -- live in page 0 and 0x00 - 0x7f in page 1, e.g. BME680
+    Paged,
+}
+
+// -- register access over spi for the drivers written against the i2c bus trait, the device
+// -- is selected by its chip select line so the device address is ignored
+pub struct SpiBus<S: SpiDevice = Spidev> {
+    // -- the spi device
+    spi: S,
+    // -- register address mapping
+    access: SpiRegisterAccess,
+    // -- memory page currently selected on paged devices, unknown until the first switch
+    page: Option<u8>,
+}
+
+impl SpiBus {
+
+    pub fn open(spi_path: &Path, access: SpiRegisterAccess) -> Result<SpiBus, std::io::Error> {
+        // -- get the device
+        let spi = get_device(spi_path)?;
+        Ok(Self::new(spi, access))
+    }
+
+}
+
+impl<S: SpiDevice> SpiBus<S> {
+
+    pub fn new(spi: S, access: SpiRegisterAccess) -> Self {
+        SpiBus {
+            spi,
+            access,
+            page: None,
+        }
+    }
+
+    pub fn release(self) -> S {
+        self.spi
+    }
+
+    fn select_page(&mut self, page: u8) -> Result<(), std::io::Error> {
+        if self.page == Some(page) {
+            return Ok(())
+        }
+        debug!("Switching SPI memory page to {page}");
+        // -- forget the current page first, a failed switch leaves it unknown
+        self.page = None;
+        // -- the status register is reachable from both pages
+        let mut rx = [0; 2];
+        self.spi.transfer(&[SPI_REG_STATUS | SPI_READ, 0], &mut rx)?;
+        let status = (rx[1] & !SPI_MEM_PAGE_BIT) | (page << SPI_MEM_PAGE_SHIFT_LEFT);
+        let mut rx = [0; 2];
+        self.spi.transfer(&[SPI_REG_STATUS & SPI_WRITE_MASK, status], &mut rx)?;
+        self.page = Some(page);
+        Ok(())
+    }
+
+    // -- the 7 bit spi address of the register, switches the page first if needed
+    fn address(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        if self.access == SpiRegisterAccess::Paged {
+            let page = if register & SPI_READ != 0 { 0 } else { 1 };
+            self.select_page(page)?;
+        }
+        Ok(register & SPI_WRITE_MASK)
+    }
+
+    fn read_registers(&mut self, register: u8, data: &mut [u8]) -> Result<(), std::io::Error> {
+        let address = self.address(register)?;
+        let header_len = match self.access {
+            SpiRegisterAccess::DummyByte => 2,
+            _ => 1,
+        };
+        let mut tx = vec![0; header_len + data.len()];
+        tx[0] = address | SPI_READ;
+        let mut rx = vec![0; tx.len()];
+        self.spi.transfer(&tx, &mut rx)?;
+        data.copy_from_slice(&rx[header_len..]);
+        Ok(())
+    }
+
+    // -- every data byte is preceded by its own register address, there is no auto-increment on writes
+    fn write_registers(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        let mut tx = Vec::with_capacity(data.len() * 2);
+        for (offset, byte) in data.iter().enumerate() {
+            let address = self.address(register.wrapping_add(offset as u8))?;
+            tx.push(address);
+            tx.push(*byte);
+        }
+        let mut rx = vec![0; tx.len()];
+        self.spi.transfer(&tx, &mut rx)
+    }
+}
+
+impl<S: SpiDevice> I2cBus for SpiBus<S> {
+
+    fn set_slave(&mut self, _dev_addr: u16) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        let mut data = [0; 1];
+        self.read_registers(register, &mut data)?;
+        Ok(data[0])
+    }
+
+    // -- least significant byte first, like a word read over smbus
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        let mut data = [0; 2];
+        self.read_registers(register, &mut data)?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    fn smbus_write_byte(&mut self, _data: u8) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "SPI devices have no register-less writes"))
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.write_registers(register, &[data])
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.write_registers(register, &data.to_le_bytes())
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.write_registers(register, data)
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.read_registers(register, data)?;
+        Ok(data.len())
+    }
+
+    fn i2c_transfer(&mut self, _messages: &mut [Message]) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "SPI devices have no raw i2c messages"))
+    }
+
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.spi.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.spi.now()
+    }
+}
@@ -0,0 +1,342 @@
+use i2c_linux::{Functionality, I2c, Message};
+#[allow(unused_imports)]
+use log::{debug, error, info};
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::i2cio::{self, BusEvent, I2cBus, RetryPolicy};
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Tca9548aDeviceAddress {
+    #[default]
+    Default,
+    Alt1,
+    Alt2,
+    Alt3,
+    Alt4,
+    Alt5,
+    Alt6,
+    Alt7,
+}
+
+impl Tca9548aDeviceAddress {
+    const DEVICE_ADDR_DEFAULT: u16 = 0x70;
+    const DEVICE_ADDR_ALT1: u16 = 0x71;
+    const DEVICE_ADDR_ALT2: u16 = 0x72;
+    const DEVICE_ADDR_ALT3: u16 = 0x73;
+    const DEVICE_ADDR_ALT4: u16 = 0x74;
+    const DEVICE_ADDR_ALT5: u16 = 0x75;
+    const DEVICE_ADDR_ALT6: u16 = 0x76;
+    const DEVICE_ADDR_ALT7: u16 = 0x77;
+
+    fn value(&self) -> u16 {
+        match *self {
+            Self::Default => Self::DEVICE_ADDR_DEFAULT,
+            Self::Alt1 => Self::DEVICE_ADDR_ALT1,
+            Self::Alt2 => Self::DEVICE_ADDR_ALT2,
+            Self::Alt3 => Self::DEVICE_ADDR_ALT3,
+            Self::Alt4 => Self::DEVICE_ADDR_ALT4,
+            Self::Alt5 => Self::DEVICE_ADDR_ALT5,
+            Self::Alt6 => Self::DEVICE_ADDR_ALT6,
+            Self::Alt7 => Self::DEVICE_ADDR_ALT7,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tca9548aChannel {
+    Channel0,
+    Channel1,
+    Channel2,
+    Channel3,
+    Channel4,
+    Channel5,
+    Channel6,
+    Channel7,
+}
+
+impl Tca9548aChannel {
+    // -- one bit per channel in the control register
+    fn value(&self) -> u8 {
+        match *self {
+            Self::Channel0 => 0x01,
+            Self::Channel1 => 0x02,
+            Self::Channel2 => 0x04,
+            Self::Channel3 => 0x08,
+            Self::Channel4 => 0x10,
+            Self::Channel5 => 0x20,
+            Self::Channel6 => 0x40,
+            Self::Channel7 => 0x80,
+        }
+    }
+}
+
+struct Tca9548aState<B> {
+    // -- the upstream bus
+    bus: B,
+    // -- device address of the mux itself
+    mux_addr: u16,
+    // -- control register value the mux currently holds
+    current_channels: Option<u8>,
+    // -- device address the bus currently points to
+    current_addr: Option<u16>,
+    // -- channel handle holding the mux for a transaction
+    owner: Option<usize>,
+    // -- nesting depth of the owner's transactions
+    depth: usize,
+}
+
+struct Tca9548aShared<B> {
+    state: Mutex<Tca9548aState<B>>,
+    // -- signalled when a transaction ends and the mux is free again
+    released: Condvar,
+    // -- id of the next channel handle handed out
+    next_id: AtomicUsize,
+}
+
+// -- TCA9548A or PCA9548 multiplexer, hands out one bus handle per downstream channel so
+// -- devices at colliding addresses can sit on different channels
+pub struct TCA9548A<B: I2cBus = I2c<File>, C: Clock = SystemClock> {
+    shared: Arc<Tca9548aShared<B>>,
+    // -- every channel handle waits on a copy of it, outside the mux lock
+    clock: C,
+}
+
+impl TCA9548A {
+
+    pub fn new(i2c_bus_path: &Path, device_addr: Tca9548aDeviceAddress) -> Result<Self, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr)
+    }
+
+}
+
+impl<B: I2cBus> TCA9548A<B> {
+
+    pub fn with_bus(i2c: B, device_addr: Tca9548aDeviceAddress) -> Result<Self, std::io::Error> {
+        Self::with_clock(i2c, device_addr, SystemClock)
+    }
+}
+
+impl<B: I2cBus, C: Clock + Clone> TCA9548A<B, C> {
+
+    // -- the channel handles wait and take the time from a clock of choice, e.g. a VirtualClock in tests
+    pub fn with_clock(i2c: B, device_addr: Tca9548aDeviceAddress, clock: C) -> Result<Self, std::io::Error> {
+        let state = Tca9548aState {
+            bus: i2c,
+            mux_addr: device_addr.value(),
+            current_channels: None,
+            current_addr: None,
+            owner: None,
+            depth: 0,
+        };
+        let shared = Tca9548aShared {
+            state: Mutex::new(state),
+            released: Condvar::new(),
+            next_id: AtomicUsize::new(0),
+        };
+        let mut mux = TCA9548A {
+            shared: Arc::new(shared),
+            clock,
+        };
+        // -- start with all channels off, this also checks if the mux is available
+        mux.disable_all()?;
+        Ok(mux)
+    }
+
+    // -- hand out a bus handle for one driver, e.g. SHT31::with_bus(mux.channel(Tca9548aChannel::Channel0), ...)
+    pub fn channel(&self, channel: Tca9548aChannel) -> Tca9548aChannelBus<B, C> {
+        Tca9548aChannelBus {
+            shared: Arc::clone(&self.shared),
+            id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+            channel,
+            device_addr: None,
+            clock: self.clock.clone(),
+        }
+    }
+
+    pub fn disable_all(&mut self) -> Result<(), std::io::Error> {
+        let mut state = lock(&self.shared, None)?;
+        select(&mut state, 0x00)
+    }
+}
+
+impl<B: I2cBus, C: Clock + Clone> Clone for TCA9548A<B, C> {
+    fn clone(&self) -> Self {
+        TCA9548A {
+            shared: Arc::clone(&self.shared),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+// -- lock the mux, waiting for a transaction of any channel handle but the given one to end
+fn lock<B>(shared: &Tca9548aShared<B>, id: Option<usize>) -> Result<MutexGuard<'_, Tca9548aState<B>>, std::io::Error> {
+    let poisoned = |_| std::io::Error::other("Multiplexer lock poisoned by a panicking thread");
+    let state = shared.state.lock().map_err(poisoned)?;
+    shared.released
+        .wait_while(state, |state| state.owner.is_some_and(|owner| Some(owner) != id))
+        .map_err(poisoned)
+}
+
+fn select<B: I2cBus>(state: &mut Tca9548aState<B>, channels: u8) -> Result<(), std::io::Error> {
+    debug!("TCA9548A switching to channels {channels:#010b}");
+    // -- forget the current state first, a failed switch leaves it unknown
+    state.current_channels = None;
+    state.current_addr = None;
+    state.bus.set_slave(state.mux_addr)?;
+    state.current_addr = Some(state.mux_addr);
+    state.bus.smbus_write_byte(channels)?;
+    state.current_channels = Some(channels);
+    Ok(())
+}
+
+// -- a driver's view of one mux channel, selects the channel and re-applies its device
+// -- address under the mux lock before every transfer, like a dedicated adapter; from
+// -- begin_transaction until the matching end_transaction the channel stays selected
+pub struct Tca9548aChannelBus<B: I2cBus = I2c<File>, C: Clock = SystemClock> {
+    shared: Arc<Tca9548aShared<B>>,
+    id: usize,
+    channel: Tca9548aChannel,
+    device_addr: Option<u16>,
+    clock: C,
+}
+
+impl<B: I2cBus, C: Clock> Tca9548aChannelBus<B, C> {
+
+    pub fn get_channel(&self) -> Tca9548aChannel {
+        self.channel
+    }
+
+    // -- give up one level of the transaction and hand the mux on when the outermost ends
+    fn release(&self, state: &mut Tca9548aState<B>) {
+        if state.owner != Some(self.id) {
+            return;
+        }
+        state.depth -= 1;
+        if state.depth == 0 {
+            state.owner = None;
+            self.shared.released.notify_all();
+        }
+    }
+
+    fn transaction<R>(&mut self, op: impl FnOnce(&mut B) -> Result<R, std::io::Error>) -> Result<R, std::io::Error> {
+        let mut state = lock(&self.shared, Some(self.id))?;
+        let channels = self.channel.value();
+        if state.current_channels != Some(channels) {
+            select(&mut state, channels)?;
+        }
+        if let Some(device_addr) = self.device_addr {
+            if state.current_addr != Some(device_addr) {
+                state.current_addr = None;
+                state.bus.set_slave(device_addr)?;
+                state.current_addr = Some(device_addr);
+            }
+        }
+        op(&mut state.bus)
+    }
+}
+
+impl<B: I2cBus, C: Clock> I2cBus for Tca9548aChannelBus<B, C> {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.device_addr = Some(dev_addr);
+        // -- switch right away so a bad address is reported to the caller
+        self.transaction(|_| Ok(()))
+    }
+
+    fn smbus_read_byte_data(&mut self, register: u8) -> Result<u8, std::io::Error> {
+        self.transaction(|bus| bus.smbus_read_byte_data(register))
+    }
+
+    fn smbus_read_word_data(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        self.transaction(|bus| bus.smbus_read_word_data(register))
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_byte(data))
+    }
+
+    fn smbus_write_byte_data(&mut self, register: u8, data: u8) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_byte_data(register, data))
+    }
+
+    fn smbus_write_word_data(&mut self, register: u8, data: u16) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_word_data(register, data))
+    }
+
+    fn smbus_write_block_data(&mut self, register: u8, data: &[u8]) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.smbus_write_block_data(register, data))
+    }
+
+    fn i2c_read_block_data(&mut self, register: u8, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.transaction(|bus| bus.i2c_read_block_data(register, data))
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        self.transaction(|bus| bus.i2c_transfer(messages))
+    }
+
+    // -- waits and time go to the clock of the mux without locking it or selecting the channel,
+    // -- the other channels keep transferring while this one waits
+    fn delay_ms(&mut self, milli_secs: u32) {
+        self.clock.delay_ms(milli_secs)
+    }
+
+    fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), std::io::Error> {
+        let mut state = lock(&self.shared, Some(self.id))?;
+        // -- pin the channel until the matching end_transaction, nested transactions only count up
+        state.owner = Some(self.id);
+        state.depth += 1;
+        let result = state.bus.begin_transaction();
+        if result.is_err() {
+            // -- no end_transaction follows a failed begin
+            self.release(&mut state);
+        }
+        result
+    }
+
+    fn end_transaction(&mut self) -> Result<(), std::io::Error> {
+        let mut state = lock(&self.shared, Some(self.id))?;
+        let result = state.bus.end_transaction();
+        self.release(&mut state);
+        result
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        lock(&self.shared, Some(self.id)).ok()?.bus.retry_policy()
+    }
+
+    // -- a property of the upstream adapter, no channel switch needed
+    fn functionality(&mut self) -> Result<Functionality, std::io::Error> {
+        lock(&self.shared, Some(self.id))?.bus.functionality()
+    }
+
+    fn record(&mut self, event: BusEvent) {
+        if let Ok(mut state) = lock(&self.shared, Some(self.id)) {
+            state.bus.record(event)
+        }
+    }
+}
+
+impl<B: I2cBus, C: Clock> Drop for Tca9548aChannelBus<B, C> {
+    fn drop(&mut self) {
+        // -- a driver dropped mid-transaction, e.g. by a panic, must not keep the mux forever
+        let mut state = self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.owner == Some(self.id) {
+            state.owner = None;
+            state.depth = 0;
+            self.shared.released.notify_all();
+        }
+    }
+}
@@ -3,28 +3,30 @@ use i2c_linux::I2c;
 use log::{debug, error, log_enabled, info, Level};
 use std::fs::File;
 use std::path::Path;
-use std::{thread, time};
 
-use crate::i2cio;
+use crate::error::Error;
+use crate::i2cio::{self, I2cBus};
+use crate::i2cio_async::{self, AsyncI2cBus};
+use crate::sensor::{Measurement, Sensor};
+use crate::units::Temperature;
+use crate::trace::{self, AsyncDriverCall, DriverCall};
 
 const TMP117_REG_TEMPERATURE: u8 = 0x00;
 const TMP117_REG_CONFIGURATION: u8 = 0x01;
-#[allow(dead_code)]
 const TMP117_REG_HIGH_LIMIT: u8 = 0x02;
-#[allow(dead_code)]
 const TMP117_REG_LOW_LIMIT: u8 = 0x03;
-#[allow(dead_code)]
 const TMP117_REG_EEPROM_UNLOCK: u8 = 0x04;
-#[allow(dead_code)]
 const TMP117_REG_EEPROM1: u8 = 0x05;
-#[allow(dead_code)]
 const TMP117_REG_EEPROM2: u8 = 0x06;
 const TMP117_REG_TEMPERATURE_OFFSET: u8 = 0x07;
-#[allow(dead_code)]
 const TMP117_REG_EEPROM3: u8 = 0x08;
-const TMP117_REG_DEVICE_ID: u8 = 0x0f;
+pub(crate) const TMP117_REG_DEVICE_ID: u8 = 0x0f;
+
+trace::register_names!(TMP117_REG_TEMPERATURE, TMP117_REG_CONFIGURATION, TMP117_REG_HIGH_LIMIT,
+    TMP117_REG_LOW_LIMIT, TMP117_REG_EEPROM_UNLOCK, TMP117_REG_EEPROM1, TMP117_REG_EEPROM2,
+    TMP117_REG_TEMPERATURE_OFFSET, TMP117_REG_EEPROM3, TMP117_REG_DEVICE_ID);
 
-const TMP117_DEVICE_ID: u16 = 0x117;
+pub(crate) const TMP117_DEVICE_ID: u16 = 0x117;
 const TMP117_DEVICE_ID_MASK: u16 = 0xfff;
 const TMP117_REVISION_SHIFT_RIGHT: u8 = 12;
 const TMP117_CONFIG_DATA_READY_BIT: u16 = 0x2000;
@@ -36,7 +38,7 @@ const TMP117_CONFIG_MODE_SHIFT_LEFT: u8 = 10;
 const TMP117_CONFIG_CONVERSION_CYCLE_SHIFT_LEFT: u8 = 7;
 const TMP117_CONFIG_AVERAGING_SHIFT_LEFT: u8 = 5;
 #[allow(dead_code)]
-const TMP117_STARTUP_DELAY_MS: u64 = 2;
+const TMP117_STARTUP_DELAY_MS: u32 = 2;
 
 const TMP117_TEMPERATURE_FACTOR: f64 = 0.0078125;
 
@@ -49,6 +51,7 @@ pub enum Tmp117DeviceAddress {
     Alt3,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Tmp117DeviceAddress {
     fn default() -> Self {
         Tmp117DeviceAddress::Default
@@ -61,7 +64,7 @@ impl Tmp117DeviceAddress {
     const DEVICE_ADDR_ALT2: u16 = 0x4A;
     const DEVICE_ADDR_ALT3: u16 = 0x4B;
 
-    fn value(&self) -> u16 {
+    pub(crate) fn value(&self) -> u16 {
         match *self {
             Self::Default => Self::DEVICE_ADDR_DEFAULT,
             Self::Alt1 => Self::DEVICE_ADDR_ALT1,
@@ -72,13 +75,14 @@ impl Tmp117DeviceAddress {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Tmp117SensorMode {
     ModeContinuousConversion,    
     ModeShutDown,
     ModeOneShot,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Tmp117SensorMode {
     fn default() -> Self {
         Tmp117SensorMode::ModeContinuousConversion
@@ -100,7 +104,7 @@ impl Tmp117SensorMode {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Tmp117ConversionCycleTime {
     Shortest,
     Shorter,
@@ -146,7 +150,7 @@ impl Tmp117ConversionCycleTime {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Tmp117Averaging {
     NoAveraging,
     Averaging8Conversions,
@@ -171,10 +175,135 @@ impl Tmp117Averaging {
     }
 }
 
+pub(crate) fn device_id_and_revision(reg_val: u16) -> (u16, u8) {
+    debug!("TMP117 device id register: {reg_val:#018b}");
+    let device_id = reg_val & TMP117_DEVICE_ID_MASK;
+    let device_rev = (reg_val >> TMP117_REVISION_SHIFT_RIGHT) as u8;
+    (device_id, device_rev)
+}
+
+fn config_with_mode(reg_val: u16, sensor_mode: &Tmp117SensorMode,
+    conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> u16 {
+    debug!("TMP117 config register: {reg_val:#018b}");
+    // -- keep bit 0 - 4 as is
+    let reg_val_masked = reg_val & TMP117_CONFIG_MODE_CONV_AVG_MASK;
+    debug!("TMP117 reg value masked: {reg_val_masked:#018b}");
+    // -- prepare mode bits
+    let mode_bits = sensor_mode.value() << TMP117_CONFIG_MODE_SHIFT_LEFT;
+    // -- prepare conversion cycle bits
+    let conversion_cycle_bits = conversion_cycle.value() << TMP117_CONFIG_CONVERSION_CYCLE_SHIFT_LEFT;
+    // -- prepare averaging bits
+    let averaging_bits = averaging.value() << TMP117_CONFIG_AVERAGING_SHIFT_LEFT;
+    debug!("TMP117 mode bits: {mode_bits:#018b}, conversion cycle bits: {conversion_cycle_bits:#018b}, averaging bits: {averaging_bits:#018b}");
+    let reg_val = reg_val_masked | mode_bits | conversion_cycle_bits | averaging_bits;
+    debug!("TMP117 change config register to: {reg_val:#018b}");
+    reg_val
+}
+
+fn data_ready(reg_val: u16) -> bool {
+    debug!("TMP117 config register: {reg_val:#018b}");
+    let is_data_ready = (reg_val & TMP117_CONFIG_DATA_READY_BIT) > 0;
+    debug!("TMP117 is data ready: {is_data_ready}");
+    is_data_ready
+}
+
+fn temperature(reg_val: u16) -> Temperature {
+    debug!("TMP117 temperature register: {reg_val:#018b}");
+    Temperature::from_celsius(((reg_val as i16) as f64) * TMP117_TEMPERATURE_FACTOR)
+}
+
+// -- a temperature difference in kelvin, a plain number since it converts unlike a temperature
+fn temperature_offset(reg_val: u16) -> f64 {
+    debug!("TMP117 temperature offset register: {reg_val:#06x}");
+    if reg_val != 0 {
+        ((reg_val as i16) as f64) * TMP117_TEMPERATURE_FACTOR
+    } else {
+        0.0
+    }
+}
+
+fn temperature_offset_register(offset: f64) -> u16 {
+    // -- convert float to register value
+    let reg_val = (offset / TMP117_TEMPERATURE_FACTOR) as i16;
+    debug!("TMP117 writing temperature offset: {reg_val:#06x}");
+    // -- TMP117 expects most significant byte first so a swap is required
+    (reg_val.swap_bytes()) as u16
+}
+
+// -- everything with_bus and set_sensor_mode configure, written in one go by with_config and
+// -- apply_config; the default is the power-on state, continuous conversion every second with
+// -- 8 averaged conversions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tmp117Config {
+    device_addr: Tmp117DeviceAddress,
+    sensor_mode: Tmp117SensorMode,
+    conversion_cycle: Tmp117ConversionCycleTime,
+    averaging: Tmp117Averaging,
+}
+
+impl Default for Tmp117Config {
+    fn default() -> Self {
+        Tmp117Config {
+            device_addr: Tmp117DeviceAddress::Default,
+            sensor_mode: Tmp117SensorMode::ModeContinuousConversion,
+            conversion_cycle: Tmp117ConversionCycleTime::Ms1000,
+            averaging: Tmp117Averaging::Averaging8Conversions,
+        }
+    }
+}
+
+impl Tmp117Config {
+
+    pub fn with_device_addr(mut self, device_addr: Tmp117DeviceAddress) -> Self {
+        self.device_addr = device_addr;
+        self
+    }
+
+    pub fn with_sensor_mode(mut self, sensor_mode: Tmp117SensorMode) -> Self {
+        self.sensor_mode = sensor_mode;
+        self
+    }
+
+    pub fn with_conversion_cycle(mut self, conversion_cycle: Tmp117ConversionCycleTime) -> Self {
+        self.conversion_cycle = conversion_cycle;
+        self
+    }
+
+    pub fn with_averaging(mut self, averaging: Tmp117Averaging) -> Self {
+        self.averaging = averaging;
+        self
+    }
+
+    pub fn get_device_addr(&self) -> Tmp117DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    // -- checked before anything is written; in continuous conversion the device silently
+    // -- stretches a cycle shorter than the averaging takes, see Tmp117ConversionCycleTime,
+    // -- Shortest asks for exactly that and is always fine
+    pub fn validate(&self) -> Result<(), std::io::Error> {
+        if self.sensor_mode != Tmp117SensorMode::ModeContinuousConversion {
+            return Ok(())
+        }
+        let stretched = match self.averaging {
+            Tmp117Averaging::NoAveraging | Tmp117Averaging::Averaging8Conversions => false,
+            Tmp117Averaging::Averaging32Conversions => matches!(self.conversion_cycle,
+                Tmp117ConversionCycleTime::Shorter | Tmp117ConversionCycleTime::Short),
+            Tmp117Averaging::Averaging64Conversions => matches!(self.conversion_cycle,
+                Tmp117ConversionCycleTime::Shorter | Tmp117ConversionCycleTime::Short | Tmp117ConversionCycleTime::Medium),
+        };
+        if stretched {
+            let errmsg = format!("Conversion cycle {:?} is shorter than {:?} takes", self.conversion_cycle, self.averaging);
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
-pub struct TMP117 {
+pub struct TMP117<B: I2cBus = I2c<File>> {
     // -- i2c bus
-    i2c: I2c<File>,
+    i2c: B,
     // -- device address.
     device_addr: Tmp117DeviceAddress,
     // -- device id
@@ -188,30 +317,58 @@ impl TMP117
     pub fn new(i2c_bus_path: &Path, device_addr: Tmp117DeviceAddress, sensor_mode: &Tmp117SensorMode, 
         conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<Self, std::io::Error> {
         // -- get the bus
-        let mut i2c = i2cio::get_bus(i2c_bus_path)?;
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_bus(i2c, device_addr, sensor_mode, conversion_cycle, averaging)
+    }
+
+    pub fn new_with_config(i2c_bus_path: &Path, config: &Tmp117Config) -> Result<Self, std::io::Error> {
+        // -- get the bus
+        let i2c = i2cio::get_bus(i2c_bus_path)?;
+        Self::with_config(i2c, config)
+    }
+}
+
+impl<B: I2cBus> TMP117<B>
+{
+    pub fn with_bus(i2c: B, device_addr: Tmp117DeviceAddress, sensor_mode: &Tmp117SensorMode, 
+        conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("TMP117", "with_bus", device_addr.value(), register_name);
+        let mut tmp117 = Self::init(i2c, device_addr)?;
+        // -- set the desired mode
+        Self::set_sensor_mode_internal(&mut tmp117.i2c, sensor_mode, conversion_cycle, averaging)?;
+        // -- ready to measure steady
+        Ok(tmp117)
+    }
+
+    // -- a config that doesn't validate fails before the bus is touched
+    pub fn with_config(i2c: B, config: &Tmp117Config) -> Result<Self, std::io::Error> {
+        let _call = trace::driver_call("TMP117", "with_config", config.device_addr.value(), register_name);
+        config.validate()?;
+        let mut tmp117 = Self::init(i2c, config.get_device_addr())?;
+        tmp117.apply_config(config)?;
+        Ok(tmp117)
+    }
+
+    // -- id check and soft reset, the device is left in its power-on state
+    fn init(mut i2c: B, device_addr: Tmp117DeviceAddress) -> Result<Self, std::io::Error> {
         // -- set device address
         i2cio::set_slave(&mut i2c, device_addr.value())?;
         // -- check if device is available by reading id and revision
         let (device_id, device_rev) = Self::read_device_id_and_revision(&mut i2c)?;
         if device_id != TMP117_DEVICE_ID {
-            let errmsg = format!("Found unknown device id '{device_id:#06x}', expected '{TMP117_DEVICE_ID:#06x}'");
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, errmsg))
+            return Err(Error::ChipId { expected: TMP117_DEVICE_ID, found: device_id }.into())
         }
         // -- do a soft reset since it's in an unknown state
         Self::soft_reset(&mut i2c)?;
-        // -- set the desired mode
-        Self::set_sensor_mode_internal(&mut i2c, &sensor_mode, &conversion_cycle, &averaging)?;
-        // -- ready to measure steady
-        let tmp117 = TMP117 {
+        Ok(TMP117 {
             i2c,
             device_addr,
             device_id,
             device_rev,
-        };
-        Ok(tmp117)
+        })
     }    
 
-    fn read_device_id_and_revision(i2c: &mut I2c<File>) -> Result<(u16, u8), std::io::Error> {
+    fn read_device_id_and_revision(i2c: &mut B) -> Result<(u16, u8), std::io::Error> {
         // let mut reg_val: [u8; 2] = [0, 0];
         // let _bytes_read = i2c.i2c_read_block_data(TMP117_REG_DEVICE_ID, &mut reg_val)?;
         // debug!("TMP117 device id register: {reg_val:#?}, byted read {_bytes_read}");
@@ -221,10 +378,12 @@ impl TMP117
         // -- TMP117 sends most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
         //let reg_val = reg_val >> 8 | ((reg_val & 0xf) << 8);
-        debug!("TMP117 device id register: {reg_val:#018b}");
-        let device_id = reg_val & TMP117_DEVICE_ID_MASK;
-        let device_rev = (reg_val >> TMP117_REVISION_SHIFT_RIGHT) as u8;
-        Ok((device_id, device_rev))
+        Ok(device_id_and_revision(reg_val))
+    }
+
+    // -- span of a public call, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> DriverCall {
+        trace::driver_call("TMP117", call, self.device_addr.value(), register_name)
     }
 
     #[allow(dead_code)]
@@ -240,36 +399,23 @@ impl TMP117
         self.device_rev
     }    
 
-    fn soft_reset(i2c: &mut I2c<File>) -> Result<(), std::io::Error> {
+    fn soft_reset(i2c: &mut B) -> Result<(), std::io::Error> {
         let reg_val = TMP117_CONFIG_SOFT_RESET_BIT;
         // -- TMP117 expects most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
         i2cio::write_word(i2c, TMP117_REG_CONFIGURATION, reg_val)?;
         // -- wait for the device to startup
-        let startup_delay = time::Duration::from_millis(TMP117_STARTUP_DELAY_MS);
-        thread::sleep(startup_delay);
+        i2cio::delay(i2c, TMP117_STARTUP_DELAY_MS);
         Ok(())
     }
 
-    fn set_sensor_mode_internal(i2c: &mut I2c<File>, sensor_mode: &Tmp117SensorMode, 
+    fn set_sensor_mode_internal(i2c: &mut B, sensor_mode: &Tmp117SensorMode, 
         conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<(), std::io::Error> {
         // -- read the 16 bit (word) config register
         let reg_val = i2cio::read_word(i2c, TMP117_REG_CONFIGURATION)?;
         // -- TMP117 sends most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
-        debug!("TMP117 config register: {reg_val:#018b}");
-        // -- keep bit 0 - 4 as is
-        let reg_val_masked = reg_val & TMP117_CONFIG_MODE_CONV_AVG_MASK;
-        debug!("TMP117 reg value masked: {reg_val_masked:#018b}");
-        // -- prepare mode bits
-        let mode_bits = sensor_mode.value() << TMP117_CONFIG_MODE_SHIFT_LEFT;
-        // -- prepare conversion cycle bits
-        let conversion_cycle_bits = conversion_cycle.value() << TMP117_CONFIG_CONVERSION_CYCLE_SHIFT_LEFT;
-        // -- prepare averaging bits
-        let averaging_bits = averaging.value() << TMP117_CONFIG_AVERAGING_SHIFT_LEFT;
-        debug!("TMP117 mode bits: {mode_bits:#018b}, conversion cycle bits: {conversion_cycle_bits:#018b}, averaging bits: {averaging_bits:#018b}");
-        let reg_val = reg_val_masked | mode_bits | conversion_cycle_bits | averaging_bits;
-        debug!("TMP117 change config register to: {reg_val:#018b}");
+        let reg_val = config_with_mode(reg_val, sensor_mode, conversion_cycle, averaging);
         // -- TMP117 expects most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
         i2cio::write_word(i2c, TMP117_REG_CONFIGURATION, reg_val)
@@ -277,7 +423,20 @@ impl TMP117
 
     pub fn set_sensor_mode(&mut self, sensor_mode: &Tmp117SensorMode, 
         conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<(), std::io::Error> {
-        Self::set_sensor_mode_internal(&mut self.i2c, &sensor_mode, &conversion_cycle, &averaging)
+        let _call = self.call("set_sensor_mode");
+        Self::set_sensor_mode_internal(&mut self.i2c, sensor_mode, conversion_cycle, averaging)
+    }
+
+    // -- mode, conversion cycle and averaging share the config register and go in one write
+    pub fn apply_config(&mut self, config: &Tmp117Config) -> Result<(), std::io::Error> {
+        let _call = self.call("apply_config");
+        config.validate()?;
+        if config.device_addr != self.device_addr {
+            let errmsg = format!("Config is for device address {:#04x}, not {:#04x}",
+                config.device_addr.value(), self.device_addr.value());
+            return Err(Error::InvalidConfig(errmsg).into())
+        }
+        Self::set_sensor_mode_internal(&mut self.i2c, &config.sensor_mode, &config.conversion_cycle, &config.averaging)
     }
 
     // pub fn get_config(&mut self) -> Result<u16, std::io::Error> {
@@ -290,48 +449,165 @@ impl TMP117
     // }
 
     pub fn is_data_ready(&mut self) -> Result<bool, std::io::Error> {
+        let _call = self.call("is_data_ready");
         // -- read the 16 bit (word) config register
         let reg_val = i2cio::read_word(&mut self.i2c, TMP117_REG_CONFIGURATION)?;
         // -- TMP117 sends most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
-        debug!("TMP117 config register: {reg_val:#018b}");
-        let is_data_ready = (reg_val & TMP117_CONFIG_DATA_READY_BIT) > 0;
-        debug!("TMP117 is data ready: {is_data_ready}");
-        Ok(is_data_ready)
+        Ok(data_ready(reg_val))
     }
 
-    pub fn get_temperature(&mut self) -> Result<f64, std::io::Error> {
+    pub fn get_temperature(&mut self) -> Result<Temperature, std::io::Error> {
+        let _call = self.call("get_temperature");
         // -- read the 16 bit (word) config register
         let reg_val = i2cio::read_word(&mut self.i2c, TMP117_REG_TEMPERATURE)?;
         // -- TMP117 sends most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
-        debug!("TMP117 temperature register: {reg_val:#018b}");
-        let temp_celcius = ((reg_val as i16) as f64) * TMP117_TEMPERATURE_FACTOR;
-        Ok(temp_celcius)
+        Ok(temperature(reg_val))
     }
 
     pub fn get_temperature_offset(&mut self) -> Result<f64, std::io::Error> {
+        let _call = self.call("get_temperature_offset");
         // -- read the 16 bit (word) config register
         let reg_val = i2cio::read_word(&mut self.i2c, TMP117_REG_TEMPERATURE_OFFSET)?;
         // -- TMP117 sends most significant byte first so a swap is required
         let reg_val = reg_val.swap_bytes();
-        debug!("TMP117 temperature offset register: {reg_val:#06x}");
-        let temp_offset = if reg_val != 0 {
-            ((reg_val as i16) as f64) * TMP117_TEMPERATURE_FACTOR
-        } else {
-            0.0
-        };
-        Ok(temp_offset)
+        Ok(temperature_offset(reg_val))
     }
 
     pub fn set_temperature_offset(&mut self, offset: f64) -> Result<(), std::io::Error> {
-        // -- convert float to register value
-        let reg_val = (offset / TMP117_TEMPERATURE_FACTOR) as i16;
-        debug!("TMP117 writing temperature offset: {reg_val:#06x}");
-        // -- TMP117 expects most significant byte first so a swap is required
-        let reg_val = (reg_val.swap_bytes()) as u16;
+        let _call = self.call("set_temperature_offset");
+        let reg_val = temperature_offset_register(offset);
         // -- read the 16 bit (word) config register
         i2cio::write_word(&mut self.i2c, TMP117_REG_TEMPERATURE_OFFSET, reg_val)
     }
 
-}
\ No newline at end of file
+}
+
+// -- the result of the last conversion, in one-shot mode trigger it through set_sensor_mode first
+impl<B: I2cBus> Sensor for TMP117<B> {
+
+    fn name(&self) -> &'static str {
+        "TMP117"
+    }
+
+    fn measure(&mut self) -> Result<Measurement, std::io::Error> {
+        let _call = self.call("measure");
+        Ok(Measurement {
+            temperature: Some(self.get_temperature()?),
+            ..Default::default()
+        })
+    }
+}
+
+// -- async variant for executors polling many sensors, reset waits yield instead of blocking
+// -- the thread, poll is_data_ready between conversions
+pub struct TMP117Async<B: AsyncI2cBus> {
+    // -- i2c bus
+    i2c: B,
+    // -- device address.
+    device_addr: Tmp117DeviceAddress,
+    // -- device id
+    device_id: u16,
+    // -- device revision
+    device_rev: u8,
+}
+
+impl<B: AsyncI2cBus> TMP117Async<B>
+{
+    pub async fn with_bus(mut i2c: B, device_addr: Tmp117DeviceAddress, sensor_mode: &Tmp117SensorMode,
+        conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<Self, std::io::Error> {
+        trace::async_driver_call("TMP117", "with_bus", device_addr.value(), register_name).run(async move {
+            // -- set device address
+            i2cio_async::set_slave(&mut i2c, device_addr.value()).await?;
+            // -- check if device is available by reading id and revision, most significant byte first
+            let reg_val = i2cio_async::read_word(&mut i2c, TMP117_REG_DEVICE_ID).await?.swap_bytes();
+            let (device_id, device_rev) = device_id_and_revision(reg_val);
+            if device_id != TMP117_DEVICE_ID {
+                return Err(Error::ChipId { expected: TMP117_DEVICE_ID, found: device_id }.into())
+            }
+            let mut tmp117 = TMP117Async {
+                i2c,
+                device_addr,
+                device_id,
+                device_rev,
+            };
+            // -- do a soft reset since it's in an unknown state
+            tmp117.soft_reset().await?;
+            // -- set the desired mode
+            tmp117.set_sensor_mode(sensor_mode, conversion_cycle, averaging).await?;
+            // -- ready to measure steady
+            Ok(tmp117)
+        }).await
+    }
+
+    // -- span of a public call, attached to its future, names the registers of the transfers in it
+    fn call(&self, call: &'static str) -> AsyncDriverCall {
+        trace::async_driver_call("TMP117", call, self.device_addr.value(), register_name)
+    }
+
+    pub fn get_device_addr(&self) -> Tmp117DeviceAddress {
+        self.device_addr.clone()
+    }
+
+    pub fn get_device_id(&self) -> u16 {
+        self.device_id
+    }
+
+    pub fn get_device_revision(&self) -> u8 {
+        self.device_rev
+    }
+
+    async fn soft_reset(&mut self) -> Result<(), std::io::Error> {
+        // -- TMP117 expects most significant byte first so a swap is required
+        let reg_val = TMP117_CONFIG_SOFT_RESET_BIT.swap_bytes();
+        i2cio_async::write_word(&mut self.i2c, TMP117_REG_CONFIGURATION, reg_val).await?;
+        // -- wait for the device to startup
+        i2cio_async::delay(&mut self.i2c, TMP117_STARTUP_DELAY_MS).await;
+        Ok(())
+    }
+
+    async fn read_register(&mut self, register: u8) -> Result<u16, std::io::Error> {
+        // -- TMP117 sends most significant byte first so a swap is required
+        let reg_val = i2cio_async::read_word(&mut self.i2c, register).await?;
+        Ok(reg_val.swap_bytes())
+    }
+
+    pub async fn set_sensor_mode(&mut self, sensor_mode: &Tmp117SensorMode,
+        conversion_cycle: &Tmp117ConversionCycleTime, averaging: &Tmp117Averaging) -> Result<(), std::io::Error> {
+        self.call("set_sensor_mode").run(async {
+            let reg_val = self.read_register(TMP117_REG_CONFIGURATION).await?;
+            let reg_val = config_with_mode(reg_val, sensor_mode, conversion_cycle, averaging);
+            // -- TMP117 expects most significant byte first so a swap is required
+            i2cio_async::write_word(&mut self.i2c, TMP117_REG_CONFIGURATION, reg_val.swap_bytes()).await
+        }).await
+    }
+
+    pub async fn is_data_ready(&mut self) -> Result<bool, std::io::Error> {
+        self.call("is_data_ready").run(async {
+            let reg_val = self.read_register(TMP117_REG_CONFIGURATION).await?;
+            Ok(data_ready(reg_val))
+        }).await
+    }
+
+    pub async fn get_temperature(&mut self) -> Result<Temperature, std::io::Error> {
+        self.call("get_temperature").run(async {
+            let reg_val = self.read_register(TMP117_REG_TEMPERATURE).await?;
+            Ok(temperature(reg_val))
+        }).await
+    }
+
+    pub async fn get_temperature_offset(&mut self) -> Result<f64, std::io::Error> {
+        self.call("get_temperature_offset").run(async {
+            let reg_val = self.read_register(TMP117_REG_TEMPERATURE_OFFSET).await?;
+            Ok(temperature_offset(reg_val))
+        }).await
+    }
+
+    pub async fn set_temperature_offset(&mut self, offset: f64) -> Result<(), std::io::Error> {
+        self.call("set_temperature_offset").run(async {
+            let reg_val = temperature_offset_register(offset);
+            i2cio_async::write_word(&mut self.i2c, TMP117_REG_TEMPERATURE_OFFSET, reg_val).await
+        }).await
+    }
+}
@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::future::Future;
+use tracing::span::EnteredSpan;
+use tracing::Instrument;
+
+// -- structured tracing of the drivers: every public driver call is a span named "driver_call"
+// -- with the fields driver, call and device_addr, every bus transfer below it is an event with
+// -- target "i2c_sensors::bus" carrying driver, device_addr, register, register_name, direction
+// -- and bytes as they went over the wire (smbus words lsb first), failed transfers carry the error
+pub const BUS_TARGET: &str = "i2c_sensors::bus";
+
+// -- maps a register address to the name of its constant, see register_names!
+pub(crate) type RegisterNames = fn(u8) -> Option<&'static str>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Direction {
+    Read,
+    Write,
+}
+
+impl Direction {
+    fn value(&self) -> &'static str {
+        match *self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+// -- the driver call a transfer belongs to
+#[derive(Clone, Copy)]
+struct Scope {
+    driver: &'static str,
+    device_addr: u16,
+    register_names: RegisterNames,
+}
+
+thread_local! {
+    // -- driver calls nest, e.g. a measurement triggering a mode change, the innermost one wins
+    static SCOPES: RefCell<Vec<Scope>> = const { RefCell::new(Vec::new()) };
+}
+
+// -- keeps the register names of a driver call in scope until dropped
+struct ScopeGuard;
+
+impl ScopeGuard {
+    fn push(scope: Scope) -> Self {
+        SCOPES.with(|scopes| scopes.borrow_mut().push(scope));
+        ScopeGuard
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPES.with(|scopes| scopes.borrow_mut().pop());
+    }
+}
+
+// -- keeps the span of a driver call entered and its register names in scope until dropped
+pub(crate) struct DriverCall {
+    _scope: ScopeGuard,
+    _span: EnteredSpan,
+}
+
+// -- to be held for the whole call, e.g. let _call = trace::driver_call("BMP388", "enable_fifo", ...)
+pub(crate) fn driver_call(driver: &'static str, call: &'static str, device_addr: u16,
+    register_names: RegisterNames) -> DriverCall {
+    let span = tracing::debug_span!("driver_call", driver, call, device_addr).entered();
+    DriverCall {
+        _scope: ScopeGuard::push(Scope { driver, device_addr, register_names }),
+        _span: span,
+    }
+}
+
+// -- the driver call of an async driver, a future may be polled on another thread after every
+// -- await, so nothing is held across them: the span is attached to the future and the register
+// -- names are only in scope while the future is polled
+#[derive(Clone, Copy)]
+pub(crate) struct AsyncDriverCall {
+    call: &'static str,
+    scope: Scope,
+}
+
+impl AsyncDriverCall {
+    // -- e.g. self.call("get_status").run(async { ... }).await
+    pub(crate) async fn run<F: Future>(self, fut: F) -> F::Output {
+        let Scope { driver, device_addr, .. } = self.scope;
+        let span = tracing::debug_span!("driver_call", driver, call = self.call, device_addr);
+        let mut fut = std::pin::pin!(fut);
+        std::future::poll_fn(|cx| {
+            let _scope = ScopeGuard::push(self.scope);
+            fut.as_mut().poll(cx)
+        }).instrument(span).await
+    }
+}
+
+pub(crate) fn async_driver_call(driver: &'static str, call: &'static str, device_addr: u16,
+    register_names: RegisterNames) -> AsyncDriverCall {
+    AsyncDriverCall {
+        call,
+        scope: Scope { driver, device_addr, register_names },
+    }
+}
+
+fn with_scope(f: impl FnOnce(Option<&'static str>, Option<u16>, Option<RegisterNames>)) {
+    SCOPES.with(|scopes| match scopes.borrow().last() {
+        Some(scope) => f(Some(scope.driver), Some(scope.device_addr), Some(scope.register_names)),
+        None => f(None, None, None),
+    })
+}
+
+// -- a completed transfer, register is none for plain reads and writes without a register
+pub(crate) fn transfer(direction: Direction, register: Option<u8>, bytes: &[u8]) {
+    if !tracing::enabled!(target: BUS_TARGET, tracing::Level::TRACE) {
+        return
+    }
+    with_scope(|driver, device_addr, register_names| {
+        let register_name = register.zip(register_names).and_then(|(register, names)| names(register));
+        tracing::trace!(target: BUS_TARGET, driver, device_addr, register, register_name,
+            direction = direction.value(), bytes = ?bytes);
+    })
+}
+
+// -- a transfer the bus reported an error for
+pub(crate) fn failed(direction: Direction, register: Option<u8>, err: &std::io::Error) {
+    if !tracing::enabled!(target: BUS_TARGET, tracing::Level::TRACE) {
+        return
+    }
+    with_scope(|driver, device_addr, register_names| {
+        let register_name = register.zip(register_names).and_then(|(register, names)| names(register));
+        tracing::trace!(target: BUS_TARGET, driver, device_addr, register, register_name,
+            direction = direction.value(), error = %err);
+    })
+}
+
+// -- generates fn register_name(register: u8) -> Option<&'static str> from the register constants
+// -- of a driver, the first constant with a matching address wins, none for command based devices
+macro_rules! register_names {
+    () => {
+        fn register_name(_register: u8) -> Option<&'static str> {
+            None
+        }
+    };
+    ($($register:ident),+ $(,)?) => {
+        fn register_name(register: u8) -> Option<&'static str> {
+            $(
+                if register == $register {
+                    return Some(stringify!($register))
+                }
+            )*
+            None
+        }
+    };
+}
+
+pub(crate) use register_names;
@@ -0,0 +1,310 @@
+use i2c_linux::Functionality;
+use std::io::ErrorKind;
+
+use i2c_sensors::bme280::{BME280, Bme280Config, Bme280DeviceAddress, Bme280IrrFilter, Bme280OverSampling,
+    Bme280SensorMode, Bme280Spi3w, Bme280TimeStandby};
+use i2c_sensors::error::Error;
+use i2c_sensors::mock::{MockBus, MockSpi, MockSpiTransfer as S, MockTransaction as T};
+use i2c_sensors::sensor::Sensor;
+
+const ADDR: u16 = 0x77;
+const REG_PART_ID: u8 = 0xd0;
+const REG_RESET: u8 = 0xe0;
+const REG_TEMP_PRESS_CALIB_DATA: u8 = 0x88;
+const REG_HUMIDITY_CALIB_DATA: u8 = 0xe1;
+const REG_CTRL_HUM: u8 = 0xf2;
+const REG_STATUS: u8 = 0xf3;
+const REG_CTRL_MEAS: u8 = 0xf4;
+const REG_CONFIG: u8 = 0xf5;
+const REG_DATA: u8 = 0xf7;
+
+// -- calibration example of the data sheet, dig_t1 to dig_p9 little endian, dig_h1 last
+fn temp_press_calib_data() -> Vec<u8> {
+    let dig_t: [i32; 3] = [27504, 26435, -1000];
+    let dig_p: [i32; 9] = [36477, -10685, 3024, 2855, 140, -7, 15500, -14600, 6000];
+    let mut data: Vec<u8> = dig_t.iter().chain(dig_p.iter())
+        .flat_map(|dig| (*dig as u16).to_le_bytes())
+        .collect();
+    data.push(0x00);
+    // -- dig_h1
+    data.push(75);
+    data
+}
+
+// -- dig_h2 = 362, dig_h3 = 0, dig_h4 = 324, dig_h5 = 0, dig_h6 = 30 with h4 and h5 sharing a nibble byte
+fn humidity_calib_data() -> Vec<u8> {
+    vec![0x6a, 0x01, 0x00, 0x14, 0x04, 0x00, 0x1e]
+}
+
+fn init_script() -> Vec<T> {
+    vec![
+        T::set_slave(ADDR),
+        T::read_byte(REG_PART_ID, 0x60),
+        T::write_byte(REG_RESET, 0xb6),
+        T::read_block(REG_TEMP_PRESS_CALIB_DATA, &temp_press_calib_data()),
+        T::read_block(REG_HUMIDITY_CALIB_DATA, &humidity_calib_data()),
+    ]
+}
+
+fn init(mock: &MockBus) -> BME280<MockBus> {
+    let bme280 = BME280::with_bus(mock.clone(), Bme280DeviceAddress::Default).expect("init failed");
+    mock.done();
+    bme280
+}
+
+#[test]
+fn init_resets_and_reads_calibration() {
+    let mock = MockBus::new(&init_script());
+    init(&mock);
+    assert_eq!(mock.delay_total_ms(), 2);
+}
+
+#[test]
+fn init_without_block_reads_uses_combined_transfers() {
+    let mock = MockBus::new(&[
+        T::set_slave(ADDR),
+        T::read_byte(REG_PART_ID, 0x60),
+        T::write_byte(REG_RESET, 0xb6),
+        T::write_bytes(ADDR, &[REG_TEMP_PRESS_CALIB_DATA]),
+        T::read_bytes(ADDR, &temp_press_calib_data()),
+        T::write_bytes(ADDR, &[REG_HUMIDITY_CALIB_DATA]),
+        T::read_bytes(ADDR, &humidity_calib_data()),
+    ]).with_functionality(Functionality::I2C | Functionality::SMBUS_BYTE_DATA);
+    init(&mock);
+}
+
+#[test]
+fn init_on_smbus_only_adapter_reads_byte_by_byte() {
+    let mut script = vec![
+        T::set_slave(ADDR),
+        T::read_byte(REG_PART_ID, 0x60),
+        T::write_byte(REG_RESET, 0xb6),
+    ];
+    for (offset, data) in temp_press_calib_data().into_iter().enumerate() {
+        script.push(T::read_byte(REG_TEMP_PRESS_CALIB_DATA + offset as u8, data));
+    }
+    for (offset, data) in humidity_calib_data().into_iter().enumerate() {
+        script.push(T::read_byte(REG_HUMIDITY_CALIB_DATA + offset as u8, data));
+    }
+    let mock = MockBus::new(&script).with_functionality(Functionality::SMBUS_BYTE_DATA);
+    init(&mock);
+}
+
+#[test]
+fn init_fails_on_adapter_without_block_read_fallback() {
+    let mock = MockBus::new(&[
+        T::set_slave(ADDR),
+        T::read_byte(REG_PART_ID, 0x60),
+        T::write_byte(REG_RESET, 0xb6),
+    ]).with_functionality(Functionality::SMBUS_WRITE_BYTE_DATA);
+    let err = BME280::with_bus(mock.clone(), Bme280DeviceAddress::Default).err().expect("init succeeded");
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert!(matches!(Error::from(err), Error::Unsupported(msg) if msg.starts_with("adapter lacks i2c block reads")));
+    mock.done();
+}
+
+#[test]
+fn init_rejects_unknown_chip_id() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x76),
+        T::read_byte(REG_PART_ID, 0x58),
+    ]);
+    let result = BME280::with_bus(mock.clone(), Bme280DeviceAddress::Secondary);
+    mock.done();
+    let err = Error::from(result.err().expect("init succeeded"));
+    assert!(matches!(err, Error::ChipId { expected: 0x60, found: 0x58 }), "{err:?}");
+}
+
+#[test]
+fn oversampling_and_config() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    mock.expect(&[
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        // -- ctrl_hum takes effect with the next ctrl_meas write
+        T::read_byte(REG_CTRL_MEAS, 0x27),
+        T::write_byte(REG_CTRL_MEAS, 0x27),
+        // -- keep mode bits, 16x pressure, 2x temperature
+        T::read_byte(REG_CTRL_MEAS, 0x03),
+        T::write_byte(REG_CTRL_MEAS, 0x57),
+        T::write_byte(REG_CONFIG, 0xb0),
+    ]);
+    bme280.set_osr_humidity(Bme280OverSampling::Oversampling1x).expect("write failed");
+    bme280.set_osr_pressure_temperature(Bme280OverSampling::Oversampling16x, Bme280OverSampling::Oversampling2x)
+        .expect("write failed");
+    bme280.set_sensor_config(Bme280TimeStandby::Ms1000, Bme280IrrFilter::Filter16x, Bme280Spi3w::Disable)
+        .expect("write failed");
+    mock.done();
+}
+
+#[test]
+fn sensor_mode() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    mock.expect(&[
+        // -- keep oversampling bits, set forced mode
+        T::read_byte(REG_CTRL_MEAS, 0x57),
+        T::write_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_STATUS, 0x08),
+        T::read_byte(REG_STATUS, 0x00),
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+    ]);
+    bme280.set_sensor_mode(Bme280SensorMode::Bme280PowerModeForced).expect("write failed");
+    assert!(matches!(bme280.get_sensor_mode().expect("read failed"), Bme280SensorMode::Bme280PowerModeForced));
+    assert!(bme280.is_measuring().expect("read failed"));
+    assert!(!bme280.is_measuring().expect("read failed"));
+    assert!(matches!(bme280.get_sensor_mode().expect("read failed"), Bme280SensorMode::Bme280PowerModeSleep));
+    mock.done();
+}
+
+#[test]
+fn with_config_writes_config_in_sleep_mode() {
+    let mut script = init_script();
+    script.extend([
+        T::read_byte(REG_CTRL_MEAS, 0x00),
+        T::write_byte(REG_CTRL_MEAS, 0x00),
+        // -- 62.5 ms standby, filter 16x
+        T::write_byte(REG_CONFIG, 0x30),
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        // -- temperature 2x, pressure 16x, normal mode
+        T::write_byte(REG_CTRL_MEAS, 0x57),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Bme280Config::default()
+        .with_oversampling(Bme280OverSampling::Oversampling1x, Bme280OverSampling::Oversampling16x,
+            Bme280OverSampling::Oversampling2x)
+        .with_irr_filter(Bme280IrrFilter::Filter16x)
+        .with_t_standby(Bme280TimeStandby::Ms62_5)
+        .with_sensor_mode(Bme280SensorMode::Bme280PowerModeNormal);
+    BME280::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn config_enables_spi3w() {
+    let mut script = init_script();
+    script.extend([
+        T::read_byte(REG_CTRL_MEAS, 0x00),
+        T::write_byte(REG_CTRL_MEAS, 0x00),
+        // -- 1000 ms standby, filter off, 3-wire spi
+        T::write_byte(REG_CONFIG, 0xa1),
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        T::write_byte(REG_CTRL_MEAS, 0x24),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Bme280Config::default().with_spi3w(Bme280Spi3w::Enable);
+    BME280::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn config_without_oversampling_is_rejected_before_any_transfer() {
+    let mock = MockBus::new(&[]);
+    let config = Bme280Config::default()
+        .with_oversampling(Bme280OverSampling::NoOversampling, Bme280OverSampling::NoOversampling,
+            Bme280OverSampling::NoOversampling)
+        .with_sensor_mode(Bme280SensorMode::Bme280PowerModeForced);
+    let err = BME280::with_config(mock.clone(), &config).err().expect("init succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+}
+
+#[test]
+fn data_read_and_compensation() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    mock.expect(&[
+        // -- data sheet example, adc_p = 415148, adc_t = 519888, adc_h = 0x6e5a
+        T::read_block(REG_DATA, &[0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x6e, 0x5a]),
+    ]);
+    bme280.get_sensor_data().expect("read failed");
+    mock.done();
+    let temperature = bme280.compensate_temperature_float();
+    assert!((temperature - 25.08).abs() < 0.01, "temperature {temperature}");
+    assert_eq!(bme280.compensate_temperature_fixed(), 25.08);
+    let pressure = bme280.compensate_pressure_float();
+    assert!((pressure - 100653.27).abs() < 0.1, "pressure {pressure}");
+    let humidity = bme280.compensate_humidity_float();
+    assert!((humidity - 42.20).abs() < 0.01, "humidity {humidity}");
+}
+
+#[test]
+fn typed_readings() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    mock.expect(&[
+        T::read_block(REG_DATA, &[0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x6e, 0x5a]),
+    ]);
+    bme280.get_sensor_data().expect("read failed");
+    mock.done();
+    let temperature = bme280.get_temperature();
+    assert!((temperature.celsius() - 25.08).abs() < 0.01, "temperature {temperature}");
+    let pressure = bme280.get_pressure();
+    assert!((pressure.hectopascal() - 1006.5327).abs() < 0.001, "pressure {pressure}");
+    let humidity = bme280.get_humidity();
+    assert!((humidity.percent() - 42.20).abs() < 0.01, "humidity {humidity}");
+}
+
+#[test]
+fn init_and_config_over_spi() {
+    // -- bit 7 of the register address turns into the read/write bit
+    let mock = MockSpi::new(&[
+        S::read(&[REG_PART_ID], &[0x60]),
+        S::write(&[REG_RESET & 0x7f, 0xb6]),
+        S::read(&[REG_TEMP_PRESS_CALIB_DATA], &temp_press_calib_data()),
+        S::read(&[REG_HUMIDITY_CALIB_DATA], &humidity_calib_data()),
+    ]);
+    let mut bme280 = BME280::with_spi(mock.clone()).expect("init failed");
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2);
+    mock.expect(&[
+        S::write(&[REG_CTRL_HUM & 0x7f, 0x01]),
+        S::read(&[REG_CTRL_MEAS], &[0x27]),
+        S::write(&[REG_CTRL_MEAS & 0x7f, 0x27]),
+        S::read(&[REG_DATA], &[0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x6e, 0x5a]),
+    ]);
+    bme280.set_osr_humidity(Bme280OverSampling::Oversampling1x).expect("write failed");
+    bme280.get_sensor_data().expect("read failed");
+    mock.done();
+    assert_eq!(bme280.compensate_temperature_fixed(), 25.08);
+}
+
+#[test]
+fn measure_triggers_forced_conversion() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    mock.expect(&[
+        // -- sleeping, set forced mode and poll until it drops back to sleep
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::write_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::read_block(REG_DATA, &[0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x6e, 0x5a]),
+    ]);
+    let measurement = bme280.measure().expect("measure failed");
+    mock.done();
+    assert!((measurement.temperature.unwrap().celsius() - 25.08).abs() < 0.01, "{measurement:?}");
+    assert!((measurement.pressure.unwrap().hectopascal() - 1006.5327).abs() < 0.001, "{measurement:?}");
+    assert!((measurement.humidity.unwrap().percent() - 42.20).abs() < 0.01, "{measurement:?}");
+    assert_eq!((measurement.gas_resistance, measurement.voc_index, measurement.aqi), (None, None, None));
+}
+
+#[test]
+fn measure_times_out_on_stuck_conversion() {
+    let mock = MockBus::new(&init_script());
+    let mut bme280 = init(&mock);
+    let mut script = vec![
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::write_byte(REG_CTRL_MEAS, 0x55),
+    ];
+    // -- polled every 10 ms for 500 ms
+    script.extend((0..51).map(|_| T::read_byte(REG_CTRL_MEAS, 0x55)));
+    mock.expect(&script);
+    let err = bme280.measure().expect_err("measure succeeded");
+    mock.done();
+    assert_eq!(err.to_string(), "Timed out: BME280 measurement not done after 500 ms");
+    assert!(matches!(Error::from(err), Error::Timeout(_)));
+}
@@ -0,0 +1,293 @@
+use futures_executor::block_on;
+use i2c_sensors::bme680::{BME680, BME680Async, Bme680Config, Bme680DeviceAddress, Bme680HeaterProfile, Bme680IrrFilter,
+    Bme680OverSampling};
+use i2c_sensors::error::Error;
+use i2c_sensors::mock::{MockBus, MockSpi, MockSpiTransfer as S, MockTransaction as T};
+
+const ADDR: u16 = 0x77;
+const REG_MEAS_STATUS_0: u8 = 0x1d;
+const REG_MEAS_RESULT_BASE: u8 = 0x1f;
+const REG_GAS_ACD_MSB: u8 = 0x2a;
+const REG_GAS_ACD_LSB_RANGE: u8 = 0x2b;
+const REG_RES_HEAT_BASE: u8 = 0x5a;
+const REG_GAS_WAIT_BASE: u8 = 0x64;
+const REG_CTRL_GAS_1: u8 = 0x71;
+const REG_CTRL_HUM: u8 = 0x72;
+const REG_CTRL_MEAS: u8 = 0x74;
+const REG_CONFIG: u8 = 0x75;
+const REG_CHIP_ID: u8 = 0xd0;
+const REG_RESET: u8 = 0xe0;
+const REG_CALIB_DATA1_BASE: u8 = 0x8a;
+const REG_CALIB_DATA2_BASE: u8 = 0xe1;
+const REG_CALIB_DATA3_BASE: u8 = 0x00;
+
+// -- the three calibration blocks concatenated, par_t1 = 25907, par_t2 = 26467, par_t3 = 3, all others zero
+fn calib_data() -> [u8; 42] {
+    let mut data = [0; 42];
+    data[0..2].copy_from_slice(&26467_u16.to_le_bytes());
+    data[2] = 3;
+    data[31..33].copy_from_slice(&25907_u16.to_le_bytes());
+    data
+}
+
+fn init_script() -> Vec<T> {
+    let calib_data = calib_data();
+    vec![
+        T::set_slave(ADDR),
+        T::read_byte(REG_CHIP_ID, 0x61),
+        T::read_block(REG_CALIB_DATA1_BASE, &calib_data[0..23]),
+        T::read_block(REG_CALIB_DATA2_BASE, &calib_data[23..37]),
+        T::read_block(REG_CALIB_DATA3_BASE, &calib_data[37..42]),
+        T::write_byte(REG_RESET, 0xb6),
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        // -- temperature 2x, pressure 16x, sleep mode
+        T::write_byte(REG_CTRL_MEAS, 0x54),
+        // -- filter coefficient 3
+        T::write_byte(REG_CONFIG, 0x08),
+    ]
+}
+
+fn init(mock: &MockBus) -> BME680<MockBus> {
+    let bme680 = BME680::with_bus(mock.clone(), Bme680DeviceAddress::Default, Bme680OverSampling::Oversampling1x,
+        Bme680OverSampling::Oversampling16x, Bme680OverSampling::Oversampling2x, Bme680IrrFilter::Coef3)
+        .expect("init failed");
+    mock.done();
+    bme680
+}
+
+#[test]
+fn init_reads_calibration_and_configures() {
+    let mock = MockBus::new(&init_script());
+    let bme680 = init(&mock);
+    assert_eq!(bme680.get_chip_id(), 0x61);
+    assert_eq!(mock.delay_total_ms(), 2);
+}
+
+#[test]
+fn init_rejects_unknown_chip_id() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x76),
+        T::read_byte(REG_CHIP_ID, 0x60),
+    ]);
+    let result = BME680::with_bus(mock.clone(), Bme680DeviceAddress::Secondary, Bme680OverSampling::Oversampling1x,
+        Bme680OverSampling::Oversampling1x, Bme680OverSampling::Oversampling1x, Bme680IrrFilter::FilterOff);
+    mock.done();
+    assert!(result.is_err());
+}
+
+#[test]
+fn with_config_sets_up_the_heater_profile() {
+    let mut script = init_script();
+    script.truncate(6);
+    script.extend([
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        // -- temperature 1x, pressure 1x, sleep mode
+        T::write_byte(REG_CTRL_MEAS, 0x24),
+        T::write_byte(REG_CONFIG, 0x00),
+        // -- 320 degree celsius with the heater calibration at zero
+        T::write_byte(REG_RES_HEAT_BASE + 1, 0xce),
+        // -- 150 ms as 38 times 4 ms
+        T::write_byte(REG_GAS_WAIT_BASE + 1, 0x66),
+        T::read_byte(REG_CTRL_GAS_1, 0x00),
+        T::write_byte(REG_CTRL_GAS_1, 0x01),
+        T::read_byte(REG_CTRL_GAS_1, 0x01),
+        T::write_byte(REG_CTRL_GAS_1, 0x11),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Bme680Config::default()
+        .with_heater(Bme680HeaterProfile::SetPoint1, 320.0, 150);
+    BME680::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn applied_configs_replace_the_heater_profile() {
+    let mut script = init_script();
+    script.truncate(6);
+    script.extend([
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        T::write_byte(REG_CTRL_MEAS, 0x24),
+        T::write_byte(REG_CONFIG, 0x00),
+        T::write_byte(REG_RES_HEAT_BASE + 1, 0xce),
+        T::write_byte(REG_GAS_WAIT_BASE + 1, 0x66),
+        T::read_byte(REG_CTRL_GAS_1, 0x00),
+        T::write_byte(REG_CTRL_GAS_1, 0x01),
+        T::read_byte(REG_CTRL_GAS_1, 0x01),
+        T::write_byte(REG_CTRL_GAS_1, 0x11),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Bme680Config::default()
+        .with_heater(Bme680HeaterProfile::SetPoint1, 320.0, 150);
+    let mut bme680 = BME680::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+    // -- set point 1 is replaced by set point 2, not merged into set point 3
+    mock.expect(&[
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        T::write_byte(REG_CTRL_MEAS, 0x24),
+        T::write_byte(REG_CONFIG, 0x00),
+        T::write_byte(REG_RES_HEAT_BASE + 2, 0xce),
+        T::write_byte(REG_GAS_WAIT_BASE + 2, 0x66),
+        T::read_byte(REG_CTRL_GAS_1, 0x11),
+        T::write_byte(REG_CTRL_GAS_1, 0x12),
+        T::read_byte(REG_CTRL_GAS_1, 0x12),
+        T::write_byte(REG_CTRL_GAS_1, 0x12),
+    ]);
+    let config = Bme680Config::default()
+        .with_heater(Bme680HeaterProfile::SetPoint2, 320.0, 150);
+    bme680.apply_config(&config).expect("apply failed");
+    mock.done();
+    // -- without a heater the profile goes back to set point 0 and the gas measurement is off
+    mock.expect(&[
+        T::write_byte(REG_CTRL_HUM, 0x01),
+        T::write_byte(REG_CTRL_MEAS, 0x24),
+        T::write_byte(REG_CONFIG, 0x00),
+        T::read_byte(REG_CTRL_GAS_1, 0x12),
+        T::write_byte(REG_CTRL_GAS_1, 0x10),
+        T::read_byte(REG_CTRL_GAS_1, 0x10),
+        T::write_byte(REG_CTRL_GAS_1, 0x00),
+    ]);
+    bme680.apply_config(&Bme680Config::default()).expect("apply failed");
+    mock.done();
+}
+
+#[test]
+fn config_with_heater_out_of_range_is_rejected_before_any_transfer() {
+    let mock = MockBus::new(&[]);
+    let config = Bme680Config::default()
+        .with_heater(Bme680HeaterProfile::SetPoint0, 450.0, 150);
+    let err = BME680::with_config(mock.clone(), &config).err().expect("init succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+    let config = Bme680Config::default()
+        .with_heater(Bme680HeaterProfile::SetPoint0, 320.0, 5000);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn forced_mode_and_run_gas_keep_other_bits() {
+    let mock = MockBus::new(&init_script());
+    let mut bme680 = init(&mock);
+    mock.expect(&[
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::write_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_CTRL_GAS_1, 0x02),
+        T::write_byte(REG_CTRL_GAS_1, 0x12),
+        T::read_byte(REG_CTRL_GAS_1, 0x12),
+        T::write_byte(REG_CTRL_GAS_1, 0x02),
+    ]);
+    bme680.set_forced_mode().expect("write failed");
+    bme680.enable_run_gas().expect("write failed");
+    bme680.disable_run_gas().expect("write failed");
+    mock.done();
+}
+
+#[test]
+fn measurement_status_and_result() {
+    let mock = MockBus::new(&init_script());
+    let mut bme680 = init(&mock);
+    mock.expect(&[
+        T::read_byte(REG_MEAS_STATUS_0, 0xa3),
+        T::read_block(REG_MEAS_RESULT_BASE, &[0x12, 0x34, 0x50, 0x7a, 0x5c, 0x00, 0x56, 0x78]),
+    ]);
+    let status = bme680.get_meas_status().expect("read failed");
+    assert!(status.new_data);
+    assert!(!status.gas_measuring);
+    assert!(status.measuring);
+    assert_eq!(status.gas_meas_index, 3);
+    let result = bme680.get_meas_result().expect("read failed");
+    mock.done();
+    assert_eq!(result.pressure_raw, 0x12345);
+    assert_eq!(result.temperature_raw, 0x7a5c0);
+    assert_eq!(result.humidity_raw, 0x5678);
+    let (temperature, _t_fine) = bme680.get_temperature(result.temperature_raw);
+    assert!((temperature.celsius() - 27.35).abs() < 0.01, "temperature {temperature}");
+}
+
+#[test]
+fn gas_measurement_result() {
+    let mock = MockBus::new(&init_script());
+    let mut bme680 = init(&mock);
+    mock.expect(&[
+        // -- gas adc 612, valid, heater stable, range 4
+        T::read_byte(REG_GAS_ACD_MSB, 0x99),
+        T::read_byte(REG_GAS_ACD_LSB_RANGE, 0x34),
+    ]);
+    let result = bme680.get_gas_meas_result().expect("read failed");
+    mock.done();
+    assert!(result.gas_valid);
+    assert!(result.heat_stab);
+    assert!((result.gas_res.ohms() - 464812.96).abs() < 0.01, "gas resistance {}", result.gas_res);
+}
+
+#[test]
+fn async_init_and_measurement() {
+    let mut script = init_script();
+    script.extend([
+        T::read_byte(REG_CTRL_MEAS, 0x54),
+        T::write_byte(REG_CTRL_MEAS, 0x55),
+        T::read_byte(REG_MEAS_STATUS_0, 0x80),
+        T::read_block(REG_MEAS_RESULT_BASE, &[0x12, 0x34, 0x50, 0x7a, 0x5c, 0x00, 0x56, 0x78]),
+    ]);
+    let mock = MockBus::new(&script);
+    let (bme680, new_data, result) = block_on(async {
+        let mut bme680 = BME680Async::with_bus(mock.clone(), Bme680DeviceAddress::Default, Bme680OverSampling::Oversampling1x,
+            Bme680OverSampling::Oversampling16x, Bme680OverSampling::Oversampling2x, Bme680IrrFilter::Coef3)
+            .await.expect("init failed");
+        bme680.set_forced_mode().await.expect("write failed");
+        let status = bme680.get_meas_status().await.expect("read failed");
+        let result = bme680.get_meas_result().await.expect("read failed");
+        (bme680, status.new_data, result)
+    });
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2);
+    assert!(new_data);
+    let (temperature, _t_fine) = bme680.get_temperature(result.temperature_raw);
+    assert!((temperature.celsius() - 27.35).abs() < 0.01, "temperature {temperature}");
+}
+
+// -- spi_mem_page in the status register, switched by read-modify-write
+fn select_page(from: u8, to: u8) -> Vec<S> {
+    vec![
+        S::read(&[0xf3], &[from << 4]),
+        S::write(&[0x73, to << 4]),
+    ]
+}
+
+#[test]
+fn init_over_spi_switches_memory_pages() {
+    let calib_data = calib_data();
+    // -- registers 0x80 - 0xff live in page 0, 0x00 - 0x7f in page 1, at 7 bit spi addresses
+    let mut script = select_page(1, 0);
+    script.extend([
+        S::read(&[REG_CHIP_ID], &[0x61]),
+        S::read(&[REG_CALIB_DATA1_BASE], &calib_data[0..23]),
+        S::read(&[REG_CALIB_DATA2_BASE], &calib_data[23..37]),
+    ]);
+    script.extend(select_page(0, 1));
+    script.extend([
+        S::read(&[0x80 | REG_CALIB_DATA3_BASE], &calib_data[37..42]),
+    ]);
+    script.extend(select_page(1, 0));
+    script.extend([
+        S::write(&[REG_RESET & 0x7f, 0xb6]),
+    ]);
+    script.extend(select_page(0, 1));
+    script.extend([
+        S::write(&[REG_CTRL_HUM, 0x01]),
+        S::write(&[REG_CTRL_MEAS, 0x54]),
+        S::write(&[REG_CONFIG, 0x08]),
+    ]);
+    let mock = MockSpi::new(&script);
+    let mut bme680 = BME680::with_spi(mock.clone(), Bme680OverSampling::Oversampling1x,
+        Bme680OverSampling::Oversampling16x, Bme680OverSampling::Oversampling2x, Bme680IrrFilter::Coef3)
+        .expect("init failed");
+    mock.done();
+    assert_eq!(bme680.get_chip_id(), 0x61);
+    // -- no switch while the page stays the same
+    mock.expect(&[
+        S::read(&[0x80 | REG_CTRL_MEAS], &[0x54]),
+        S::write(&[REG_CTRL_MEAS, 0x55]),
+    ]);
+    bme680.set_forced_mode().expect("write failed");
+    mock.done();
+}
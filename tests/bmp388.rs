@@ -0,0 +1,239 @@
+mod common;
+
+use common::{bmp388_init_script, bmp388_trimming_coefficients, init_bmp388};
+use i2c_sensors::bmp388::*;
+use i2c_sensors::error::Error;
+use i2c_sensors::mock::{MockBus, MockSpi, MockSpiTransfer as S, MockTransaction as T};
+
+const REG_CHIP_ID: u8 = 0x00;
+const REG_STATUS: u8 = 0x03;
+const REG_PRESSURE_DATA: u8 = 0x04;
+const REG_FIFO_LENGTH: u8 = 0x12;
+const REG_FIFO_DATA: u8 = 0x14;
+const REG_FIFO_CONFIG_1: u8 = 0x17;
+const REG_FIFO_CONFIG_2: u8 = 0x18;
+const REG_POWER_CONTROL: u8 = 0x1b;
+const REG_OVERSAMPLING_RATE: u8 = 0x1c;
+const REG_OUTPUT_DATA_RATE: u8 = 0x1d;
+const REG_CONFIG: u8 = 0x1f;
+const REG_TRIMMING_COEFFICIENTS: u8 = 0x31;
+const REG_CMD: u8 = 0x7e;
+
+fn init_with_fifo() -> (MockBus, BMP388<MockBus>) {
+    let mock = MockBus::new(&bmp388_init_script());
+    let mut bmp388 = init_bmp388(&mock);
+    mock.expect(&[
+        T::write_byte(REG_CMD, 0xb0),
+        // -- filtered data, subsampling 2
+        T::write_byte(REG_FIFO_CONFIG_2, 0x0a),
+        // -- temperature, pressure, sensor time, enabled
+        T::write_byte(REG_FIFO_CONFIG_1, 0x1d),
+    ]);
+    bmp388.enable_fifo(Bmp388FifoStopOnFull::Disabled, Bmp388FifoWithPressureData::Enabled,
+        Bmp388FifoWithTemperatureData::Enabled, Bmp388FifoWithSensorTime::Enabled,
+        Bmp388FifoDataFiltered::Filtered, 2).expect("enable FIFO failed");
+    mock.done();
+    (mock, bmp388)
+}
+
+#[test]
+fn init_configures_sensor() {
+    let mock = MockBus::new(&bmp388_init_script());
+    init_bmp388(&mock);
+    assert_eq!(mock.delay_total_ms(), 2);
+}
+
+#[test]
+fn init_rejects_unknown_chip_id() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x76),
+        T::read_byte(REG_CHIP_ID, 0x60),
+    ]);
+    let result = BMP388::with_bus(mock.clone(), Bmp388DeviceAddress::Secondary, Bmp388OverSamplingPr::StandardX4,
+        Bmp388OverSamplingTp::X2, Bmp388IrrFilter::Coef3, Bmp388OutputDataRate::Fx6_25Hz);
+    mock.done();
+    assert!(result.is_err());
+}
+
+#[test]
+fn with_config_starts_normal_mode_with_fifo_last() {
+    let mut script = bmp388_init_script();
+    script.truncate(4);
+    script.extend([
+        // -- sleep with pressure and temperature enabled
+        T::write_byte(REG_POWER_CONTROL, 0x03),
+        T::write_byte(REG_OVERSAMPLING_RATE, 0x0a),
+        T::write_byte(REG_CONFIG, 0x02),
+        T::write_byte(REG_OUTPUT_DATA_RATE, 0x05),
+        T::write_byte(REG_CMD, 0xb0),
+        T::write_byte(REG_FIFO_CONFIG_2, 0x0a),
+        T::write_byte(REG_FIFO_CONFIG_1, 0x1d),
+        T::write_byte(REG_POWER_CONTROL, 0x33),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Bmp388Config::default()
+        .with_oversampling(Bmp388OverSamplingPr::StandardX4, Bmp388OverSamplingTp::X2)
+        .with_irr_filter(Bmp388IrrFilter::Coef3)
+        .with_output_data_rate(Bmp388OutputDataRate::Fx6_25Hz)
+        .with_sensor_mode(Bmp388SensorPowerMode::Normal, Bmp388StatusPressureSensor::Enabled,
+            Bmp388StatusTemperatureSensor::Enabled)
+        .with_fifo(Bmp388FifoStopOnFull::Disabled, Bmp388FifoWithPressureData::Enabled,
+            Bmp388FifoWithTemperatureData::Enabled, Bmp388FifoWithSensorTime::Enabled,
+            Bmp388FifoDataFiltered::Filtered, 2);
+    BMP388::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn config_with_conversion_longer_than_odr_period_is_rejected() {
+    let mock = MockBus::new(&[]);
+    // -- 32x pressure and temperature take about 130 ms, longer than the 5 ms at 200 Hz
+    let config = Bmp388Config::default()
+        .with_oversampling(Bmp388OverSamplingPr::HighestX32, Bmp388OverSamplingTp::X32)
+        .with_sensor_mode(Bmp388SensorPowerMode::Normal, Bmp388StatusPressureSensor::Enabled,
+            Bmp388StatusTemperatureSensor::Enabled);
+    let err = BMP388::with_config(mock.clone(), &config).err().expect("init succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+    // -- fits at 6.25 Hz, forced mode has no ODR to keep up with
+    assert!(config.clone().with_output_data_rate(Bmp388OutputDataRate::Fx6_25Hz).validate().is_ok());
+    assert!(config.with_sensor_mode(Bmp388SensorPowerMode::Forced, Bmp388StatusPressureSensor::Enabled,
+        Bmp388StatusTemperatureSensor::Enabled).validate().is_ok());
+}
+
+#[test]
+fn sensor_mode_and_status() {
+    let mock = MockBus::new(&bmp388_init_script());
+    let mut bmp388 = init_bmp388(&mock);
+    mock.expect(&[
+        T::write_byte(REG_POWER_CONTROL, 0x33),
+        T::read_byte(REG_POWER_CONTROL, 0x31),
+        T::read_byte(REG_STATUS, 0x50),
+    ]);
+    bmp388.set_sensor_mode(Bmp388SensorPowerMode::Normal, Bmp388StatusPressureSensor::Enabled,
+        Bmp388StatusTemperatureSensor::Enabled).expect("write failed");
+    let (power_mode, pressure, temperature) = bmp388.get_sensor_mode().expect("read failed");
+    assert!(power_mode == Bmp388SensorPowerMode::Normal);
+    assert!(pressure == Bmp388StatusPressureSensor::Enabled);
+    assert!(temperature == Bmp388StatusTemperatureSensor::Disabled);
+    let (cmd_decoder, pressure_data, temperature_data) = bmp388.get_status().expect("read failed");
+    assert!(cmd_decoder == Bmp388StatusCommandDecoder::Ready);
+    assert!(pressure_data == Bmp388StatusPressureData::NotReady);
+    assert!(temperature_data == Bmp388StatusTemperatureData::Ready);
+    mock.done();
+}
+
+#[test]
+fn data_read_and_temperature() {
+    let mock = MockBus::new(&bmp388_init_script());
+    let mut bmp388 = init_bmp388(&mock);
+    mock.expect(&[
+        // -- pressure and temperature, least significant byte first
+        T::read_block(REG_PRESSURE_DATA, &[0x56, 0x34, 0x12, 0x80, 0x2c, 0x80]),
+    ]);
+    let data_raw = bmp388.get_data_raw().expect("read failed");
+    mock.done();
+    assert_eq!(data_raw.pressure, 0x123456);
+    assert_eq!(data_raw.temperature, 0x802c80);
+    let temperature = bmp388.get_temperature(data_raw.temperature);
+    assert!((temperature.celsius() - 26.27).abs() < 0.01, "temperature {temperature}");
+}
+
+#[test]
+fn fifo_pressure_temperature_frame() {
+    let (mock, mut bmp388) = init_with_fifo();
+    mock.expect(&[
+        T::read_byte(REG_FIFO_DATA, 0x94),
+        T::read_word(REG_FIFO_LENGTH, 14),
+        T::read_block(REG_FIFO_DATA, &[0x94, 0x80, 0x2c, 0x80, 0x56, 0x34, 0x12]),
+    ]);
+    let fifo_data = bmp388.read_next_fifo_data_frame().expect("read failed");
+    mock.done();
+    assert_eq!(fifo_data.temperature_raw, Some(0x802c80));
+    assert_eq!(fifo_data.pressure_raw, Some(0x123456));
+    assert_eq!(fifo_data.sensor_time, None);
+    assert!(!fifo_data.config_change);
+}
+
+#[test]
+fn fifo_config_error_frame() {
+    let (mock, mut bmp388) = init_with_fifo();
+    mock.expect(&[
+        T::read_byte(REG_FIFO_DATA, 0x44),
+        T::read_word(REG_FIFO_DATA, 0x0000),
+    ]);
+    let err = bmp388.read_next_fifo_data_frame().expect_err("read succeeded");
+    mock.done();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(matches!(Error::from(err), Error::Fifo(_)));
+}
+
+#[test]
+fn fifo_last_frame_carries_sensor_time() {
+    let (mock, mut bmp388) = init_with_fifo();
+    mock.expect(&[
+        T::read_byte(REG_FIFO_DATA, 0x94),
+        T::read_word(REG_FIFO_LENGTH, 7),
+        T::read_block(REG_FIFO_DATA, &[0x94, 0x80, 0x2c, 0x80, 0x56, 0x34, 0x12, 0xa0, 0x03, 0x02, 0x01]),
+    ]);
+    let fifo_data = bmp388.read_next_fifo_data_frame().expect("read failed");
+    mock.done();
+    assert_eq!(fifo_data.pressure_raw, Some(0x123456));
+    assert_eq!(fifo_data.sensor_time, Some(0x010203));
+}
+
+#[test]
+fn fifo_short_frame_drops_pressure() {
+    let (mock, mut bmp388) = init_with_fifo();
+    mock.expect(&[
+        T::read_byte(REG_FIFO_DATA, 0x94),
+        T::read_word(REG_FIFO_LENGTH, 4),
+        T::read_block(REG_FIFO_DATA, &[0x94, 0x80, 0x2c, 0x80]),
+    ]);
+    let fifo_data = bmp388.read_next_fifo_data_frame().expect("read failed");
+    mock.done();
+    assert_eq!(fifo_data.temperature_raw, Some(0x802c80));
+    assert_eq!(fifo_data.pressure_raw, None);
+}
+
+#[test]
+fn fifo_control_frames() {
+    let (mock, mut bmp388) = init_with_fifo();
+    mock.expect(&[
+        T::read_byte(REG_FIFO_DATA, 0x48),
+        T::read_word(REG_FIFO_DATA, 0x0000),
+        T::read_byte(REG_FIFO_DATA, 0x44),
+        T::read_word(REG_FIFO_DATA, 0x0000),
+    ]);
+    let fifo_data = bmp388.read_next_fifo_data_frame().expect("read failed");
+    assert!(fifo_data.config_change);
+    assert!(bmp388.read_next_fifo_data_frame().is_err());
+    mock.done();
+}
+
+#[test]
+fn init_over_spi_skips_dummy_byte() {
+    // -- reads set bit 7 and return a dummy byte ahead of the data
+    let mock = MockSpi::new(&[
+        S::read(&[0x80 | REG_CHIP_ID, 0x00], &[0x50]),
+        S::write(&[REG_CMD, 0xb6]),
+        S::read(&[0x80 | REG_TRIMMING_COEFFICIENTS, 0x00], &bmp388_trimming_coefficients()),
+        S::write(&[REG_OVERSAMPLING_RATE, 0x0a]),
+        S::write(&[REG_CONFIG, 0x02]),
+        S::write(&[REG_OUTPUT_DATA_RATE, 0x05]),
+    ]);
+    BMP388::with_spi(mock.clone(), Bmp388OverSamplingPr::StandardX4, Bmp388OverSamplingTp::X2,
+        Bmp388IrrFilter::Coef3, Bmp388OutputDataRate::Fx6_25Hz).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn init_over_spi_rejects_unknown_chip_id() {
+    let mock = MockSpi::new(&[
+        S::read(&[0x80 | REG_CHIP_ID, 0x00], &[0xff]),
+    ]);
+    let result = BMP388::with_spi(mock.clone(), Bmp388OverSamplingPr::StandardX4, Bmp388OverSamplingTp::X2,
+        Bmp388IrrFilter::Coef3, Bmp388OutputDataRate::Fx6_25Hz);
+    mock.done();
+    assert!(result.is_err());
+}
@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use i2c_sensors::bus_lock::LockedBus;
+use i2c_sensors::error::Error;
+use i2c_sensors::i2cio::{self, I2cBus};
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use tempfile::TempDir;
+
+// -- what another process does to the lock file, through a descriptor of its own
+fn flock(file: &File, operation: libc::c_int) -> bool {
+    // -- SAFETY: the descriptor is owned by file and stays open for the call
+    unsafe { libc::flock(file.as_raw_fd(), operation) == 0 }
+}
+
+fn lock_is_free(file: &File) -> bool {
+    let free = flock(file, libc::LOCK_EX | libc::LOCK_NB);
+    if free {
+        flock(file, libc::LOCK_UN);
+    }
+    free
+}
+
+#[test]
+fn busy_bus_times_out_with_path_and_wait() {
+    let dir = TempDir::new().unwrap();
+    let lock_path = dir.path().join("i2c-1.lock");
+    let mock = MockBus::new(&[]);
+    let mut bus = LockedBus::new(mock.clone(), &lock_path, Duration::from_millis(20)).expect("open failed");
+    let other = File::open(&lock_path).unwrap();
+    assert!(flock(&other, libc::LOCK_EX));
+
+    let err = i2cio::read_byte(&mut bus, 0x10).expect_err("read succeeded");
+    // -- nothing reached the bus, the waits went through the bus clock
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 20);
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert_eq!(err.to_string(), format!("Timed out: I2C bus '{}' is still locked by another process after 20 ms", lock_path.display()));
+    assert!(matches!(Error::from(err), Error::Timeout(msg) if msg.ends_with("after 20 ms")));
+
+    // -- the transfer goes through once the other process lets go
+    assert!(flock(&other, libc::LOCK_UN));
+    mock.expect(&[T::read_byte(0x10, 0x42)]);
+    assert_eq!(i2cio::read_byte(&mut bus, 0x10).expect("read failed"), 0x42);
+    mock.done();
+}
+
+#[test]
+fn single_transfer_releases_the_lock() {
+    let dir = TempDir::new().unwrap();
+    let lock_path = dir.path().join("i2c-1.lock");
+    let mock = MockBus::new(&[T::write_byte(0x10, 0x01)]);
+    let mut bus = LockedBus::new(mock.clone(), &lock_path, Duration::from_millis(20)).expect("open failed");
+    let other = File::open(&lock_path).unwrap();
+    i2cio::write_byte(&mut bus, 0x10, 0x01).expect("write failed");
+    mock.done();
+    assert!(lock_is_free(&other));
+}
+
+#[test]
+fn nested_transactions_hold_the_lock_until_outermost_end() {
+    let dir = TempDir::new().unwrap();
+    let lock_path = dir.path().join("i2c-1.lock");
+    let mock = MockBus::new(&[
+        T::write_byte(0x10, 0x01),
+        T::read_byte(0x11, 0x42),
+    ]);
+    let mut bus = LockedBus::new(mock.clone(), &lock_path, Duration::from_millis(20)).expect("open failed");
+    let other = File::open(&lock_path).unwrap();
+
+    bus.begin_transaction().expect("begin failed");
+    assert!(!lock_is_free(&other));
+    bus.begin_transaction().expect("begin failed");
+    // -- transfers inside the transaction don't give the lock up either
+    i2cio::write_byte(&mut bus, 0x10, 0x01).expect("write failed");
+    assert_eq!(i2cio::read_byte(&mut bus, 0x11).expect("read failed"), 0x42);
+    assert!(!lock_is_free(&other));
+    bus.end_transaction().expect("end failed");
+    assert!(!lock_is_free(&other));
+    bus.end_transaction().expect("end failed");
+    assert!(lock_is_free(&other));
+    mock.done();
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use common::{SHT31_ADDR, sht31_init_script, sht31_measure_script, tmp117_init_script};
+use i2c_sensors::bus_stats::StatsBus;
+use i2c_sensors::i2cio::RetryPolicy;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::retry::RetryBus;
+use i2c_sensors::sensirion_crc::CrcPolicy;
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress, SHT31SingleShotAcquisition};
+use i2c_sensors::tmp117::{TMP117, Tmp117Averaging, Tmp117ConversionCycleTime, Tmp117DeviceAddress, Tmp117SensorMode};
+
+#[test]
+fn counts_reads_writes_and_bytes() {
+    let mock = MockBus::new(&sht31_init_script());
+    let bus = StatsBus::new(mock.clone());
+    let stats = bus.handle();
+    let mut sht31 = SHT31::with_bus(bus, SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    let snapshot = stats.snapshot();
+    assert_eq!((snapshot.reads(), snapshot.writes()), (1, 2));
+    assert_eq!((snapshot.bytes_read(), snapshot.bytes_written()), (3, 2));
+    assert_eq!(snapshot.error_count(), 0);
+
+    stats.reset();
+    mock.expect(&sht31_measure_script());
+    sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed");
+    mock.done();
+    let snapshot = stats.snapshot();
+    assert_eq!((snapshot.reads(), snapshot.writes()), (1, 1));
+    assert_eq!((snapshot.bytes_read(), snapshot.bytes_written()), (6, 1));
+}
+
+#[test]
+fn counts_errors_by_kind_and_retries() {
+    let mock = MockBus::new(&sht31_init_script());
+    let bus = StatsBus::new(mock.clone());
+    let stats = bus.handle();
+    let mut sht31 = SHT31::with_bus(RetryBus::new(bus, RetryPolicy::new(3, 1)), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    mock.done();
+    stats.reset();
+    mock.expect(&[
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0; 6]).with_error(ErrorKind::TimedOut),
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed");
+    mock.done();
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.retries(), 1);
+    assert_eq!(snapshot.errors().get(&ErrorKind::TimedOut), Some(&1));
+    assert_eq!((snapshot.reads(), snapshot.writes()), (2, 2));
+    assert_eq!(snapshot.bytes_read(), 6);
+}
+
+#[test]
+fn counts_crc_failures() {
+    let mock = MockBus::new(&sht31_init_script());
+    let bus = StatsBus::new(mock.clone());
+    let stats = bus.handle();
+    let mut sht31 = SHT31::with_bus(bus, SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    mock.expect(&[
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x00, 0x80, 0x00, 0x00]),
+    ]);
+    sht31.set_crc_policy(CrcPolicy::Warn);
+    sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed");
+    mock.done();
+    assert_eq!(stats.snapshot().crc_failures(), 2);
+}
+
+#[test]
+fn devices_on_one_bus_keep_their_own_counters() {
+    let mock = MockBus::new(&sht31_init_script());
+    let sht31_bus = StatsBus::new(mock.clone());
+    let sht31_stats = sht31_bus.handle();
+    let _sht31 = SHT31::with_bus(sht31_bus, SHT31DeviceAddress::Default).expect("init failed");
+    mock.expect(&tmp117_init_script());
+    let tmp117_bus = StatsBus::new(mock.clone());
+    let tmp117_stats = tmp117_bus.handle();
+    let _tmp117 = TMP117::with_bus(tmp117_bus, Tmp117DeviceAddress::Default, &Tmp117SensorMode::ModeContinuousConversion,
+        &Tmp117ConversionCycleTime::Ms1000, &Tmp117Averaging::Averaging8Conversions).expect("init failed");
+    mock.done();
+    assert_eq!(sht31_stats.snapshot().reads(), 1);
+    assert_eq!(tmp117_stats.snapshot().reads(), 2);
+    assert_eq!(tmp117_stats.snapshot().writes(), 2);
+}
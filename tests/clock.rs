@@ -0,0 +1,75 @@
+mod common;
+
+use std::time::Duration;
+
+use common::sht31_reset_script;
+use i2c_sensors::clock::{Clock, ClockedBus, VirtualClock};
+use i2c_sensors::i2cio;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::shared_bus::SharedBus;
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress};
+use i2c_sensors::tca9548a::{TCA9548A, Tca9548aChannel, Tca9548aDeviceAddress};
+
+#[test]
+fn virtual_clock_advances_on_waits_only() {
+    let mut clock = VirtualClock::new();
+    let shared = clock.clone();
+    assert_eq!(clock.now(), Duration::ZERO);
+    clock.delay_ms(1500);
+    shared.advance(Duration::from_secs(3600));
+    assert_eq!(shared.now(), Duration::from_millis(3_601_500));
+}
+
+#[test]
+fn driver_waits_go_to_the_clock() {
+    let mock = MockBus::new(&sht31_reset_script());
+    let clock = VirtualClock::new();
+    let bus = ClockedBus::new(mock.clone(), clock.clone());
+    let _sht31 = SHT31::with_bus(bus, SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    // -- the soft reset wait went to the virtual clock instead of the bus
+    assert_eq!(clock.now(), Duration::from_millis(2));
+    assert_eq!(mock.delay_total_ms(), 0);
+}
+
+#[test]
+fn bus_reports_the_clock_time() {
+    let clock = VirtualClock::new();
+    let mut bus = ClockedBus::new(MockBus::new(&[]), clock.clone());
+    clock.advance(Duration::from_secs(10));
+    i2cio::delay(&mut bus, 250);
+    assert_eq!(i2cio::now(&bus), Duration::from_millis(10_250));
+}
+
+#[test]
+fn shared_bus_devices_use_the_clock_of_the_shared_bus() {
+    let mock = MockBus::new(&sht31_reset_script());
+    let clock = VirtualClock::new();
+    let shared_bus = SharedBus::with_clock(mock.clone(), clock.clone());
+    let _sht31 = SHT31::with_bus(shared_bus.device(), SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    assert_eq!(clock.now(), Duration::from_millis(2));
+    assert_eq!(mock.delay_total_ms(), 0);
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(i2cio::now(&shared_bus.device()), Duration::from_millis(10_002));
+}
+
+#[test]
+fn mux_channels_use_the_clock_of_the_mux() {
+    let mut script = vec![
+        T::set_slave(0x70),
+        T::write_byte_single(0x00),
+        T::set_slave(0x70),
+        T::write_byte_single(0x01),
+    ];
+    script.extend(sht31_reset_script());
+    let mock = MockBus::new(&script);
+    let clock = VirtualClock::new();
+    let mux = TCA9548A::with_clock(mock.clone(), Tca9548aDeviceAddress::Default, clock.clone())
+        .expect("init failed");
+    let _sht31 = SHT31::with_bus(mux.channel(Tca9548aChannel::Channel0), SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    assert_eq!(clock.now(), Duration::from_millis(2));
+    assert_eq!(mock.delay_total_ms(), 0);
+    assert_eq!(i2cio::now(&mux.channel(Tca9548aChannel::Channel1)), Duration::from_millis(2));
+}
@@ -0,0 +1,229 @@
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use i2c_linux::Message;
+use i2c_sensors::bmp388::{BMP388, Bmp388DeviceAddress, Bmp388IrrFilter, Bmp388OutputDataRate, Bmp388OverSamplingPr,
+    Bmp388OverSamplingTp};
+use i2c_sensors::i2cio::I2cBus;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sensirion_crc;
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress};
+use i2c_sensors::tmp117::{TMP117, Tmp117Averaging, Tmp117ConversionCycleTime, Tmp117DeviceAddress, Tmp117SensorMode};
+
+pub const MUX_ADDR: u16 = 0x70;
+pub const SHT31_ADDR: u16 = 0x44;
+pub const TMP117_ADDR: u16 = 0x48;
+pub const BMP388_ADDR: u16 = 0x77;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transfer {
+    // -- mux control register written
+    Select(u8),
+    // -- command sent to the sensor at channel and address
+    Command(u8, u16),
+    // -- response read from the sensor at channel and address
+    Response(u8, u16),
+}
+
+pub type TransferLog = Arc<Mutex<Vec<Transfer>>>;
+
+// -- SHT31s on a real bus, unlike MockBus it can be moved to another thread; every sensor
+// -- answers with its channel and address as data, so reads that went astray show up,
+// -- and every transfer ends up in a log shared with the test
+pub struct Sht31Board {
+    log: TransferLog,
+    // -- mux channels selected, 0 on a bus without mux
+    channels: u8,
+    addr: u16,
+    // -- sensors that got a command and have a response ready
+    pending: HashSet<(u8, u16)>,
+}
+
+impl Sht31Board {
+
+    pub fn new(log: &TransferLog) -> Self {
+        Sht31Board {
+            log: Arc::clone(log),
+            channels: 0,
+            addr: 0,
+            pending: HashSet::new(),
+        }
+    }
+
+    // -- the data every sensor answers with, which is also what a driver reads as raw value
+    pub fn raw_value(channels: u8, addr: u16) -> u16 {
+        ((channels as u16) << 8) | addr
+    }
+
+    fn push(&self, transfer: Transfer) {
+        self.log.lock().unwrap().push(transfer);
+    }
+}
+
+fn unsupported() -> Result<(), std::io::Error> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+}
+
+impl I2cBus for Sht31Board {
+
+    fn set_slave(&mut self, dev_addr: u16) -> Result<(), std::io::Error> {
+        self.addr = dev_addr;
+        Ok(())
+    }
+
+    fn smbus_read_byte_data(&mut self, _register: u8) -> Result<u8, std::io::Error> {
+        unsupported().map(|_| 0)
+    }
+
+    fn smbus_read_word_data(&mut self, _register: u8) -> Result<u16, std::io::Error> {
+        unsupported().map(|_| 0)
+    }
+
+    fn smbus_write_byte(&mut self, data: u8) -> Result<(), std::io::Error> {
+        if self.addr != MUX_ADDR {
+            return unsupported();
+        }
+        self.channels = data;
+        self.push(Transfer::Select(data));
+        Ok(())
+    }
+
+    fn smbus_write_byte_data(&mut self, _register: u8, _data: u8) -> Result<(), std::io::Error> {
+        self.pending.insert((self.channels, self.addr));
+        self.push(Transfer::Command(self.channels, self.addr));
+        Ok(())
+    }
+
+    fn smbus_write_word_data(&mut self, _register: u8, _data: u16) -> Result<(), std::io::Error> {
+        unsupported()
+    }
+
+    fn smbus_write_block_data(&mut self, _register: u8, _data: &[u8]) -> Result<(), std::io::Error> {
+        unsupported()
+    }
+
+    fn i2c_read_block_data(&mut self, _register: u8, _data: &mut [u8]) -> Result<usize, std::io::Error> {
+        unsupported().map(|_| 0)
+    }
+
+    fn i2c_transfer(&mut self, messages: &mut [Message]) -> Result<(), std::io::Error> {
+        for message in messages.iter_mut() {
+            let Message::Read { address, data, .. } = message else {
+                return unsupported();
+            };
+            if !self.pending.remove(&(self.channels, *address)) {
+                // -- nothing measured, the sensor does not acknowledge
+                return Err(std::io::Error::other("Read without command"));
+            }
+            let value = Self::raw_value(self.channels, *address).to_be_bytes();
+            for word in data.chunks_mut(3) {
+                word[..2].copy_from_slice(&value);
+                word[2] = sensirion_crc::crc8(&value);
+            }
+            self.push(Transfer::Response(self.channels, *address));
+        }
+        Ok(())
+    }
+}
+
+// -- every command is directly followed by the response of the same sensor,
+// -- nothing of another bus user slipped in between
+pub fn assert_not_interleaved(log: &[Transfer]) {
+    for (i, transfer) in log.iter().enumerate() {
+        if let Transfer::Command(channels, addr) = *transfer {
+            assert_eq!(log.get(i + 1), Some(&Transfer::Response(channels, addr)), "interleaved at {i}: {log:?}");
+        }
+    }
+}
+
+// -- driver inits scripted for the MockBus, for the tests starting from an initialized sensor
+
+// -- SHT31 init with status clear, continuous mode stopped
+pub fn sht31_init_script() -> Vec<T> {
+    vec![
+        T::set_slave(SHT31_ADDR),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(SHT31_ADDR, &[0x00, 0x00, 0x81]),
+        T::write_byte(0x30, 0x93),
+    ]
+}
+
+// -- SHT31 init where stopping continuous mode fails, the driver soft-resets and waits 2 ms for the reset
+pub fn sht31_reset_script() -> Vec<T> {
+    vec![
+        T::set_slave(SHT31_ADDR),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(SHT31_ADDR, &[0x00, 0x00, 0x81]),
+        T::write_byte(0x30, 0x93).with_error(std::io::ErrorKind::Other),
+        T::write_byte(0x30, 0xa2),
+    ]
+}
+
+// -- one high repeatability single shot, 25 °C and 50 %
+pub fn sht31_measure_script() -> Vec<T> {
+    vec![
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]
+}
+
+pub fn init_sht31(mock: &MockBus) -> SHT31<MockBus> {
+    let sht31 = SHT31::with_bus(mock.clone(), SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    sht31
+}
+
+// -- the smbus delivers words least significant byte first, TMP117 sends most significant byte first,
+// -- so every register value appears byte swapped on the bus
+pub fn tmp117_init_script() -> Vec<T> {
+    vec![
+        T::set_slave(TMP117_ADDR),
+        // -- device id 0x117, revision 1
+        T::read_word(0x0f, 0x1711),
+        // -- soft reset bit
+        T::write_word(0x01, 0x0200),
+        // -- power-on config 0x0220, continuous conversion, 1 s cycle, 8 conversions averaged
+        T::read_word(0x01, 0x2002),
+        T::write_word(0x01, 0x2002),
+    ]
+}
+
+pub fn init_tmp117(mock: &MockBus) -> TMP117<MockBus> {
+    let tmp117 = TMP117::with_bus(mock.clone(), Tmp117DeviceAddress::Default, &Tmp117SensorMode::ModeContinuousConversion,
+        &Tmp117ConversionCycleTime::Ms1000, &Tmp117Averaging::Averaging8Conversions).expect("init failed");
+    mock.done();
+    tmp117
+}
+
+// -- par_t1 = 27000, par_t2 = 19000, par_t3 = -7, pressure offsets p1 and p2 at their zero point
+pub fn bmp388_trimming_coefficients() -> [u8; 21] {
+    let mut data = [0; 21];
+    data[0..2].copy_from_slice(&27000_u16.to_le_bytes());
+    data[2..4].copy_from_slice(&19000_u16.to_le_bytes());
+    data[4] = (-7_i8) as u8;
+    data[5..7].copy_from_slice(&16384_u16.to_le_bytes());
+    data[7..9].copy_from_slice(&16384_u16.to_le_bytes());
+    data
+}
+
+pub fn bmp388_init_script() -> Vec<T> {
+    vec![
+        T::set_slave(BMP388_ADDR),
+        T::read_byte(0x00, 0x50),
+        T::write_byte(0x7e, 0xb6),
+        T::read_block(0x31, &bmp388_trimming_coefficients()),
+        // -- temperature 2x, pressure 4x
+        T::write_byte(0x1c, 0x0a),
+        T::write_byte(0x1f, 0x02),
+        T::write_byte(0x1d, 0x05),
+    ]
+}
+
+pub fn init_bmp388(mock: &MockBus) -> BMP388<MockBus> {
+    let bmp388 = BMP388::with_bus(mock.clone(), Bmp388DeviceAddress::Default, Bmp388OverSamplingPr::StandardX4,
+        Bmp388OverSamplingTp::X2, Bmp388IrrFilter::Coef3, Bmp388OutputDataRate::Fx6_25Hz).expect("init failed");
+    mock.done();
+    bmp388
+}
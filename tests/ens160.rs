@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use i2c_sensors::ens160::{ENS160, Ens160AirQualityIndex, Ens160DeviceAddress, Ens160EquivalentCO2, Ens160Validity};
+use i2c_sensors::error::Error;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sensor::{Measurement, Sensor};
+use i2c_sensors::units::{Concentration, RelativeHumidity, Temperature};
+
+const ADDR: u16 = 0x53;
+const REG_PART_ID: u8 = 0x00;
+const REG_OP_MODE: u8 = 0x10;
+const REG_TEMP_IN: u8 = 0x13;
+const REG_RH_IN: u8 = 0x15;
+const REG_DEVICE_STATUS: u8 = 0x20;
+const REG_DATA_AQI: u8 = 0x21;
+const REG_DATA_TVOC: u8 = 0x22;
+const REG_DATA_ECO2: u8 = 0x24;
+const OP_MODE_IDLE: u8 = 0x01;
+const OP_MODE_OPERATIONAL: u8 = 0x02;
+
+fn init(mock: &MockBus) -> ENS160<MockBus> {
+    let ens160 = ENS160::with_bus(mock.clone(), Ens160DeviceAddress::Default).expect("init failed");
+    mock.done();
+    ens160
+}
+
+fn init_operational() -> (MockBus, ENS160<MockBus>) {
+    let mock = MockBus::new(&[
+        T::set_slave(ADDR),
+        T::read_word(REG_PART_ID, 0x0160),
+        T::read_byte(REG_OP_MODE, OP_MODE_OPERATIONAL),
+    ]);
+    let ens160 = init(&mock);
+    (mock, ens160)
+}
+
+#[test]
+fn init_checks_part_id() {
+    let (_mock, ens160) = init_operational();
+    assert_eq!(ens160.get_part_id(), 0x160);
+}
+
+#[test]
+fn init_rejects_unknown_part_id() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x52),
+        T::read_word(REG_PART_ID, 0x0161),
+    ]);
+    let result = ENS160::with_bus(mock.clone(), Ens160DeviceAddress::Secondary);
+    mock.done();
+    let err = result.err().expect("unknown part id accepted");
+    assert!(err.to_string().contains("0x0161"));
+}
+
+#[test]
+fn init_switches_to_operational_mode() {
+    let mock = MockBus::new(&[
+        T::set_slave(ADDR),
+        T::read_word(REG_PART_ID, 0x0160),
+        T::read_byte(REG_OP_MODE, OP_MODE_IDLE),
+        T::write_byte(REG_OP_MODE, OP_MODE_OPERATIONAL),
+        T::read_byte(REG_OP_MODE, OP_MODE_OPERATIONAL),
+    ]);
+    init(&mock);
+}
+
+#[test]
+fn init_reports_absent_device() {
+    let mock = MockBus::new(&[
+        T::set_slave(ADDR),
+        T::read_word(REG_PART_ID, 0).with_error(std::io::ErrorKind::NotFound),
+    ]);
+    let result = ENS160::with_bus(mock.clone(), Ens160DeviceAddress::Default);
+    mock.done();
+    assert_eq!(result.err().map(|err| err.kind()), Some(std::io::ErrorKind::NotFound));
+}
+
+#[test]
+fn data_reads() {
+    let (mock, mut ens160) = init_operational();
+    mock.expect(&[
+        // -- validity bits 3:2 set to warm-up
+        T::read_byte(REG_DEVICE_STATUS, 0b1000_0100),
+        T::read_byte(REG_DATA_AQI, 2),
+        T::read_word(REG_DATA_TVOC, 123),
+        T::read_word(REG_DATA_ECO2, 900),
+    ]);
+    assert!(matches!(ens160.get_validity().expect("read failed"), Ens160Validity::WarmUp));
+    assert!(matches!(ens160.get_air_quality_index().expect("read failed"), Ens160AirQualityIndex::Good));
+    assert_eq!(ens160.get_total_volatile_organic_compounds().expect("read failed").ppb(), 123.0);
+    assert!(matches!(ens160.get_equivalent_co2().expect("read failed"), Ens160EquivalentCO2::FairModerate));
+    mock.done();
+}
+
+#[test]
+fn compensation_round_trip() {
+    let (mock, mut ens160) = init_operational();
+    mock.expect(&[
+        // -- 25 °C in 1/64 K
+        T::write_word(REG_TEMP_IN, 19081),
+        // -- 50 %RH in 1/512 %
+        T::write_word(REG_RH_IN, 25600),
+        T::read_word(REG_TEMP_IN, 19081),
+        T::read_word(REG_RH_IN, 25600),
+    ]);
+    ens160.set_temperature(Temperature::from_celsius(25.0)).expect("write failed");
+    ens160.set_relative_humidity(RelativeHumidity::from_percent(50.0)).expect("write failed");
+    assert!((ens160.get_temperature().expect("read failed").celsius() - 25.0).abs() < 0.02);
+    assert_eq!(ens160.get_relative_humidity().expect("read failed"), RelativeHumidity::from_percent(50.0));
+    mock.done();
+}
+
+#[test]
+fn wait_for_valid_output_polls_once_a_second() {
+    let (mock, mut ens160) = init_operational();
+    mock.expect(&[
+        // -- warm-up, warm-up, valid
+        T::read_byte(REG_DEVICE_STATUS, 0x04),
+        T::read_byte(REG_DEVICE_STATUS, 0x04),
+        T::read_byte(REG_DEVICE_STATUS, 0x00),
+    ]);
+    ens160.wait_for_valid_output(Duration::from_secs(180)).expect("wait failed");
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2000);
+}
+
+#[test]
+fn wait_for_valid_output_times_out() {
+    let (mock, mut ens160) = init_operational();
+    let polls: Vec<T> = (0..4).map(|_| T::read_byte(REG_DEVICE_STATUS, 0x04)).collect();
+    mock.expect(&polls);
+    let err = ens160.wait_for_valid_output(Duration::from_secs(3)).expect_err("wait succeeded");
+    mock.done();
+    assert_eq!(err.to_string(), "Timed out: ENS160 output still not valid after 3000 ms");
+    assert!(matches!(Error::from(err), Error::Timeout(_)));
+}
+
+#[test]
+fn measure_reads_eco2_in_ppm() {
+    let (mock, mut ens160) = init_operational();
+    mock.expect(&[
+        T::read_byte(REG_DATA_AQI, 3),
+        T::read_word(REG_DATA_TVOC, 123),
+        T::read_word(REG_DATA_ECO2, 900),
+    ]);
+    let measurement = ens160.measure().expect("measure failed");
+    mock.done();
+    let expected = Measurement {
+        tvoc: Some(Concentration::from_ppb(123.0)),
+        eco2: Some(Concentration::from_ppm(900.0)),
+        aqi: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(measurement, expected);
+    assert_eq!(measurement.to_string(), "TVOC 123 ppb, eCO2 900 ppm, AQI 3");
+}
@@ -0,0 +1,42 @@
+use std::io::ErrorKind;
+
+use i2c_sensors::error::Error;
+
+#[test]
+fn driver_errors_survive_the_round_trip_through_io_error() {
+    let err: std::io::Error = Error::ChipId { expected: 0x0117, found: 0x0118 }.into();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Found unknown chip id '0x0118', expected '0x0117'");
+    assert!(matches!(Error::from(err), Error::ChipId { expected: 0x0117, found: 0x0118 }));
+
+    let err: std::io::Error = Error::Crc { expected: 0x92, found: 0x93 }.into();
+    assert!(matches!(Error::from(err), Error::Crc { expected: 0x92, found: 0x93 }));
+}
+
+#[test]
+fn bus_errors_are_classified() {
+    assert!(matches!(Error::from(std::io::Error::from_raw_os_error(libc::ENXIO)), Error::Nack { errno: Some(libc::ENXIO) }));
+    assert!(matches!(Error::from(std::io::Error::from_raw_os_error(libc::EREMOTEIO)), Error::Nack { errno: Some(libc::EREMOTEIO) }));
+    assert!(matches!(Error::from(std::io::Error::from(ErrorKind::TimedOut)), Error::Timeout(_)));
+    match Error::from(std::io::Error::from_raw_os_error(libc::EIO)) {
+        Error::Bus(err) => assert_eq!(err.raw_os_error(), Some(libc::EIO)),
+        err => panic!("unexpected {err:?}"),
+    }
+}
+
+#[test]
+fn bus_errors_go_back_unchanged() {
+    let err: std::io::Error = Error::Bus(std::io::Error::from_raw_os_error(libc::EIO)).into();
+    assert_eq!(err.raw_os_error(), Some(libc::EIO));
+}
+
+#[test]
+fn nacks_keep_their_errno_through_the_round_trip() {
+    let err = std::io::Error::from(Error::from(std::io::Error::from_raw_os_error(libc::EREMOTEIO)));
+    assert_eq!(err.raw_os_error(), Some(libc::EREMOTEIO));
+    assert!(matches!(Error::from(err), Error::Nack { errno: Some(libc::EREMOTEIO) }));
+
+    let err: std::io::Error = Error::Nack { errno: None }.into();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(matches!(Error::from(err), Error::Nack { errno: None }));
+}
@@ -0,0 +1,56 @@
+use i2c_linux::{I2c, Message, ReadFlags, WriteFlags};
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::symlink;
+use tempfile::TempDir;
+
+use i2c_sensors::error::Error;
+use i2c_sensors::forced_bus::ForcedBus;
+use i2c_sensors::i2cio::{self, I2cBus};
+
+// -- a forced bus on a plain file, transfers that get past the read-only check fail in the ioctl
+fn forced_bus() -> ForcedBus {
+    ForcedBus::new(I2c::new(tempfile::tempfile().unwrap()))
+}
+
+#[test]
+fn bound_driver_is_read_from_sysfs() {
+    let sysfs = TempDir::new().unwrap();
+    fs::create_dir_all(sysfs.path().join("drivers/bmp280")).unwrap();
+    fs::create_dir_all(sysfs.path().join("devices/1-0077")).unwrap();
+    fs::create_dir_all(sysfs.path().join("devices/1-0076")).unwrap();
+    symlink("../../drivers/bmp280", sysfs.path().join("devices/1-0077/driver")).unwrap();
+    let devices_path = sysfs.path().join("devices");
+    assert_eq!(i2cio::bound_driver(&devices_path, 1, 0x77).as_deref(), Some("bmp280"));
+    // -- instantiated but not bound, and not instantiated at all
+    assert_eq!(i2cio::bound_driver(&devices_path, 1, 0x76), None);
+    assert_eq!(i2cio::bound_driver(&devices_path, 0, 0x77), None);
+}
+
+#[test]
+fn address_in_use_names_the_driver() {
+    let err: std::io::Error = Error::AddressInUse { device_addr: 0x77, driver: Some("bmp280".to_string()) }.into();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+    assert!(err.to_string().contains("kernel driver 'bmp280'"));
+    assert!(matches!(Error::from(err), Error::AddressInUse { device_addr: 0x77, .. }));
+}
+
+#[test]
+fn forced_bus_refuses_writes() {
+    let mut bus = forced_bus();
+    assert_eq!(bus.smbus_write_byte(0x00).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    assert_eq!(bus.smbus_write_byte_data(0x7e, 0xb6).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    assert_eq!(bus.smbus_write_word_data(0x01, 0x0220).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    assert_eq!(bus.smbus_write_block_data(0x00, &[0x01]).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    let data = [0x30, 0xa2];
+    let mut messages = [Message::Write { address: 0x44, data: &data, flags: WriteFlags::empty() }];
+    assert_eq!(bus.i2c_transfer(&mut messages).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    // -- the register pointer of a combined read is let through
+    let register = [0xd0];
+    let mut read_buf = [0; 1];
+    let mut messages = [
+        Message::Write { address: 0x77, data: &register, flags: WriteFlags::empty() },
+        Message::Read { address: 0x77, data: &mut read_buf, flags: ReadFlags::empty() },
+    ];
+    assert_ne!(bus.i2c_transfer(&mut messages).unwrap_err().kind(), ErrorKind::PermissionDenied);
+}
@@ -0,0 +1,125 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use embedded_hal::i2c::{ErrorType, Operation};
+use futures_executor::block_on;
+use i2c_linux::{Message, ReadFlags, WriteFlags};
+use i2c_sensors::clock::VirtualClock;
+use i2c_sensors::hal::{AsyncHalBus, HalBus};
+use i2c_sensors::i2cio::{self, I2cBus};
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sht31::{SHT31, SHT31Async, SHT31DeviceAddress, SHT31SingleShotAcquisitionNoClockStretch};
+
+const ADDR: u16 = 0x44;
+
+// -- an embedded-hal i2c bus and delay on top of the mock, each operation becomes one
+// -- scripted message and the delays add up in the mock
+struct HalMock(MockBus);
+
+impl HalMock {
+
+    fn transaction(&mut self, address: u8, operations: &mut [Operation]) -> Result<(), embedded_hal::i2c::ErrorKind> {
+        let address = address as u16;
+        let mut messages: Vec<Message> = operations.iter_mut()
+            .map(|operation| match operation {
+                Operation::Read(data) => Message::Read { address, data, flags: ReadFlags::empty() },
+                Operation::Write(data) => Message::Write { address, data, flags: WriteFlags::empty() },
+            })
+            .collect();
+        self.0.i2c_transfer(&mut messages)
+            .map_err(|_| embedded_hal::i2c::ErrorKind::Other)
+    }
+
+    fn delay_ns(&mut self, ns: u32) {
+        I2cBus::delay_ms(&mut self.0, ns / 1_000_000)
+    }
+}
+
+impl ErrorType for HalMock {
+    type Error = embedded_hal::i2c::ErrorKind;
+}
+
+impl embedded_hal::i2c::I2c for HalMock {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation]) -> Result<(), Self::Error> {
+        HalMock::transaction(self, address, operations)
+    }
+}
+
+impl embedded_hal::delay::DelayNs for HalMock {
+    fn delay_ns(&mut self, ns: u32) {
+        HalMock::delay_ns(self, ns)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c for HalMock {
+    async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        HalMock::transaction(self, address, operations)
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for HalMock {
+    async fn delay_ns(&mut self, ns: u32) {
+        HalMock::delay_ns(self, ns)
+    }
+}
+
+// -- stopping continuous mode fails, the driver soft-resets and waits for the reset
+fn init_script() -> Vec<T> {
+    vec![
+        T::write_bytes(ADDR, &[0xf3, 0x2d]),
+        T::read_bytes(ADDR, &[0x00, 0x00, 0x81]),
+        T::write_bytes(ADDR, &[0x30, 0x93]).with_error(ErrorKind::Other),
+        T::write_bytes(ADDR, &[0x30, 0xa2]),
+    ]
+}
+
+// -- a no clock stretch measurement, the driver waits between command and read
+fn measure_script() -> Vec<T> {
+    vec![
+        T::write_bytes(ADDR, &[0x24, 0x00]),
+        T::read_bytes(ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]
+}
+
+#[test]
+fn driver_runs_on_embedded_hal_bus_and_delay() {
+    let mock = MockBus::new(&init_script());
+    let bus = HalBus::new(HalMock(mock.clone()), HalMock(mock.clone()));
+    let mut sht31 = SHT31::with_bus(bus, SHT31DeviceAddress::Default).expect("init failed");
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2);
+
+    mock.expect(&measure_script());
+    let data = sht31.get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityHigh)
+        .expect("read failed");
+    assert_eq!(data, (0x6666, 0x8000));
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2 + 5);
+}
+
+#[test]
+fn async_driver_runs_on_embedded_hal_async_bus_and_delay() {
+    let mock = MockBus::new(&init_script());
+    let bus = AsyncHalBus::new(HalMock(mock.clone()), HalMock(mock.clone()));
+    block_on(async {
+        let mut sht31 = SHT31Async::with_bus(bus, SHT31DeviceAddress::Default).await.expect("init failed");
+        mock.done();
+        assert_eq!(mock.delay_total_ms(), 2);
+
+        mock.expect(&measure_script());
+        let data = sht31.get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityHigh)
+            .await.expect("read failed");
+        assert_eq!(data, (0x6666, 0x8000));
+    });
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 2 + 5);
+}
+
+#[test]
+fn time_comes_from_the_given_clock() {
+    let mock = MockBus::new(&[]);
+    let clock = VirtualClock::new();
+    let bus = HalBus::with_clock(HalMock(mock.clone()), HalMock(mock.clone()), clock.clone());
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(i2cio::now(&bus), Duration::from_secs(5));
+}
@@ -0,0 +1,83 @@
+use i2c_linux::Functionality;
+
+use i2c_sensors::ht16k33::{HT16K33, HT16K33BlinkRate, HT16K33DeviceAddress, HT16K33DimmingDuty};
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+
+const ADDR: u16 = 0x70;
+
+fn init_script() -> Vec<T> {
+    vec![
+        T::set_slave(ADDR),
+        // -- system setup, clock enabled
+        T::write_byte_single(0x21),
+        // -- dimming duty 15
+        T::write_byte_single(0xef),
+        // -- display on, no blinking
+        T::write_byte_single(0x81),
+    ]
+}
+
+fn init(mock: &MockBus) -> HT16K33<MockBus> {
+    let ht16k33 = HT16K33::with_bus(mock.clone(), HT16K33DeviceAddress::Default,
+        HT16K33DimmingDuty::Duty15, HT16K33BlinkRate::NoBlink).expect("init failed");
+    mock.done();
+    ht16k33
+}
+
+#[test]
+fn init_enables_clock_and_display() {
+    let mock = MockBus::new(&init_script());
+    init(&mock);
+}
+
+#[test]
+fn blink_rate_survives_display_off_and_on() {
+    let mock = MockBus::new(&init_script());
+    let mut ht16k33 = init(&mock);
+    mock.expect(&[
+        T::write_byte_single(0x85),
+        T::write_byte_single(0x84),
+        T::write_byte_single(0x85),
+        T::write_byte_single(0xe3),
+    ]);
+    ht16k33.set_blinkrate(HT16K33BlinkRate::BlinkRate1Hz).expect("write failed");
+    ht16k33.set_disply_off().expect("write failed");
+    ht16k33.set_disply_on().expect("write failed");
+    ht16k33.set_brightness(HT16K33DimmingDuty::Duty3).expect("write failed");
+    mock.done();
+}
+
+#[test]
+fn print_maps_segments_to_display_ram() {
+    let mock = MockBus::new(&init_script());
+    let mut ht16k33 = init(&mock);
+    // -- '0' lights segments 0 to 5 of digit 0, '1' segments 1, 2 and 10 of digit 1, plus the colon
+    let mut display_ram = [0_u8; 16];
+    display_ram[0] = 0x01;
+    display_ram[1] = 0x03;
+    display_ram[3] = 0x03;
+    display_ram[5] = 0x21;
+    display_ram[7] = 0x01;
+    display_ram[9] = 0x01;
+    display_ram[15] = 0x01;
+    mock.expect(&[
+        T::write_block(0x00, &display_ram),
+    ]);
+    ht16k33.print("01".to_string(), true, false).expect("write failed");
+    mock.done();
+}
+
+#[test]
+fn print_without_smbus_block_writes_uses_plain_write() {
+    let mock = MockBus::new(&init_script()).with_functionality(Functionality::I2C);
+    let mut ht16k33 = init(&mock);
+    // -- register and byte count ahead of the display ram, like the smbus block write
+    let mut block = vec![0x00, 16, 0x01, 0x03, 0x00, 0x03, 0x00, 0x21, 0x00, 0x01, 0x00, 0x01];
+    block.extend([0x00; 5]);
+    block.push(0x01);
+    mock.expect(&[
+        T::write_bytes(ADDR, &block),
+    ]);
+    ht16k33.print("01".to_string(), true, false).expect("write failed");
+    mock.done();
+}
@@ -0,0 +1,27 @@
+use i2c_linux::Functionality;
+use std::io::ErrorKind;
+
+use i2c_sensors::error::Error;
+use i2c_sensors::i2cio;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+
+const ADDR: u16 = 0x70;
+
+#[test]
+fn block_write_without_smbus_takes_up_to_255_bytes() {
+    let data = [0x5a; 255];
+    let mut block = vec![0x10, 255];
+    block.extend(data);
+    let mut mock = MockBus::new(&[T::write_bytes(ADDR, &block)]).with_functionality(Functionality::I2C);
+    i2cio::write_block(&mut mock, Functionality::I2C, ADDR, 0x10, &data).expect("write failed");
+    mock.done();
+}
+
+#[test]
+fn block_write_over_255_bytes_is_rejected_before_the_transfer() {
+    let mut mock = MockBus::new(&[]).with_functionality(Functionality::I2C);
+    let err = i2cio::write_block(&mut mock, Functionality::I2C, ADDR, 0x10, &[0x5a; 256]).expect_err("write succeeded");
+    mock.done();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(matches!(Error::from(err), Error::InvalidConfig(msg) if msg.starts_with("block of 256 bytes")));
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use tempfile::TempDir;
+
+use i2c_sensors::error::Error;
+use i2c_sensors::iio::{IioChannel, IioChip, IioSensor};
+use i2c_sensors::sensor::Sensor;
+
+// -- one iio:deviceN directory with its name, the device link and the given attributes
+fn add_device(devices_path: &Path, index: usize, name: &str, i2c_device: &str, attributes: &[(&str, &str)]) {
+    let device_path = devices_path.join(format!("iio:device{index}"));
+    fs::create_dir_all(&device_path).unwrap();
+    fs::write(device_path.join("name"), format!("{name}\n")).unwrap();
+    symlink(format!("../../../devices/platform/soc/i2c-1/{i2c_device}"), device_path.join("device")).unwrap();
+    for (attribute, val) in attributes {
+        fs::write(device_path.join(attribute), format!("{val}\n")).unwrap();
+    }
+}
+
+fn fake_sysfs() -> TempDir {
+    let sysfs = TempDir::new().unwrap();
+    add_device(sysfs.path(), 0, "tmp117", "1-0048", &[
+        ("in_temp_raw", "2944"),
+        ("in_temp_scale", "7.8125"),
+    ]);
+    add_device(sysfs.path(), 1, "bme280", "1-0076", &[
+        ("in_temp_input", "21530"),
+        ("in_pressure_input", "101.325"),
+        ("in_humidityrelative_input", "45678"),
+        ("in_temp_oversampling_ratio", "2"),
+        ("in_temp_oversampling_ratio_available", "1 2 4 8 16"),
+    ]);
+    add_device(sysfs.path(), 2, "bme280", "1-0077", &[
+        ("in_temp_input", "22000"),
+    ]);
+    sysfs
+}
+
+#[test]
+fn processed_values_in_driver_units() {
+    let sysfs = fake_sysfs();
+    let bme280 = IioSensor::find(sysfs.path(), IioChip::Bme280, None).expect("BME280 not found");
+    assert!(bme280.get_device_path().ends_with("iio:device1"));
+    assert!((bme280.get_temperature().unwrap().celsius() - 21.53).abs() < 1e-9);
+    assert!((bme280.get_pressure().unwrap().pascal() - 101325.0).abs() < 1e-6);
+    assert!((bme280.get_humidity().unwrap().percent() - 45.678).abs() < 1e-9);
+    let err = bme280.get_gas_resistance().expect_err("BME280 has no gas channel");
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn raw_values_are_scaled() {
+    let sysfs = fake_sysfs();
+    let tmp117 = IioSensor::find(sysfs.path(), IioChip::Tmp117, None).expect("TMP117 not found");
+    assert!((tmp117.get_temperature().unwrap().celsius() - 23.0).abs() < 1e-9);
+}
+
+#[test]
+fn devices_are_told_apart_by_address() {
+    let sysfs = fake_sysfs();
+    let bme280 = IioSensor::find(sysfs.path(), IioChip::Bme280, Some(0x77)).expect("BME280 at 0x77 not found");
+    assert!((bme280.get_temperature().unwrap().celsius() - 22.0).abs() < 1e-9);
+    let err = IioSensor::find(sysfs.path(), IioChip::Bmp388, None).err().expect("there is no BMP388");
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn oversampling_is_checked_against_the_available_ratios() {
+    let sysfs = fake_sysfs();
+    let mut bme280 = IioSensor::find(sysfs.path(), IioChip::Bme280, Some(0x76)).expect("BME280 not found");
+    assert_eq!(bme280.get_oversampling(IioChannel::Temperature).unwrap(), 2);
+    bme280.set_oversampling(IioChannel::Temperature, 16).expect("setting oversampling failed");
+    assert_eq!(bme280.get_oversampling(IioChannel::Temperature).unwrap(), 16);
+    let err = bme280.set_oversampling(IioChannel::Temperature, 3).expect_err("3 is not available");
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+    assert_eq!(bme280.get_oversampling(IioChannel::Temperature).unwrap(), 16);
+}
+
+#[test]
+fn measure_reads_the_channels_of_the_chip() {
+    let sysfs = fake_sysfs();
+    let mut sensors: Vec<Box<dyn Sensor>> = vec![
+        Box::new(IioSensor::find(sysfs.path(), IioChip::Bme280, Some(0x76)).expect("BME280 not found")),
+        Box::new(IioSensor::find(sysfs.path(), IioChip::Tmp117, None).expect("TMP117 not found")),
+    ];
+    let bme280 = sensors[0].measure().expect("measure failed");
+    assert_eq!(sensors[0].name(), "BME280");
+    assert!((bme280.pressure.unwrap().pascal() - 101325.0).abs() < 1e-6);
+    assert!((bme280.humidity.unwrap().percent() - 45.678).abs() < 1e-9);
+    assert_eq!(bme280.gas_resistance, None);
+    let tmp117 = sensors[1].measure().expect("measure failed");
+    assert!((tmp117.temperature.unwrap().kelvin() - 296.15).abs() < 1e-9);
+    assert_eq!((tmp117.pressure, tmp117.humidity), (None, None));
+}
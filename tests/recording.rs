@@ -0,0 +1,64 @@
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use common::{sht31_init_script, sht31_measure_script};
+use i2c_linux::Functionality;
+use i2c_sensors::i2cio::I2cBus;
+use i2c_sensors::mock::MockBus;
+use i2c_sensors::recording::{RecordingBus, ReplayBus};
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress, SHT31SingleShotAcquisition};
+
+fn replay(recording: Vec<u8>) -> ReplayBus {
+    ReplayBus::from_reader(Cursor::new(recording)).expect("loading recording failed")
+}
+
+#[test]
+fn replay_reports_recorded_functionality() {
+    let functionality = Functionality::I2C | Functionality::SMBUS_BYTE_DATA;
+    let mock = MockBus::new(&[]).with_functionality(functionality);
+    let (_, recording) = RecordingBus::new(mock, Vec::new()).release();
+    assert_eq!(replay(recording).functionality().expect("functionality failed"), functionality);
+}
+
+#[test]
+fn replay_without_functionality_reports_everything() {
+    let recording = b"# i2c-sensors recording started 2024-01-01T00:00:00+00:00\n".to_vec();
+    assert_eq!(replay(recording).functionality().expect("functionality failed"), Functionality::all());
+}
+
+// -- SHT31 init and one high repeatability measurement, recorded from the mock
+fn sht31_recording() -> Vec<u8> {
+    let mut script = sht31_init_script();
+    script.extend(sht31_measure_script());
+    let mock = MockBus::new(&script);
+    let mut recording = Vec::new();
+    let mut sht31 = SHT31::with_bus(RecordingBus::new(mock.clone(), &mut recording), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    assert_eq!(sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed"), (0x6666, 0x8000));
+    drop(sht31);
+    mock.done();
+    recording
+}
+
+#[test]
+fn recorded_driver_replays_without_hardware() {
+    let recording = sht31_recording();
+    let text = String::from_utf8(recording.clone()).unwrap();
+    assert!(text.lines().any(|line| line.ends_with("0x44 smbus_write_byte_data W 0x2c 06")), "{text}");
+
+    let mut sht31 = SHT31::with_bus(replay(recording), SHT31DeviceAddress::Default).expect("init failed");
+    assert_eq!(sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed"), (0x6666, 0x8000));
+    // -- nothing left to replay
+    let err = sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect_err("read succeeded");
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn replay_rejects_write_not_in_recording() {
+    let mut sht31 = SHT31::with_bus(replay(sht31_recording()), SHT31DeviceAddress::Default).expect("init failed");
+    // -- low repeatability sends 0x2c 0x10 where the recording has 0x2c 0x06
+    let err = sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityLow).expect_err("read succeeded");
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().starts_with("Replay mismatch at transaction 4"), "{err}");
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use common::{SHT31_ADDR, sht31_init_script};
+use i2c_sensors::ens160::{ENS160, Ens160DeviceAddress};
+use i2c_sensors::i2cio::{self, RetryErrorClass, RetryPolicy};
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::retry::{Recovering, RetryBus};
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress, SHT31SingleShotAcquisition};
+
+const ENS160_ADDR: u16 = 0x53;
+const ENS160_REG_PART_ID: u8 = 0x00;
+const ENS160_REG_OP_MODE: u8 = 0x10;
+const ENS160_REG_DATA_TVOC: u8 = 0x22;
+
+#[test]
+fn single_transfer_retried_with_backoff() {
+    let mock = MockBus::new(&[
+        T::read_byte(0x10, 0).with_error(ErrorKind::TimedOut),
+        T::read_byte(0x10, 0).with_error(ErrorKind::TimedOut),
+        T::read_byte(0x10, 0x42),
+    ]);
+    let mut bus = RetryBus::new(mock.clone(), RetryPolicy::default());
+    assert_eq!(i2cio::read_byte(&mut bus, 0x10).expect("read failed"), 0x42);
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 10 + 20);
+}
+
+#[test]
+fn gives_up_after_last_attempt() {
+    let mock = MockBus::new(&[
+        T::write_byte(0x10, 0x01).with_error(ErrorKind::TimedOut),
+        T::write_byte(0x10, 0x01).with_error(ErrorKind::TimedOut),
+    ]);
+    let mut bus = RetryBus::new(mock.clone(), RetryPolicy::new(2, 5));
+    let err = i2cio::write_byte(&mut bus, 0x10, 0x01).expect_err("write succeeded");
+    mock.done();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert_eq!(mock.delay_total_ms(), 5);
+}
+
+#[test]
+fn other_error_classes_fail_right_away() {
+    let mock = MockBus::new(&[
+        T::read_word(0x00, 0).with_error(ErrorKind::NotFound),
+    ]);
+    let mut bus = RetryBus::new(mock.clone(), RetryPolicy::default());
+    assert!(i2cio::read_word(&mut bus, 0x00).is_err());
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 0);
+}
+
+#[test]
+fn configured_error_classes() {
+    let policy = RetryPolicy::new(2, 1).with_error_classes(&[RetryErrorClass::Any]);
+    let mock = MockBus::new(&[
+        T::read_word(0x00, 0).with_error(ErrorKind::NotFound),
+        T::read_word(0x00, 0x1234),
+    ]);
+    let mut bus = mock.clone();
+    let result = i2cio::retry(&mut bus, &policy, |bus| i2cio::read_word(bus, 0x00));
+    mock.done();
+    assert_eq!(result.expect("read failed"), 0x1234);
+    let policy = RetryPolicy::new(2, 1).with_error_classes(&[RetryErrorClass::Io]);
+    assert!(!policy.is_retryable(&std::io::Error::from(ErrorKind::TimedOut)));
+    assert!(policy.is_retryable(&std::io::Error::from_raw_os_error(libc::EIO)));
+}
+
+#[test]
+fn transaction_retried_as_a_whole() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = SHT31::with_bus(RetryBus::new(mock.clone(), RetryPolicy::new(3, 1)), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    mock.done();
+    mock.expect(&[
+        // -- the read fails, the command is sent again before the next read
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0; 6]).with_error(ErrorKind::TimedOut),
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let data = sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed");
+    mock.done();
+    assert_eq!(data, (0x6666, 0x8000));
+    assert_eq!(mock.delay_total_ms(), 1);
+}
+
+#[test]
+fn recovers_after_repeated_failures() {
+    let mock = MockBus::new(&[
+        T::set_slave(ENS160_ADDR),
+        T::read_word(ENS160_REG_PART_ID, 0x0160),
+        T::read_byte(ENS160_REG_OP_MODE, 0x02),
+    ]);
+    let ens160 = ENS160::with_bus(mock.clone(), Ens160DeviceAddress::Default).expect("init failed");
+    mock.done();
+    let policy = RetryPolicy::new(1, 0).with_recover_after(2);
+    let mut ens160 = Recovering::new(ens160, policy);
+    mock.expect(&[
+        T::read_word(ENS160_REG_DATA_TVOC, 0).with_error(ErrorKind::TimedOut),
+        T::read_word(ENS160_REG_DATA_TVOC, 0).with_error(ErrorKind::TimedOut),
+        // -- the sensor fell back to deep sleep
+        T::set_slave(ENS160_ADDR),
+        T::read_byte(ENS160_REG_OP_MODE, 0x00),
+        T::write_byte(ENS160_REG_OP_MODE, 0x02),
+        T::read_word(ENS160_REG_DATA_TVOC, 0x0064),
+    ]);
+    assert!(ens160.run(|ens160| ens160.get_total_volatile_organic_compounds()).is_err());
+    assert_eq!(ens160.get_failures(), 1);
+    assert!(ens160.run(|ens160| ens160.get_total_volatile_organic_compounds()).is_err());
+    assert_eq!(ens160.get_failures(), 0);
+    assert_eq!(ens160.run(|ens160| ens160.get_total_volatile_organic_compounds()).expect("read failed").ppb(), 100.0);
+    mock.done();
+}
+
+#[test]
+fn recovers_with_own_hook() {
+    let mock = MockBus::new(&[
+        T::read_byte(0x10, 0).with_error(ErrorKind::TimedOut),
+        T::write_byte(0x10, 0x01),
+    ]);
+    let policy = RetryPolicy::new(1, 0).with_recover_after(1);
+    let mut bus = Recovering::with_hook(mock.clone(), policy, |bus: &mut MockBus| i2cio::write_byte(bus, 0x10, 0x01));
+    assert!(bus.run(|bus| i2cio::read_byte(bus, 0x10)).is_err());
+    mock.done();
+}
@@ -0,0 +1,88 @@
+use std::io::ErrorKind;
+
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::scanner::{self, ScanChip, SCAN_ADDR_FIRST, SCAN_ADDR_LAST};
+
+// -- the device did not acknowledge its address
+fn nack(transaction: T) -> T {
+    transaction.with_error(ErrorKind::Other)
+}
+
+#[test]
+fn identifies_chips_on_the_whole_bus() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x44),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(0x44, &[0x00, 0x00, 0x81]),
+        T::set_slave(0x45),
+        nack(T::write_byte(0xf3, 0x2d)),
+        T::set_slave(0x48),
+        T::read_word(0x0f, 0x1711),
+        T::set_slave(0x49),
+        nack(T::read_word(0x0f, 0)),
+        T::set_slave(0x4a),
+        nack(T::read_word(0x0f, 0)),
+        T::set_slave(0x4b),
+        nack(T::read_word(0x0f, 0)),
+        T::set_slave(0x52),
+        nack(T::read_word(0x00, 0)),
+        T::set_slave(0x53),
+        T::read_word(0x00, 0x0160),
+        // -- no BME280 or BME680 id, the BMP388 keeps its id at 0x00
+        T::set_slave(0x76),
+        T::read_byte(0xd0, 0x00),
+        T::read_byte(0x00, 0x50),
+        T::set_slave(0x77),
+        T::read_byte(0xd0, 0x60),
+    ]);
+    let mut bus = mock.clone();
+    let results = scanner::scan(&mut bus, SCAN_ADDR_FIRST..=SCAN_ADDR_LAST).expect("scan failed");
+    mock.done();
+    let found: Vec<(u16, ScanChip)> = results.iter().map(|result| (result.get_addr(), result.get_chip())).collect();
+    assert_eq!(found, [
+        (0x44, ScanChip::Sht31),
+        (0x48, ScanChip::Tmp117),
+        (0x53, ScanChip::Ens160),
+        (0x76, ScanChip::Bmp388),
+        (0x77, ScanChip::Bme280),
+    ]);
+    assert_eq!(results[1].get_id(), 0x117);
+    assert_eq!(results[1].get_revision(), Some(1));
+    assert_eq!(results[1].to_string(), "0x48: TMP117 (id 0x0117, revision 1)");
+    assert_eq!(results[4].to_string(), "0x77: BME280 (id 0x0060)");
+}
+
+#[test]
+fn skips_unknown_ids_and_bad_crc() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x44),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(0x44, &[0x00, 0x00, 0x00]),
+        T::set_slave(0x45),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(0x45, &[0x80, 0x10, 0xe1]),
+    ]);
+    let mut bus = mock.clone();
+    let results = scanner::scan(&mut bus, 0x40..=0x45).expect("scan failed");
+    mock.done();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].to_string(), "0x45: SHT31 (status 0x8010)");
+
+    let mock = MockBus::new(&[
+        T::set_slave(0x77),
+        T::read_byte(0xd0, 0x61),
+    ]);
+    let mut bus = mock.clone();
+    let results = scanner::scan(&mut bus, 0x77..=0x77).expect("scan failed");
+    mock.done();
+    assert_eq!(results[0].get_chip(), ScanChip::Bme680);
+
+    let mock = MockBus::new(&[
+        T::set_slave(0x76),
+        T::read_byte(0xd0, 0x58),
+        T::read_byte(0x00, 0x00),
+    ]);
+    let mut bus = mock.clone();
+    assert!(scanner::scan(&mut bus, 0x76..=0x76).expect("scan failed").is_empty());
+    mock.done();
+}
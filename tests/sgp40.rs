@@ -0,0 +1,136 @@
+use futures_executor::block_on;
+use i2c_sensors::error::Error;
+use i2c_sensors::i2cio::BusEvent;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sensor::Sensor;
+use i2c_sensors::sgp40::{SGP40, SGP40Async};
+
+const ADDR: u16 = 0x59;
+
+fn init(mock: &MockBus) -> SGP40<MockBus> {
+    let sgp40 = SGP40::with_bus(mock.clone()).expect("init failed");
+    mock.done();
+    sgp40
+}
+
+fn init_script() -> Vec<T> {
+    vec![
+        T::set_slave(ADDR),
+        T::write_byte_single(0x06),
+    ]
+}
+
+#[test]
+fn init_soft_resets() {
+    let mock = MockBus::new(&init_script());
+    init(&mock);
+    assert_eq!(mock.delay_total_ms(), 1);
+}
+
+#[test]
+fn measure_without_compensation() {
+    let mock = MockBus::new(&init_script());
+    let mut sgp40 = init(&mock);
+    mock.expect(&[
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0xbe, 0xef, 0x92]),
+    ]);
+    assert_eq!(sgp40.get_voc_data_no_compensation().expect("read failed"), 0xbeef);
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 1 + 30);
+}
+
+#[test]
+fn measure_with_compensation_adds_crcs() {
+    let mock = MockBus::new(&init_script());
+    let mut sgp40 = init(&mock);
+    mock.expect(&[
+        // -- same as the defaults, 50 %RH and 25 °C
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0x12, 0x34, 0x37]),
+    ]);
+    assert_eq!(sgp40.get_voc_data_with_compensation(0x8000, 0x6666).expect("read failed"), 0x1234);
+    mock.done();
+}
+
+#[test]
+fn corrupted_voc_word_never_reaches_the_algorithm() {
+    let mock = MockBus::new(&init_script());
+    let mut sgp40 = init(&mock);
+    mock.expect(&[
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0xbe, 0xee, 0x92]),
+    ]);
+    let err = sgp40.get_voc_data_no_compensation().expect_err("read succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::Crc { found: 0x92, .. }));
+}
+
+#[test]
+fn voc_index_keeps_one_second_pace() {
+    let mock = MockBus::new(&init_script());
+    let mut sgp40 = init(&mock);
+    for _ in 0..3 {
+        mock.expect(&[
+            T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+            T::read_bytes(ADDR, &[0x12, 0x34, 0x37]),
+        ]);
+    }
+    for _ in 0..3 {
+        sgp40.get_voc_index(0x8000, 0x6666).expect("read failed");
+    }
+    mock.done();
+    // -- soft reset, then 30 ms per measurement plus the rest of each second in between
+    assert_eq!(mock.delay_total_ms(), 1 + 30 + 970 + 30 + 970 + 30);
+}
+
+#[test]
+fn async_measure_with_compensation() {
+    let mut script = init_script();
+    script.extend([
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0x12, 0x34, 0x37]),
+    ]);
+    let mock = MockBus::new(&script);
+    let voc_raw = block_on(async {
+        let mut sgp40 = SGP40Async::with_bus(mock.clone()).await.expect("init failed");
+        sgp40.get_voc_data_with_compensation(0x8000, 0x6666).await.expect("read failed")
+    });
+    mock.done();
+    assert_eq!(voc_raw, 0x1234);
+    assert_eq!(mock.delay_total_ms(), 1 + 30);
+}
+
+#[test]
+fn async_crc_failure_is_recorded_on_the_bus() {
+    let mut script = init_script();
+    script.extend([
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0x12, 0x34, 0x00]),
+    ]);
+    let mock = MockBus::new(&script);
+    let result = block_on(async {
+        let mut sgp40 = SGP40Async::with_bus(mock.clone()).await.expect("init failed");
+        sgp40.get_voc_data_no_compensation().await
+    });
+    mock.done();
+    assert!(matches!(Error::from(result.expect_err("crc failure not detected")), Error::Crc { found: 0x00, .. }));
+    assert_eq!(mock.events(), [BusEvent::CrcFailure]);
+}
+
+#[test]
+fn measure_uses_the_set_compensation() {
+    let mock = MockBus::new(&init_script());
+    let mut sgp40 = init(&mock);
+    mock.expect(&[
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0x80, 0x00, 0xa2, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0x12, 0x34, 0x37]),
+        T::write_bytes(ADDR, &[0x26, 0x0f, 0xbe, 0xef, 0x92, 0x66, 0x66, 0x93]),
+        T::read_bytes(ADDR, &[0x12, 0x34, 0x37]),
+    ]);
+    let measurement = sgp40.measure().expect("measure failed");
+    assert!(measurement.voc_index.is_some());
+    sgp40.set_compensation(0xbeef, 0x6666);
+    sgp40.measure().expect("measure failed");
+    mock.done();
+}
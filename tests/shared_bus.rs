@@ -0,0 +1,83 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use common::{Sht31Board, Transfer, TransferLog, assert_not_interleaved};
+use i2c_sensors::i2cio::{self, I2cBus};
+use i2c_sensors::shared_bus::SharedBus;
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress, SHT31SingleShotAcquisitionNoClockStretch};
+
+const SHT31_ADDR: u16 = 0x44;
+const SHT31_ADDR_ALT: u16 = 0x45;
+
+#[test]
+fn drivers_on_two_threads_do_not_interleave() {
+    let log = TransferLog::default();
+    let shared_bus = SharedBus::new(Sht31Board::new(&log));
+    let mut sht31 = SHT31::with_bus(shared_bus.device(), SHT31DeviceAddress::Default).expect("init failed");
+    let mut sht31_alt = SHT31::with_bus(shared_bus.device(), SHT31DeviceAddress::Secondary).expect("init failed");
+    log.lock().unwrap().clear();
+
+    let measure = |sht31: &mut SHT31<_>, device_addr| {
+        let raw_value = Sht31Board::raw_value(0, device_addr);
+        for _ in 0..20 {
+            // -- the driver waits between command and read, the other thread has to wait as well
+            let data = sht31.get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityHigh)
+                .expect("read failed");
+            assert_eq!(data, (raw_value, raw_value));
+        }
+    };
+    thread::scope(|scope| {
+        scope.spawn(|| measure(&mut sht31, SHT31_ADDR));
+        scope.spawn(|| measure(&mut sht31_alt, SHT31_ADDR_ALT));
+    });
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 2 * 2 * 20);
+    assert_not_interleaved(&log);
+}
+
+#[test]
+fn nested_transaction_holds_the_bus_until_outermost_end() {
+    let log = TransferLog::default();
+    let shared_bus = SharedBus::new(Sht31Board::new(&log));
+    let mut device = shared_bus.device();
+    let mut other = shared_bus.device();
+    device.begin_transaction().expect("begin failed");
+    device.begin_transaction().expect("begin failed");
+    let written = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            i2cio::set_slave(&mut other, SHT31_ADDR_ALT).expect("set slave failed");
+            i2cio::write_byte(&mut other, 0x24, 0x00).expect("write failed");
+            written.store(true, Ordering::SeqCst);
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!written.load(Ordering::SeqCst));
+        device.end_transaction().expect("end failed");
+        thread::sleep(Duration::from_millis(20));
+        // -- still inside the outer transaction
+        assert!(!written.load(Ordering::SeqCst));
+        device.end_transaction().expect("end failed");
+    });
+    assert!(written.load(Ordering::SeqCst));
+    assert_eq!(*log.lock().unwrap(), [Transfer::Command(0, SHT31_ADDR_ALT)]);
+}
+
+#[test]
+fn dropped_device_releases_the_bus() {
+    let log = TransferLog::default();
+    let shared_bus = SharedBus::new(Sht31Board::new(&log));
+    let mut device = shared_bus.device();
+    device.begin_transaction().expect("begin failed");
+    drop(device);
+    let mut other = shared_bus.device();
+    let handle = thread::spawn(move || {
+        i2cio::set_slave(&mut other, SHT31_ADDR).expect("set slave failed");
+        i2cio::write_byte(&mut other, 0x24, 0x00)
+    });
+    handle.join().expect("thread panicked").expect("write failed");
+    assert_eq!(*log.lock().unwrap(), [Transfer::Command(0, SHT31_ADDR)]);
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use common::{SHT31_ADDR, init_sht31, sht31_init_script, sht31_measure_script};
+use futures_executor::block_on;
+use i2c_sensors::error::Error;
+use i2c_sensors::i2cio::BusEvent;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sensirion_crc::CrcPolicy;
+use i2c_sensors::sensor::{Measurement, Sensor};
+use i2c_sensors::sht31::{SHT31, SHT31Async, SHT31ContinuousAcquisition, SHT31DeviceAddress,
+    SHT31SingleShotAcquisition, SHT31SingleShotAcquisitionNoClockStretch};
+
+#[test]
+fn init_with_clear_status() {
+    let mock = MockBus::new(&sht31_init_script());
+    init_sht31(&mock);
+}
+
+#[test]
+fn init_resets_pending_status() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x45),
+        T::write_byte(0xf3, 0x2d),
+        T::read_bytes(0x45, &[0x80, 0x10, 0xe1]),
+        T::write_byte(0x30, 0x41),
+        T::write_byte(0x30, 0x93),
+    ]);
+    SHT31::with_bus(mock.clone(), SHT31DeviceAddress::Secondary).expect("init failed");
+    mock.done();
+}
+
+#[test]
+fn init_soft_resets_when_stop_fails() {
+    let mut script = sht31_init_script();
+    script[3] = T::write_byte(0x30, 0x93).with_error(std::io::ErrorKind::Other);
+    script.push(T::write_byte(0x30, 0xa2));
+    let mock = MockBus::new(&script);
+    init_sht31(&mock);
+    assert_eq!(mock.delay_total_ms(), 2);
+}
+
+#[test]
+fn single_shot_encodes_command_msb_first() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&[
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let (temperature_raw, humidity_raw) = sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh)
+        .expect("read failed");
+    mock.done();
+    assert_eq!(temperature_raw, 0x6666);
+    assert_eq!(humidity_raw, 0x8000);
+    assert!((sht31.get_temperature(temperature_raw).celsius() - 25.0).abs() < 0.01);
+    assert!((sht31.get_relative_humidity(humidity_raw).percent() - 50.0).abs() < 0.01);
+}
+
+#[test]
+fn single_shot_no_clock_stretch_waits_before_reading() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&[
+        T::write_byte(0x24, 0x16),
+        T::read_bytes(SHT31_ADDR, &[0x00, 0x00, 0x81, 0xff, 0xff, 0xac]),
+    ]);
+    let (temperature_raw, humidity_raw) = sht31
+        .get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityLow)
+        .expect("read failed");
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 5);
+    assert_eq!(sht31.get_temperature(temperature_raw).celsius(), -45.0);
+    assert_eq!(sht31.get_relative_humidity(humidity_raw).percent(), 100.0);
+}
+
+#[test]
+fn continuous_mode_start_fetch_stop() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&[
+        T::write_byte(0x27, 0x37),
+        T::write_byte(0xe0, 0x00),
+        T::read_bytes(SHT31_ADDR, &[0x12, 0x34, 0x37, 0x56, 0x78, 0x7d]),
+        T::write_byte(0x30, 0x93),
+    ]);
+    sht31.start_continuous_mode(SHT31ContinuousAcquisition::RepeatabilityHigh10Mps).expect("start failed");
+    let data = sht31.get_data_continuous().expect("fetch failed");
+    sht31.stop_continuous_mode().expect("stop failed");
+    mock.done();
+    assert_eq!(data, (0x1234, 0x5678));
+}
+
+#[test]
+fn corrupted_word_fails_the_read() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&[
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x01, 0xa2]),
+    ]);
+    let err = sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect_err("read succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::Crc { expected: 0x93, found: 0xa2 }));
+}
+
+#[test]
+fn crc_policy_warn_and_ignore_keep_the_data() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&[
+        T::write_byte(0xe0, 0x00),
+        T::read_bytes(SHT31_ADDR, &[0x12, 0x34, 0x00, 0x56, 0x78, 0x7d]),
+        T::write_byte(0xe0, 0x00),
+        T::read_bytes(SHT31_ADDR, &[0x12, 0x34, 0x00, 0x56, 0x78, 0x00]),
+    ]);
+    sht31.set_crc_policy(CrcPolicy::Warn);
+    assert_eq!(sht31.get_data_continuous().expect("fetch failed"), (0x1234, 0x5678));
+    sht31.set_crc_policy(CrcPolicy::Ignore);
+    assert_eq!(sht31.get_data_continuous().expect("fetch failed"), (0x1234, 0x5678));
+    mock.done();
+}
+
+#[test]
+fn async_init_and_single_shot_no_clock_stretch() {
+    let mut script = sht31_init_script();
+    script.extend([
+        T::write_byte(0x24, 0x00),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mock = MockBus::new(&script);
+    let (temperature_raw, humidity_raw, sht31) = block_on(async {
+        let mut sht31 = SHT31Async::with_bus(mock.clone(), SHT31DeviceAddress::Default).await.expect("init failed");
+        let (temperature_raw, humidity_raw) = sht31
+            .get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityHigh)
+            .await.expect("read failed");
+        (temperature_raw, humidity_raw, sht31)
+    });
+    mock.done();
+    assert_eq!(mock.delay_total_ms(), 5);
+    assert!((sht31.get_temperature(temperature_raw).celsius() - 25.0).abs() < 0.01);
+    assert!((sht31.get_relative_humidity(humidity_raw).percent() - 50.0).abs() < 0.01);
+}
+
+#[test]
+fn async_crc_failures_are_recorded_on_the_bus() {
+    let mut script = sht31_init_script();
+    script.extend([
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x00, 0x80, 0x00, 0x00]),
+    ]);
+    let mock = MockBus::new(&script);
+    block_on(async {
+        let mut sht31 = SHT31Async::with_bus(mock.clone(), SHT31DeviceAddress::Default).await.expect("init failed");
+        sht31.set_crc_policy(CrcPolicy::Warn);
+        sht31.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).await.expect("read failed");
+    });
+    mock.done();
+    assert_eq!(mock.events(), [BusEvent::CrcFailure, BusEvent::CrcFailure]);
+}
+
+#[test]
+fn measure_converts_single_shot() {
+    let mock = MockBus::new(&sht31_init_script());
+    let mut sht31 = init_sht31(&mock);
+    mock.expect(&sht31_measure_script());
+    let measurement = sht31.measure().expect("measure failed");
+    mock.done();
+    assert_eq!(sht31.name(), "SHT31");
+    assert!((measurement.temperature.unwrap().fahrenheit() - 77.0).abs() < 0.01);
+    assert!((measurement.humidity.unwrap().percent() - 50.0).abs() < 0.01);
+    assert_eq!(Measurement { temperature: None, humidity: None, ..measurement.clone() }, Measurement::default());
+    // -- psychrometrics straight from the measurement, the mixing ratio needs a pressure
+    assert!((measurement.dew_point().unwrap().celsius() - 13.85).abs() < 0.01);
+    assert!((measurement.absolute_humidity().unwrap() - 11.5).abs() < 0.05);
+    assert_eq!(measurement.mixing_ratio(), None);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::io::ErrorKind;
+use std::thread;
+
+use common::{MUX_ADDR, SHT31_ADDR, Sht31Board, Transfer, TransferLog, assert_not_interleaved, sht31_init_script};
+use i2c_sensors::i2cio;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::sht31::{SHT31, SHT31DeviceAddress, SHT31SingleShotAcquisition, SHT31SingleShotAcquisitionNoClockStretch};
+use i2c_sensors::tca9548a::{TCA9548A, Tca9548aChannel, Tca9548aDeviceAddress};
+
+fn select(channels: u8) -> Vec<T> {
+    vec![
+        T::set_slave(MUX_ADDR),
+        T::write_byte_single(channels),
+    ]
+}
+
+fn init(mock: &MockBus) -> TCA9548A<MockBus> {
+    let mux = TCA9548A::with_bus(mock.clone(), Tca9548aDeviceAddress::Default).expect("init failed");
+    mock.done();
+    mux
+}
+
+#[test]
+fn init_disables_all_channels() {
+    let mock = MockBus::new(&select(0x00));
+    init(&mock);
+}
+
+#[test]
+fn init_fails_without_mux() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x77),
+        T::write_byte_single(0x00).with_error(ErrorKind::Other),
+    ]);
+    assert!(TCA9548A::with_bus(mock.clone(), Tca9548aDeviceAddress::Alt7).is_err());
+    mock.done();
+}
+
+#[test]
+fn channels_selected_before_each_transfer() {
+    let mock = MockBus::new(&select(0x00));
+    let mux = init(&mock);
+
+    let mut script = select(0x01);
+    script.extend(sht31_init_script());
+    script.extend(select(0x02));
+    script.extend(sht31_init_script());
+    mock.expect(&script);
+    let mut sht31_0 = SHT31::with_bus(mux.channel(Tca9548aChannel::Channel0), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    let mut sht31_1 = SHT31::with_bus(mux.channel(Tca9548aChannel::Channel1), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    mock.done();
+
+    let mut script = select(0x01);
+    script.extend([
+        // -- the device address is re-applied after talking to the mux
+        T::set_slave(SHT31_ADDR),
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+        // -- no switch while the channel stays the same
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    script.extend(select(0x02));
+    script.extend([
+        T::set_slave(SHT31_ADDR),
+        T::write_byte(0x2c, 0x06),
+        T::read_bytes(SHT31_ADDR, &[0x00, 0x00, 0x81, 0xff, 0xff, 0xac]),
+    ]);
+    mock.expect(&script);
+    assert_eq!(sht31_0.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed"), (0x6666, 0x8000));
+    assert_eq!(sht31_0.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed"), (0x6666, 0x8000));
+    assert_eq!(sht31_1.get_data_single(SHT31SingleShotAcquisition::RepeatabilityHigh).expect("read failed"), (0x0000, 0xffff));
+    mock.done();
+}
+
+#[test]
+fn failed_switch_is_retried_on_next_transfer() {
+    let mock = MockBus::new(&select(0x00));
+    let mux = init(&mock);
+    let mut channel = mux.channel(Tca9548aChannel::Channel7);
+    mock.expect(&[
+        T::set_slave(MUX_ADDR),
+        T::write_byte_single(0x80).with_error(ErrorKind::Other),
+    ]);
+    assert!(i2cio::set_slave(&mut channel, 0x48).is_err());
+    mock.done();
+    mock.expect(&[
+        T::set_slave(MUX_ADDR),
+        T::write_byte_single(0x80),
+        T::set_slave(0x48),
+        T::read_word(0x0f, 0x1711),
+    ]);
+    assert_eq!(i2cio::read_word(&mut channel, 0x0f).expect("read failed"), 0x1711);
+    mock.done();
+}
+
+#[test]
+fn same_address_sensors_on_two_threads_do_not_interleave() {
+    let log = TransferLog::default();
+    let mux = TCA9548A::with_bus(Sht31Board::new(&log), Tca9548aDeviceAddress::Default).expect("init failed");
+    let mut sht31_0 = SHT31::with_bus(mux.channel(Tca9548aChannel::Channel0), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    let mut sht31_1 = SHT31::with_bus(mux.channel(Tca9548aChannel::Channel1), SHT31DeviceAddress::Default)
+        .expect("init failed");
+    log.lock().unwrap().clear();
+
+    let measure = |sht31: &mut SHT31<_>, channels| {
+        let raw_value = Sht31Board::raw_value(channels, SHT31_ADDR);
+        for _ in 0..20 {
+            // -- the read after the wait has to reach the sensor that got the command
+            let data = sht31.get_data_single_no_clock_stretch(SHT31SingleShotAcquisitionNoClockStretch::RepeatabilityHigh)
+                .expect("read failed");
+            assert_eq!(data, (raw_value, raw_value));
+        }
+    };
+    thread::scope(|scope| {
+        scope.spawn(|| measure(&mut sht31_0, 0x01));
+        scope.spawn(|| measure(&mut sht31_1, 0x02));
+    });
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().filter(|transfer| matches!(transfer, Transfer::Response(..))).count(), 2 * 20);
+    assert_not_interleaved(&log);
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use common::{init_tmp117, tmp117_init_script};
+use futures_executor::block_on;
+use i2c_sensors::error::Error;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::tmp117::{TMP117, TMP117Async, Tmp117Averaging, Tmp117Config, Tmp117ConversionCycleTime,
+    Tmp117DeviceAddress, Tmp117SensorMode};
+use i2c_sensors::units::Temperature;
+
+const REG_TEMPERATURE: u8 = 0x00;
+const REG_CONFIGURATION: u8 = 0x01;
+const REG_TEMPERATURE_OFFSET: u8 = 0x07;
+const REG_DEVICE_ID: u8 = 0x0f;
+
+#[test]
+fn init_reads_device_id_and_revision() {
+    let mock = MockBus::new(&tmp117_init_script());
+    let tmp117 = init_tmp117(&mock);
+    assert_eq!(tmp117.get_device_id(), 0x117);
+    assert_eq!(tmp117.get_device_revision(), 1);
+    assert_eq!(mock.delay_total_ms(), 2);
+}
+
+#[test]
+fn init_rejects_unknown_device_id() {
+    let mock = MockBus::new(&[
+        T::set_slave(0x49),
+        T::read_word(REG_DEVICE_ID, 0x1801),
+    ]);
+    let result = TMP117::with_bus(mock.clone(), Tmp117DeviceAddress::Alt1, &Tmp117SensorMode::ModeContinuousConversion,
+        &Tmp117ConversionCycleTime::Ms1000, &Tmp117Averaging::Averaging8Conversions);
+    mock.done();
+    assert!(result.is_err());
+}
+
+#[test]
+fn set_sensor_mode_keeps_low_bits() {
+    let mock = MockBus::new(&tmp117_init_script());
+    let mut tmp117 = init_tmp117(&mock);
+    mock.expect(&[
+        // -- config 0x221f, the low five bits stay untouched
+        T::read_word(REG_CONFIGURATION, 0x1f22),
+        // -- one shot 0x0c00, shortest cycle, 64 conversions 0x0060
+        T::write_word(REG_CONFIGURATION, 0x7f0c),
+    ]);
+    tmp117.set_sensor_mode(&Tmp117SensorMode::ModeOneShot, &Tmp117ConversionCycleTime::Shortest,
+        &Tmp117Averaging::Averaging64Conversions).expect("set mode failed");
+    mock.done();
+}
+
+#[test]
+fn with_config_writes_mode_cycle_and_averaging_at_once() {
+    let mut script = tmp117_init_script();
+    script.truncate(3);
+    script.extend([
+        T::read_word(REG_CONFIGURATION, 0x2002),
+        // -- one shot 0x0c00, shortest cycle, 64 conversions 0x0060
+        T::write_word(REG_CONFIGURATION, 0x600c),
+    ]);
+    let mock = MockBus::new(&script);
+    let config = Tmp117Config::default()
+        .with_sensor_mode(Tmp117SensorMode::ModeOneShot)
+        .with_conversion_cycle(Tmp117ConversionCycleTime::Shortest)
+        .with_averaging(Tmp117Averaging::Averaging64Conversions);
+    let mut tmp117 = TMP117::with_config(mock.clone(), &config).expect("init failed");
+    mock.done();
+    // -- re-applied as is, e.g. after a reset
+    mock.expect(&[
+        T::read_word(REG_CONFIGURATION, 0x2002),
+        T::write_word(REG_CONFIGURATION, 0x600c),
+    ]);
+    tmp117.apply_config(&config).expect("apply failed");
+    mock.done();
+}
+
+#[test]
+fn config_with_stretched_cycle_is_rejected_before_any_transfer() {
+    let mock = MockBus::new(&[]);
+    let config = Tmp117Config::default()
+        .with_conversion_cycle(Tmp117ConversionCycleTime::Medium)
+        .with_averaging(Tmp117Averaging::Averaging64Conversions);
+    let err = TMP117::with_config(mock.clone(), &config).err().expect("init succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+    // -- in one shot mode the cycle doesn't matter
+    assert!(config.with_sensor_mode(Tmp117SensorMode::ModeOneShot).validate().is_ok());
+}
+
+#[test]
+fn apply_config_rejects_another_device_address() {
+    let mock = MockBus::new(&tmp117_init_script());
+    let mut tmp117 = init_tmp117(&mock);
+    let config = Tmp117Config::default().with_device_addr(Tmp117DeviceAddress::Alt1);
+    let err = tmp117.apply_config(&config).expect_err("apply succeeded");
+    mock.done();
+    assert!(matches!(Error::from(err), Error::InvalidConfig(_)));
+}
+
+#[test]
+fn data_ready_and_temperature_are_byte_swapped() {
+    let mock = MockBus::new(&tmp117_init_script());
+    let mut tmp117 = init_tmp117(&mock);
+    mock.expect(&[
+        T::read_word(REG_CONFIGURATION, 0x2020),
+        // -- 0x0c80 is 25 °C
+        T::read_word(REG_TEMPERATURE, 0x800c),
+        // -- 0xff80 is -1 °C
+        T::read_word(REG_TEMPERATURE, 0x80ff),
+    ]);
+    assert!(tmp117.is_data_ready().expect("read failed"));
+    assert_eq!(tmp117.get_temperature().expect("read failed"), Temperature::from_celsius(25.0));
+    assert_eq!(tmp117.get_temperature().expect("read failed"), Temperature::from_celsius(-1.0));
+    mock.done();
+}
+
+#[test]
+fn temperature_offset_round_trip() {
+    let mock = MockBus::new(&tmp117_init_script());
+    let mut tmp117 = init_tmp117(&mock);
+    mock.expect(&[
+        // -- -0.5 °C is 0xffc0
+        T::write_word(REG_TEMPERATURE_OFFSET, 0xc0ff),
+        T::read_word(REG_TEMPERATURE_OFFSET, 0xc0ff),
+    ]);
+    tmp117.set_temperature_offset(-0.5).expect("write failed");
+    assert_eq!(tmp117.get_temperature_offset().expect("read failed"), -0.5);
+    mock.done();
+}
+
+#[test]
+fn async_init_and_temperature() {
+    let mut script = tmp117_init_script();
+    script.extend([
+        T::read_word(REG_CONFIGURATION, 0x2020),
+        T::read_word(REG_TEMPERATURE, 0x800c),
+    ]);
+    let mock = MockBus::new(&script);
+    let (device_id, data_ready, temperature) = block_on(async {
+        let mut tmp117 = TMP117Async::with_bus(mock.clone(), Tmp117DeviceAddress::Default,
+            &Tmp117SensorMode::ModeContinuousConversion, &Tmp117ConversionCycleTime::Ms1000,
+            &Tmp117Averaging::Averaging8Conversions).await.expect("init failed");
+        let data_ready = tmp117.is_data_ready().await.expect("read failed");
+        let temperature = tmp117.get_temperature().await.expect("read failed");
+        (tmp117.get_device_id(), data_ready, temperature)
+    });
+    mock.done();
+    assert_eq!(device_id, 0x117);
+    assert!(data_ready);
+    assert_eq!(temperature, Temperature::from_celsius(25.0));
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use common::{bmp388_init_script, bmp388_trimming_coefficients, init_bmp388, tmp117_init_script};
+use futures_executor::block_on;
+use i2c_sensors::bmp388::*;
+use i2c_sensors::mock::{MockBus, MockTransaction as T};
+use i2c_sensors::tmp117::{TMP117Async, Tmp117Averaging, Tmp117ConversionCycleTime, Tmp117DeviceAddress,
+    Tmp117SensorMode};
+use i2c_sensors::trace::BUS_TARGET;
+
+#[derive(Default)]
+struct Fields(HashMap<String, String>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+// -- a bus event with the fields of the driver call span it happened in
+struct Transfer {
+    span: HashMap<String, String>,
+    event: HashMap<String, String>,
+}
+
+#[derive(Clone, Default)]
+struct Collector {
+    transfers: Arc<Mutex<Vec<Transfer>>>,
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Collector {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        ctx.span(id).expect("span exists").extensions_mut().insert(fields);
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != BUS_TARGET {
+            return
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let span = ctx.event_span(event)
+            .and_then(|span| span.extensions().get::<Fields>().map(|fields| fields.0.clone()))
+            .unwrap_or_default();
+        self.transfers.lock().unwrap().push(Transfer { span, event: fields.0 });
+    }
+}
+
+fn traced(f: impl FnOnce()) -> Vec<Transfer> {
+    let collector = Collector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+    tracing::subscriber::with_default(subscriber, f);
+    let transfers = std::mem::take(&mut *collector.transfers.lock().unwrap());
+    transfers
+}
+
+#[test]
+fn driver_calls_name_their_registers() {
+    let mock = MockBus::new(&bmp388_init_script());
+    let transfers = traced(|| {
+        let mut bmp388 = init_bmp388(&mock);
+        mock.expect(&[
+            T::write_byte(0x7e, 0xb0),
+            T::write_byte(0x18, 0x0a),
+            T::write_byte(0x17, 0x1d),
+        ]);
+        bmp388.enable_fifo(Bmp388FifoStopOnFull::Disabled, Bmp388FifoWithPressureData::Enabled,
+            Bmp388FifoWithTemperatureData::Enabled, Bmp388FifoWithSensorTime::Enabled,
+            Bmp388FifoDataFiltered::Filtered, 2).expect("enable FIFO failed");
+    });
+    mock.done();
+    let wire: Vec<(&str, &str, &str, &str)> = transfers.iter()
+        .map(|transfer| (transfer.span["call"].as_str(), transfer.event["register_name"].as_str(),
+            transfer.event["direction"].as_str(), transfer.event["bytes"].as_str()))
+        .collect();
+    assert_eq!(wire[..3], [
+        ("with_bus", "BMP388_REG_CHIP_ID", "read", "[80]"),
+        ("with_bus", "BMP388_REG_CMD", "write", "[182]"),
+        ("with_bus", "BMP388_REG_TRIMMING_COEFFICIENTS", "read", &format!("{:?}", bmp388_trimming_coefficients())),
+    ]);
+    // -- enable_fifo flushes the fifo first, the innermost call wins
+    assert_eq!(wire[6..], [
+        ("flush_fifo", "BMP388_REG_CMD", "write", "[176]"),
+        ("enable_fifo", "BMP388_REG_FIFO_CONFIG_2", "write", "[10]"),
+        ("enable_fifo", "BMP388_REG_FIFO_CONFIG_1", "write", "[29]"),
+    ]);
+    for transfer in &transfers {
+        assert_eq!(transfer.span["driver"], "BMP388");
+        assert_eq!(transfer.span["device_addr"], "119");
+        assert_eq!(transfer.event["driver"], "BMP388");
+        assert_eq!(transfer.event["device_addr"], "119");
+    }
+}
+
+#[test]
+fn failed_transfers_carry_the_error() {
+    let mock = MockBus::new(&bmp388_init_script());
+    let transfers = traced(|| {
+        let mut bmp388 = init_bmp388(&mock);
+        mock.expect(&[
+            T::read_byte(0x11, 0x00).with_error(std::io::ErrorKind::TimedOut),
+        ]);
+        assert!(bmp388.is_data_ready().is_err());
+    });
+    mock.done();
+    let last = transfers.last().expect("no transfers traced");
+    assert_eq!(last.span["call"], "is_data_ready");
+    assert_eq!(last.event["register_name"], "BMP388_REG_INT_STATUS");
+    assert!(last.event.contains_key("error"));
+    assert!(!last.event.contains_key("bytes"));
+}
+
+#[test]
+fn async_driver_calls_are_spans_of_their_futures() {
+    let mut script = tmp117_init_script();
+    script.push(T::read_word(0x00, 0x800c));
+    let mock = MockBus::new(&script);
+    let transfers = traced(|| block_on(async {
+        let mut tmp117 = TMP117Async::with_bus(mock.clone(), Tmp117DeviceAddress::Default,
+            &Tmp117SensorMode::ModeContinuousConversion, &Tmp117ConversionCycleTime::Ms1000,
+            &Tmp117Averaging::Averaging8Conversions).await.expect("init failed");
+        tmp117.get_temperature().await.expect("read failed");
+    }));
+    mock.done();
+    let wire: Vec<(&str, &str, &str)> = transfers.iter()
+        .map(|transfer| (transfer.span["call"].as_str(), transfer.event["register_name"].as_str(),
+            transfer.event["direction"].as_str()))
+        .collect();
+    // -- the innermost call wins across awaits too
+    assert_eq!(wire, [
+        ("with_bus", "TMP117_REG_DEVICE_ID", "read"),
+        ("with_bus", "TMP117_REG_CONFIGURATION", "write"),
+        ("set_sensor_mode", "TMP117_REG_CONFIGURATION", "read"),
+        ("set_sensor_mode", "TMP117_REG_CONFIGURATION", "write"),
+        ("get_temperature", "TMP117_REG_TEMPERATURE", "read"),
+    ]);
+    for transfer in &transfers {
+        assert_eq!(transfer.span["driver"], "TMP117");
+        assert_eq!(transfer.span["device_addr"], "72");
+        assert_eq!(transfer.event["driver"], "TMP117");
+        assert_eq!(transfer.event["device_addr"], "72");
+    }
+}